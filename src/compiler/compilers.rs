@@ -1,8 +1,22 @@
-use super::fetcher::FetchError;
-use crate::compiler::{self, DownloadCache, Fetcher};
-use ethers_solc::{artifacts::Severity, error::SolcError, CompilerInput, CompilerOutput, Solc};
-use std::{fmt::Debug, path::PathBuf, sync::Arc};
+use crate::{
+    compiler::{self, DownloadCache, FetchError, Fetcher, Language, ScratchDir},
+    metrics,
+};
+use ethers_solc::{
+    artifacts::Severity,
+    error::{SolcError, SolcIoError},
+    CompilerOutput,
+};
+use serde::Serialize;
+use std::{
+    fmt::Debug,
+    path::Path,
+    process::Stdio,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 use thiserror::Error as DeriveError;
+use tokio::io::AsyncWriteExt;
 
 #[derive(Debug, DeriveError)]
 pub enum Error {
@@ -10,31 +24,100 @@ pub enum Error {
     Fetch(#[from] FetchError),
     #[error("Internal error while compiling: {0}")]
     Internal(#[from] SolcError),
-    #[error("Compilation error: {0:?}")]
-    Compilation(Vec<String>),
+    #[error("Cannot prepare compilation directory: {0}")]
+    ScratchDir(#[from] std::io::Error),
+    /// Formatted messages of the `errors`, alongside all the errors, warnings
+    /// and info messages reported by the compiler
+    #[error("Compilation error: {errors:?}")]
+    Compilation {
+        errors: Vec<String>,
+        diagnostics: Vec<ethers_solc::artifacts::Error>,
+    },
 }
 
 pub struct Compilers {
     cache: DownloadCache,
+    /// Language of the compilers, used to label the metrics
+    language: Language,
     fetcher: Arc<dyn Fetcher>,
+    scratch_dir: ScratchDir,
 }
 
 impl Compilers {
-    pub fn new(fetcher: Arc<dyn Fetcher>) -> Self {
+    pub fn new(fetcher: Arc<dyn Fetcher>, scratch_dir: ScratchDir) -> Self {
         Self {
             cache: DownloadCache::new(),
+            language: Language::Solidity,
             fetcher,
+            scratch_dir,
         }
     }
 
-    pub async fn compile(
+    /// Cached compilers validated longer than `max_age` ago are checked
+    /// against the hashsum currently published by the fetcher before use.
+    pub fn with_cache_max_age(mut self, max_age: Option<Duration>) -> Self {
+        self.cache = self.cache.with_max_age(max_age);
+        self
+    }
+
+    /// Hashsums of fetched compilers are persisted into an index file under the `root`,
+    /// see [`DownloadCache::with_index`]. Metrics are labeled with the `language`.
+    pub fn with_index(mut self, root: &Path, language: Language) -> Self {
+        self.cache = self.cache.with_index(root, language);
+        self.language = language;
+        self
+    }
+
+    /// Least recently used compilers are removed once the compilers occupy more than `max_bytes`.
+    pub fn with_max_cache_bytes(mut self, max_bytes: Option<u64>) -> Self {
+        self.cache = self.cache.with_max_bytes(max_bytes);
+        self
+    }
+
+    /// Fetches of compilers not completed within `fetch_timeout` are aborted.
+    pub fn with_fetch_timeout(mut self, fetch_timeout: Option<Duration>) -> Self {
+        self.cache = self.cache.with_fetch_timeout(fetch_timeout);
+        self
+    }
+
+    /// Versions not found are not fetched again for a while, see [`DownloadCache::with_not_found_ttl`].
+    pub fn with_not_found_ttl(mut self, not_found_ttl: Duration) -> Self {
+        self.cache = self.cache.with_not_found_ttl(not_found_ttl);
+        self
+    }
+
+    /// Fetched compilers are run with `--version` before use, see [`DownloadCache::with_binary_check`].
+    pub fn with_binary_check(mut self, check_binaries: bool) -> Self {
+        self.cache = self.cache.with_binary_check(check_binaries);
+        self
+    }
+
+    /// Compiles the standard json `input` (e.g. [`ethers_solc::CompilerInput`] for solidity)
+    /// using the compiler of the `compiler_version`.
+    pub async fn compile<I: Serialize>(
         &self,
         compiler_version: &compiler::Version,
-        input: &CompilerInput,
+        input: &I,
     ) -> Result<CompilerOutput, Error> {
-        let solc_path = self.cache.get(&*self.fetcher, compiler_version).await?;
-        let solc = Solc::from(solc_path);
-        let output = solc.compile(&input)?;
+        let fetch_time = metrics::compiler_fetch_time(self.language, compiler_version);
+        // the handle keeps the compiler from being evicted until the compilation completes
+        let solc_path =
+            metrics::timed(fetch_time, self.cache.get(&*self.fetcher, compiler_version)).await?;
+        let compile_time = metrics::compile_time(self.language, compiler_version);
+        metrics::timed(compile_time, self.compile_with(&solc_path, input)).await
+    }
+
+    /// Compiles the input using the compiler located at `solc_path`,
+    /// bypassing the fetcher and the cache.
+    ///
+    /// The compiler process is killed if the returned future is dropped before completion.
+    pub async fn compile_with<I: Serialize>(
+        &self,
+        solc_path: &Path,
+        input: &I,
+    ) -> Result<CompilerOutput, Error> {
+        let compilation_dir = self.scratch_dir.create()?;
+        let output = run_solc(solc_path, compilation_dir.path(), input).await?;
 
         // Compilations errors, warnings and info messages are returned in `CompilerOutput.error`
         let mut errors = Vec::new();
@@ -49,7 +132,10 @@ impl Compilers {
             }
         }
         if !errors.is_empty() {
-            return Err(Error::Compilation(errors));
+            return Err(Error::Compilation {
+                errors,
+                diagnostics: output.errors,
+            });
         }
 
         Ok(output)
@@ -59,9 +145,43 @@ impl Compilers {
         self.fetcher.all_versions()
     }
 
-    pub async fn load_from_dir(&self, dir: &PathBuf) {
-        match self.cache.load_from_dir(dir).await {
-            Ok(_) => {}
+    /// Whether the compiler of the version is present in the cache.
+    pub fn is_cached(&self, version: &compiler::Version) -> bool {
+        self.cache.is_cached(version)
+    }
+
+    /// Time and result of the last refresh of the compiler versions, if the fetcher refreshes them.
+    pub fn last_refresh(&self) -> Option<(Instant, Result<(), String>)> {
+        self.fetcher.last_refresh()
+    }
+
+    /// Downloads the newest `count` release versions into the cache.
+    /// Failures are logged, as the versions would be fetched on demand otherwise.
+    pub async fn prefetch_latest(&self, count: usize) {
+        let mut versions: Vec<_> = self
+            .all_versions()
+            .into_iter()
+            .filter(|version| version.is_release())
+            .collect();
+        versions.sort_unstable_by(|a, b| b.cmp(a));
+        versions.truncate(count);
+        self.prefetch(&versions).await;
+    }
+
+    /// Downloads the `versions` into the cache, logging the failures.
+    pub async fn prefetch(&self, versions: &[compiler::Version]) {
+        let fetches = versions.iter().map(|version| async move {
+            match self.cache.get(&*self.fetcher, version).await {
+                Ok(_) => log::info!("prefetched compiler version {}", version),
+                Err(err) => log::warn!("cannot prefetch compiler version {}: {}", version, err),
+            }
+        });
+        futures::future::join_all(fetches).await;
+    }
+
+    pub async fn load_from_dir(&self, root: &Path, language: Language) {
+        match self.cache.load_from_dir(root, language).await {
+            Ok(_) => self.cache.validate_loaded(&*self.fetcher).await,
             Err(e) => {
                 log::error!("error during local compilers loading: {}", e)
             }
@@ -69,27 +189,66 @@ impl Compilers {
     }
 }
 
+async fn run_solc<I: Serialize>(
+    solc_path: &Path,
+    base_path: &Path,
+    input: &I,
+) -> Result<CompilerOutput, SolcError> {
+    let io_error = |err| SolcError::from(SolcIoError::new(err, solc_path));
+    let content = serde_json::to_vec(input)?;
+    // the compiler is run in the `base_path`, so a relative path would be resolved against it
+    let solc_path = tokio::fs::canonicalize(solc_path).await.map_err(io_error)?;
+    let mut child = tokio::process::Command::new(&solc_path)
+        .current_dir(base_path)
+        .arg("--standard-json")
+        .stdin(Stdio::piped())
+        .stderr(Stdio::piped())
+        .stdout(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(io_error)?;
+    {
+        let mut stdin = child.stdin.take().expect("stdin is piped");
+        stdin.write_all(&content).await.map_err(io_error)?;
+        // stdin is closed on drop, so that the compiler stops waiting for the input
+    }
+    let output = child.wait_with_output().await.map_err(io_error)?;
+    if !output.status.success() {
+        return Err(SolcError::SolcError(
+            String::from_utf8_lossy(&output.stderr).to_string(),
+        ));
+    }
+    Ok(serde_json::from_slice(&output.stdout)?)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::compiler::ListFetcher;
-    use std::{env::temp_dir, str::FromStr};
+    use std::{env::temp_dir, path::PathBuf, str::FromStr};
 
     use crate::consts::DEFAULT_COMPILER_LIST;
     use async_once_cell::OnceCell;
-    use ethers_solc::artifacts::{Source, Sources};
+    use ethers_solc::{
+        artifacts::{Source, Sources},
+        CompilerInput,
+    };
     use std::default::Default;
 
+    fn scratch_dir() -> ScratchDir {
+        ScratchDir::new(temp_dir()).expect("scratch dir should be created")
+    }
+
     async fn global_compilers() -> &'static Compilers {
         static COMPILERS: OnceCell<Compilers> = OnceCell::new();
         COMPILERS
             .get_or_init(async {
                 let url = DEFAULT_COMPILER_LIST.try_into().expect("Getting url");
-                let fetcher = ListFetcher::new(url, None, temp_dir())
+                let fetcher = ListFetcher::new(url, vec![], None, temp_dir(), None)
                     .await
                     .expect("Fetch releases");
-                let compilers = Compilers::new(Arc::new(fetcher));
-                compilers
+
+                Compilers::new(Arc::new(fetcher), scratch_dir())
             })
             .await
     }
@@ -121,6 +280,207 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn prefetches_latest_versions() {
+        #[derive(Default)]
+        struct MockFetcher {
+            fetched: parking_lot::Mutex<Vec<compiler::Version>>,
+        }
+
+        #[async_trait::async_trait]
+        impl Fetcher for MockFetcher {
+            async fn fetch(&self, ver: &compiler::Version) -> Result<PathBuf, FetchError> {
+                self.fetched.lock().push(ver.clone());
+                Ok(PathBuf::from(ver.to_string()))
+            }
+
+            fn all_versions(&self) -> Vec<compiler::Version> {
+                [
+                    "v0.8.9+commit.e5eed63a",
+                    "v0.8.15-nightly.2022.5.27+commit.095cc647",
+                    "v0.4.24+commit.e67f0147",
+                    "v0.8.14+commit.80d49f37",
+                    "v0.8.10+commit.fc410830",
+                ]
+                .into_iter()
+                .map(|version| compiler::Version::from_str(version).unwrap())
+                .collect()
+            }
+        }
+
+        let fetcher = Arc::new(MockFetcher::default());
+        let compilers = Arc::new(Compilers::new(fetcher.clone(), scratch_dir()));
+        let handle = {
+            let compilers = compilers.clone();
+            tokio::spawn(async move { compilers.prefetch_latest(2).await })
+        };
+        handle.await.unwrap();
+
+        let mut fetched: Vec<_> = fetcher
+            .fetched
+            .lock()
+            .iter()
+            .map(|v| v.to_string())
+            .collect();
+        fetched.sort();
+        assert_eq!(
+            fetched,
+            vec!["v0.8.10+commit.fc410830", "v0.8.14+commit.80d49f37"]
+        );
+
+        // explicitly listed versions are fetched even if they are not the newest
+        let nightly =
+            compiler::Version::from_str("v0.8.15-nightly.2022.5.27+commit.095cc647").unwrap();
+        compilers.prefetch(std::slice::from_ref(&nightly)).await;
+        assert_eq!(fetcher.fetched.lock().last(), Some(&nightly));
+    }
+
+    #[tokio::test]
+    async fn compiles_in_scratch_dir() {
+        use std::os::unix::fs::PermissionsExt;
+
+        struct NoFetcher;
+
+        #[async_trait::async_trait]
+        impl Fetcher for NoFetcher {
+            async fn fetch(&self, ver: &compiler::Version) -> Result<PathBuf, FetchError> {
+                Err(FetchError::NotFound(ver.clone()))
+            }
+
+            fn all_versions(&self) -> Vec<compiler::Version> {
+                vec![]
+            }
+        }
+
+        let root = temp_dir().join("blockscout/verification/compilers/test/scratch/");
+        let _ = std::fs::remove_dir_all(&root);
+        let marker = temp_dir().join(format!("solc_working_dir_{}", std::process::id()));
+        // Stub compiler reporting its working directory
+        let stub = temp_dir().join(format!("solc_stub_{}", std::process::id()));
+        std::fs::write(
+            &stub,
+            format!(
+                "#!/bin/sh\ncat > /dev/null\npwd > {:?}\necho '{{\"contracts\":{{}},\"sources\":{{}}}}'\n",
+                marker
+            ),
+        )
+        .unwrap();
+        std::fs::set_permissions(&stub, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let compilers = Compilers::new(Arc::new(NoFetcher), ScratchDir::new(root.clone()).unwrap());
+        let input: CompilerInput = Input::with_source_code("contract A {}".into()).into();
+        compilers
+            .compile_with(&stub, &input)
+            .await
+            .expect("stub compilation failed");
+
+        let working_dir = PathBuf::from(std::fs::read_to_string(&marker).unwrap().trim());
+        assert_eq!(
+            working_dir.parent().unwrap().canonicalize().unwrap(),
+            root.canonicalize().unwrap()
+        );
+        assert!(!working_dir.exists(), "compilation dir should be removed");
+    }
+
+    #[tokio::test]
+    async fn compiles_with_relative_compiler_path() {
+        use std::os::unix::fs::PermissionsExt;
+
+        struct NoFetcher;
+
+        #[async_trait::async_trait]
+        impl Fetcher for NoFetcher {
+            async fn fetch(&self, ver: &compiler::Version) -> Result<PathBuf, FetchError> {
+                Err(FetchError::NotFound(ver.clone()))
+            }
+
+            fn all_versions(&self) -> Vec<compiler::Version> {
+                vec![]
+            }
+        }
+
+        // relative to the working directory of the tests, as `compilers/` in production
+        let dir = PathBuf::from(format!("solc_relative_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let stub = dir.join("solc");
+        std::fs::write(
+            &stub,
+            "#!/bin/sh\ncat > /dev/null\necho '{\"contracts\":{},\"sources\":{}}'\n",
+        )
+        .unwrap();
+        std::fs::set_permissions(&stub, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let compilers = Compilers::new(Arc::new(NoFetcher), scratch_dir());
+        let input: CompilerInput = Input::with_source_code("contract A {}".into()).into();
+        let result = compilers.compile_with(&stub, &input).await;
+        std::fs::remove_dir_all(&dir).unwrap();
+        result.expect("compiler at a relative path should be run");
+    }
+
+    #[tokio::test]
+    async fn records_fetch_and_compile_time() {
+        use std::os::unix::fs::PermissionsExt;
+
+        /// Fetcher returning a stub compiler for versions below 0.2
+        struct StubFetcher {
+            stub: PathBuf,
+        }
+
+        #[async_trait::async_trait]
+        impl Fetcher for StubFetcher {
+            async fn fetch(&self, ver: &compiler::Version) -> Result<PathBuf, FetchError> {
+                match ver.version().minor {
+                    0 | 1 => Ok(self.stub.clone()),
+                    _ => Err(FetchError::NotFound(ver.clone())),
+                }
+            }
+
+            fn all_versions(&self) -> Vec<compiler::Version> {
+                vec![]
+            }
+        }
+
+        let stub = temp_dir().join(format!("solc_timed_stub_{}", std::process::id()));
+        std::fs::write(
+            &stub,
+            "#!/bin/sh\ncat > /dev/null\necho '{\"contracts\":{},\"sources\":{}}'\n",
+        )
+        .unwrap();
+        std::fs::set_permissions(&stub, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let compilers = Compilers::new(Arc::new(StubFetcher { stub }), scratch_dir());
+        let input: CompilerInput = Input::with_source_code("contract A {}".into()).into();
+        let samples = |histogram: prometheus::Histogram| histogram.get_sample_count();
+
+        let version = compiler::Version::from_str("v0.1.1+commit.6ff4cd6a").unwrap();
+        compilers
+            .compile(&version, &input)
+            .await
+            .expect("stub compilation failed");
+        assert_eq!(
+            samples(metrics::compiler_fetch_time(Language::Solidity, &version)),
+            1
+        );
+        assert_eq!(
+            samples(metrics::compile_time(Language::Solidity, &version)),
+            1
+        );
+
+        // failed fetches are timed as well
+        let version = compiler::Version::from_str("v0.2.0+commit.4dc2445e").unwrap();
+        compilers
+            .compile(&version, &input)
+            .await
+            .expect_err("version should not be found");
+        assert_eq!(
+            samples(metrics::compiler_fetch_time(Language::Solidity, &version)),
+            1
+        );
+        assert_eq!(
+            samples(metrics::compile_time(Language::Solidity, &version)),
+            0
+        );
+    }
+
     #[tokio::test]
     async fn successful_compilation() {
         let source_code = r#"
@@ -187,7 +547,7 @@ mod tests {
             .await
             .expect_err("Compilation should fail");
         match result {
-            Error::Compilation(errors) => {
+            Error::Compilation { errors, .. } => {
                 assert!(errors.into_iter().any(|err| err.contains("ParserError")))
             }
             _ => panic!("Invalid compilation error: {:?}", result),
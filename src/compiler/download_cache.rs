@@ -1,22 +1,240 @@
 use super::{
     fetcher::{FetchError, Fetcher},
+    language::Language,
+    list_fetcher::{check_hashsum, ChecksumKind},
     version::Version,
 };
-use std::{collections::HashMap, path::PathBuf, str::FromStr, sync::Arc};
+use crate::{metrics, types::Mismatch};
+use primitive_types::H256;
+use prometheus::{IntCounterVec, IntGauge};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, Instant, SystemTime},
+};
+
+/// Name of the index file inside the folder containing the compilers of a language
+const INDEX_FILE_NAME: &str = "index.json";
+/// Maximum number of versions remembered as not found. Requested versions are controlled
+/// by the clients, so the oldest records are forgotten once the limit is reached.
+const MAX_NOT_FOUND: usize = 1024;
+
+#[derive(Clone, Debug)]
+struct CachedCompiler {
+    path: PathBuf,
+    /// When the file has been downloaded or its hashsum last checked
+    /// against the published one
+    validated_at: SystemTime,
+    /// Hashsum of the file recorded when it was fetched, if known
+    sha256: Option<H256>,
+}
+
+impl CachedCompiler {
+    #[cfg(test)]
+    fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            validated_at: SystemTime::now(),
+            sha256: None,
+        }
+    }
+
+    fn revalidated(&self) -> Self {
+        Self {
+            validated_at: SystemTime::now(),
+            ..self.clone()
+        }
+    }
+}
+
+/// Cached compiler persisted in the index file, so that it retains its recorded hashsum
+/// and validation time across restarts while the file is not modified.
+#[derive(Debug, Deserialize, Serialize)]
+struct IndexEntry {
+    path: PathBuf,
+    size: u64,
+    modified: SystemTime,
+    sha256: H256,
+    validated_at: SystemTime,
+}
+
+impl IndexEntry {
+    /// Whether the indexed file has the recorded size and modification time.
+    fn is_unmodified(&self) -> bool {
+        match std::fs::metadata(&self.path) {
+            Ok(metadata) => {
+                self.size == metadata.len() && metadata.modified().ok() == Some(self.modified)
+            }
+            Err(_) => false,
+        }
+    }
+}
+
+type Entry = Arc<tokio::sync::RwLock<Option<CachedCompiler>>>;
+
+/// Compiler returned by [`DownloadCache::get`]. The compiler is not evicted
+/// while any of its handles is alive, so the handle should be kept until
+/// the compilation completes.
+#[derive(Clone, Debug)]
+pub struct CompilerHandle {
+    path: PathBuf,
+    _lease: Arc<()>,
+}
+
+impl CompilerHandle {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl AsRef<Path> for CompilerHandle {
+    fn as_ref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl std::ops::Deref for CompilerHandle {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.path
+    }
+}
 
-#[derive(Default)]
 pub struct DownloadCache {
-    cache: parking_lot::Mutex<HashMap<Version, Arc<tokio::sync::RwLock<Option<PathBuf>>>>>,
+    cache: parking_lot::Mutex<HashMap<Version, Entry>>,
+    /// If set, cached compilers validated earlier are checked
+    /// against the hashsum currently published by the fetcher
+    max_age: Option<Duration>,
+    /// If set, fetched compilers are run with `--version` before being cached
+    check_binaries: bool,
+    /// If set, fetches taking longer are aborted
+    fetch_timeout: Option<Duration>,
+    /// If set, least recently used compilers are removed
+    /// once the compilers occupy more bytes on disk
+    max_bytes: Option<u64>,
+    last_access: parking_lot::Mutex<HashMap<Version, Instant>>,
+    /// Shared with the [`CompilerHandle`]s of the versions, so that the versions
+    /// in use could be told by the number of references
+    leases: parking_lot::Mutex<HashMap<Version, Arc<()>>>,
+    /// Versions not found by the fetcher are not fetched again for this long
+    not_found_ttl: Duration,
+    /// When the versions were last not found by the fetcher
+    not_found: parking_lot::Mutex<HashMap<Version, Instant>>,
+    /// If set, cached compilers with known hashsums are persisted into the file
+    index_file: Option<PathBuf>,
+    /// Set to the number of cached compilers whenever compilers are added or evicted
+    size_metric: IntGauge,
+    /// Counts requests by result: `hit` if the compiler was present, `miss` otherwise
+    requests_metric: IntCounterVec,
+}
+
+impl Default for DownloadCache {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl DownloadCache {
     pub fn new() -> Self {
         DownloadCache {
             cache: Default::default(),
+            max_age: None,
+            check_binaries: false,
+            fetch_timeout: None,
+            max_bytes: None,
+            last_access: Default::default(),
+            leases: Default::default(),
+            not_found_ttl: Duration::ZERO,
+            not_found: Default::default(),
+            index_file: None,
+            size_metric: metrics::cached_compilers(Language::Solidity),
+            requests_metric: metrics::COMPILER_CACHE_REQUESTS.clone(),
         }
     }
 
-    async fn try_get(&self, ver: &Version) -> Option<PathBuf> {
+    #[cfg(test)]
+    fn with_size_metric(mut self, size_metric: IntGauge) -> Self {
+        self.size_metric = size_metric;
+        self
+    }
+
+    #[cfg(test)]
+    fn with_requests_metric(mut self, requests_metric: IntCounterVec) -> Self {
+        self.requests_metric = requests_metric;
+        self
+    }
+
+    pub fn with_max_age(mut self, max_age: Option<Duration>) -> Self {
+        self.max_age = max_age;
+        self
+    }
+
+    /// Hashsums of the fetched compilers of the `language` are recorded into an index file
+    /// under the shared `root`, which is updated after every fetch. Compilers loaded
+    /// by [`DownloadCache::load_from_dir`] retain the recorded hashsums while their files
+    /// are not modified, so that they are not read and hashed again during revalidation.
+    /// The number of cached compilers is reported with the `language` label as well.
+    pub fn with_index(mut self, root: &Path, language: Language) -> Self {
+        self.index_file = Some(language.compilers_dir(root).join(INDEX_FILE_NAME));
+        self.size_metric = metrics::cached_compilers(language);
+        self
+    }
+
+    /// After a fetch, the folders of the least recently used versions are removed
+    /// until the compilers occupy no more than `max_bytes` on disk.
+    /// The version just fetched, versions being read at the moment and versions
+    /// with alive [`CompilerHandle`]s are never removed.
+    pub fn with_max_bytes(mut self, max_bytes: Option<u64>) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    /// Fetches not completed within `fetch_timeout` fail with [`FetchError::Timeout`].
+    /// Already cached compilers are returned regardless of the timeout.
+    pub fn with_fetch_timeout(mut self, fetch_timeout: Option<Duration>) -> Self {
+        self.fetch_timeout = fetch_timeout;
+        self
+    }
+
+    /// Versions the fetcher has not found fail with [`FetchError::NotFound`] without
+    /// being fetched again for `not_found_ttl`, unless the fetcher refreshes its versions
+    /// successfully in the meantime. A zero `not_found_ttl` disables the caching.
+    pub fn with_not_found_ttl(mut self, not_found_ttl: Duration) -> Self {
+        self.not_found_ttl = not_found_ttl;
+        self
+    }
+
+    /// Fetched compilers failing to report the requested version are removed
+    /// and [`FetchError::InvalidBinary`] is returned instead. Only compilers
+    /// downloaded by the service are checked, see [`Fetcher::downloads`].
+    pub fn with_binary_check(mut self, check_binaries: bool) -> Self {
+        self.check_binaries = check_binaries;
+        self
+    }
+
+    fn lease(&self, ver: &Version) -> Arc<()> {
+        let mut leases = self.leases.lock();
+        Arc::clone(leases.entry(ver.clone()).or_default())
+    }
+
+    /// Whether any [`CompilerHandle`] of the version is alive.
+    fn is_in_use(&self, ver: &Version) -> bool {
+        self.leases
+            .lock()
+            .get(ver)
+            .map_or(false, |lease| Arc::strong_count(lease) > 1)
+    }
+
+    fn entry(&self, ver: &Version) -> Entry {
+        let mut cache = self.cache.lock();
+        Arc::clone(cache.entry(ver.clone()).or_default())
+    }
+
+    async fn try_get_cached(&self, ver: &Version) -> Option<CachedCompiler> {
         let entry = {
             let cache = self.cache.lock();
             cache.get(ver).cloned()
@@ -29,17 +247,254 @@ impl DownloadCache {
             None => None,
         }
     }
+
+    /// Sets the size metric to the number of cached compilers.
+    /// Compilers being fetched at the moment are not counted.
+    fn update_size_metric(&self) {
+        let cached = self
+            .cache
+            .lock()
+            .values()
+            .filter(|entry| matches!(entry.try_read(), Ok(cached) if cached.is_some()))
+            .count();
+        self.size_metric.set(cached as i64);
+    }
+
+    /// Returns the cached compiler without waiting for the entry,
+    /// so compilers being fetched or revalidated at the moment are not returned.
+    fn try_get_present(&self, ver: &Version) -> Option<CachedCompiler> {
+        let entry = self.cache.lock().get(ver).cloned()?;
+        let cached = entry.try_read().ok()?;
+        cached.as_ref().cloned()
+    }
+
+    /// Whether the compiler of the version is present, so that it would be returned
+    /// without being fetched (unless it has to be revalidated).
+    pub fn is_cached(&self, ver: &Version) -> bool {
+        self.try_get_present(ver).is_some()
+    }
+
+    #[cfg(test)]
+    async fn try_get(&self, ver: &Version) -> Option<PathBuf> {
+        self.try_get_cached(ver).await.map(|cached| cached.path)
+    }
+
+    /// Whether the fetcher has not found the version within the `not_found_ttl`
+    /// and has not refreshed its versions since then. Outdated records are removed.
+    fn is_known_missing<D: Fetcher + ?Sized>(&self, fetcher: &D, ver: &Version) -> bool {
+        let mut not_found = self.not_found.lock();
+        let missed_at = match not_found.get(ver) {
+            Some(missed_at) => *missed_at,
+            None => return false,
+        };
+        let refreshed = matches!(
+            fetcher.last_refresh(),
+            Some((refreshed_at, Ok(()))) if refreshed_at > missed_at
+        );
+        if refreshed || missed_at.elapsed() >= self.not_found_ttl {
+            not_found.remove(ver);
+            return false;
+        }
+        true
+    }
+
+    /// Remembers the version as not found, keeping at most [`MAX_NOT_FOUND`] records:
+    /// outdated records are removed first, then the oldest ones.
+    fn record_not_found(&self, ver: &Version) {
+        let mut not_found = self.not_found.lock();
+        if not_found.len() >= MAX_NOT_FOUND {
+            not_found.retain(|_, missed_at| missed_at.elapsed() < self.not_found_ttl);
+        }
+        if not_found.len() >= MAX_NOT_FOUND {
+            let oldest = not_found
+                .iter()
+                .min_by_key(|(_, missed_at)| **missed_at)
+                .map(|(ver, _)| ver.clone());
+            if let Some(oldest) = oldest {
+                not_found.remove(&oldest);
+            }
+        }
+        not_found.insert(ver.clone(), Instant::now());
+    }
+
+    fn is_expired(&self, cached: &CachedCompiler) -> bool {
+        match self.max_age {
+            Some(max_age) => cached
+                .validated_at
+                .elapsed()
+                .map_or(true, |age| age >= max_age),
+            None => false,
+        }
+    }
 }
 
 impl DownloadCache {
+    /// Returns the compiler of the version, fetching it if not cached.
+    /// The compiler is not evicted until the returned handle is dropped.
     pub async fn get<D: Fetcher + ?Sized>(
         &self,
         fetcher: &D,
         ver: &Version,
+    ) -> Result<CompilerHandle, FetchError> {
+        // the lease is taken before the compiler is looked up,
+        // so that it could not be evicted in between
+        let lease = self.lease(ver);
+        self.last_access.lock().insert(ver.clone(), Instant::now());
+        // awaiting a concurrent fetch is not a hit, as the file was not present
+        let cached = match self.try_get_present(ver) {
+            Some(cached) => {
+                self.requests_metric.with_label_values(&["hit"]).inc();
+                Some(cached)
+            }
+            None => {
+                self.requests_metric.with_label_values(&["miss"]).inc();
+                self.try_get_cached(ver).await
+            }
+        };
+        let handle = |path| CompilerHandle {
+            path,
+            _lease: lease,
+        };
+        let path = match cached {
+            Some(cached) if !self.is_expired(&cached) => return Ok(handle(cached.path)),
+            Some(_) => self.revalidate(fetcher, ver).await?,
+            None => self.fetch(fetcher, ver).await?,
+        };
+        self.evict(ver).await;
+        self.write_index();
+        self.update_size_metric();
+        Ok(handle(path))
+    }
+
+    /// Removes the least recently used compilers except `fetched`
+    /// while the compilers occupy more than `max_bytes` on disk.
+    async fn evict(&self, fetched: &Version) {
+        let max_bytes = match self.max_bytes {
+            Some(max_bytes) => max_bytes,
+            None => return,
+        };
+        let entries: Vec<_> = {
+            let cache = self.cache.lock();
+            cache
+                .iter()
+                .map(|(ver, entry)| (ver.clone(), Arc::clone(entry)))
+                .collect()
+        };
+        let mut total = 0;
+        let mut candidates = Vec::new();
+        for (ver, entry) in entries {
+            // entries locked for writing are being fetched and have no size yet
+            let path = match entry.try_read() {
+                Ok(cached) => match cached.as_ref() {
+                    Some(cached) => cached.path.clone(),
+                    None => continue,
+                },
+                Err(_) => continue,
+            };
+            total += path.parent().map(dir_size).unwrap_or_default();
+            if &ver != fetched {
+                let last_access = self.last_access.lock().get(&ver).copied();
+                candidates.push((last_access, ver, entry));
+            }
+        }
+        // never accessed versions (loaded from disk on startup) go first
+        candidates.sort_by_key(|(last_access, _, _)| *last_access);
+
+        for (_, ver, entry) in candidates {
+            if total <= max_bytes {
+                break;
+            }
+            // the compiler is being read at the moment
+            let mut cached = match entry.try_write() {
+                Ok(cached) => cached,
+                Err(_) => continue,
+            };
+            // checked while the entry is locked, as new handles are only
+            // returned after the entry is read
+            if self.is_in_use(&ver) {
+                continue;
+            }
+            let folder = match cached.as_ref().and_then(|cached| cached.path.parent()) {
+                Some(folder) => folder.to_path_buf(),
+                None => continue,
+            };
+            let size = dir_size(&folder);
+            log::info!(target: "compiler_cache", "evicting compiler version {} ({} bytes)", ver, size);
+            if let Err(err) = std::fs::remove_dir_all(&folder) {
+                log::warn!(target: "compiler_cache", "cannot remove compiler folder {:?}: {}", folder, err);
+                continue;
+            }
+            *cached = None;
+            total = total.saturating_sub(size);
+            self.cache.lock().remove(&ver);
+            self.last_access.lock().remove(&ver);
+        }
+    }
+
+    /// Compares the cached compiler with the hashsum currently published by the fetcher
+    /// and downloads the compiler again if the hashsum has changed.
+    /// The cached compiler is kept if the published hashsum could not be obtained,
+    /// and is checked again only once it expires again.
+    async fn revalidate<D: Fetcher + ?Sized>(
+        &self,
+        fetcher: &D,
+        ver: &Version,
     ) -> Result<PathBuf, FetchError> {
-        match self.try_get(ver).await {
-            Some(file) => Ok(file),
-            None => self.fetch(fetcher, ver).await,
+        let lock = self.entry(ver);
+        let mut entry = lock.write().await;
+        let cached = match entry.as_ref() {
+            Some(cached) if !self.is_expired(cached) => return Ok(cached.path.clone()),
+            Some(cached) => cached.clone(),
+            None => {
+                let fetched = self.fetch_checked(fetcher, ver).await?;
+                let file = fetched.path.clone();
+                *entry = Some(fetched);
+                return Ok(file);
+            }
+        };
+
+        let published = match fetcher.published_hash(ver).await {
+            Ok(Some(published)) => published,
+            Ok(None) => {
+                *entry = Some(cached.revalidated());
+                return Ok(cached.path);
+            }
+            Err(err) => {
+                log::warn!(target: "compiler_cache", "cannot get published hashsum of version {}, using cached compiler: {}", ver, err);
+                *entry = Some(cached.revalidated());
+                return Ok(cached.path);
+            }
+        };
+        let check_result = match cached.sha256 {
+            // the file is not read again if its hashsum has been recorded
+            Some(recorded) if recorded == published => Ok(()),
+            Some(recorded) => Err(Mismatch::new(published, recorded)),
+            None => {
+                let path = cached.path.clone();
+                tokio::task::spawn_blocking(move || {
+                    std::fs::read(path)
+                        .map(|bytes| check_hashsum(&bytes.into(), published, ChecksumKind::Sha256))
+                })
+                .await??
+            }
+        };
+        match check_result {
+            Ok(()) => {
+                *entry = Some(cached.revalidated());
+                Ok(cached.path)
+            }
+            Err(mismatch) => {
+                log::error!(
+                    target: "compiler_cache",
+                    "PUBLISHED HASHSUM OF COMPILER VERSION {} HAS CHANGED, the compiler was re-published upstream: {}; downloading it again",
+                    ver,
+                    mismatch
+                );
+                let fetched = self.fetch_checked(fetcher, ver).await?;
+                let file = fetched.path.clone();
+                *entry = Some(fetched);
+                Ok(file)
+            }
         }
     }
 
@@ -48,35 +503,270 @@ impl DownloadCache {
         fetcher: &D,
         ver: &Version,
     ) -> Result<PathBuf, FetchError> {
-        let lock = {
-            let mut cache = self.cache.lock();
-            Arc::clone(cache.entry(ver.clone()).or_default())
-        };
+        if self.is_known_missing(fetcher, ver) {
+            return Err(FetchError::NotFound(ver.clone()));
+        }
+        let lock = self.entry(ver);
         let mut entry = lock.write().await;
         match entry.as_ref() {
-            Some(file) => Ok(file.clone()),
+            Some(cached) => Ok(cached.path.clone()),
             None => {
                 log::info!(target: "compiler_cache", "installing file version {}", ver);
-                let file = fetcher.fetch(ver).await?;
-                *entry = Some(file.clone());
+                let fetched = match self.fetch_checked(fetcher, ver).await {
+                    Err(FetchError::NotFound(ver)) if !self.not_found_ttl.is_zero() => {
+                        self.record_not_found(&ver);
+                        return Err(FetchError::NotFound(ver));
+                    }
+                    result => result?,
+                };
+                self.not_found.lock().remove(ver);
+                let file = fetched.path.clone();
+                *entry = Some(fetched);
                 Ok(file)
             }
         }
     }
+
+    /// Fetches the compiler and, if binary checks are enabled, makes sure the downloaded
+    /// compiler runs and reports the requested version. Compilers failing the check are removed.
+    async fn fetch_checked<D: Fetcher + ?Sized>(
+        &self,
+        fetcher: &D,
+        ver: &Version,
+    ) -> Result<CachedCompiler, FetchError> {
+        let file = match self.fetch_timeout {
+            Some(fetch_timeout) => tokio::time::timeout(fetch_timeout, fetcher.fetch(ver))
+                .await
+                .map_err(|_| FetchError::Timeout(ver.clone()))??,
+            None => fetcher.fetch(ver).await?,
+        };
+        if self.check_binaries && fetcher.downloads() {
+            self.check_binary(&file, ver).await?;
+        }
+        // the hashsum is recorded only to be persisted in the index
+        let sha256 = match self.index_file {
+            Some(_) => {
+                let path = file.clone();
+                Some(tokio::task::spawn_blocking(move || file_hash(&path)).await??)
+            }
+            None => None,
+        };
+        Ok(CachedCompiler {
+            path: file,
+            validated_at: SystemTime::now(),
+            sha256,
+        })
+    }
+
+    /// Removes the compiler failing the binary check.
+    async fn check_binary(&self, file: &Path, ver: &Version) -> Result<(), FetchError> {
+        if let Err(reason) = check_binary(file, ver).await {
+            log::error!(target: "compiler_cache", "fetched compiler {:?} of version {} is invalid, removing it: {}", file, ver, reason);
+            if let Err(err) = std::fs::remove_file(file) {
+                log::warn!(target: "compiler_cache", "cannot remove invalid compiler {:?}: {}", file, err);
+            }
+            return Err(FetchError::InvalidBinary(ver.clone(), reason));
+        }
+        Ok(())
+    }
+
+    /// Persists cached compilers with recorded hashsums. Compilers being fetched are skipped.
+    fn write_index(&self) {
+        let index_file = match &self.index_file {
+            Some(index_file) => index_file,
+            None => return,
+        };
+        let entries: Vec<_> = {
+            let cache = self.cache.lock();
+            cache
+                .iter()
+                .map(|(ver, entry)| (ver.clone(), Arc::clone(entry)))
+                .collect()
+        };
+        let mut index = BTreeMap::new();
+        for (ver, entry) in entries {
+            let cached = match entry.try_read() {
+                Ok(cached) => cached.clone(),
+                Err(_) => continue,
+            };
+            let (cached, sha256) =
+                match cached.and_then(|cached| cached.sha256.map(|sha256| (cached, sha256))) {
+                    Some(indexed) => indexed,
+                    None => continue,
+                };
+            let metadata = std::fs::metadata(&cached.path);
+            if let Ok((size, Ok(modified))) = metadata.map(|m| (m.len(), m.modified())) {
+                let entry = IndexEntry {
+                    path: cached.path,
+                    size,
+                    modified,
+                    sha256,
+                    validated_at: cached.validated_at,
+                };
+                index.insert(ver.to_string(), entry);
+            }
+        }
+        let temp_file = index_file.with_extension("json.tmp");
+        let result = serde_json::to_vec_pretty(&index)
+            .map_err(std::io::Error::from)
+            .and_then(|content| {
+                if let Some(dir) = index_file.parent() {
+                    std::fs::create_dir_all(dir)?;
+                }
+                std::fs::write(&temp_file, content)?;
+                std::fs::rename(&temp_file, index_file)
+            });
+        if let Err(err) = result {
+            log::warn!(target: "compiler_cache", "cannot write compilers index {:?}: {}", index_file, err);
+        }
+    }
+
+    /// Reads the persisted index. Missing or invalid index is treated as empty.
+    fn read_index(&self) -> HashMap<String, IndexEntry> {
+        let index_file = match &self.index_file {
+            Some(index_file) => index_file,
+            None => return HashMap::new(),
+        };
+        let content = match std::fs::read(index_file) {
+            Ok(content) => content,
+            Err(err) => {
+                log::info!(target: "compiler_cache", "compilers index {:?} is not loaded: {}", index_file, err);
+                return HashMap::new();
+            }
+        };
+        serde_json::from_slice(&content).unwrap_or_else(|err| {
+            log::warn!(target: "compiler_cache", "compilers index {:?} is invalid, ignoring it: {}", index_file, err);
+            HashMap::new()
+        })
+    }
+}
+
+fn file_hash(path: &Path) -> std::io::Result<H256> {
+    let bytes = std::fs::read(path)?;
+    Ok(H256::from_slice(&Sha256::digest(bytes)))
+}
+
+/// Total size of the files directly inside the `dir`.
+fn dir_size(dir: &Path) -> u64 {
+    std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok()?.metadata().ok())
+                .filter(|metadata| metadata.is_file())
+                .map(|metadata| metadata.len())
+                .sum()
+        })
+        .unwrap_or_default()
+}
+
+/// Compilers not reporting their version within this time are considered invalid.
+const BINARY_CHECK_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Runs the compiler with `--version` and checks that the reported version
+/// (e.g. "Version: 0.8.14+commit.80d49f37.Linux.g++") matches `ver`.
+async fn check_binary(path: &Path, ver: &Version) -> Result<(), String> {
+    let output = tokio::process::Command::new(path)
+        .arg("--version")
+        .kill_on_drop(true)
+        .output();
+    let output = tokio::time::timeout(BINARY_CHECK_TIMEOUT, output)
+        .await
+        .map_err(|_| "the compiler has not reported its version in time".to_string())?
+        .map_err(|err| format!("cannot run the compiler: {}", err))?;
+    if !output.status.success() {
+        return Err(format!("the compiler exited with {}", output.status));
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let expected = ver.to_string();
+    let expected = expected.trim_start_matches('v');
+    let reported = stdout
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("Version:"))
+        .map(str::trim)
+        .ok_or_else(|| format!("the compiler has not reported its version: {:?}", stdout))?;
+    // the version is followed by the platform, e.g. ".Linux.g++"
+    match reported.strip_prefix(expected) {
+        Some(rest) if rest.is_empty() || rest.starts_with('.') => Ok(()),
+        _ => Err(format!("the compiler reported version {}", reported)),
+    }
 }
 
 impl DownloadCache {
-    pub async fn load_from_dir(&self, dir: &PathBuf) -> std::io::Result<()> {
+    /// Loads compilers of the `language` located under the shared `root` folder.
+    pub async fn load_from_dir(&self, root: &Path, language: Language) -> std::io::Result<()> {
+        let versions = DownloadCache::find_versions_in_dir(&language.compilers_dir(root))?;
+        let index = self.read_index();
+        self.add_versions(versions, index, language).await
+    }
+
+    /// Checks the loaded compilers without recorded hashsums against the hashsums
+    /// published by the fetcher, so that local compilers are validated regardless of
+    /// the fetcher they were downloaded with. Mismatching compilers are not used,
+    /// and the hashsums of the matching ones are recorded into the index.
+    pub async fn validate_loaded<D: Fetcher + ?Sized>(&self, fetcher: &D) {
+        let entries: Vec<_> = {
+            let cache = self.cache.lock();
+            cache
+                .iter()
+                .map(|(ver, entry)| (ver.clone(), Arc::clone(entry)))
+                .collect()
+        };
+        let checks = entries.into_iter().map(|(ver, entry)| async move {
+            let mut cached = entry.write().await;
+            let path = match cached.as_ref() {
+                Some(loaded) if loaded.sha256.is_none() => loaded.path.clone(),
+                _ => return,
+            };
+            let published = match fetcher.published_hash(&ver).await {
+                Ok(Some(published)) => published,
+                Ok(None) | Err(FetchError::NotFound(_)) => return,
+                Err(err) => {
+                    log::warn!(target: "compiler_cache", "cannot get published hashsum of local compiler version {}: {}", ver, err);
+                    return;
+                }
+            };
+            let found = {
+                let path = path.clone();
+                tokio::task::spawn_blocking(move || file_hash(&path)).await
+            };
+            match found {
+                Ok(Ok(found)) if found == published => {
+                    *cached = Some(CachedCompiler {
+                        path,
+                        validated_at: SystemTime::now(),
+                        sha256: Some(found),
+                    });
+                }
+                Ok(Ok(found)) => {
+                    log::warn!(
+                        target: "compiler_cache",
+                        "local compiler version {} does not match the published hashsum, skipping it: {}",
+                        ver,
+                        Mismatch::new(published, found)
+                    );
+                    *cached = None;
+                }
+                Ok(Err(err)) => {
+                    log::warn!(target: "compiler_cache", "cannot read local compiler {:?}: {}", path, err)
+                }
+                Err(err) => {
+                    log::warn!(target: "compiler_cache", "cannot hash local compiler {:?}: {}", path, err)
+                }
+            }
+        });
+        futures::future::join_all(checks).await;
+        self.write_index();
+        self.update_size_metric();
+    }
+
+    /// Returns the folders inside `dir` named after compiler versions.
+    pub(super) fn find_versions_in_dir(dir: &Path) -> std::io::Result<HashMap<Version, PathBuf>> {
         let paths = DownloadCache::read_dir_paths(dir)?;
-        let versions = DownloadCache::filter_versions(paths);
-        self.add_versions(versions).await;
-        Ok(())
+        Ok(DownloadCache::filter_versions(paths))
     }
 
-    fn read_dir_paths(dir: &PathBuf) -> std::io::Result<impl Iterator<Item = PathBuf>> {
-        let paths = std::fs::read_dir(dir)?
-            .into_iter()
-            .filter_map(|r| r.ok().map(|e| e.path()));
+    fn read_dir_paths(dir: &Path) -> std::io::Result<impl Iterator<Item = PathBuf>> {
+        let paths = std::fs::read_dir(dir)?.filter_map(|r| r.ok().map(|e| e.path()));
         Ok(paths)
     }
 
@@ -85,33 +775,114 @@ impl DownloadCache {
             path.file_name()
                 .and_then(|n| n.to_str())
                 .map(String::from)
-                .and_then(|n| Version::from_str(&n).ok())
+                .and_then(|n| Version::from_path_segment(&n).ok())
                 .map(|v| (v, path))
         })
         .collect()
     }
 
-    async fn add_versions(&self, versions: HashMap<Version, PathBuf>) {
-        for (version, path) in versions {
-            let solc_path = path.join("solc");
-            if solc_path.exists() {
-                log::info!("found local compiler version {}", version);
-                let lock = {
-                    let mut cache = self.cache.lock();
-                    Arc::clone(cache.entry(version.clone()).or_default())
+    /// Loads the found versions concurrently, returning the first IO error
+    /// after all other versions have been loaded.
+    async fn add_versions(
+        &self,
+        versions: HashMap<Version, PathBuf>,
+        mut index: HashMap<String, IndexEntry>,
+        language: Language,
+    ) -> std::io::Result<()> {
+        let loads = versions.into_iter().map(|(version, path)| {
+            let indexed = index.remove(&version.to_string());
+            async move {
+                let loaded = {
+                    let version = version.clone();
+                    tokio::task::spawn_blocking(move || {
+                        load_version(&version, &path, indexed, language)
+                    })
+                    .await
+                    .map_err(std::io::Error::other)?
                 };
-                *lock.write().await = Some(solc_path);
-            } else {
-                log::warn!(
-                    "found verions {} but file {:?} doesn't exists",
-                    version,
-                    solc_path
-                );
+                loaded.map(|cached| (version, cached))
+            }
+        });
+        let mut result = Ok(());
+        for loaded in futures::future::join_all(loads).await {
+            match loaded {
+                Ok((version, Some(cached))) => {
+                    *self.entry(&version).write().await = Some(cached);
+                }
+                Ok((_, None)) => {}
+                Err(err) => {
+                    log::error!("cannot load local compiler: {}", err);
+                    if result.is_ok() {
+                        result = Err(err);
+                    }
+                }
             }
         }
+        self.update_size_metric();
+        result
     }
 }
 
+/// Returns the compiler of the `version` found in the `path` folder, if it could be used.
+/// Compilers modified since they were indexed are checked against the recorded hashsums.
+fn load_version(
+    version: &Version,
+    path: &Path,
+    indexed: Option<IndexEntry>,
+    language: Language,
+) -> std::io::Result<Option<CachedCompiler>> {
+    let solc_path = path.join(language.executable_name());
+    let indexed = indexed.filter(|entry| entry.path == solc_path);
+    if !solc_path.exists() {
+        if path.is_dir() {
+            // Most probably the folder is left after an interrupted download
+            log::warn!(
+                "found version {} but file {:?} doesn't exist, removing incomplete folder",
+                version,
+                solc_path
+            );
+            if let Err(err) = std::fs::remove_dir_all(path) {
+                log::warn!("cannot remove incomplete folder {:?}: {}", path, err);
+            }
+        } else {
+            log::warn!("found version {} but {:?} is not a folder", version, path);
+        }
+        return Ok(None);
+    }
+    let entry = match indexed {
+        Some(entry) => entry,
+        None => {
+            log::info!("found local compiler version {}", version);
+            // the age of local compilers is counted from the moment they were written
+            let validated_at = std::fs::metadata(&solc_path)
+                .and_then(|metadata| metadata.modified())
+                .unwrap_or(SystemTime::UNIX_EPOCH);
+            return Ok(Some(CachedCompiler {
+                path: solc_path,
+                validated_at,
+                sha256: None,
+            }));
+        }
+    };
+    if !entry.is_unmodified() {
+        let found = file_hash(&solc_path)?;
+        if found != entry.sha256 {
+            log::warn!(
+                "local compiler version {} does not match the recorded hashsum, skipping it: {}",
+                version,
+                Mismatch::new(entry.sha256, found)
+            );
+            return Ok(None);
+        }
+    }
+    log::info!("found indexed local compiler version {}", version);
+    Ok(Some(CachedCompiler {
+        path: solc_path,
+        validated_at: entry.validated_at,
+        sha256: Some(entry.sha256),
+    }))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -122,16 +893,525 @@ mod tests {
     use async_trait::async_trait;
     use futures::{executor::block_on, join, pin_mut};
     use pretty_assertions::assert_eq;
-    use std::{collections::HashSet, env::temp_dir, time::Duration};
+    use std::{collections::HashSet, env::temp_dir, str::FromStr, time::Duration};
     use tokio::{spawn, task::yield_now, time::timeout};
 
     fn new_version(major: u64) -> Version {
         Version::Release(ReleaseVersion {
+            vendor: None,
             version: semver::Version::new(major, 0, 0),
             commit: [0, 1, 2, 3],
         })
     }
 
+    /// Fetcher writing a stub compiler, which reports the `reported` version
+    struct StubFetcher {
+        dir: PathBuf,
+        reported: &'static str,
+        downloads: bool,
+    }
+
+    #[async_trait]
+    impl Fetcher for StubFetcher {
+        async fn fetch(&self, ver: &Version) -> Result<PathBuf, FetchError> {
+            use std::os::unix::fs::PermissionsExt;
+            let path = self.dir.join(ver.to_path_segment());
+            std::fs::write(
+                &path,
+                format!(
+                    "#!/bin/sh\necho 'solc, the solidity compiler commandline interface'\necho 'Version: {}'\n",
+                    self.reported
+                ),
+            )?;
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755))?;
+            Ok(path)
+        }
+
+        fn all_versions(&self) -> Vec<Version> {
+            vec![]
+        }
+
+        fn downloads(&self) -> bool {
+            self.downloads
+        }
+    }
+
+    fn stub_dir(name: &str) -> PathBuf {
+        let dir = temp_dir().join(format!(
+            "blockscout/verification/compiler_cache/test/{}_{}",
+            name,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[tokio::test]
+    async fn evicts_least_recently_used() {
+        /// Writes fake compilers of 10 bytes into version folders
+        struct FakeFetcher {
+            dir: PathBuf,
+        }
+
+        #[async_trait]
+        impl Fetcher for FakeFetcher {
+            async fn fetch(&self, ver: &Version) -> Result<PathBuf, FetchError> {
+                let folder = self.dir.join(ver.to_path_segment());
+                std::fs::create_dir_all(&folder)?;
+                let path = folder.join("solc");
+                std::fs::write(&path, [0u8; 10])?;
+                Ok(path)
+            }
+
+            fn all_versions(&self) -> Vec<Version> {
+                vec![]
+            }
+        }
+
+        let fetcher = FakeFetcher {
+            dir: stub_dir("eviction"),
+        };
+        let folder = |ver: &Version| fetcher.dir.join(ver.to_path_segment());
+        let size_metric = IntGauge::new("cached_compilers", "test").unwrap();
+        let cache = DownloadCache::new()
+            .with_max_bytes(Some(25))
+            .with_size_metric(size_metric.clone());
+        let vers: Vec<_> = (1..=4).map(new_version).collect();
+
+        cache.get(&fetcher, &vers[0]).await.unwrap();
+        cache.get(&fetcher, &vers[1]).await.unwrap();
+        assert_eq!(size_metric.get(), 2);
+        cache.get(&fetcher, &vers[2]).await.unwrap();
+        // evicted versions are not counted
+        assert_eq!(size_metric.get(), 2);
+        assert!(
+            !folder(&vers[0]).exists(),
+            "oldest version should be evicted"
+        );
+        assert_eq!(cache.try_get(&vers[0]).await, None);
+        assert!(folder(&vers[1]).exists());
+        assert!(folder(&vers[2]).exists());
+
+        // the accessed version becomes the most recently used one
+        cache.get(&fetcher, &vers[1]).await.unwrap();
+        cache.get(&fetcher, &vers[3]).await.unwrap();
+        assert!(!folder(&vers[2]).exists());
+        assert!(folder(&vers[1]).exists());
+        assert!(folder(&vers[3]).exists());
+
+        {
+            // versions being read are not evicted, even if they are the least recently used
+            let lock = cache.entry(&vers[1]);
+            let _reading = lock.read().await;
+            cache.get(&fetcher, &vers[0]).await.unwrap();
+        }
+        assert!(folder(&vers[1]).exists());
+        assert!(!folder(&vers[3]).exists());
+        assert!(folder(&vers[0]).exists());
+
+        // compilers in use are not evicted until their handles are dropped
+        let compiler = cache.get(&fetcher, &vers[0]).await.unwrap();
+        cache.get(&fetcher, &vers[1]).await.unwrap();
+        cache.get(&fetcher, &vers[2]).await.unwrap();
+        assert!(compiler.exists(), "compiler in use should not be evicted");
+        assert!(!folder(&vers[1]).exists());
+        std::mem::drop(compiler);
+        cache.get(&fetcher, &vers[3]).await.unwrap();
+        assert!(!folder(&vers[0]).exists());
+        assert!(folder(&vers[2]).exists());
+        assert!(folder(&vers[3]).exists());
+    }
+
+    #[tokio::test]
+    async fn index_survives_restart() {
+        struct FakeFetcher {
+            root: PathBuf,
+        }
+
+        #[async_trait]
+        impl Fetcher for FakeFetcher {
+            async fn fetch(&self, ver: &Version) -> Result<PathBuf, FetchError> {
+                let folder = Language::Solidity.version_dir(&self.root, ver);
+                std::fs::create_dir_all(&folder)?;
+                let path = folder.join("solc");
+                std::fs::write(&path, "compiler")?;
+                Ok(path)
+            }
+
+            fn all_versions(&self) -> Vec<Version> {
+                vec![]
+            }
+        }
+
+        let fetcher = FakeFetcher {
+            root: stub_dir("index"),
+        };
+        let ver = new_version(1);
+        let new_cache = || DownloadCache::new().with_index(&fetcher.root, Language::Solidity);
+
+        let cache = new_cache();
+        let path = cache.get(&fetcher, &ver).await.unwrap();
+        let fetched = cache.try_get_cached(&ver).await.unwrap();
+        assert_eq!(fetched.sha256, Some(file_hash(&path).unwrap()));
+
+        // the recorded hashsum and validation time are restored
+        let cache = new_cache();
+        cache
+            .load_from_dir(&fetcher.root, Language::Solidity)
+            .await
+            .unwrap();
+        let loaded = cache.try_get_cached(&ver).await.unwrap();
+        assert_eq!(loaded.path, path);
+        assert_eq!(loaded.sha256, fetched.sha256);
+        assert_eq!(loaded.validated_at, fetched.validated_at);
+
+        // modified files not matching the recorded hashsum are skipped
+        std::fs::write(&path, "modified compiler").unwrap();
+        let cache = new_cache();
+        cache
+            .load_from_dir(&fetcher.root, Language::Solidity)
+            .await
+            .unwrap();
+        assert_eq!(cache.try_get(&ver).await, None);
+
+        // the directory scan is authoritative if the index is missing
+        std::fs::remove_file(
+            Language::Solidity
+                .compilers_dir(&fetcher.root)
+                .join(INDEX_FILE_NAME),
+        )
+        .unwrap();
+        let cache = new_cache();
+        cache
+            .load_from_dir(&fetcher.root, Language::Solidity)
+            .await
+            .unwrap();
+        assert_eq!(cache.try_get(&ver).await, Some(path));
+    }
+
+    #[tokio::test]
+    async fn verifies_modified_compilers() {
+        let root = stub_dir("verify_modified");
+        let good = new_version(1);
+        let mismatched = new_version(2);
+        let mut index = BTreeMap::new();
+        for (ver, recorded) in [(&good, "compiler"), (&mismatched, "another compiler")] {
+            let folder = Language::Solidity.version_dir(&root, ver);
+            std::fs::create_dir_all(&folder).unwrap();
+            let path = folder.join("solc");
+            std::fs::write(&path, "compiler").unwrap();
+            let entry = IndexEntry {
+                path,
+                size: recorded.len() as u64,
+                // recorded before the files were written, so both hashsums are checked
+                modified: SystemTime::UNIX_EPOCH,
+                sha256: H256::from_slice(&Sha256::digest(recorded)),
+                validated_at: SystemTime::UNIX_EPOCH,
+            };
+            index.insert(ver.to_string(), entry);
+        }
+        std::fs::write(
+            Language::Solidity
+                .compilers_dir(&root)
+                .join(INDEX_FILE_NAME),
+            serde_json::to_vec(&index).unwrap(),
+        )
+        .unwrap();
+
+        let cache = DownloadCache::new().with_index(&root, Language::Solidity);
+        cache
+            .load_from_dir(&root, Language::Solidity)
+            .await
+            .unwrap();
+        let loaded = cache.try_get_cached(&good).await.expect("good compiler");
+        assert_eq!(loaded.validated_at, SystemTime::UNIX_EPOCH);
+        assert_eq!(cache.try_get(&mismatched).await, None);
+        assert_eq!(cache.cache.lock().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn validates_loaded_compilers() {
+        struct PublishingFetcher {
+            published: HashMap<Version, H256>,
+        }
+
+        #[async_trait]
+        impl Fetcher for PublishingFetcher {
+            async fn fetch(&self, ver: &Version) -> Result<PathBuf, FetchError> {
+                Err(FetchError::NotFound(ver.clone()))
+            }
+
+            fn all_versions(&self) -> Vec<Version> {
+                self.published.keys().cloned().collect()
+            }
+
+            async fn published_hash(&self, ver: &Version) -> Result<Option<H256>, FetchError> {
+                self.published
+                    .get(ver)
+                    .copied()
+                    .map(Some)
+                    .ok_or_else(|| FetchError::NotFound(ver.clone()))
+            }
+        }
+
+        let root = stub_dir("validate_loaded");
+        let (valid, mismatched, unpublished) = (new_version(1), new_version(2), new_version(3));
+        for ver in [&valid, &mismatched, &unpublished] {
+            let folder = Language::Solidity.version_dir(&root, ver);
+            std::fs::create_dir_all(&folder).unwrap();
+            std::fs::write(folder.join("solc"), "compiler").unwrap();
+        }
+        let fetcher = PublishingFetcher {
+            published: HashMap::from([
+                (valid.clone(), H256::from_slice(&Sha256::digest("compiler"))),
+                (
+                    mismatched.clone(),
+                    H256::from_slice(&Sha256::digest("other")),
+                ),
+            ]),
+        };
+
+        let cache = DownloadCache::new().with_index(&root, Language::Solidity);
+        cache
+            .load_from_dir(&root, Language::Solidity)
+            .await
+            .unwrap();
+        cache.validate_loaded(&fetcher).await;
+        assert_eq!(
+            cache.try_get_cached(&valid).await.unwrap().sha256,
+            fetcher.published.get(&valid).copied()
+        );
+        assert_eq!(cache.try_get(&mismatched).await, None);
+        assert_eq!(
+            cache.try_get_cached(&unpublished).await.unwrap().sha256,
+            None
+        );
+
+        // the validated compiler is indexed
+        let cache = DownloadCache::new().with_index(&root, Language::Solidity);
+        cache
+            .load_from_dir(&root, Language::Solidity)
+            .await
+            .unwrap();
+        assert!(cache.try_get_cached(&valid).await.unwrap().sha256.is_some());
+    }
+
+    #[tokio::test]
+    async fn fetch_times_out() {
+        struct MockBlockingFetcher;
+
+        #[async_trait]
+        impl Fetcher for MockBlockingFetcher {
+            async fn fetch(&self, _ver: &Version) -> Result<PathBuf, FetchError> {
+                std::future::pending().await
+            }
+
+            fn all_versions(&self) -> Vec<Version> {
+                vec![]
+            }
+        }
+
+        let fetch_timeout = Duration::from_millis(100);
+        let cache = DownloadCache::new().with_fetch_timeout(Some(fetch_timeout));
+        let ver = new_version(1);
+        let started = std::time::Instant::now();
+        let err = timeout(
+            Duration::from_secs(10),
+            cache.get(&MockBlockingFetcher, &ver),
+        )
+        .await
+        .expect("fetch should time out")
+        .expect_err("fetch should fail");
+        assert!(
+            matches!(&err, FetchError::Timeout(v) if v == &ver),
+            "{}",
+            err
+        );
+        assert!(started.elapsed() >= fetch_timeout);
+        assert_eq!(cache.try_get(&ver).await, None);
+
+        // cached versions are returned without fetching
+        let cached = new_version(2);
+        *cache.entry(&cached).write().await = Some(CachedCompiler::new("cached".into()));
+        assert_eq!(
+            cache
+                .get(&MockBlockingFetcher, &cached)
+                .await
+                .unwrap()
+                .path(),
+            Path::new("cached")
+        );
+    }
+
+    #[tokio::test]
+    async fn caches_missing_versions() {
+        #[derive(Default)]
+        struct MockMissingFetcher {
+            fetches: parking_lot::Mutex<u32>,
+            published: parking_lot::Mutex<bool>,
+            last_refresh: parking_lot::Mutex<Option<Instant>>,
+        }
+
+        #[async_trait]
+        impl Fetcher for MockMissingFetcher {
+            async fn fetch(&self, ver: &Version) -> Result<PathBuf, FetchError> {
+                *self.fetches.lock() += 1;
+                match *self.published.lock() {
+                    true => Ok(PathBuf::from(ver.to_string())),
+                    false => Err(FetchError::NotFound(ver.clone())),
+                }
+            }
+
+            fn all_versions(&self) -> Vec<Version> {
+                vec![]
+            }
+
+            fn last_refresh(&self) -> Option<(Instant, Result<(), String>)> {
+                self.last_refresh.lock().map(|at| (at, Ok(())))
+            }
+        }
+
+        let fetcher = MockMissingFetcher::default();
+        let cache = DownloadCache::new().with_not_found_ttl(Duration::from_secs(60));
+        let ver = new_version(1);
+        for _ in 0..3 {
+            let err = cache
+                .get(&fetcher, &ver)
+                .await
+                .expect_err("version is missing");
+            assert!(
+                matches!(&err, FetchError::NotFound(v) if v == &ver),
+                "{}",
+                err
+            );
+        }
+        assert_eq!(
+            *fetcher.fetches.lock(),
+            1,
+            "missing version should be fetched once"
+        );
+
+        // the version published by a refresh is fetched again before the ttl lapses
+        *fetcher.published.lock() = true;
+        *fetcher.last_refresh.lock() = Some(Instant::now());
+        assert_eq!(
+            cache.get(&fetcher, &ver).await.unwrap().path(),
+            Path::new(&ver.to_string())
+        );
+        assert_eq!(*fetcher.fetches.lock(), 2);
+
+        // the version published otherwise is fetched once the ttl lapses
+        let fetcher = MockMissingFetcher::default();
+        let cache = DownloadCache::new().with_not_found_ttl(Duration::from_millis(100));
+        cache
+            .get(&fetcher, &ver)
+            .await
+            .expect_err("version is missing");
+        *fetcher.published.lock() = true;
+        cache
+            .get(&fetcher, &ver)
+            .await
+            .expect_err("version is cached as missing");
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(
+            cache.get(&fetcher, &ver).await.unwrap().path(),
+            Path::new(&ver.to_string())
+        );
+        assert_eq!(*fetcher.fetches.lock(), 2);
+    }
+
+    #[tokio::test]
+    async fn missing_versions_are_bounded() {
+        struct MissingFetcher;
+
+        #[async_trait]
+        impl Fetcher for MissingFetcher {
+            async fn fetch(&self, ver: &Version) -> Result<PathBuf, FetchError> {
+                Err(FetchError::NotFound(ver.clone()))
+            }
+
+            fn all_versions(&self) -> Vec<Version> {
+                vec![]
+            }
+        }
+
+        let cache = DownloadCache::new().with_not_found_ttl(Duration::from_secs(60));
+        for major in 0..(MAX_NOT_FOUND as u64 + 10) {
+            cache
+                .get(&MissingFetcher, &new_version(major))
+                .await
+                .expect_err("version is missing");
+        }
+        let not_found = cache.not_found.lock();
+        assert_eq!(not_found.len(), MAX_NOT_FOUND);
+        assert!(
+            not_found.contains_key(&new_version(MAX_NOT_FOUND as u64 + 9)),
+            "the latest missing version should be remembered"
+        );
+    }
+
+    #[tokio::test]
+    async fn checks_fetched_binaries() {
+        let ver = Version::from_str("v0.8.14+commit.80d49f37").unwrap();
+        let fetcher = StubFetcher {
+            dir: stub_dir("valid"),
+            reported: "0.8.14+commit.80d49f37.Linux.g++",
+            downloads: true,
+        };
+        let cache = DownloadCache::new().with_binary_check(true);
+        let path = cache.get(&fetcher, &ver).await.expect("valid compiler");
+        assert!(path.exists());
+    }
+
+    #[tokio::test]
+    async fn removes_invalid_binaries() {
+        let ver = Version::from_str("v0.8.14+commit.80d49f37").unwrap();
+        for reported in ["0.8.1+commit.df193b15.Linux.g++", "0.8.14+commit.80d49f3"] {
+            let fetcher = StubFetcher {
+                dir: stub_dir("invalid"),
+                reported,
+                downloads: true,
+            };
+            let cache = DownloadCache::new().with_binary_check(true);
+            let err = cache
+                .get(&fetcher, &ver)
+                .await
+                .expect_err("invalid compiler");
+            assert!(
+                matches!(&err, FetchError::InvalidBinary(v, _) if v == &ver),
+                "{}",
+                err
+            );
+            assert!(!fetcher.dir.join(ver.to_path_segment()).exists());
+            assert_eq!(
+                cache.try_get(&ver).await,
+                None,
+                "invalid compiler is cached"
+            );
+
+            // without the check the compiler is not run
+            let cache = DownloadCache::new();
+            cache.get(&fetcher, &ver).await.expect("unchecked compiler");
+        }
+    }
+
+    #[tokio::test]
+    async fn keeps_staged_binaries() {
+        let ver = Version::from_str("v0.8.14+commit.80d49f37").unwrap();
+        let fetcher = StubFetcher {
+            dir: stub_dir("staged"),
+            reported: "0.8.1+commit.df193b15.Linux.g++",
+            downloads: false,
+        };
+        let cache = DownloadCache::new().with_binary_check(true);
+        let path = cache
+            .get(&fetcher, &ver)
+            .await
+            .expect("staged compiler is not checked");
+        assert!(path.exists());
+    }
+
     /// Tests, that caching works, meaning that cache downloads each version only once
     #[test]
     fn value_is_cached() {
@@ -153,13 +1433,18 @@ mod tests {
         }
 
         let fetcher = MockFetcher::default();
-        let cache = DownloadCache::new();
+        let requests_metric = IntCounterVec::new(
+            prometheus::Opts::new("compiler_cache_requests", "test"),
+            &["result"],
+        )
+        .unwrap();
+        let cache = DownloadCache::new().with_requests_metric(requests_metric.clone());
 
         let vers: Vec<_> = (0..3).map(new_version).collect();
 
         let get_and_check = |ver: &Version| {
             let value = block_on(cache.get(&fetcher, ver)).unwrap();
-            assert_eq!(value, PathBuf::from(ver.to_string()));
+            assert_eq!(value.path(), Path::new(&ver.to_string()));
         };
 
         get_and_check(&vers[0]);
@@ -176,6 +1461,8 @@ mod tests {
         let counter = fetcher.counter.lock();
         assert_eq!(counter.len(), 3);
         assert!(counter.values().all(|&count| count == 1));
+        let requests = |result| requests_metric.with_label_values(&[result]).get();
+        assert_eq!((requests("hit"), requests("miss")), (7, 3));
     }
 
     /// Tests, that cache will not block requests for already downloaded values,
@@ -250,12 +1537,174 @@ mod tests {
         vals.1.expect("expected value got error");
     }
 
+    /// Tests, that requests awaiting a concurrent fetch of the version are not counted as hits
+    #[tokio::test]
+    async fn awaited_fetch_is_miss() {
+        struct MockBlockingFetcher {
+            sync: Arc<tokio::sync::Mutex<()>>,
+        }
+
+        #[async_trait]
+        impl Fetcher for MockBlockingFetcher {
+            async fn fetch(&self, ver: &Version) -> Result<PathBuf, FetchError> {
+                let _guard = self.sync.lock().await;
+                Ok(PathBuf::from(ver.to_string()))
+            }
+
+            fn all_versions(&self) -> Vec<Version> {
+                vec![]
+            }
+        }
+
+        let sync = Arc::<tokio::sync::Mutex<()>>::default();
+        let fetcher = MockBlockingFetcher { sync: sync.clone() };
+        let requests_metric = IntCounterVec::new(
+            prometheus::Opts::new("compiler_cache_requests", "test"),
+            &["result"],
+        )
+        .unwrap();
+        let cache = DownloadCache::new().with_requests_metric(requests_metric.clone());
+        let requests = |result| requests_metric.with_label_values(&[result]).get();
+        let ver = new_version(1);
+
+        let guard = sync.lock().await;
+        let fetches = async { join!(cache.get(&fetcher, &ver), cache.get(&fetcher, &ver)) };
+        let release = async {
+            yield_now().await;
+            std::mem::drop(guard);
+        };
+        let ((first, second), ()) = join!(fetches, release);
+        first.expect("first fetch failed");
+        second.expect("second fetch failed");
+        assert_eq!((requests("hit"), requests("miss")), (0, 2));
+
+        cache.get(&fetcher, &ver).await.unwrap();
+        assert_eq!((requests("hit"), requests("miss")), (1, 2));
+    }
+
+    /// Tests, that expired compilers are downloaded again if the published hash has changed
+    #[tokio::test]
+    async fn redownloads_republished_compiler() {
+        use sha2::{Digest, Sha256};
+
+        struct MockFetcher {
+            dir: PathBuf,
+            published: parking_lot::Mutex<&'static str>,
+            fetched: parking_lot::Mutex<u32>,
+        }
+
+        #[async_trait]
+        impl Fetcher for MockFetcher {
+            async fn fetch(&self, ver: &Version) -> Result<PathBuf, FetchError> {
+                *self.fetched.lock() += 1;
+                let file = self.dir.join(ver.to_path_segment());
+                std::fs::write(&file, *self.published.lock())?;
+                Ok(file)
+            }
+
+            fn all_versions(&self) -> Vec<Version> {
+                vec![]
+            }
+
+            async fn published_hash(
+                &self,
+                _ver: &Version,
+            ) -> Result<Option<primitive_types::H256>, FetchError> {
+                let published = *self.published.lock();
+                Ok(Some(primitive_types::H256::from_slice(&Sha256::digest(
+                    published,
+                ))))
+            }
+        }
+
+        let dir = temp_dir().join(format!("republished_compilers_{}", rand::random::<u64>()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let fetcher = MockFetcher {
+            dir,
+            published: parking_lot::Mutex::new("solc"),
+            fetched: Default::default(),
+        };
+        let cache = DownloadCache::new().with_max_age(Some(Duration::ZERO));
+        let ver = new_version(1);
+
+        let file = cache.get(&fetcher, &ver).await.unwrap();
+        // the published hash has not changed
+        cache.get(&fetcher, &ver).await.unwrap();
+        assert_eq!(*fetcher.fetched.lock(), 1);
+
+        *fetcher.published.lock() = "republished solc";
+        let updated = cache.get(&fetcher, &ver).await.unwrap();
+        assert_eq!(*fetcher.fetched.lock(), 2);
+        assert_eq!(updated.path(), file.path());
+        assert_eq!(
+            std::fs::read_to_string(updated).unwrap(),
+            "republished solc"
+        );
+
+        // compilers are not revalidated until they expire
+        let cache = DownloadCache::new().with_max_age(Some(Duration::from_secs(3600)));
+        cache.get(&fetcher, &ver).await.unwrap();
+        *fetcher.published.lock() = "solc";
+        cache.get(&fetcher, &ver).await.unwrap();
+        assert_eq!(*fetcher.fetched.lock(), 3);
+    }
+
+    #[tokio::test]
+    async fn unavailable_published_hash_is_not_requested_again() {
+        struct MockFetcher {
+            dir: PathBuf,
+            hash_requests: parking_lot::Mutex<u32>,
+        }
+
+        #[async_trait]
+        impl Fetcher for MockFetcher {
+            async fn fetch(&self, ver: &Version) -> Result<PathBuf, FetchError> {
+                let file = self.dir.join(ver.to_path_segment());
+                std::fs::write(&file, "solc")?;
+                Ok(file)
+            }
+
+            fn all_versions(&self) -> Vec<Version> {
+                vec![]
+            }
+
+            async fn published_hash(
+                &self,
+                _ver: &Version,
+            ) -> Result<Option<primitive_types::H256>, FetchError> {
+                *self.hash_requests.lock() += 1;
+                Err(FetchError::Fetch(anyhow::anyhow!("list is unavailable")))
+            }
+        }
+
+        let dir = temp_dir().join(format!("unavailable_hashes_{}", rand::random::<u64>()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let fetcher = MockFetcher {
+            dir,
+            hash_requests: Default::default(),
+        };
+        let max_age = Duration::from_millis(200);
+        let cache = DownloadCache::new().with_max_age(Some(max_age));
+        let ver = new_version(1);
+
+        cache.get(&fetcher, &ver).await.unwrap();
+        tokio::time::sleep(max_age).await;
+        cache
+            .get(&fetcher, &ver)
+            .await
+            .expect("cached compiler is used");
+        assert_eq!(*fetcher.hash_requests.lock(), 1);
+        // the failed attempt is checked again only once the compiler expires again
+        cache.get(&fetcher, &ver).await.unwrap();
+        assert_eq!(*fetcher.hash_requests.lock(), 1);
+        tokio::time::sleep(max_age).await;
+        cache.get(&fetcher, &ver).await.unwrap();
+        assert_eq!(*fetcher.hash_requests.lock(), 2);
+    }
+
     #[tokio::test]
     async fn filter_versions() {
-        let versions: HashSet<Version> = vec![1, 2, 3, 4, 5]
-            .into_iter()
-            .map(|i| new_version(i))
-            .collect();
+        let versions: HashSet<Version> = vec![1, 2, 3, 4, 5].into_iter().map(new_version).collect();
 
         let paths = versions.iter().map(|v| v.to_string().into()).chain(vec![
             "some_random_dir".into(),
@@ -269,20 +1718,57 @@ mod tests {
         assert_eq!(versions, filtered_versions,);
     }
 
+    #[tokio::test]
+    async fn skips_incomplete_versions() {
+        let root = temp_dir().join(format!("incomplete_compilers_{}", rand::random::<u64>()));
+        let dir = Language::Solidity.compilers_dir(&root);
+        let complete = new_version(1);
+        let only_temp = new_version(2);
+        let empty = new_version(3);
+        for version in [&complete, &only_temp, &empty] {
+            std::fs::create_dir_all(dir.join(version.to_path_segment())).unwrap();
+        }
+        std::fs::write(dir.join(complete.to_path_segment()).join("solc"), "solc").unwrap();
+        std::fs::write(
+            dir.join(only_temp.to_path_segment()).join("solc.tmp"),
+            "sol",
+        )
+        .unwrap();
+
+        let cache = DownloadCache::new();
+        cache
+            .load_from_dir(&root, Language::Solidity)
+            .await
+            .expect("incomplete versions should not fail the scan");
+
+        assert_eq!(
+            cache.try_get(&complete).await,
+            Some(dir.join(complete.to_path_segment()).join("solc"))
+        );
+        for version in [&only_temp, &empty] {
+            assert_eq!(cache.try_get(version).await, None);
+            assert!(
+                !dir.join(version.to_path_segment()).exists(),
+                "incomplete folder of version {} should be removed",
+                version
+            );
+        }
+    }
+
     #[tokio::test]
     async fn load_downloaded_compiler() {
         let ver = Version::from_str("0.7.0+commit.9e61f92b").unwrap();
-        let dir = temp_dir();
+        let root = temp_dir();
 
         let url = DEFAULT_COMPILER_LIST.try_into().expect("Getting url");
-        let fetcher = ListFetcher::new(url, None, temp_dir())
+        let fetcher = ListFetcher::new(url, vec![], None, temp_dir(), None)
             .await
             .expect("Fetch releases");
         fetcher.fetch(&ver).await.expect("download should complete");
 
         let cache = DownloadCache::new();
         cache
-            .load_from_dir(&dir)
+            .load_from_dir(&root, Language::Solidity)
             .await
             .expect("cannot load compilers");
 
@@ -292,4 +1778,50 @@ mod tests {
             .expect("version should appear in cache");
         assert!(path.exists(), "solc compiler file should exists");
     }
+
+    #[tokio::test]
+    async fn languages_are_isolated() {
+        let root = temp_dir().join(format!("language_compilers_{}", rand::random::<u64>()));
+        let solidity_version = new_version(1);
+        let vyper_version = new_version(2);
+        let shared_version = new_version(3);
+        for (language, version) in [
+            (Language::Solidity, &solidity_version),
+            (Language::Solidity, &shared_version),
+            (Language::Vyper, &vyper_version),
+            (Language::Vyper, &shared_version),
+        ] {
+            let dir = language.version_dir(&root, version);
+            std::fs::create_dir_all(&dir).unwrap();
+            std::fs::write(dir.join(language.executable_name()), language.dir_name()).unwrap();
+        }
+
+        for (language, own_version, other_version) in [
+            (Language::Solidity, &solidity_version, &vyper_version),
+            (Language::Vyper, &vyper_version, &solidity_version),
+        ] {
+            let cache = DownloadCache::new();
+            cache
+                .load_from_dir(&root, language)
+                .await
+                .expect("cannot load compilers");
+            for version in [own_version, &shared_version] {
+                let path = cache
+                    .try_get(version)
+                    .await
+                    .expect("version should be loaded");
+                assert_eq!(
+                    std::fs::read_to_string(path).unwrap(),
+                    language.dir_name(),
+                    "compiler of another language loaded"
+                );
+            }
+            assert_eq!(cache.try_get(other_version).await, None);
+        }
+        // folders of the other language are not considered incomplete
+        assert!(Language::Vyper.version_dir(&root, &vyper_version).exists());
+        assert!(Language::Solidity
+            .version_dir(&root, &solidity_version)
+            .exists());
+    }
 }
@@ -0,0 +1,150 @@
+use super::Version;
+use ethers_solc::EvmVersion;
+use semver::VersionReq;
+
+/// Compiler versions supporting every EVM version, in the order of the forks.
+/// Only the EVM versions the service accepts in the compiler settings are listed.
+const EVM_VERSIONS: &[(EvmVersion, &str)] = &[
+    (EvmVersion::Homestead, ">=0.4.21"),
+    (EvmVersion::TangerineWhistle, ">=0.4.21"),
+    (EvmVersion::SpuriousDragon, ">=0.4.21"),
+    (EvmVersion::Byzantium, ">=0.4.21"),
+    (EvmVersion::Constantinople, ">=0.4.21"),
+    (EvmVersion::Petersburg, ">=0.5.5"),
+    (EvmVersion::Istanbul, ">=0.5.14"),
+    (EvmVersion::Berlin, ">=0.8.5"),
+    (EvmVersion::London, ">=0.8.7"),
+];
+
+/// Compiler versions compiling for every EVM version if none is set in the settings,
+/// in the order of the forks. Later forks are not known to `EvmVersion`, so names are listed.
+const DEFAULT_EVM_VERSIONS: &[(&str, &str)] = &[
+    ("byzantium", ">=0.4.21"),
+    ("petersburg", ">=0.5.5"),
+    ("istanbul", ">=0.5.14"),
+    ("berlin", ">=0.8.5"),
+    ("london", ">=0.8.7"),
+    ("paris", ">=0.8.18"),
+    ("shanghai", ">=0.8.20"),
+    ("cancun", ">=0.8.25"),
+    ("prague", ">=0.8.30"),
+];
+
+fn release(compiler_version: &Version) -> semver::Version {
+    let version = compiler_version.version();
+    semver::Version::new(version.major, version.minor, version.patch)
+}
+
+/// Returns the EVM version the `compiler_version` compiles for if none is set in the settings,
+/// or `None` if the compiler precedes the EVM version setting (0.4.21).
+pub fn default_evm_version(compiler_version: &Version) -> Option<&'static str> {
+    let release = release(compiler_version);
+    DEFAULT_EVM_VERSIONS
+        .iter()
+        .filter(|(_, requirement)| {
+            VersionReq::parse(requirement)
+                .expect("requirement is valid")
+                .matches(&release)
+        })
+        .map(|(evm_version, _)| *evm_version)
+        .last()
+}
+
+/// Returns the EVM versions which could be set in the settings of the `compiler_version`.
+/// Nightly versions support the EVM versions of the release they precede.
+pub fn supported_evm_versions(compiler_version: &Version) -> Vec<EvmVersion> {
+    let release = release(compiler_version);
+    EVM_VERSIONS
+        .iter()
+        .filter(|(_, requirement)| {
+            VersionReq::parse(requirement)
+                .expect("requirement is valid")
+                .matches(&release)
+        })
+        .map(|(evm_version, _)| *evm_version)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::str::FromStr;
+
+    fn evm_versions(compiler_version: &str) -> Vec<String> {
+        let compiler_version = Version::from_str(compiler_version).unwrap();
+        supported_evm_versions(&compiler_version)
+            .iter()
+            .map(|evm_version| evm_version.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn evm_versions_depend_on_compiler_version() {
+        assert!(evm_versions("v0.4.20+commit.3155dd80").is_empty());
+        assert_eq!(
+            evm_versions("v0.5.5+commit.47a71e8f"),
+            vec![
+                "homestead",
+                "tangerineWhistle",
+                "spuriousDragon",
+                "byzantium",
+                "constantinople",
+                "petersburg",
+            ]
+        );
+        assert_eq!(
+            evm_versions("v0.8.6+commit.11564f7e").last().unwrap(),
+            "berlin"
+        );
+        assert_eq!(
+            evm_versions("v0.8.7-nightly.2021.8.9+commit.74c804d8")
+                .last()
+                .unwrap(),
+            "london"
+        );
+    }
+
+    #[test]
+    fn default_evm_version_depends_on_compiler_version() {
+        let default =
+            |compiler_version| default_evm_version(&Version::from_str(compiler_version).unwrap());
+        assert_eq!(default("v0.4.20+commit.3155dd80"), None);
+        assert_eq!(default("v0.4.24+commit.e67f0147"), Some("byzantium"));
+        assert_eq!(default("v0.5.5+commit.47a71e8f"), Some("petersburg"));
+        assert_eq!(default("v0.6.7+commit.b8d736ae"), Some("istanbul"));
+        assert_eq!(default("v0.8.6+commit.11564f7e"), Some("berlin"));
+        assert_eq!(
+            default("v0.8.7-nightly.2021.8.9+commit.74c804d8"),
+            Some("london")
+        );
+        assert_eq!(default("v0.8.19+commit.7dd6d404"), Some("paris"));
+        assert_eq!(default("v0.8.24+commit.e11b9ed9"), Some("shanghai"));
+        assert_eq!(default("v0.8.25+commit.b61c2a91"), Some("cancun"));
+    }
+
+    #[test]
+    fn evm_versions_match_settings_validation() {
+        for compiler_version in [
+            "v0.4.21+commit.dfe3193c",
+            "v0.5.4+commit.9549d8ff",
+            "v0.5.14+commit.01f1aaa4",
+            "v0.8.4+commit.c7e474f2",
+            "v0.8.5+commit.a4f2e591",
+            "v0.8.10+commit.fc410830",
+        ] {
+            let version = Version::from_str(compiler_version).unwrap();
+            let supported = supported_evm_versions(&version);
+            for (evm_version, _) in EVM_VERSIONS {
+                let valid = evm_version.normalize_version(version.version()) == Some(*evm_version);
+                assert_eq!(
+                    supported.contains(evm_version),
+                    valid,
+                    "{} with {}",
+                    evm_version,
+                    compiler_version
+                );
+            }
+        }
+    }
+}
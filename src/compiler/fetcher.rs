@@ -3,7 +3,7 @@ use crate::types::Mismatch;
 use super::version::Version;
 use async_trait::async_trait;
 use primitive_types::H256;
-use std::path::PathBuf;
+use std::{path::PathBuf, time::Instant};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -16,6 +16,10 @@ pub enum FetchError {
     HashMismatch(#[from] Mismatch<H256>),
     #[error("couldn't create file: {0}")]
     File(#[from] std::io::Error),
+    #[error("fetching version {0} timed out")]
+    Timeout(Version),
+    #[error("fetched compiler of version {0} is not a valid binary: {1}")]
+    InvalidBinary(Version, String),
     #[error("tokio sheduling error: {0}")]
     Schedule(#[from] tokio::task::JoinError),
 }
@@ -24,4 +28,22 @@ pub enum FetchError {
 pub trait Fetcher: Send + Sync {
     async fn fetch(&self, ver: &Version) -> Result<PathBuf, FetchError>;
     fn all_versions(&self) -> Vec<Version>;
+
+    /// Returns the hashsum currently published for the version,
+    /// or `None` if the fetcher could not provide it without downloading the compiler.
+    async fn published_hash(&self, _ver: &Version) -> Result<Option<H256>, FetchError> {
+        Ok(None)
+    }
+
+    /// Whether the fetched compilers are downloaded by the service, so that they could be
+    /// checked and removed if invalid. Compilers staged by others are used as is.
+    fn downloads(&self) -> bool {
+        true
+    }
+
+    /// Returns the time and result of the last refresh of the versions,
+    /// or `None` if the fetcher does not refresh them.
+    fn last_refresh(&self) -> Option<(Instant, Result<(), String>)> {
+        None
+    }
 }
@@ -0,0 +1,797 @@
+use super::{
+    fetcher::FetchError,
+    http_fetcher::HttpFetcher,
+    list_fetcher::{save_executable, ChecksumKind},
+    refreshable_versions::RefreshableVersions,
+    version::PATH_SEGMENT,
+};
+use crate::{
+    compiler::{Fetcher, Language, Version},
+    config::GcsFetcherConfig,
+    metrics,
+    scheduler::BackoffSchedule,
+};
+use anyhow::Context;
+use async_trait::async_trait;
+use percent_encoding::utf8_percent_encode;
+use primitive_types::H256;
+use rand::Rng;
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, Instant, SystemTime},
+};
+use url::Url;
+
+const DEFAULT_ENDPOINT: &str = "https://storage.googleapis.com/";
+const READ_ONLY_SCOPE: &str = "https://www.googleapis.com/auth/devstorage.read_only";
+/// Access tokens are refreshed the specified time before they expire
+const TOKEN_EXPIRY_MARGIN: Duration = Duration::from_secs(60);
+
+/// Fields of the service account key file used for authorization.
+#[derive(Clone, Deserialize)]
+struct ServiceAccountKey {
+    client_email: String,
+    private_key: String,
+    token_uri: Url,
+}
+
+#[derive(Serialize)]
+struct Claims<'a> {
+    iss: &'a str,
+    scope: &'a str,
+    aud: &'a str,
+    iat: u64,
+    exp: u64,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    expires_in: u64,
+}
+
+#[derive(Clone)]
+struct AccessToken {
+    token: String,
+    expires_at: SystemTime,
+}
+
+/// Issues OAuth access tokens for the service account, caching them until they expire.
+struct Authorizer {
+    key: ServiceAccountKey,
+    encoding_key: jsonwebtoken::EncodingKey,
+    token: tokio::sync::Mutex<Option<AccessToken>>,
+}
+
+impl Authorizer {
+    fn new(service_account_key: &Path) -> anyhow::Result<Self> {
+        let content = std::fs::read(service_account_key).with_context(|| {
+            format!("cannot read service account key {:?}", service_account_key)
+        })?;
+        let key: ServiceAccountKey =
+            serde_json::from_slice(&content).context("invalid service account key")?;
+        let encoding_key = jsonwebtoken::EncodingKey::from_rsa_pem(key.private_key.as_bytes())
+            .context("invalid service account private key")?;
+        Ok(Self {
+            key,
+            encoding_key,
+            token: Default::default(),
+        })
+    }
+
+    async fn token(&self) -> anyhow::Result<String> {
+        let mut token = self.token.lock().await;
+        match token.as_ref() {
+            Some(token) if token.expires_at > SystemTime::now() + TOKEN_EXPIRY_MARGIN => {
+                Ok(token.token.clone())
+            }
+            _ => {
+                let new_token = self.request_token().await?;
+                *token = Some(new_token.clone());
+                Ok(new_token.token)
+            }
+        }
+    }
+
+    async fn request_token(&self) -> anyhow::Result<AccessToken> {
+        let now = SystemTime::now();
+        let iat = now.duration_since(SystemTime::UNIX_EPOCH)?.as_secs();
+        let claims = Claims {
+            iss: &self.key.client_email,
+            scope: READ_ONLY_SCOPE,
+            aud: self.key.token_uri.as_str(),
+            iat,
+            exp: iat + 60 * 60,
+        };
+        let assertion = jsonwebtoken::encode(
+            &jsonwebtoken::Header::new(jsonwebtoken::Algorithm::RS256),
+            &claims,
+            &self.encoding_key,
+        )?;
+        let response: TokenResponse = reqwest::Client::new()
+            .post(self.key.token_uri.clone())
+            .form(&[
+                ("grant_type", "urn:ietf:params:oauth:grant-type:jwt-bearer"),
+                ("assertion", &assertion),
+            ])
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .context("requesting access token returned error")?
+            .json()
+            .await
+            .context("cannot parse access token response")?;
+        Ok(AccessToken {
+            token: response.access_token,
+            expires_at: now + Duration::from_secs(response.expires_in),
+        })
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ObjectsList {
+    #[serde(default)]
+    prefixes: Vec<String>,
+    next_page_token: Option<String>,
+}
+
+/// Retries of failed downloads with exponential backoff.
+#[derive(Clone, Copy)]
+struct RetryPolicy {
+    attempts: NonZeroUsize,
+    base_delay: Duration,
+}
+
+impl RetryPolicy {
+    /// Delay before the retry following the `attempt`, randomized
+    /// to spread the retries of concurrent downloads.
+    fn delay(&self, attempt: usize) -> Duration {
+        let delay = self.base_delay * 2u32.saturating_pow(attempt as u32 - 1);
+        rand::thread_rng().gen_range(delay / 2..=delay)
+    }
+}
+
+/// Network errors and server errors are considered transient.
+fn is_retryable(err: &reqwest::Error) -> bool {
+    match err.status() {
+        Some(status) => status.is_server_error(),
+        None => err.is_connect() || err.is_timeout() || err.is_request() || err.is_body(),
+    }
+}
+
+/// Client of the GCS JSON API for a single bucket.
+#[derive(Clone)]
+struct Bucket {
+    endpoint: Url,
+    bucket: String,
+    /// Folder inside the bucket containing version folders, ends with `/` if not empty
+    prefix: String,
+    authorizer: Arc<Authorizer>,
+    retry: RetryPolicy,
+}
+
+impl Bucket {
+    /// Url of the bucket objects; `object` is encoded as a single path segment if specified.
+    /// Url of the objects of the bucket, or of the object,
+    /// which name should be already percent-encoded as a single path segment.
+    fn objects_url(&self, encoded_object: Option<&str>) -> anyhow::Result<Url> {
+        let mut url = self.endpoint.clone();
+        url.path_segments_mut()
+            .map_err(|_| anyhow::anyhow!("invalid endpoint url: {}", self.endpoint))?
+            .pop_if_empty()
+            .extend(["storage", "v1", "b", &self.bucket, "o"]);
+        if let Some(object) = encoded_object {
+            let path = format!("{}/{}", url.path(), object);
+            url.set_path(&path);
+        }
+        Ok(url)
+    }
+
+    async fn get(&self, url: Url) -> anyhow::Result<reqwest::Response> {
+        let token = self.authorizer.token().await?;
+        let response = reqwest::Client::new()
+            .get(url)
+            .bearer_auth(token)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())?;
+        Ok(response)
+    }
+
+    /// Lists the version folders located directly under the prefix.
+    async fn fetch_versions(&self) -> anyhow::Result<HashSet<Version>> {
+        let mut versions = HashSet::new();
+        let mut page_token: Option<String> = None;
+        loop {
+            let mut url = self.objects_url(None)?;
+            url.query_pairs_mut()
+                .append_pair("delimiter", "/")
+                .append_pair("prefix", &self.prefix);
+            if let Some(page_token) = &page_token {
+                url.query_pairs_mut().append_pair("pageToken", page_token);
+            }
+            let list: ObjectsList = self
+                .get(url)
+                .await
+                .context("listing bucket returned error")?
+                .json()
+                .await
+                .context("cannot parse bucket listing")?;
+            for folder in list.prefixes {
+                let name = folder
+                    .strip_prefix(&self.prefix)
+                    .unwrap_or(&folder)
+                    .trim_end_matches('/');
+                match Version::from_path_segment(name) {
+                    Ok(version) => {
+                        versions.insert(version);
+                    }
+                    Err(err) => log::warn!("skipping invalid version folder {}: {}", folder, err),
+                }
+            }
+            if list.next_page_token.is_none() {
+                return Ok(versions);
+            }
+            page_token = list.next_page_token;
+        }
+    }
+
+    async fn download_once(&self, url: Url, token: &str) -> Result<bytes::Bytes, reqwest::Error> {
+        reqwest::Client::new()
+            .get(url)
+            .bearer_auth(token)
+            .send()
+            .await?
+            .error_for_status()?
+            .bytes()
+            .await
+    }
+
+    /// Downloads the file of the version, retrying transient failures.
+    async fn download(&self, ver: &Version, file: &str) -> Result<bytes::Bytes, FetchError> {
+        let object = format!("{}{}/{}", self.prefix, ver, file);
+        // the version is encoded the same way listed folder names are decoded,
+        // so that every listed version could be downloaded
+        let encode = |part: &str| utf8_percent_encode(part, PATH_SEGMENT).to_string();
+        let encoded_object = format!(
+            "{}{}{}",
+            encode(&self.prefix),
+            ver.to_path_segment(),
+            encode(&format!("/{}", file))
+        );
+        let mut url = self.objects_url(Some(&encoded_object))?;
+        url.query_pairs_mut().append_pair("alt", "media");
+        for attempt in 1.. {
+            let token = self.authorizer.token().await?;
+            match self.download_once(url.clone(), &token).await {
+                Ok(bytes) => return Ok(bytes),
+                Err(err) if err.status() == Some(StatusCode::NOT_FOUND) => {
+                    return Err(FetchError::NotFound(ver.clone()))
+                }
+                Err(err) if attempt < self.retry.attempts.get() && is_retryable(&err) => {
+                    let delay = self.retry.delay(attempt);
+                    log::warn!(
+                        "downloading {} failed, retrying in {:?}: {}",
+                        object,
+                        delay,
+                        err
+                    );
+                    tokio::time::sleep(delay).await;
+                }
+                Err(err) => {
+                    return Err(anyhow::Error::new(err)
+                        .context(format!("downloading {} returned error", object))
+                        .into())
+                }
+            }
+        }
+        unreachable!("attempts are not limited by the loop")
+    }
+}
+
+/// Fetches compilers from a GCS bucket, where every version is stored in its own folder
+/// (e.g. `<prefix>/v0.8.9+commit.e5eed63a/`) containing the binary and hashsum files
+/// (`solc` and `sha256.hash` by default).
+///
+/// Downloaded hashsums are kept until the next refresh of the versions,
+/// so that re-published compilers are noticed by the cache revalidation.
+pub struct GcsFetcher {
+    versions: RefreshableVersions<HashSet<Version>>,
+    hashes: Arc<parking_lot::RwLock<HashMap<Version, H256>>>,
+    bucket: Bucket,
+    folder: PathBuf,
+    binary_name: String,
+    hash_name: String,
+}
+
+impl GcsFetcher {
+    pub async fn new(
+        config: GcsFetcherConfig,
+        refresh_versions_schedule: Option<BackoffSchedule>,
+        folder: PathBuf,
+    ) -> anyhow::Result<Self> {
+        let endpoint = match config.endpoint {
+            Some(endpoint) => endpoint,
+            None => Url::parse(DEFAULT_ENDPOINT).expect("valid url"),
+        };
+        let prefix = match config
+            .prefix
+            .as_deref()
+            .map(|prefix| prefix.trim_matches('/'))
+        {
+            None | Some("") => String::new(),
+            Some(prefix) => format!("{}/", prefix),
+        };
+        let bucket = Bucket {
+            endpoint,
+            bucket: config.bucket,
+            prefix,
+            authorizer: Arc::new(Authorizer::new(&config.service_account_key)?),
+            retry: RetryPolicy {
+                attempts: config.download_attempts,
+                base_delay: Duration::from_millis(config.download_retry_delay),
+            },
+        };
+
+        let versions = bucket.fetch_versions().await?;
+        let versions =
+            RefreshableVersions::new(versions, metrics::compiler_list_size(Language::Solidity));
+        let hashes: Arc<parking_lot::RwLock<HashMap<Version, H256>>> = Default::default();
+        if let Some(schedule) = refresh_versions_schedule {
+            let bucket = bucket.clone();
+            let hashes = hashes.clone();
+            versions.clone().spawn_refresh_job(schedule, move || {
+                let bucket = bucket.clone();
+                let hashes = hashes.clone();
+                async move {
+                    let versions = bucket.fetch_versions().await?;
+                    hashes.write().clear();
+                    Ok(Some(versions))
+                }
+            });
+        }
+        Ok(Self {
+            versions,
+            hashes,
+            bucket,
+            folder,
+            binary_name: config.binary_name,
+            hash_name: config.hash_name,
+        })
+    }
+}
+
+#[async_trait]
+impl Fetcher for GcsFetcher {
+    async fn fetch(&self, ver: &Version) -> Result<PathBuf, FetchError> {
+        if !self.versions.read().contains(ver) {
+            return Err(FetchError::NotFound(ver.clone()));
+        }
+
+        let (hashsum, bytes) = futures::try_join!(
+            self.bucket.download(ver, &self.hash_name),
+            self.bucket.download(ver, &self.binary_name)
+        )?;
+        let sha256 = HttpFetcher::parse_hashsum(&hashsum)?;
+        self.hashes.write().insert(ver.clone(), sha256);
+        save_executable(
+            bytes,
+            sha256,
+            ChecksumKind::Sha256,
+            &self.folder,
+            Language::Solidity,
+            ver,
+        )
+        .await
+    }
+
+    fn all_versions(&self) -> Vec<Version> {
+        self.versions.read().iter().cloned().collect()
+    }
+
+    async fn published_hash(&self, ver: &Version) -> Result<Option<H256>, FetchError> {
+        if !self.versions.read().contains(ver) {
+            return Err(FetchError::NotFound(ver.clone()));
+        }
+        if let Some(sha256) = self.hashes.read().get(ver) {
+            return Ok(Some(*sha256));
+        }
+        let hashsum = self.bucket.download(ver, &self.hash_name).await?;
+        let sha256 = HttpFetcher::parse_hashsum(&hashsum)?;
+        self.hashes.write().insert(ver.clone(), sha256);
+        Ok(Some(sha256))
+    }
+
+    fn last_refresh(&self) -> Option<(Instant, Result<(), String>)> {
+        Some(self.versions.last_refresh())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use sha2::{Digest, Sha256};
+    use std::str::FromStr;
+    use wiremock::{
+        matchers::{header, method, path, query_param, query_param_is_missing},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    const BINARY: &[u8] = b"#!/bin/sh\necho solc\n";
+    /// Test-only RSA key, not used anywhere else
+    const PRIVATE_KEY: &str = include_str!("test_data/gcs_test_key.pem");
+
+    fn service_account_key(mock_server: &MockServer) -> PathBuf {
+        let dir = std::env::temp_dir().join("blockscout/verification/gcs_fetcher/test/");
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join(format!("key-{}.json", mock_server.address().port()));
+        let key = serde_json::json!({
+            "type": "service_account",
+            "client_email": "verifier@project.iam.gserviceaccount.com",
+            "private_key": PRIVATE_KEY,
+            "token_uri": format!("{}/token", mock_server.uri()),
+        });
+        std::fs::write(&file, key.to_string()).unwrap();
+        file
+    }
+
+    async fn mock_bucket() -> MockServer {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "access_token": "test-token",
+                "expires_in": 3600,
+                "token_type": "Bearer",
+            })))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/storage/v1/b/solc-bin/o"))
+            .and(query_param("prefix", "mirror/"))
+            .and(header("authorization", "Bearer test-token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "kind": "storage#objects",
+                "prefixes": ["mirror/v0.8.9+commit.e5eed63a/", "mirror/not-a-version/"],
+            })))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path(
+                "/storage/v1/b/solc-bin/o/mirror%2Fv0.8.9%2Bcommit.e5eed63a%2Fsolc",
+            ))
+            .and(query_param("alt", "media"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(BINARY))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path(
+                "/storage/v1/b/solc-bin/o/mirror%2Fv0.8.9%2Bcommit.e5eed63a%2Fsha256.hash",
+            ))
+            .and(query_param("alt", "media"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_string(hex::encode(Sha256::digest(BINARY))),
+            )
+            .mount(&mock_server)
+            .await;
+        mock_server
+    }
+
+    #[tokio::test]
+    async fn fetch_from_bucket() {
+        let mock_server = mock_bucket().await;
+        let config = GcsFetcherConfig {
+            bucket: "solc-bin".into(),
+            service_account_key: service_account_key(&mock_server),
+            prefix: Some("/mirror/".into()),
+            endpoint: Some(Url::parse(&mock_server.uri()).unwrap()),
+            binary_name: "solc".into(),
+            hash_name: "sha256.hash".into(),
+            download_attempts: NonZeroUsize::new(3).unwrap(),
+            download_retry_delay: 10,
+            refresh_versions_schedule: None,
+        };
+        let folder = std::env::temp_dir().join("blockscout/verification/gcs_fetcher/test/");
+        let fetcher = GcsFetcher::new(config, None, folder)
+            .await
+            .expect("fetcher should be initialized");
+
+        let version = Version::from_str("0.8.9+commit.e5eed63a").unwrap();
+        assert_eq!(fetcher.all_versions(), vec![version.clone()]);
+        let file = fetcher
+            .fetch(&version)
+            .await
+            .expect("compiler should be fetched");
+        assert_eq!(std::fs::read(file).unwrap(), BINARY);
+
+        let not_listed = Version::from_str("0.8.10+commit.fc410830").unwrap();
+        assert!(matches!(
+            fetcher.fetch(&not_listed).await,
+            Err(FetchError::NotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn caches_published_hashes() {
+        let mock_server = mock_bucket().await;
+        let folder = std::env::temp_dir().join("blockscout/verification/gcs_fetcher/test_hashes/");
+        let fetcher = GcsFetcher::new(config(&mock_server, 3), None, folder)
+            .await
+            .expect("fetcher should be initialized");
+
+        let version = Version::from_str("0.8.9+commit.e5eed63a").unwrap();
+        let expected = H256::from_slice(&Sha256::digest(BINARY));
+        for _ in 0..2 {
+            let published = fetcher.published_hash(&version).await.unwrap();
+            assert_eq!(published, Some(expected));
+        }
+        fetcher.fetch(&version).await.unwrap();
+        assert_eq!(
+            fetcher.published_hash(&version).await.unwrap(),
+            Some(expected)
+        );
+
+        let hash_requests = mock_server
+            .received_requests()
+            .await
+            .unwrap()
+            .into_iter()
+            .filter(|request| request.url.path().ends_with("sha256.hash"))
+            .count();
+        // the hashsum is downloaded once more by the fetch itself
+        assert_eq!(hash_requests, 2);
+    }
+
+    #[tokio::test]
+    async fn nested_prefix() {
+        let mock_server = mock_bucket().await;
+        Mock::given(method("GET"))
+            .and(path("/storage/v1/b/solc-bin/o"))
+            .and(query_param("prefix", "compilers/solidity/"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "prefixes": [
+                    "compilers/solidity/v0.8.10+commit.fc410830/",
+                    "compilers/solidity/v0.8.15-nightly.2022.5.27+commit.095cc647/",
+                ],
+            })))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path(
+                "/storage/v1/b/solc-bin/o/compilers%2Fsolidity%2Fv0.8.10%2Bcommit.fc410830%2Fsolc",
+            ))
+            .and(query_param("alt", "media"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(BINARY))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path(
+                "/storage/v1/b/solc-bin/o/compilers%2Fsolidity%2Fv0.8.10%2Bcommit.fc410830%2Fsha256.hash",
+            ))
+            .and(query_param("alt", "media"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_string(hex::encode(Sha256::digest(BINARY))),
+            )
+            .mount(&mock_server)
+            .await;
+        let config = GcsFetcherConfig {
+            prefix: Some("compilers/solidity/".into()),
+            ..config(&mock_server, 3)
+        };
+        let folder = std::env::temp_dir().join("blockscout/verification/gcs_fetcher/test_nested/");
+        let fetcher = GcsFetcher::new(config, None, folder)
+            .await
+            .expect("fetcher should be initialized");
+
+        let mut versions = fetcher.all_versions();
+        versions.sort();
+        let version = Version::from_str("0.8.10+commit.fc410830").unwrap();
+        assert_eq!(
+            versions,
+            vec![
+                version.clone(),
+                Version::from_str("0.8.15-nightly.2022.5.27+commit.095cc647").unwrap(),
+            ]
+        );
+        let file = fetcher
+            .fetch(&version)
+            .await
+            .expect("compiler should be fetched");
+        assert_eq!(std::fs::read(file).unwrap(), BINARY);
+    }
+
+    #[tokio::test]
+    async fn lists_all_pages() {
+        let mock_server = mock_bucket().await;
+        Mock::given(method("GET"))
+            .and(path("/storage/v1/b/solc-bin/o"))
+            .and(query_param_is_missing("pageToken"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "prefixes": ["v0.8.9+commit.e5eed63a/"],
+                "nextPageToken": "second",
+            })))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/storage/v1/b/solc-bin/o"))
+            .and(query_param("pageToken", "second"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "prefixes": ["v0.8.10+commit.fc410830/"],
+                "nextPageToken": "third",
+            })))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/storage/v1/b/solc-bin/o"))
+            .and(query_param("pageToken", "third"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "prefixes": ["v0.4.13+commit.0fb4cb1a/"],
+            })))
+            .mount(&mock_server)
+            .await;
+        let config = GcsFetcherConfig {
+            bucket: "solc-bin".into(),
+            service_account_key: service_account_key(&mock_server),
+            prefix: None,
+            endpoint: Some(Url::parse(&mock_server.uri()).unwrap()),
+            binary_name: "solc".into(),
+            hash_name: "sha256.hash".into(),
+            download_attempts: NonZeroUsize::new(3).unwrap(),
+            download_retry_delay: 10,
+            refresh_versions_schedule: None,
+        };
+        let folder = std::env::temp_dir().join("blockscout/verification/gcs_fetcher/test/");
+        let fetcher = GcsFetcher::new(config, None, folder)
+            .await
+            .expect("fetcher should be initialized");
+
+        let mut versions = fetcher.all_versions();
+        versions.sort();
+        assert_eq!(
+            versions,
+            vec![
+                Version::from_str("0.4.13+commit.0fb4cb1a").unwrap(),
+                Version::from_str("0.8.9+commit.e5eed63a").unwrap(),
+                Version::from_str("0.8.10+commit.fc410830").unwrap(),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn custom_file_names() {
+        let mock_server = mock_bucket().await;
+        Mock::given(method("GET"))
+            .and(path(
+                "/storage/v1/b/solc-bin/o/mirror%2Fv0.8.9%2Bcommit.e5eed63a%2Fsolc-static-linux",
+            ))
+            .and(query_param("alt", "media"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(BINARY))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path(
+                "/storage/v1/b/solc-bin/o/mirror%2Fv0.8.9%2Bcommit.e5eed63a%2Fchecksum.txt",
+            ))
+            .and(query_param("alt", "media"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_string(hex::encode(Sha256::digest(BINARY))),
+            )
+            .mount(&mock_server)
+            .await;
+        let config = GcsFetcherConfig {
+            binary_name: "solc-static-linux".into(),
+            hash_name: "checksum.txt".into(),
+            ..config(&mock_server, 3)
+        };
+        let folder = std::env::temp_dir().join("blockscout/verification/gcs_fetcher/test_names/");
+        let fetcher = GcsFetcher::new(config, None, folder)
+            .await
+            .expect("fetcher should be initialized");
+
+        let version = Version::from_str("0.8.9+commit.e5eed63a").unwrap();
+        let file = fetcher
+            .fetch(&version)
+            .await
+            .expect("compiler should be fetched");
+        assert_eq!(std::fs::read(file).unwrap(), BINARY);
+        assert_eq!(
+            fetcher.published_hash(&version).await.unwrap(),
+            Some(H256::from_slice(&Sha256::digest(BINARY)))
+        );
+    }
+
+    fn config(mock_server: &MockServer, download_attempts: usize) -> GcsFetcherConfig {
+        GcsFetcherConfig {
+            bucket: "solc-bin".into(),
+            service_account_key: service_account_key(mock_server),
+            prefix: Some("mirror".into()),
+            endpoint: Some(Url::parse(&mock_server.uri()).unwrap()),
+            binary_name: "solc".into(),
+            hash_name: "sha256.hash".into(),
+            download_attempts: NonZeroUsize::new(download_attempts).unwrap(),
+            download_retry_delay: 10,
+            refresh_versions_schedule: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn retries_server_errors() {
+        let mock_server = mock_bucket().await;
+        Mock::given(method("GET"))
+            .and(path(
+                "/storage/v1/b/solc-bin/o/mirror%2Fv0.8.9%2Bcommit.e5eed63a%2Fsolc",
+            ))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(2)
+            .expect(2)
+            .with_priority(1)
+            .mount(&mock_server)
+            .await;
+        let folder = std::env::temp_dir().join("blockscout/verification/gcs_fetcher/test_retry/");
+        let fetcher = GcsFetcher::new(config(&mock_server, 3), None, folder)
+            .await
+            .expect("fetcher should be initialized");
+
+        let version = Version::from_str("0.8.9+commit.e5eed63a").unwrap();
+        let file = fetcher
+            .fetch(&version)
+            .await
+            .expect("compiler should be fetched after retries");
+        assert_eq!(std::fs::read(file).unwrap(), BINARY);
+    }
+
+    #[tokio::test]
+    async fn does_not_retry_missing_files() {
+        let mock_server = mock_bucket().await;
+        Mock::given(method("GET"))
+            .and(path(
+                "/storage/v1/b/solc-bin/o/mirror%2Fv0.8.9%2Bcommit.e5eed63a%2Fsha256.hash",
+            ))
+            .respond_with(ResponseTemplate::new(404))
+            .expect(1)
+            .with_priority(1)
+            .mount(&mock_server)
+            .await;
+        let folder = std::env::temp_dir().join("blockscout/verification/gcs_fetcher/test_retry/");
+        let fetcher = GcsFetcher::new(config(&mock_server, 3), None, folder)
+            .await
+            .expect("fetcher should be initialized");
+
+        let version = Version::from_str("0.8.9+commit.e5eed63a").unwrap();
+        assert!(matches!(
+            fetcher.published_hash(&version).await,
+            Err(FetchError::NotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_attempts() {
+        let mock_server = mock_bucket().await;
+        Mock::given(method("GET"))
+            .and(path(
+                "/storage/v1/b/solc-bin/o/mirror%2Fv0.8.9%2Bcommit.e5eed63a%2Fsha256.hash",
+            ))
+            .respond_with(ResponseTemplate::new(503))
+            .expect(2)
+            .with_priority(1)
+            .mount(&mock_server)
+            .await;
+        let folder = std::env::temp_dir().join("blockscout/verification/gcs_fetcher/test_retry/");
+        let fetcher = GcsFetcher::new(config(&mock_server, 2), None, folder)
+            .await
+            .expect("fetcher should be initialized");
+
+        let version = Version::from_str("0.8.9+commit.e5eed63a").unwrap();
+        assert!(matches!(
+            fetcher.published_hash(&version).await,
+            Err(FetchError::Fetch(_))
+        ));
+    }
+}
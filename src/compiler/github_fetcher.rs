@@ -0,0 +1,440 @@
+use super::{
+    fetcher::FetchError,
+    list_fetcher::{save_executable, ChecksumKind},
+    refreshable_versions::RefreshableVersions,
+};
+use crate::{
+    compiler::{Fetcher, Language, Version},
+    config::GithubFetcherConfig,
+    metrics,
+    scheduler::BackoffSchedule,
+};
+use anyhow::Context;
+use async_trait::async_trait;
+use primitive_types::H256;
+use reqwest::{header, StatusCode};
+use serde::{de::DeserializeOwned, Deserialize};
+use std::{collections::HashMap, path::PathBuf, str::FromStr, time::Instant};
+use url::Url;
+
+const DEFAULT_API_URL: &str = "https://api.github.com";
+/// Maximum page size allowed by the GitHub API
+const PAGE_SIZE: usize = 100;
+/// GitHub API rejects requests without the user agent
+const USER_AGENT: &str = "blockscout-verification";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ReleaseAsset {
+    url: Url,
+    sha256: H256,
+}
+
+#[derive(Deserialize)]
+struct ReleaseResponse {
+    tag_name: String,
+    #[serde(default)]
+    draft: bool,
+    #[serde(default)]
+    assets: Vec<AssetResponse>,
+}
+
+#[derive(Deserialize)]
+struct AssetResponse {
+    name: String,
+    browser_download_url: Url,
+    /// Checksum computed by GitHub on upload, e.g. `sha256:<hex>`
+    digest: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct TagResponse {
+    name: String,
+    commit: TagCommit,
+}
+
+#[derive(Deserialize)]
+struct TagCommit {
+    sha: String,
+}
+
+#[derive(Clone)]
+struct GithubApi {
+    repo_url: String,
+    token: Option<String>,
+    asset_name: String,
+}
+
+impl GithubApi {
+    fn request(&self, url: Url) -> reqwest::RequestBuilder {
+        let request = reqwest::Client::new()
+            .get(url)
+            .header(header::USER_AGENT, USER_AGENT)
+            .header(header::ACCEPT, "application/vnd.github+json");
+        match &self.token {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        }
+    }
+
+    /// Returns the items of all pages of the repository list `endpoint`.
+    async fn list<T: DeserializeOwned>(&self, endpoint: &str) -> anyhow::Result<Vec<T>> {
+        let mut items = Vec::new();
+        for page in 1.. {
+            let mut url = Url::parse(&format!("{}/{}", self.repo_url, endpoint))?;
+            url.query_pairs_mut()
+                .append_pair("per_page", &PAGE_SIZE.to_string())
+                .append_pair("page", &page.to_string());
+            let response = self
+                .request(url)
+                .send()
+                .await
+                .with_context(|| format!("listing {} returned error", endpoint))?;
+            check_rate_limit(&response)?;
+            let has_next = response
+                .headers()
+                .get(header::LINK)
+                .and_then(|link| link.to_str().ok())
+                .is_some_and(|link| link.contains("rel=\"next\""));
+            let page_items: Vec<T> = response
+                .error_for_status()
+                .with_context(|| format!("listing {} returned error", endpoint))?
+                .json()
+                .await
+                .with_context(|| format!("cannot parse {} list", endpoint))?;
+            items.extend(page_items);
+            if !has_next {
+                break;
+            }
+        }
+        Ok(items)
+    }
+
+    /// Maps release tags to versions using the commits of the tags,
+    /// as tag names (e.g. `v0.8.14`) do not contain them.
+    async fn fetch_versions(&self) -> anyhow::Result<HashMap<Version, ReleaseAsset>> {
+        let (releases, tags) = futures::try_join!(
+            self.list::<ReleaseResponse>("releases"),
+            self.list::<TagResponse>("tags")
+        )?;
+        let commits: HashMap<_, _> = tags
+            .into_iter()
+            .map(|tag| (tag.name, tag.commit.sha))
+            .collect();
+
+        let mut versions = HashMap::new();
+        for release in releases.into_iter().filter(|release| !release.draft) {
+            let tag = release.tag_name;
+            let commit = match commits.get(&tag) {
+                Some(commit) => commit.get(..8).unwrap_or(commit),
+                None => {
+                    log::warn!("skipping release {}: tag commit not found", tag);
+                    continue;
+                }
+            };
+            let asset = match release
+                .assets
+                .into_iter()
+                .find(|asset| asset.name == self.asset_name)
+            {
+                Some(asset) => asset,
+                None => {
+                    log::debug!("skipping release {}: no {} asset", tag, self.asset_name);
+                    continue;
+                }
+            };
+            let sha256 = match asset
+                .digest
+                .as_deref()
+                .and_then(|digest| digest.strip_prefix("sha256:"))
+                .and_then(|hash| H256::from_str(hash).ok())
+            {
+                Some(sha256) => sha256,
+                None => {
+                    log::warn!("skipping release {}: asset has no sha256 digest", tag);
+                    continue;
+                }
+            };
+            match Version::from_str(&format!("{}+commit.{}", tag, commit)) {
+                Ok(version) => {
+                    let asset = ReleaseAsset {
+                        url: asset.browser_download_url,
+                        sha256,
+                    };
+                    versions.insert(version, asset);
+                }
+                Err(err) => log::warn!("skipping release {}: {}", tag, err),
+            }
+        }
+        Ok(versions)
+    }
+}
+
+/// Fails with a descriptive error once the rate limit of the GitHub API is exhausted.
+fn check_rate_limit(response: &reqwest::Response) -> anyhow::Result<()> {
+    let exhausted = matches!(
+        response.status(),
+        StatusCode::FORBIDDEN | StatusCode::TOO_MANY_REQUESTS
+    ) && response
+        .headers()
+        .get("x-ratelimit-remaining")
+        .is_some_and(|remaining| remaining == "0");
+    if exhausted {
+        let reset = response
+            .headers()
+            .get("x-ratelimit-reset")
+            .and_then(|reset| reset.to_str().ok())
+            .unwrap_or("unknown");
+        anyhow::bail!(
+            "GitHub API rate limit exceeded, resets at {} (unix time); configure a token to raise the limit",
+            reset
+        );
+    }
+    Ok(())
+}
+
+/// Fetches compilers from the assets of GitHub releases (`ethereum/solidity` by default),
+/// verified against the sha256 digests published by GitHub.
+///
+/// Releases without the configured asset or its digest are not offered.
+pub struct GithubFetcher {
+    versions: RefreshableVersions<HashMap<Version, ReleaseAsset>>,
+    api: GithubApi,
+    folder: PathBuf,
+}
+
+impl GithubFetcher {
+    pub async fn new(
+        config: GithubFetcherConfig,
+        refresh_versions_schedule: Option<BackoffSchedule>,
+        folder: PathBuf,
+    ) -> anyhow::Result<Self> {
+        let api_url = match &config.api_url {
+            Some(api_url) => api_url.as_str(),
+            None => DEFAULT_API_URL,
+        };
+        let api = GithubApi {
+            repo_url: format!(
+                "{}/repos/{}",
+                api_url.trim_end_matches('/'),
+                config.repo.trim_matches('/')
+            ),
+            token: config.token,
+            asset_name: config.asset_name,
+        };
+
+        let versions = api.fetch_versions().await?;
+        let versions =
+            RefreshableVersions::new(versions, metrics::compiler_list_size(Language::Solidity));
+        if let Some(schedule) = refresh_versions_schedule {
+            let api = api.clone();
+            versions.clone().spawn_refresh_job(schedule, move || {
+                let api = api.clone();
+                async move { api.fetch_versions().await.map(Some) }
+            });
+        }
+        Ok(Self {
+            versions,
+            api,
+            folder,
+        })
+    }
+
+    fn asset(&self, ver: &Version) -> Result<ReleaseAsset, FetchError> {
+        self.versions
+            .read()
+            .get(ver)
+            .cloned()
+            .ok_or_else(|| FetchError::NotFound(ver.clone()))
+    }
+}
+
+#[async_trait]
+impl Fetcher for GithubFetcher {
+    async fn fetch(&self, ver: &Version) -> Result<PathBuf, FetchError> {
+        let asset = self.asset(ver)?;
+        let bytes = self
+            .api
+            .request(asset.url)
+            .header(header::ACCEPT, "application/octet-stream")
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(anyhow::Error::msg)?
+            .bytes()
+            .await
+            .map_err(anyhow::Error::msg)?;
+        save_executable(
+            bytes,
+            asset.sha256,
+            ChecksumKind::Sha256,
+            &self.folder,
+            Language::Solidity,
+            ver,
+        )
+        .await
+    }
+
+    fn all_versions(&self) -> Vec<Version> {
+        self.versions.read().keys().cloned().collect()
+    }
+
+    async fn published_hash(&self, ver: &Version) -> Result<Option<H256>, FetchError> {
+        self.asset(ver).map(|asset| Some(asset.sha256))
+    }
+
+    fn last_refresh(&self) -> Option<(Instant, Result<(), String>)> {
+        Some(self.versions.last_refresh())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use sha2::{Digest, Sha256};
+    use wiremock::{
+        matchers::{header, method, path, query_param},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    const BINARY: &[u8] = b"#!/bin/sh\necho solc\n";
+
+    fn config(mock_server: &MockServer, token: Option<&str>) -> GithubFetcherConfig {
+        GithubFetcherConfig {
+            repo: "ethereum/solidity".into(),
+            token: token.map(String::from),
+            asset_name: "solc-static-linux".into(),
+            api_url: Some(Url::parse(&mock_server.uri()).unwrap()),
+            refresh_versions_schedule: None,
+        }
+    }
+
+    fn release(mock_server: &MockServer, tag: &str, digest: Option<String>) -> serde_json::Value {
+        serde_json::json!({
+            "tag_name": tag,
+            "draft": false,
+            "assets": [
+                {
+                    "name": "soljson.js",
+                    "browser_download_url": format!("{}/download/{}/soljson.js", mock_server.uri(), tag),
+                },
+                {
+                    "name": "solc-static-linux",
+                    "browser_download_url": format!("{}/download/{}/solc-static-linux", mock_server.uri(), tag),
+                    "digest": digest,
+                },
+            ],
+        })
+    }
+
+    async fn mock_github() -> MockServer {
+        let mock_server = MockServer::start().await;
+        let digest = format!("sha256:{}", hex::encode(Sha256::digest(BINARY)));
+        Mock::given(method("GET"))
+            .and(path("/repos/ethereum/solidity/releases"))
+            .and(query_param("page", "1"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header(
+                        "link",
+                        format!(
+                            "<{}/repos/ethereum/solidity/releases?page=2>; rel=\"next\"",
+                            mock_server.uri()
+                        )
+                        .as_str(),
+                    )
+                    .set_body_json(serde_json::json!([
+                        release(&mock_server, "v0.8.14", Some(digest.clone())),
+                        release(&mock_server, "v0.8.13", None),
+                    ])),
+            )
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/repos/ethereum/solidity/releases"))
+            .and(query_param("page", "2"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(serde_json::json!([release(
+                    &mock_server,
+                    "v0.4.24",
+                    Some(digest)
+                )])),
+            )
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/repos/ethereum/solidity/tags"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!([
+                {"name": "v0.8.14", "commit": {"sha": "80d49f37028bb4a1cd337d64dc3b4088e3d3a9d0"}},
+                {"name": "v0.8.13", "commit": {"sha": "abaa5c0eb2a8ea0b1cbc0b7f6d6e1f5e7c2b0a4e"}},
+                {"name": "v0.4.24", "commit": {"sha": "e67f0147998a9e3835ed3ce8bf6a0a0c634216c5"}},
+            ])))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/download/v0.8.14/solc-static-linux"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(BINARY))
+            .mount(&mock_server)
+            .await;
+        mock_server
+    }
+
+    #[tokio::test]
+    async fn fetch_from_releases() {
+        let mock_server = mock_github().await;
+        let folder = std::env::temp_dir().join("blockscout/verification/github_fetcher/test/");
+        let fetcher = GithubFetcher::new(config(&mock_server, None), None, folder)
+            .await
+            .expect("fetcher should be initialized");
+
+        let mut versions = fetcher.all_versions();
+        versions.sort();
+        let version = Version::from_str("v0.8.14+commit.80d49f37").unwrap();
+        // the release without digest is not offered
+        assert_eq!(
+            versions,
+            vec![
+                Version::from_str("v0.4.24+commit.e67f0147").unwrap(),
+                version.clone(),
+            ]
+        );
+        assert_eq!(
+            fetcher.published_hash(&version).await.unwrap(),
+            Some(H256::from_slice(&Sha256::digest(BINARY)))
+        );
+        let file = fetcher
+            .fetch(&version)
+            .await
+            .expect("compiler should be fetched");
+        assert_eq!(std::fs::read(file).unwrap(), BINARY);
+    }
+
+    #[tokio::test]
+    async fn authenticates_with_token() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(header("authorization", "Bearer secret"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!([])))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .respond_with(
+                ResponseTemplate::new(403)
+                    .insert_header("x-ratelimit-remaining", "0")
+                    .insert_header("x-ratelimit-reset", "1700000000"),
+            )
+            .mount(&mock_server)
+            .await;
+        let folder = std::env::temp_dir().join("blockscout/verification/github_fetcher/test/");
+
+        let err = GithubFetcher::new(config(&mock_server, None), None, folder.clone())
+            .await
+            .err()
+            .expect("rate limit should be exceeded");
+        assert!(err.to_string().contains("1700000000"), "{}", err);
+        let fetcher = GithubFetcher::new(config(&mock_server, Some("secret")), None, folder)
+            .await
+            .expect("fetcher should be initialized");
+        assert!(fetcher.all_versions().is_empty());
+    }
+}
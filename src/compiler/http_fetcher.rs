@@ -0,0 +1,285 @@
+use super::{
+    fetcher::FetchError,
+    list_fetcher::{save_executable, ChecksumKind},
+    refreshable_versions::RefreshableVersions,
+};
+use crate::{
+    compiler::{Fetcher, Language, Version},
+    config::HttpFetcherConfig,
+    metrics,
+    scheduler::BackoffSchedule,
+};
+use anyhow::Context;
+use async_trait::async_trait;
+use primitive_types::H256;
+use std::{collections::HashSet, path::PathBuf, str::FromStr, time::Instant};
+use url::Url;
+
+/// Placeholder substituted with the percent-encoded compiler version in url templates.
+const VERSION_PLACEHOLDER: &str = "{version}";
+
+/// Fetches compilers from a flat mirror, where binary and hashsum urls
+/// are derived from the templates, e.g. `https://host/{version}/solc`.
+///
+/// Available versions are discovered from the index url,
+/// which should return a json array of version strings.
+pub struct HttpFetcher {
+    versions: RefreshableVersions<HashSet<Version>>,
+    binary_url_template: String,
+    hash_url_template: String,
+    folder: PathBuf,
+    language: Language,
+}
+
+async fn try_fetch_versions(index_url: &Url) -> anyhow::Result<HashSet<Version>> {
+    let versions: Vec<String> = reqwest::get(index_url.as_str())
+        .await
+        .and_then(|response| response.error_for_status())
+        .context("fetching versions index returned error")?
+        .json()
+        .await
+        .context("cannot parse versions index")?;
+    let versions = versions
+        .iter()
+        .filter_map(|version| match Version::from_str(version) {
+            Ok(version) => Some(version),
+            Err(err) => {
+                log::warn!(
+                    "skipping invalid version {} in versions index: {}",
+                    version,
+                    err
+                );
+                None
+            }
+        })
+        .collect();
+    Ok(versions)
+}
+
+impl HttpFetcher {
+    pub async fn new(
+        config: HttpFetcherConfig,
+        refresh_versions_schedule: Option<BackoffSchedule>,
+        folder: PathBuf,
+        language: Language,
+    ) -> anyhow::Result<Self> {
+        for template in [&config.binary_url_template, &config.hash_url_template] {
+            if !template.contains(VERSION_PLACEHOLDER) {
+                anyhow::bail!(
+                    "url template '{}' does not contain '{}' placeholder",
+                    template,
+                    VERSION_PLACEHOLDER
+                );
+            }
+        }
+
+        let versions = try_fetch_versions(&config.index_url).await?;
+        let versions = RefreshableVersions::new(versions, metrics::compiler_list_size(language));
+        if let Some(schedule) = refresh_versions_schedule {
+            let index_url = config.index_url.clone();
+            versions.clone().spawn_refresh_job(schedule, move || {
+                let index_url = index_url.clone();
+                async move { try_fetch_versions(&index_url).await.map(Some) }
+            });
+        }
+        Ok(Self {
+            versions,
+            binary_url_template: config.binary_url_template,
+            hash_url_template: config.hash_url_template,
+            folder,
+            language,
+        })
+    }
+
+    fn url(template: &str, ver: &Version) -> Result<Url, FetchError> {
+        let url = template.replace(VERSION_PLACEHOLDER, &ver.to_path_segment());
+        Url::parse(&url)
+            .with_context(|| format!("invalid url: {}", url))
+            .map_err(FetchError::Fetch)
+    }
+
+    async fn get(url: Url) -> Result<bytes::Bytes, FetchError> {
+        let bytes = reqwest::get(url)
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(anyhow::Error::msg)?
+            .bytes()
+            .await
+            .map_err(anyhow::Error::msg)?;
+        Ok(bytes)
+    }
+
+    /// Parses the hashsum file content. Besides the plain hex-encoded hash,
+    /// the `sha256sum` output format (`<hash>  <filename>`) is accepted.
+    pub(super) fn parse_hashsum(content: &[u8]) -> Result<H256, FetchError> {
+        let content = String::from_utf8_lossy(content);
+        let hash = content.split_whitespace().next().unwrap_or_default();
+        H256::from_str(hash.trim_start_matches("0x"))
+            .with_context(|| format!("invalid hashsum: {}", hash))
+            .map_err(FetchError::Fetch)
+    }
+}
+
+#[async_trait]
+impl Fetcher for HttpFetcher {
+    async fn fetch(&self, ver: &Version) -> Result<PathBuf, FetchError> {
+        if !self.versions.read().contains(ver) {
+            return Err(FetchError::NotFound(ver.clone()));
+        }
+
+        let hash_url = Self::url(&self.hash_url_template, ver)?;
+        let binary_url = Self::url(&self.binary_url_template, ver)?;
+        let (hashsum, bytes) = futures::try_join!(Self::get(hash_url), Self::get(binary_url))?;
+        let sha256 = Self::parse_hashsum(&hashsum)?;
+        save_executable(
+            bytes,
+            sha256,
+            ChecksumKind::Sha256,
+            &self.folder,
+            self.language,
+            ver,
+        )
+        .await
+    }
+
+    fn all_versions(&self) -> Vec<Version> {
+        self.versions.read().iter().cloned().collect()
+    }
+
+    async fn published_hash(&self, ver: &Version) -> Result<Option<H256>, FetchError> {
+        if !self.versions.read().contains(ver) {
+            return Err(FetchError::NotFound(ver.clone()));
+        }
+        let hashsum = Self::get(Self::url(&self.hash_url_template, ver)?).await?;
+        Self::parse_hashsum(&hashsum).map(Some)
+    }
+
+    fn last_refresh(&self) -> Option<(Instant, Result<(), String>)> {
+        Some(self.versions.last_refresh())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use sha2::{Digest, Sha256};
+    use wiremock::{
+        matchers::{method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    const BINARY: &[u8] = b"#!/bin/sh\necho solc\n";
+
+    async fn mock_mirror(hashsum: String) -> MockServer {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/index.json"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(
+                r#"["v0.8.9+commit.e5eed63a", "not-a-version", "0.4.13+commit.0fb4cb1a"]"#,
+            ))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/v0.8.9%2Bcommit.e5eed63a/solc"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(BINARY))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/v0.8.9%2Bcommit.e5eed63a/solc.sha256"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(hashsum))
+            .mount(&mock_server)
+            .await;
+        mock_server
+    }
+
+    fn config(mock_server: &MockServer) -> HttpFetcherConfig {
+        HttpFetcherConfig {
+            index_url: Url::parse(&format!("{}/index.json", mock_server.uri())).unwrap(),
+            binary_url_template: format!("{}/{{version}}/solc", mock_server.uri()),
+            hash_url_template: format!("{}/{{version}}/solc.sha256", mock_server.uri()),
+            refresh_versions_schedule: None,
+        }
+    }
+
+    fn folder(name: &str) -> PathBuf {
+        std::env::temp_dir()
+            .join("blockscout/verification/http_fetcher/test/")
+            .join(name)
+    }
+
+    #[tokio::test]
+    async fn fetch_templated_layout() {
+        let hashsum = format!("{}  solc\n", hex::encode(Sha256::digest(BINARY)));
+        let mock_server = mock_mirror(hashsum).await;
+        let fetcher = HttpFetcher::new(
+            config(&mock_server),
+            None,
+            folder("templated"),
+            Language::Solidity,
+        )
+        .await
+        .expect("fetcher should be initialized");
+
+        // invalid versions in the index are skipped
+        let mut versions = fetcher.all_versions();
+        versions.sort();
+        assert_eq!(
+            versions,
+            vec![
+                Version::from_str("0.4.13+commit.0fb4cb1a").unwrap(),
+                Version::from_str("0.8.9+commit.e5eed63a").unwrap(),
+            ]
+        );
+
+        let file = fetcher
+            .fetch(&Version::from_str("0.8.9+commit.e5eed63a").unwrap())
+            .await
+            .expect("compiler should be fetched");
+        assert_eq!(std::fs::read(file).unwrap(), BINARY);
+
+        let not_listed = Version::from_str("0.8.10+commit.fc410830").unwrap();
+        assert!(matches!(
+            fetcher.fetch(&not_listed).await,
+            Err(FetchError::NotFound(_))
+        ));
+        // listed, but not hosted by the mirror
+        let not_hosted = Version::from_str("0.4.13+commit.0fb4cb1a").unwrap();
+        assert!(matches!(
+            fetcher.fetch(&not_hosted).await,
+            Err(FetchError::Fetch(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn fetch_hash_mismatch() {
+        let mock_server = mock_mirror(format!("0x{}", hex::encode([0u8; 32]))).await;
+        let fetcher = HttpFetcher::new(
+            config(&mock_server),
+            None,
+            folder("mismatch"),
+            Language::Solidity,
+        )
+        .await
+        .expect("fetcher should be initialized");
+        let result = fetcher
+            .fetch(&Version::from_str("0.8.9+commit.e5eed63a").unwrap())
+            .await;
+        assert!(matches!(result, Err(FetchError::HashMismatch(_))));
+    }
+
+    #[tokio::test]
+    async fn template_without_placeholder() {
+        let mock_server = mock_mirror(String::new()).await;
+        let config = HttpFetcherConfig {
+            binary_url_template: format!("{}/solc", mock_server.uri()),
+            ..config(&mock_server)
+        };
+        assert!(
+            HttpFetcher::new(config, None, folder("invalid"), Language::Solidity)
+                .await
+                .is_err(),
+            "templates without placeholder should be rejected"
+        );
+    }
+}
@@ -0,0 +1,102 @@
+use super::version::Version;
+use std::{
+    fmt::Display,
+    path::{Path, PathBuf},
+};
+
+/// Language the compilers are used for.
+///
+/// Compilers of each language are stored in a separate folder under the shared root,
+/// e.g. `<root>/solidity/<version>/solc` and `<root>/vyper/<version>/vyper`,
+/// so that versions of different languages never collide.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Language {
+    Solidity,
+    Vyper,
+}
+
+impl Language {
+    /// Lowercase name of the language, used as the `language` label of the metrics.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Language::Solidity => "solidity",
+            Language::Vyper => "vyper",
+        }
+    }
+
+    /// Name of the folder under the shared root containing compilers of the language.
+    pub fn dir_name(&self) -> &'static str {
+        self.as_str()
+    }
+
+    /// Name of the compiler executable inside the version folder.
+    pub fn executable_name(&self) -> &'static str {
+        match self {
+            Language::Solidity => "solc",
+            Language::Vyper => "vyper",
+        }
+    }
+
+    /// Folder under the shared `root` containing all compilers of the language.
+    pub fn compilers_dir(&self, root: &Path) -> PathBuf {
+        root.join(self.dir_name())
+    }
+
+    /// Folder under the shared `root` containing the compiler of the specified version,
+    /// named by [`Version::to_path_segment`].
+    pub fn version_dir(&self, root: &Path, ver: &Version) -> PathBuf {
+        self.compilers_dir(root).join(ver.to_path_segment())
+    }
+}
+
+impl Display for Language {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics;
+    use std::str::FromStr;
+
+    #[test]
+    fn language_labels() {
+        assert_eq!(Language::Solidity.as_str(), "solidity");
+        assert_eq!(Language::Vyper.as_str(), "vyper");
+        assert_eq!(Language::Vyper.to_string(), "vyper");
+
+        metrics::compiler_list_size(Language::Vyper).set(3);
+        assert_eq!(
+            metrics::COMPILER_LIST_SIZE
+                .with_label_values(&["vyper"])
+                .get(),
+            3
+        );
+    }
+
+    #[test]
+    fn version_dir_is_encoded() {
+        let version = Version::from_str("v0.8.7+commit.e28d00a7").unwrap();
+        let dir = Language::Solidity.version_dir(Path::new("compilers"), &version);
+        assert_eq!(
+            dir,
+            Path::new("compilers/solidity/v0.8.7%2Bcommit.e28d00a7")
+        );
+        let name = dir.file_name().and_then(|name| name.to_str()).unwrap();
+        assert_eq!(Version::from_path_segment(name).unwrap(), version);
+    }
+
+    #[test]
+    fn cached_compilers_are_labeled() {
+        metrics::cached_compilers(Language::Vyper).set(2);
+        metrics::cached_compilers(Language::Solidity).set(5);
+        assert_eq!(
+            metrics::CACHED_COMPILERS
+                .with_label_values(&["vyper"])
+                .get(),
+            2
+        );
+    }
+}
@@ -1,22 +1,34 @@
-use super::fetcher::FetchError;
+use super::{
+    fetcher::FetchError, list_history::ListHistory, refreshable_versions::RefreshableVersions,
+};
 use crate::{
-    compiler::{Fetcher, Version},
-    scheduler,
+    compiler::{Fetcher, Language, Version},
+    metrics,
+    scheduler::BackoffSchedule,
     types::Mismatch,
 };
 use async_trait::async_trait;
 use bytes::Bytes;
-use cron::Schedule;
+use parking_lot::Mutex;
 use primitive_types::H256;
+use prometheus::IntGauge;
+use reqwest::{
+    header::{
+        HeaderValue, CONTENT_ENCODING, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED,
+    },
+    StatusCode,
+};
+use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
     fmt::Debug,
     fs::{File, OpenOptions},
     io::ErrorKind,
     os::unix::prelude::OpenOptionsExt,
     path::{Path, PathBuf},
     sync::Arc,
+    time::Instant,
 };
 use thiserror::Error;
 use url::Url;
@@ -39,6 +51,8 @@ mod json {
         #[serde(with = "serde_with::rust::display_fromstr")]
         pub long_version: compiler::Version,
         pub sha256: H256,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub keccak256: Option<H256>,
     }
 
     #[derive(Debug, Deserialize, Serialize, PartialEq)]
@@ -53,8 +67,38 @@ type VersionsMap = HashMap<Version, CompilerInfo>;
 
 #[derive(Debug, PartialEq, Clone)]
 struct CompilerInfo {
-    pub url: Url,
+    /// Urls the compiler is downloaded from, tried in order
+    pub urls: Vec<Url>,
     pub sha256: H256,
+    pub keccak256: Option<H256>,
+}
+
+impl CompilerInfo {
+    /// Hashsum of the `checksum` kind published in the list, if any.
+    fn hashsum(&self, checksum: ChecksumKind) -> Option<H256> {
+        match checksum {
+            ChecksumKind::Sha256 => Some(self.sha256),
+            ChecksumKind::Keccak256 => self.keccak256,
+        }
+    }
+}
+
+/// Hash algorithm the downloaded compilers are checked with.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChecksumKind {
+    #[default]
+    Sha256,
+    Keccak256,
+}
+
+impl ChecksumKind {
+    fn digest(self, bytes: &[u8]) -> H256 {
+        match self {
+            ChecksumKind::Sha256 => H256::from_slice(&Sha256::digest(bytes)),
+            ChecksumKind::Keccak256 => H256::from(ethers_core::utils::keccak256(bytes)),
+        }
+    }
 }
 
 #[derive(Error, Debug)]
@@ -62,128 +106,309 @@ pub enum ListError {
     #[error("fetching list json returned error: {0}")]
     ListJsonFetch(reqwest::Error),
     #[error("cannot parse list json file: {0}")]
-    ParseListJson(reqwest::Error),
+    ParseListJson(serde_json::Error),
+    #[error("cannot decompress list json file: {0}")]
+    Decompress(std::io::Error),
     #[error("error parsing 'path' field: {0}")]
     Path(url::ParseError),
+    #[error("no compilers list urls configured")]
+    NoUrls,
 }
 
-async fn try_fetch_versions(versions_list_url: &Url) -> Result<VersionsMap, ListError> {
-    let list_json_file: json::List = reqwest::get(versions_list_url.as_str())
-        .await
-        .map_err(ListError::ListJsonFetch)?
-        .json()
+/// `ETag` and `Last-Modified` of the list served by the `url`, sent with the next request
+/// to the same url, so that the unchanged list is answered with `304 Not Modified`.
+#[derive(Debug, Clone)]
+struct ListValidators {
+    url: Url,
+    etag: Option<HeaderValue>,
+    last_modified: Option<HeaderValue>,
+}
+
+type SharedValidators = Arc<Mutex<Option<ListValidators>>>;
+
+/// Returns `None` if the list has not been modified since the last fetch from the same url.
+/// The validators of the fetched list are returned to be saved once the list is parsed.
+async fn try_fetch_list(
+    versions_list_url: &Url,
+    validators: &Mutex<Option<ListValidators>>,
+) -> Result<Option<(json::List, ListValidators)>, ListError> {
+    let mut request = reqwest::Client::new().get(versions_list_url.as_str());
+    if let Some(previous) = validators
+        .lock()
+        .as_ref()
+        .filter(|previous| &previous.url == versions_list_url)
+    {
+        if let Some(etag) = &previous.etag {
+            request = request.header(IF_NONE_MATCH, etag.clone());
+        }
+        if let Some(last_modified) = &previous.last_modified {
+            request = request.header(IF_MODIFIED_SINCE, last_modified.clone());
+        }
+    }
+    let response = request
+        .send()
         .await
-        .map_err(ListError::ParseListJson)?;
-    try_parse_json_file(list_json_file, versions_list_url)
+        .and_then(|response| response.error_for_status())
+        .map_err(ListError::ListJsonFetch)?;
+    if response.status() == StatusCode::NOT_MODIFIED {
+        metrics::COMPILER_LIST_NOT_MODIFIED.inc();
+        return Ok(None);
+    }
+    let fetched = ListValidators {
+        url: versions_list_url.clone(),
+        etag: response.headers().get(ETAG).cloned(),
+        last_modified: response.headers().get(LAST_MODIFIED).cloned(),
+    };
+    let gzipped = versions_list_url.path().ends_with(".gz")
+        || response
+            .headers()
+            .get(CONTENT_ENCODING)
+            .is_some_and(|encoding| encoding.as_bytes().eq_ignore_ascii_case(b"gzip"));
+    let body = response.bytes().await.map_err(ListError::ListJsonFetch)?;
+    let list_json_file = parse_list(&body, gzipped)?;
+    Ok(Some((list_json_file, fetched)))
+}
+
+/// Parses the list json, decompressing it first if `gzipped`.
+fn parse_list(body: &[u8], gzipped: bool) -> Result<json::List, ListError> {
+    if !gzipped {
+        return serde_json::from_slice(body).map_err(ListError::ParseListJson);
+    }
+    // reqwest is built without the `gzip` feature, so the body is decompressed here
+    let mut decompressed = Vec::new();
+    std::io::Read::read_to_end(&mut flate2::read::GzDecoder::new(body), &mut decompressed)
+        .map_err(ListError::Decompress)?;
+    serde_json::from_slice(&decompressed).map_err(ListError::ParseListJson)
+}
+
+/// Fetches the list from the first of the `versions_list_urls` (the primary url
+/// followed by its mirrors) which succeeds. Returns `None` if the list has not been modified.
+async fn try_fetch_versions(
+    versions_list_urls: &[Url],
+    validators: &Mutex<Option<ListValidators>>,
+) -> Result<Option<VersionsMap>, ListError> {
+    let mut last_error = ListError::NoUrls;
+    for (i, versions_list_url) in versions_list_urls.iter().enumerate() {
+        match try_fetch_list(versions_list_url, validators).await {
+            Ok(None) => {
+                log::info!("compilers list at {} not modified", versions_list_url);
+                return Ok(None);
+            }
+            Ok(Some((list_json_file, fetched))) => {
+                log::info!("compilers list fetched from {}", versions_list_url);
+                // compilers are downloaded from the mirror which served the list first
+                let mut download_urls = versions_list_urls.to_vec();
+                download_urls[..=i].rotate_right(1);
+                let versions = try_parse_json_file(list_json_file, &download_urls)?;
+                // saved only now, so that the invalid list is not reported as not modified
+                *validators.lock() = Some(fetched);
+                return Ok(Some(versions));
+            }
+            Err(err) => {
+                log::warn!(
+                    "fetching compilers list from {} failed: {}",
+                    versions_list_url,
+                    err
+                );
+                last_error = err;
+            }
+        }
+    }
+    Err(last_error)
 }
 
 fn try_parse_json_file(
     list_json_file: json::List,
-    versions_list_url: &Url,
+    versions_list_urls: &[Url],
 ) -> Result<VersionsMap, ListError> {
     let mut compiler_versions = HashMap::default();
     for json_compiler_info in list_json_file.builds {
         let version = json_compiler_info.long_version.clone();
-        let compiler_info = CompilerInfo::try_from((json_compiler_info, versions_list_url))
+        let compiler_info = CompilerInfo::try_from((json_compiler_info, versions_list_urls))
             .map_err(ListError::Path)?;
         compiler_versions.insert(version, compiler_info);
     }
     Ok(compiler_versions)
 }
 
-impl TryFrom<(json::CompilerInfo, &Url)> for CompilerInfo {
+impl TryFrom<(json::CompilerInfo, &[Url])> for CompilerInfo {
     type Error = url::ParseError;
 
+    /// Filenames are resolved relative to every list url, so that they are downloaded
+    /// from the mirrors as well; absolute urls are downloaded as is.
     fn try_from(
-        (compiler_info, download_url): (json::CompilerInfo, &Url),
+        (compiler_info, download_urls): (json::CompilerInfo, &[Url]),
     ) -> Result<Self, Self::Error> {
-        let url = match compiler_info.path {
-            json::DownloadPath::Url(url) => url,
+        let urls = match compiler_info.path {
+            json::DownloadPath::Url(url) => vec![url],
             // download_url ends with `.../list.json` but join() will replace this with `filename`
-            json::DownloadPath::Filename(filename) => download_url.join(&filename)?,
+            json::DownloadPath::Filename(filename) => download_urls
+                .iter()
+                .map(|download_url| download_url.join(&filename))
+                .collect::<Result<_, _>>()?,
         };
         Ok(Self {
-            url,
+            urls,
             sha256: compiler_info.sha256,
+            keccak256: compiler_info.keccak256,
         })
     }
 }
 
-#[derive(Default, Clone)]
-struct Versions(Arc<parking_lot::RwLock<VersionsMap>>);
+/// Downloads the compiler from the first of its urls which succeeds.
+async fn download(compiler_info: &CompilerInfo) -> Result<Bytes, FetchError> {
+    let mut last_error = None;
+    for url in &compiler_info.urls {
+        let response = reqwest::get(url.as_str())
+            .await
+            .and_then(|response| response.error_for_status());
+        let bytes = match response {
+            Ok(response) => response.bytes().await,
+            Err(err) => Err(err),
+        };
+        match bytes {
+            Ok(bytes) => return Ok(bytes),
+            Err(err) => {
+                log::warn!("downloading compiler from {} failed: {}", url, err);
+                last_error = Some(err);
+            }
+        }
+    }
+    Err(match last_error {
+        Some(err) => anyhow::Error::msg(err).into(),
+        None => anyhow::anyhow!("no compiler urls in the list").into(),
+    })
+}
+
+#[derive(Clone)]
+struct Versions {
+    versions: RefreshableVersions<VersionsMap>,
+    /// Records a snapshot of the versions on every refresh, if set
+    history: Option<Arc<ListHistory>>,
+    validators: SharedValidators,
+}
 
 impl Versions {
-    fn spawn_refresh_job(self, versions_list_url: Url, cron_schedule: Schedule) {
-        log::info!("spawn version refresh job");
-        scheduler::spawn_job(cron_schedule, "refresh compiler versions", move || {
-            let versions_list_url = versions_list_url.clone();
-            let versions = self.clone();
-            async move {
-                let refresh_result = versions.refresh_versions(&versions_list_url).await;
-                if let Err(err) = refresh_result {
-                    log::error!("error during version refresh: {}", err);
-                };
-            }
-        });
+    fn new(versions: VersionsMap) -> Self {
+        let size_metric = metrics::compiler_list_size(Language::Solidity);
+        Self::with_size_metric(versions, size_metric)
     }
 
-    async fn refresh_versions(&self, versions_list_url: &Url) -> anyhow::Result<()> {
-        log::info!("looking for new compilers versions");
-        let fetched_versions = try_fetch_versions(versions_list_url)
+    fn with_size_metric(versions: VersionsMap, size_metric: IntGauge) -> Self {
+        Self {
+            versions: RefreshableVersions::new(versions, size_metric),
+            history: None,
+            validators: Default::default(),
+        }
+    }
+
+    fn with_validators(mut self, validators: SharedValidators) -> Self {
+        self.validators = validators;
+        self
+    }
+
+    fn with_history(mut self, history: Option<ListHistory>) -> Self {
+        self.history = history.map(Arc::new);
+        self
+    }
+
+    async fn record_history(history: Option<&ListHistory>, versions: &VersionsMap) {
+        if let Some(history) = history {
+            let compilers: BTreeMap<_, _> = versions
+                .iter()
+                .map(|(version, info)| (version.to_string(), info.sha256))
+                .collect();
+            history.record(compilers).await;
+        }
+    }
+
+    /// Fetches the list and records its snapshot into the history.
+    /// Returns `None` if the list has not been modified.
+    async fn fetch(
+        history: Option<Arc<ListHistory>>,
+        validators: SharedValidators,
+        versions_list_urls: &[Url],
+    ) -> anyhow::Result<Option<VersionsMap>> {
+        let fetched_versions = try_fetch_versions(versions_list_urls, &validators)
             .await
             .map_err(anyhow::Error::msg)?;
-        let need_to_update = {
-            let versions = self.0.read();
-            fetched_versions != *versions
-        };
-        if need_to_update {
-            let (old_len, new_len) = {
-                // we don't need to check condition again,
-                // we can just override the value
-                let mut versions = self.0.write();
-                let old_len = versions.len();
-                *versions = fetched_versions;
-                let new_len = versions.len();
-                (old_len, new_len)
-            };
-            log::info!(
-                "found new compiler versions. old length: {}, new length: {}",
-                old_len,
-                new_len,
-            );
-        } else {
-            log::info!("no new versions found")
+        if let Some(fetched_versions) = &fetched_versions {
+            Self::record_history(history.as_deref(), fetched_versions).await;
+        }
+        Ok(fetched_versions)
+    }
+
+    fn spawn_refresh_job(self, versions_list_urls: Vec<Url>, schedule: BackoffSchedule) {
+        let history = self.history;
+        let validators = self.validators;
+        self.versions.spawn_refresh_job(schedule, move || {
+            let history = history.clone();
+            let validators = validators.clone();
+            let versions_list_urls = versions_list_urls.clone();
+            async move { Self::fetch(history, validators, &versions_list_urls).await }
+        });
+    }
+
+    #[cfg(test)]
+    async fn refresh_versions(&self, versions_list_url: &Url) -> anyhow::Result<()> {
+        let fetched_versions = Self::fetch(
+            self.history.clone(),
+            self.validators.clone(),
+            std::slice::from_ref(versions_list_url),
+        )
+        .await?;
+        if let Some(fetched_versions) = fetched_versions {
+            self.versions.update(fetched_versions);
         }
         Ok(())
     }
 }
 
-#[derive(Default)]
 pub struct ListFetcher {
     compiler_versions: Versions,
     folder: PathBuf,
+    checksum: ChecksumKind,
 }
 
 impl ListFetcher {
+    /// The list is fetched from the `versions_list_url`, or from the `mirrors` tried in order
+    /// if it fails; the compilers with relative paths are downloaded from the mirrors as well.
     pub async fn new(
         versions_list_url: Url,
-        refresh_versions_schedule: Option<Schedule>,
+        mirrors: Vec<Url>,
+        refresh_versions_schedule: Option<BackoffSchedule>,
         folder: PathBuf,
+        history: Option<ListHistory>,
     ) -> anyhow::Result<Self> {
-        let compiler_versions = try_fetch_versions(&versions_list_url)
+        let versions_list_urls: Vec<_> =
+            std::iter::once(versions_list_url).chain(mirrors).collect();
+        let validators = SharedValidators::default();
+        let compiler_versions = try_fetch_versions(&versions_list_urls, &validators)
             .await
-            .map_err(anyhow::Error::msg)?;
-        let compiler_versions = Versions(Arc::new(parking_lot::RwLock::new(compiler_versions)));
-        if let Some(cron_schedule) = refresh_versions_schedule {
+            .map_err(anyhow::Error::msg)?
+            .ok_or_else(|| anyhow::anyhow!("compilers list not modified before being fetched"))?;
+        Versions::record_history(history.as_ref(), &compiler_versions).await;
+        let compiler_versions = Versions::new(compiler_versions)
+            .with_history(history)
+            .with_validators(validators);
+        if let Some(schedule) = refresh_versions_schedule {
             compiler_versions
                 .clone()
-                .spawn_refresh_job(versions_list_url.clone(), cron_schedule)
+                .spawn_refresh_job(versions_list_urls, schedule)
         }
         Ok(Self {
             compiler_versions,
             folder,
+            checksum: ChecksumKind::default(),
         })
     }
+
+    /// Downloaded compilers are checked against the hashsums of the `checksum` kind
+    /// published in the list. Compilers without such hashsums fail to be fetched.
+    pub fn with_checksum(mut self, checksum: ChecksumKind) -> Self {
+        self.checksum = checksum;
+        self
+    }
 }
 
 #[cfg(target_family = "unix")]
@@ -196,14 +421,17 @@ fn create_executable(path: &Path) -> Result<File, std::io::Error> {
         .open(path)
 }
 
-pub fn check_hashsum(bytes: &Bytes, expected: H256) -> Result<(), Mismatch<H256>> {
-    let start = std::time::Instant::now();
+pub fn check_hashsum(
+    bytes: &Bytes,
+    expected: H256,
+    checksum: ChecksumKind,
+) -> Result<(), Mismatch<H256>> {
+    let timer = metrics::COMPILER_HASH_SECONDS.start_timer();
 
-    let mut hasher = Sha256::new();
-    hasher.update(bytes);
-    let found = H256::from_slice(&hasher.finalize());
+    let found = checksum.digest(bytes);
 
-    let took = std::time::Instant::now() - start;
+    let took = std::time::Duration::from_secs_f64(timer.stop_and_record());
+    metrics::COMPILER_HASHED_BYTES.inc_by(bytes.len() as u64);
     log::debug!("check hashsum of {} bytes took {:?}", bytes.len(), took,);
     if expected != found {
         Err(Mismatch::new(expected, found))
@@ -212,69 +440,128 @@ pub fn check_hashsum(bytes: &Bytes, expected: H256) -> Result<(), Mismatch<H256>
     }
 }
 
+/// Checks the hashsum of the `checksum` kind of the fetched compiler and saves it
+/// as the `language` executable inside the version folder under the shared `root`.
+pub(super) async fn save_executable(
+    bytes: Bytes,
+    expected: H256,
+    checksum: ChecksumKind,
+    root: &Path,
+    language: Language,
+    ver: &Version,
+) -> Result<PathBuf, FetchError> {
+    let folder = language.version_dir(root, ver);
+    let file = folder.join(language.executable_name());
+    // The compiler is written into a temporary file first, so that an interrupted
+    // download never leaves a partially written executable in the folder
+    let temp_file = folder.join(format!("{}.tmp", language.executable_name()));
+
+    let save_result = {
+        let file = file.clone();
+        let temp_file = temp_file.clone();
+        let bytes = bytes.clone();
+        tokio::task::spawn_blocking(move || -> Result<(), FetchError> {
+            std::fs::create_dir_all(&folder)?;
+            std::fs::remove_file(file.as_path()).or_else(|e| {
+                if e.kind() == ErrorKind::NotFound {
+                    Ok(())
+                } else {
+                    Err(e)
+                }
+            })?;
+            let mut file = create_executable(temp_file.as_path())?;
+            std::io::copy(&mut bytes.as_ref(), &mut file)?;
+            Ok(())
+        })
+    };
+
+    let check_result =
+        tokio::task::spawn_blocking(move || check_hashsum(&bytes, expected, checksum));
+
+    let result: Result<(), FetchError> = async {
+        // both tasks are awaited, so that the temporary file is not written after its removal
+        let checked = check_result.await;
+        let saved = save_result.await;
+        checked??;
+        saved??;
+        tokio::fs::rename(&temp_file, &file).await?;
+        Ok(())
+    }
+    .await;
+    if let Err(err) = result {
+        if let Err(remove_err) = tokio::fs::remove_file(&temp_file).await {
+            if remove_err.kind() != ErrorKind::NotFound {
+                log::warn!(
+                    "cannot remove temporary file {:?}: {}",
+                    temp_file,
+                    remove_err
+                );
+            }
+        }
+        return Err(err);
+    }
+
+    Ok(file)
+}
+
 #[async_trait]
 impl Fetcher for ListFetcher {
     async fn fetch(&self, ver: &Version) -> Result<PathBuf, FetchError> {
         let compiler_info = {
-            let compiler_versions = self.compiler_versions.0.read();
+            let compiler_versions = self.compiler_versions.versions.read();
             let compiler_info = compiler_versions
                 .get(ver)
                 .ok_or_else(|| FetchError::NotFound(ver.clone()))?;
             (*compiler_info).clone()
         };
 
-        let response = reqwest::get(compiler_info.url.to_string())
-            .await
-            .map_err(anyhow::Error::msg)?;
-        let folder = self.folder.join(ver.to_string());
-        let file = folder.join("solc");
-        let bytes = response.bytes().await.map_err(anyhow::Error::msg)?;
-
-        let save_result = {
-            let file = file.clone();
-            let bytes = bytes.clone();
-            tokio::task::spawn_blocking(move || -> Result<(), FetchError> {
-                std::fs::create_dir_all(&folder)?;
-                std::fs::remove_file(file.as_path()).or_else(|e| {
-                    if e.kind() == ErrorKind::NotFound {
-                        Ok(())
-                    } else {
-                        Err(e)
-                    }
-                })?;
-                let mut file = create_executable(file.as_path())?;
-                std::io::copy(&mut bytes.as_ref(), &mut file)?;
-                Ok(())
-            })
-        };
-
-        let check_result =
-            tokio::task::spawn_blocking(move || check_hashsum(&bytes, compiler_info.sha256));
+        let expected = compiler_info.hashsum(self.checksum).ok_or_else(|| {
+            anyhow::anyhow!(
+                "no {:?} hashsum of version {} in the list",
+                self.checksum,
+                ver
+            )
+        })?;
+        let bytes = download(&compiler_info).await?;
+        save_executable(
+            bytes,
+            expected,
+            self.checksum,
+            &self.folder,
+            Language::Solidity,
+            ver,
+        )
+        .await
+    }
 
-        check_result.await??;
-        save_result.await??;
+    fn all_versions(&self) -> Vec<Version> {
+        let compiler_versions = self.compiler_versions.versions.read();
+        compiler_versions.keys().cloned().collect()
+    }
 
-        Ok(file)
+    async fn published_hash(&self, ver: &Version) -> Result<Option<H256>, FetchError> {
+        let compiler_versions = self.compiler_versions.versions.read();
+        let compiler_info = compiler_versions
+            .get(ver)
+            .ok_or_else(|| FetchError::NotFound(ver.clone()))?;
+        Ok(Some(compiler_info.sha256))
     }
 
-    fn all_versions(&self) -> Vec<Version> {
-        let compiler_versions = self.compiler_versions.0.read();
-        compiler_versions
-            .iter()
-            .map(|(ver, _)| ver.clone())
-            .collect()
+    fn last_refresh(&self) -> Option<(Instant, Result<(), String>)> {
+        Some(self.compiler_versions.versions.last_refresh())
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{tests::parse::test_deserialize_ok, Config};
+    use crate::{compiler::list_history, tests::parse::test_deserialize_ok, Config};
+    use cron::Schedule;
     use ethers_solc::Solc;
     use pretty_assertions::assert_eq;
     use std::{env::temp_dir, str::FromStr};
     use wiremock::{
-        matchers::{method, path},
+        matchers::{header, method, path},
         Mock, MockServer, ResponseTemplate,
     };
 
@@ -288,7 +575,8 @@ mod tests {
             {
                 "path": "https://binaries.soliditylang.org/linux-amd64/solc-linux-amd64-v0.4.13+commit.0fb4cb1a",
                 "longVersion": "0.4.13+commit.0fb4cb1a",
-                "sha256": "0x791ee3a20adf6c5ab76cc889f13cca102f76eb0b7cf0da4a0b5b11dc46edf349"
+                "sha256": "0x791ee3a20adf6c5ab76cc889f13cca102f76eb0b7cf0da4a0b5b11dc46edf349",
+                "keccak256": "0x4e8c1a9ae8e2e24b8b4e3c3e6e8e3b7b5c5c1ea1cb0a1b9f8f5f2e0d1c3b4a59"
             },
             {
                 "path": "https://binaries.soliditylang.org/linux-amd64/solc-linux-amd64-v0.4.14+commit.c2215d46",
@@ -325,31 +613,37 @@ mod tests {
                         path: json::DownloadPath::Url(Url::from_str("https://github.com/blockscout/solc-bin/releases/download/solc-v0.8.15-nightly.2022.5.27%2Bcommit.095cc647/solc").unwrap()),
                         long_version: ver("0.8.15-nightly.2022.5.27+commit.095cc647"),
                         sha256: H256::from_str("35708c1593f3daddae734065e361a839ee39d400825972fb3f50718495be82b1").unwrap(),
+                        keccak256: None,
                     },
                     json::CompilerInfo {
                         path: json::DownloadPath::Url(Url::from_str("https://binaries.soliditylang.org/linux-amd64/solc-linux-amd64-v0.4.13+commit.0fb4cb1a").unwrap()),
                         long_version: ver("0.4.13+commit.0fb4cb1a"),
                         sha256: H256::from_str("0x791ee3a20adf6c5ab76cc889f13cca102f76eb0b7cf0da4a0b5b11dc46edf349").unwrap(),
+                        keccak256: Some(H256::from_str("0x4e8c1a9ae8e2e24b8b4e3c3e6e8e3b7b5c5c1ea1cb0a1b9f8f5f2e0d1c3b4a59").unwrap()),
                     },
                     json::CompilerInfo {
                         path: json::DownloadPath::Url(Url::from_str("https://binaries.soliditylang.org/linux-amd64/solc-linux-amd64-v0.4.14+commit.c2215d46").unwrap()),
                         long_version: ver("0.4.14+commit.c2215d46"),
                         sha256: H256::from_str("0x28ce35a0941d9ecd59a2b1a377c019110e79a6b38bdbf5a3bffea811f9c2a13b").unwrap(),
+                        keccak256: None,
                     },
                     json::CompilerInfo {
                         path: json::DownloadPath::Filename("solc-linux-amd64-v0.4.15+commit.8b45bddb".to_string()),
                         long_version: ver("0.4.15+commit.8b45bddb"),
                         sha256: H256::from_str("0xc71ac6c28bf3b1a425e77e97f5df67a80da3e4c047261875206561c0a110c0cb").unwrap(),
+                        keccak256: None,
                     },
                     json::CompilerInfo {
                         path: json::DownloadPath::Filename("download/files/solc-linux-amd64-v0.4.16+commit.d7661dd9".to_string()),
                         long_version: ver("0.4.16+commit.d7661dd9"),
                         sha256: H256::from_str("0x78e0da6cad24ab145a8d17420c4f094c8314418ca23cff4b050bb2bfd36f3af2").unwrap(),
+                        keccak256: None,
                     },
                     json::CompilerInfo {
                         path: json::DownloadPath::Filename("solc-linux-amd64-v10.8.9-nightly.2021.9.11+commit.e5eed63a".to_string()),
                         long_version: ver("10.8.9-nightly.2021.9.11+commit.e5eed63a"),
                         sha256: H256::from_str("0x791ee3a20adf6c5ab76cc889f13cca102f76eb0b7cf0da4a0b5b11dc46edf349").unwrap(),
+                        keccak256: None,
                     },
                 ]
             })
@@ -359,7 +653,7 @@ mod tests {
     fn assert_has_version(versions: &VersionsMap, ver: &str, expect: &str) {
         let ver = Version::from_str(ver).unwrap();
         let info = versions.get(&ver).unwrap();
-        let url = info.url.to_string();
+        let url = info.urls[0].to_string();
         assert_eq!(url, expect, "urls don't match");
     }
 
@@ -367,7 +661,7 @@ mod tests {
     fn parse_versions() {
         let list_json_file: json::List = serde_json::from_str(DEFAULT_LIST_JSON).unwrap();
         let download_url = Url::from_str(DEFAULT_DOWNLOAD_PREFIX).expect("valid url");
-        let verions = try_parse_json_file(list_json_file, &download_url).unwrap();
+        let verions = try_parse_json_file(list_json_file, &[download_url]).unwrap();
         assert_has_version(
             &verions,
             "0.8.15-nightly.2022.5.27+commit.095cc647",
@@ -393,13 +687,15 @@ mod tests {
         let config = Config::default();
         let fetcher = ListFetcher::new(
             config.solidity.compilers_list_url,
+            vec![],
             None,
             std::env::temp_dir().join("blockscout/verification/compiler_fetcher/test/"),
+            None,
         )
         .await
         .expect("list.json file should be valid");
 
-        for compiler_version in vec![
+        for compiler_version in [
             Version::from_str("0.7.0+commit.9e61f92b").unwrap(),
             Version::from_str("0.8.9+commit.e5eed63a").unwrap(),
         ] {
@@ -417,6 +713,358 @@ mod tests {
         }
     }
 
+    #[test]
+    fn checks_hashsums_of_both_kinds() {
+        let bytes = Bytes::from_static(b"solc");
+        let sha256 = H256::from_slice(&Sha256::digest(&bytes));
+        let keccak256 = H256::from(ethers_core::utils::keccak256(&bytes));
+        check_hashsum(&bytes, sha256, ChecksumKind::Sha256).expect("sha256 should match");
+        check_hashsum(&bytes, keccak256, ChecksumKind::Keccak256).expect("keccak256 should match");
+        assert_eq!(
+            check_hashsum(&bytes, sha256, ChecksumKind::Keccak256),
+            Err(Mismatch::new(sha256, keccak256))
+        );
+    }
+
+    #[test]
+    fn check_hashsum_records_metrics() {
+        let bytes = Bytes::from_static(b"solc");
+        let sha256 = H256::from_slice(&Sha256::digest(&bytes));
+        let samples = metrics::COMPILER_HASH_SECONDS.get_sample_count();
+        let hashed_bytes = metrics::COMPILER_HASHED_BYTES.get();
+
+        check_hashsum(&bytes, sha256, ChecksumKind::Sha256).expect("hashsum should match");
+        // the metrics are global, so other tests may increase them concurrently
+        assert!(metrics::COMPILER_HASH_SECONDS.get_sample_count() > samples);
+        assert!(metrics::COMPILER_HASHED_BYTES.get() >= hashed_bytes + bytes.len() as u64);
+
+        check_hashsum(&bytes, H256::zero(), ChecksumKind::Sha256)
+            .expect_err("hashsum should mismatch");
+        assert!(metrics::COMPILER_HASH_SECONDS.get_sample_count() > samples + 1);
+    }
+
+    #[tokio::test]
+    async fn refresh_updates_size_metric() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(DEFAULT_LIST_JSON))
+            .mount(&mock_server)
+            .await;
+        let url = Url::parse(&mock_server.uri()).unwrap();
+        let expected_len = try_fetch_versions(std::slice::from_ref(&url), &Default::default())
+            .await
+            .unwrap()
+            .unwrap()
+            .len() as i64;
+
+        let size_metric = IntGauge::new("compiler_list_size", "test").unwrap();
+        let versions = Versions::with_size_metric(VersionsMap::default(), size_metric.clone());
+        assert_eq!(size_metric.get(), 0);
+
+        versions.refresh_versions(&url).await.unwrap();
+        assert_eq!(size_metric.get(), expected_len);
+
+        // the metric is set even if the list has not changed
+        size_metric.set(0);
+        versions.refresh_versions(&url).await.unwrap();
+        assert_eq!(size_metric.get(), expected_len);
+    }
+
+    #[tokio::test]
+    async fn refresh_records_history() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(DEFAULT_LIST_JSON))
+            .mount(&mock_server)
+            .await;
+        let url = Url::parse(&mock_server.uri()).unwrap();
+        let (history, file) = list_history::tests::history("refresh.jsonl", 30);
+        let size_metric = IntGauge::new("compiler_list_size", "test").unwrap();
+        let versions = Versions::with_size_metric(VersionsMap::default(), size_metric)
+            .with_history(Some(history));
+
+        versions.refresh_versions(&url).await.unwrap();
+        // an unchanged list is not recorded again
+        versions.refresh_versions(&url).await.unwrap();
+        let snapshots = list_history::tests::snapshots(&file);
+        assert_eq!(snapshots.len(), 1);
+        assert_eq!(
+            snapshots[0].compilers.get("v0.4.13+commit.0fb4cb1a"),
+            Some(
+                &H256::from_str(
+                    "0x791ee3a20adf6c5ab76cc889f13cca102f76eb0b7cf0da4a0b5b11dc46edf349"
+                )
+                .unwrap()
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn unmodified_list_is_not_parsed() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/"))
+            .and(header("If-None-Match", "\"v1\""))
+            .respond_with(ResponseTemplate::new(304))
+            .with_priority(1)
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("ETag", "\"v1\"")
+                    .set_body_bytes(DEFAULT_LIST_JSON),
+            )
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        let url = Url::parse(&mock_server.uri()).unwrap();
+        let size_metric = IntGauge::new("compiler_list_size", "test").unwrap();
+        let versions = Versions::with_size_metric(VersionsMap::default(), size_metric);
+        let not_modified = metrics::COMPILER_LIST_NOT_MODIFIED.get();
+
+        versions.refresh_versions(&url).await.unwrap();
+        let fetched = versions.versions.read().clone();
+        assert!(!fetched.is_empty());
+        versions.refresh_versions(&url).await.unwrap();
+        assert_eq!(*versions.versions.read(), fetched);
+        // the metric is global, so other tests may increase it concurrently
+        assert!(metrics::COMPILER_LIST_NOT_MODIFIED.get() > not_modified);
+    }
+
+    #[tokio::test]
+    async fn validators_of_invalid_list_are_not_saved() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/list.json"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("ETag", "\"v1\"")
+                    .set_body_json(serde_json::json!({
+                        "builds": [{
+                            "path": "http://[invalid",
+                            "longVersion": "0.8.9+commit.e5eed63a",
+                            "sha256": H256::zero(),
+                        }]
+                    })),
+            )
+            .mount(&mock_server)
+            .await;
+        let url = Url::parse(&format!("{}/list.json", mock_server.uri())).unwrap();
+        let validators = Mutex::default();
+
+        let err = try_fetch_versions(&[url], &validators)
+            .await
+            .expect_err("list with invalid urls should be rejected");
+        assert!(matches!(err, ListError::Path(_)), "{:?}", err);
+        assert!(validators.lock().is_none());
+    }
+
+    fn gzip(content: &str) -> Vec<u8> {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        std::io::Write::write_all(&mut encoder, content.as_bytes()).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[tokio::test]
+    async fn gzipped_lists_are_decompressed() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/list.json"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("Content-Encoding", "gzip")
+                    .set_body_bytes(gzip(DEFAULT_LIST_JSON)),
+            )
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/list.json.gz"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(gzip(DEFAULT_LIST_JSON)))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/plain.json"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(DEFAULT_LIST_JSON))
+            .mount(&mock_server)
+            .await;
+
+        let fetch = |file: &str| {
+            let url = Url::parse(&format!("{}/{}", mock_server.uri(), file)).unwrap();
+            async move {
+                try_fetch_versions(&[url], &Default::default())
+                    .await
+                    .expect("list should be parsed")
+                    .unwrap()
+            }
+        };
+        let version = Version::from_str("0.4.13+commit.0fb4cb1a").unwrap();
+        for file in ["list.json", "list.json.gz", "plain.json"] {
+            let versions = fetch(file).await;
+            assert_eq!(versions.len(), 6, "{}", file);
+            assert!(versions.contains_key(&version), "{}", file);
+        }
+    }
+
+    async fn failing_server() -> MockServer {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(500))
+            .mount(&mock_server)
+            .await;
+        mock_server
+    }
+
+    #[tokio::test]
+    async fn list_is_fetched_from_mirror() {
+        let primary = failing_server().await;
+        let mirror = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/list.json"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(DEFAULT_LIST_JSON))
+            .mount(&mirror)
+            .await;
+        let primary_url = Url::parse(&format!("{}/list.json", primary.uri())).unwrap();
+        let mirror_url = Url::parse(&format!("{}/list.json", mirror.uri())).unwrap();
+
+        let versions = try_fetch_versions(
+            &[primary_url.clone(), mirror_url.clone()],
+            &Default::default(),
+        )
+        .await
+        .expect("list should be fetched from the mirror")
+        .unwrap();
+        let version = Version::from_str("0.4.15+commit.8b45bddb").unwrap();
+        assert_eq!(
+            versions.get(&version).unwrap().urls,
+            vec![
+                mirror_url
+                    .join("solc-linux-amd64-v0.4.15+commit.8b45bddb")
+                    .unwrap(),
+                primary_url
+                    .join("solc-linux-amd64-v0.4.15+commit.8b45bddb")
+                    .unwrap(),
+            ],
+            "the mirror serving the list should be tried first"
+        );
+
+        try_fetch_versions(&[primary_url], &Default::default())
+            .await
+            .unwrap_err();
+    }
+
+    #[tokio::test]
+    async fn compiler_is_downloaded_from_mirror() {
+        let binary = b"#!/bin/sh\necho solc\n";
+        let sha256 = H256::from_slice(&Sha256::digest(binary));
+        let primary = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/list.json"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "builds": [{
+                    "path": "solc-v0.8.9",
+                    "longVersion": "0.8.9+commit.e5eed63a",
+                    "sha256": sha256,
+                }]
+            })))
+            .mount(&primary)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/solc-v0.8.9"))
+            .respond_with(ResponseTemplate::new(500))
+            .mount(&primary)
+            .await;
+        let mirror = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/solc-v0.8.9"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(binary.to_vec()))
+            .expect(1)
+            .mount(&mirror)
+            .await;
+
+        let folder = temp_dir().join(format!("list_fetcher_mirror_{}", rand::random::<u64>()));
+        let fetcher = ListFetcher::new(
+            Url::parse(&format!("{}/list.json", primary.uri())).unwrap(),
+            vec![Url::parse(&format!("{}/list.json", mirror.uri())).unwrap()],
+            None,
+            folder.clone(),
+            None,
+        )
+        .await
+        .expect("list should be fetched from the primary url");
+        let file = fetcher
+            .fetch(&Version::from_str("0.8.9+commit.e5eed63a").unwrap())
+            .await
+            .expect("compiler should be downloaded from the mirror");
+        assert_eq!(std::fs::read(file).unwrap(), binary);
+        std::fs::remove_dir_all(folder).unwrap();
+    }
+
+    #[tokio::test]
+    async fn compiler_is_checked_with_configured_checksum() {
+        let binary = b"#!/bin/sh\necho solc\n";
+        let sha256 = H256::from_slice(&Sha256::digest(binary));
+        let keccak256 = H256::from(ethers_core::utils::keccak256(binary));
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/list.json"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "builds": [{
+                    "path": "solc-v0.8.9",
+                    "longVersion": "0.8.9+commit.e5eed63a",
+                    // the keccak256 is published as the sha256 to make sure it's not used
+                    "sha256": keccak256,
+                    "keccak256": keccak256,
+                }, {
+                    "path": "solc-v0.8.10",
+                    "longVersion": "0.8.10+commit.fc410830",
+                    "sha256": sha256,
+                }]
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(binary.to_vec()))
+            .mount(&server)
+            .await;
+
+        let folder = temp_dir().join(format!("list_fetcher_checksum_{}", rand::random::<u64>()));
+        let list_url = Url::parse(&format!("{}/list.json", server.uri())).unwrap();
+        let fetcher = ListFetcher::new(list_url.clone(), vec![], None, folder.clone(), None)
+            .await
+            .unwrap();
+        let keccak_published = Version::from_str("0.8.9+commit.e5eed63a").unwrap();
+        let err = fetcher.fetch(&keccak_published).await.unwrap_err();
+        assert!(matches!(err, FetchError::HashMismatch(_)), "{}", err);
+        let temp_file = Language::Solidity
+            .version_dir(&folder, &keccak_published)
+            .join("solc.tmp");
+        assert!(
+            !temp_file.exists(),
+            "temporary file is left after the mismatch"
+        );
+
+        let fetcher = ListFetcher::new(list_url, vec![], None, folder.clone(), None)
+            .await
+            .unwrap()
+            .with_checksum(ChecksumKind::Keccak256);
+        let file = fetcher
+            .fetch(&keccak_published)
+            .await
+            .expect("keccak256 hashsum should match");
+        assert_eq!(std::fs::read(file).unwrap(), binary);
+        // versions without the keccak256 in the list could not be checked
+        fetcher
+            .fetch(&Version::from_str("0.8.10+commit.fc410830").unwrap())
+            .await
+            .expect_err("keccak256 hashsum is not published");
+        std::fs::remove_dir_all(folder).unwrap();
+    }
+
     #[tokio::test]
     async fn check_refresh_versions() {
         let mock_server = MockServer::start().await;
@@ -429,8 +1077,14 @@ mod tests {
             .await;
         let fetcher = ListFetcher::new(
             Url::parse(&mock_server.uri()).unwrap(),
-            Some(Schedule::from_str("* * * * * * *").unwrap()),
+            vec![],
+            Some(BackoffSchedule {
+                schedule: Schedule::from_str("* * * * * * *").unwrap(),
+                max_skipped_ticks: 0,
+                max_jitter: std::time::Duration::ZERO,
+            }),
             temp_dir(),
+            None,
         )
         .await
         .expect("cannot initialize fetcher");
@@ -0,0 +1,253 @@
+use crate::config::ListHistoryConfiguration;
+use primitive_types::H256;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime},
+};
+
+/// Compiler versions with their hashsums offered by the list at the moment of `timestamp`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct Snapshot {
+    /// Unix timestamp in seconds
+    pub timestamp: u64,
+    pub compilers: BTreeMap<String, H256>,
+}
+
+/// Appends a snapshot of the compiler list to a json lines file on every refresh
+/// which changed the list. Snapshots older than the retention period are removed
+/// on each new record.
+///
+/// Hashsums changed for already published versions are reported as warnings.
+pub(crate) struct ListHistory {
+    file: PathBuf,
+    retention: Duration,
+    state: Arc<Mutex<Option<State>>>,
+}
+
+/// What is known about the history file, read from the file on the first record only.
+#[derive(Debug)]
+struct State {
+    /// Compilers of the last recorded snapshot
+    last: Option<BTreeMap<String, H256>>,
+    /// Timestamp of the oldest recorded snapshot
+    oldest: Option<u64>,
+}
+
+impl State {
+    fn load(file: &Path) -> std::io::Result<Self> {
+        let snapshots = read_snapshots(file)?;
+        Ok(Self {
+            oldest: snapshots.iter().map(|snapshot| snapshot.timestamp).min(),
+            last: snapshots
+                .into_iter()
+                .last()
+                .map(|snapshot| snapshot.compilers),
+        })
+    }
+}
+
+impl ListHistory {
+    pub fn new(config: ListHistoryConfiguration) -> std::io::Result<Self> {
+        if let Some(parent) = config.file.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        Ok(Self {
+            file: config.file,
+            retention: Duration::from_secs(config.retention_days * 24 * 60 * 60),
+            state: Default::default(),
+        })
+    }
+
+    /// Records the snapshot of `compilers`.
+    /// Errors are only logged, as recording must not affect the refresh.
+    pub async fn record(&self, compilers: BTreeMap<String, H256>) {
+        let now = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default();
+        let snapshot = Snapshot {
+            timestamp: now.as_secs(),
+            compilers,
+        };
+        let expired_before = now.saturating_sub(self.retention).as_secs();
+        let file = self.file.clone();
+        let state = self.state.clone();
+        let result = tokio::task::spawn_blocking(move || {
+            let mut state = state
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            append(file, &mut state, snapshot, expired_before)
+        })
+        .await
+        .map_err(std::io::Error::from)
+        .and_then(|result| result);
+        if let Err(err) = result {
+            log::warn!(target: "list_history", "failed to record compiler list snapshot: {}", err)
+        }
+    }
+}
+
+fn read_snapshots(file: &Path) -> std::io::Result<Vec<Snapshot>> {
+    let content = match std::fs::read_to_string(file) {
+        Ok(content) => content,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(err) => return Err(err),
+    };
+    let snapshots = content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| match serde_json::from_str(line) {
+            Ok(snapshot) => Some(snapshot),
+            Err(err) => {
+                log::warn!(target: "list_history", "skipping invalid compiler list snapshot: {}", err);
+                None
+            }
+        })
+        .collect();
+    Ok(snapshots)
+}
+
+fn warn_changed_hashes(previous: &BTreeMap<String, H256>, current: &BTreeMap<String, H256>) {
+    for (version, hash) in current {
+        match previous.get(version) {
+            Some(previous_hash) if previous_hash != hash => log::warn!(
+                target: "list_history",
+                "hashsum of published compiler version {} changed: {:?} -> {:?}",
+                version,
+                previous_hash,
+                hash
+            ),
+            _ => {}
+        }
+    }
+}
+
+fn append(
+    file: PathBuf,
+    state: &mut Option<State>,
+    snapshot: Snapshot,
+    expired_before: u64,
+) -> std::io::Result<()> {
+    let state = match state {
+        Some(state) => state,
+        None => state.insert(State::load(&file)?),
+    };
+    if let Some(previous) = &state.last {
+        if previous == &snapshot.compilers {
+            log::debug!(target: "list_history", "compiler list has not changed, skipping snapshot");
+            return Ok(());
+        }
+        warn_changed_hashes(previous, &snapshot.compilers);
+    }
+    let line = serde_json::to_string(&snapshot).expect("serialization is infallible");
+
+    if state.oldest.map_or(true, |oldest| oldest >= expired_before) {
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(file)?;
+        writeln!(file, "{}", line)?;
+        state.oldest.get_or_insert(snapshot.timestamp);
+        state.last = Some(snapshot.compilers);
+        return Ok(());
+    }
+
+    // rewrite the file without expired snapshots
+    let retained: Vec<_> = read_snapshots(&file)?
+        .into_iter()
+        .filter(|snapshot| snapshot.timestamp >= expired_before)
+        .collect();
+    let mut content = String::new();
+    for snapshot in &retained {
+        content.push_str(&serde_json::to_string(snapshot).expect("serialization is infallible"));
+        content.push('\n');
+    }
+    content.push_str(&line);
+    content.push('\n');
+    let tmp = file.with_extension("tmp");
+    std::fs::write(&tmp, content)?;
+    std::fs::rename(tmp, file)?;
+    state.oldest = retained
+        .iter()
+        .map(|snapshot| snapshot.timestamp)
+        .chain([snapshot.timestamp])
+        .min();
+    state.last = Some(snapshot.compilers);
+    Ok(())
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::env::temp_dir;
+
+    pub fn history(name: &str, retention_days: u64) -> (ListHistory, PathBuf) {
+        let file = temp_dir()
+            .join("blockscout/verification/list_history/test/")
+            .join(name);
+        let _ = std::fs::remove_file(&file);
+        let history = ListHistory::new(ListHistoryConfiguration {
+            enabled: true,
+            file: file.clone(),
+            retention_days,
+        })
+        .unwrap();
+        (history, file)
+    }
+
+    pub fn snapshots(file: &Path) -> Vec<Snapshot> {
+        read_snapshots(file).unwrap()
+    }
+
+    #[tokio::test]
+    async fn appends_snapshots() {
+        let (history, file) = history("appends.jsonl", 30);
+        let compilers = BTreeMap::from([("v0.8.7+commit.e28d00a7".to_string(), H256::zero())]);
+        history.record(compilers.clone()).await;
+        history.record(BTreeMap::new()).await;
+
+        let snapshots = snapshots(&file);
+        assert_eq!(snapshots.len(), 2);
+        assert_eq!(snapshots[0].compilers, compilers);
+        assert!(snapshots[1].compilers.is_empty());
+    }
+
+    #[tokio::test]
+    async fn prunes_expired_snapshots() {
+        let (history, file) = history("prunes.jsonl", 1);
+        let expired = Snapshot {
+            timestamp: 0,
+            compilers: BTreeMap::new(),
+        };
+        std::fs::write(&file, serde_json::to_string(&expired).unwrap() + "\n").unwrap();
+        let compilers = BTreeMap::from([("v0.8.7+commit.e28d00a7".to_string(), H256::zero())]);
+        history.record(compilers).await;
+
+        let snapshots = snapshots(&file);
+        assert_eq!(snapshots.len(), 1);
+        assert_ne!(snapshots[0].timestamp, 0);
+    }
+
+    #[tokio::test]
+    async fn skips_unchanged_snapshots() {
+        let (history, file) = history("unchanged.jsonl", 30);
+        let compilers = BTreeMap::from([("v0.8.7+commit.e28d00a7".to_string(), H256::zero())]);
+        history.record(compilers.clone()).await;
+        history.record(compilers.clone()).await;
+        assert_eq!(snapshots(&file).len(), 1);
+
+        // the last snapshot is read from the file after a restart
+        let history = ListHistory::new(ListHistoryConfiguration {
+            enabled: true,
+            file: file.clone(),
+            retention_days: 30,
+        })
+        .unwrap();
+        history.record(compilers).await;
+        assert_eq!(snapshots(&file).len(), 1);
+    }
+}
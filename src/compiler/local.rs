@@ -0,0 +1,102 @@
+use super::list_fetcher::{check_hashsum, ChecksumKind};
+use crate::types::Mismatch;
+use primitive_types::H256;
+#[cfg(target_family = "unix")]
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum LocalCompilerError {
+    #[error("compiler file {0:?} does not exist")]
+    NotFound(PathBuf),
+    #[error("compiler file {0:?} is not executable")]
+    NotExecutable(PathBuf),
+    #[error("couldn't read compiler file: {0}")]
+    Read(#[from] std::io::Error),
+    #[error("hashsum of compiler file mismatch: {0}")]
+    HashMismatch(#[from] Mismatch<H256>),
+}
+
+/// Checks that the externally provided compiler at `path` could be used as is:
+/// the file exists, is executable, and matches `sha256` if one is specified.
+pub fn validate_local_compiler(
+    path: &Path,
+    sha256: Option<H256>,
+) -> Result<(), LocalCompilerError> {
+    let metadata = match std::fs::metadata(path) {
+        Ok(metadata) if metadata.is_file() => metadata,
+        Ok(_) => return Err(LocalCompilerError::NotFound(path.to_path_buf())),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            return Err(LocalCompilerError::NotFound(path.to_path_buf()))
+        }
+        Err(err) => return Err(err.into()),
+    };
+    if !is_executable(&metadata) {
+        return Err(LocalCompilerError::NotExecutable(path.to_path_buf()));
+    }
+    if let Some(sha256) = sha256 {
+        let bytes = std::fs::read(path)?;
+        check_hashsum(&bytes.into(), sha256, ChecksumKind::Sha256)?;
+    }
+    Ok(())
+}
+
+#[cfg(target_family = "unix")]
+fn is_executable(metadata: &std::fs::Metadata) -> bool {
+    metadata.permissions().mode() & 0o111 != 0
+}
+
+/// Executable permissions exist on unix only, other files are assumed to be executable.
+#[cfg(not(target_family = "unix"))]
+fn is_executable(_metadata: &std::fs::Metadata) -> bool {
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sha2::{Digest, Sha256};
+    use std::env::temp_dir;
+
+    const STUB: &[u8] = b"#!/bin/sh\necho solc\n";
+
+    #[cfg_attr(not(target_family = "unix"), allow(unused_variables))]
+    fn stub(mode: u32) -> PathBuf {
+        let path = temp_dir().join(format!("local_compiler_{}", rand::random::<u64>()));
+        std::fs::write(&path, STUB).unwrap();
+        #[cfg(target_family = "unix")]
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(mode)).unwrap();
+        path
+    }
+
+    #[test]
+    fn valid_stub() {
+        let path = stub(0o755);
+        validate_local_compiler(&path, None).expect("stub without hash should be valid");
+        let sha256 = H256::from_slice(&Sha256::digest(STUB));
+        validate_local_compiler(&path, Some(sha256)).expect("stub with hash should be valid");
+    }
+
+    #[test]
+    fn invalid_stub() {
+        let path = stub(0o755);
+        assert!(matches!(
+            validate_local_compiler(&path, Some(H256::zero())),
+            Err(LocalCompilerError::HashMismatch(_))
+        ));
+        #[cfg(target_family = "unix")]
+        assert!(matches!(
+            validate_local_compiler(&stub(0o644), None),
+            Err(LocalCompilerError::NotExecutable(_))
+        ));
+        assert!(matches!(
+            validate_local_compiler(&path.with_extension("missing"), None),
+            Err(LocalCompilerError::NotFound(_))
+        ));
+        assert!(matches!(
+            validate_local_compiler(&temp_dir(), None),
+            Err(LocalCompilerError::NotFound(_))
+        ));
+    }
+}
@@ -0,0 +1,167 @@
+use super::{
+    download_cache::DownloadCache,
+    fetcher::FetchError,
+    http_fetcher::HttpFetcher,
+    list_fetcher::{check_hashsum, ChecksumKind},
+};
+use crate::{
+    compiler::{Fetcher, Language, Version},
+    config::LocalFetcherConfig,
+};
+use async_trait::async_trait;
+use primitive_types::H256;
+use std::path::{Path, PathBuf};
+
+const HASH_FILE: &str = "sha256.hash";
+
+/// Uses compilers pre-staged in a local directory, without downloading anything.
+/// Every version has its own folder (e.g. `<dir>/v0.8.9+commit.e5eed63a/`)
+/// containing the compiler binary (e.g. `solc`) and `sha256.hash` file.
+pub struct LocalFetcher {
+    dir: PathBuf,
+    language: Language,
+}
+
+impl LocalFetcher {
+    pub fn new(config: LocalFetcherConfig, language: Language) -> anyhow::Result<Self> {
+        if !config.dir.is_dir() {
+            anyhow::bail!("local compilers directory {:?} does not exist", config.dir);
+        }
+        Ok(Self {
+            dir: config.dir,
+            language,
+        })
+    }
+
+    /// Folder of the version, named as listed by [`LocalFetcher::all_versions`]:
+    /// encoded by [`Version::to_path_segment`], or not encoded if staged by hand.
+    fn version_dir(&self, ver: &Version) -> Result<PathBuf, FetchError> {
+        [ver.to_path_segment(), ver.to_string()]
+            .into_iter()
+            .map(|name| self.dir.join(name))
+            .find(|folder| folder.join(self.language.executable_name()).is_file())
+            .ok_or_else(|| FetchError::NotFound(ver.clone()))
+    }
+
+    fn read_hashsum(folder: &Path) -> Result<H256, FetchError> {
+        let hashsum = std::fs::read(folder.join(HASH_FILE))?;
+        HttpFetcher::parse_hashsum(&hashsum)
+    }
+}
+
+#[async_trait]
+impl Fetcher for LocalFetcher {
+    async fn fetch(&self, ver: &Version) -> Result<PathBuf, FetchError> {
+        let folder = self.version_dir(ver)?;
+        let executable_name = self.language.executable_name();
+        tokio::task::spawn_blocking(move || {
+            let sha256 = Self::read_hashsum(&folder)?;
+            let file = folder.join(executable_name);
+            let bytes = std::fs::read(&file)?;
+            check_hashsum(&bytes.into(), sha256, ChecksumKind::Sha256)?;
+            Ok(file)
+        })
+        .await?
+    }
+
+    fn all_versions(&self) -> Vec<Version> {
+        match DownloadCache::find_versions_in_dir(&self.dir) {
+            Ok(versions) => versions
+                .into_iter()
+                .filter(|(_, path)| path.join(self.language.executable_name()).is_file())
+                .map(|(version, _)| version)
+                .collect(),
+            Err(err) => {
+                log::error!("cannot read local compilers directory: {}", err);
+                vec![]
+            }
+        }
+    }
+
+    async fn published_hash(&self, ver: &Version) -> Result<Option<H256>, FetchError> {
+        let folder = self.version_dir(ver)?;
+        Self::read_hashsum(&folder).map(Some)
+    }
+
+    /// The compilers are staged by the operator and must not be removed.
+    fn downloads(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use sha2::{Digest, Sha256};
+    use std::str::FromStr;
+
+    const BINARY: &[u8] = b"#!/bin/sh\necho solc\n";
+
+    fn stage(dir: &Path, version: &str, hashsum: &str) {
+        let folder = dir.join(version);
+        std::fs::create_dir_all(&folder).unwrap();
+        std::fs::write(folder.join("solc"), BINARY).unwrap();
+        std::fs::write(folder.join(HASH_FILE), hashsum).unwrap();
+    }
+
+    fn fetcher() -> LocalFetcher {
+        let dir = std::env::temp_dir().join(format!(
+            "blockscout/verification/local_fetcher/test_{}/",
+            rand::random::<u64>()
+        ));
+        let valid_hash = hex::encode(Sha256::digest(BINARY));
+        stage(&dir, "v0.8.9+commit.e5eed63a", &valid_hash);
+        stage(&dir, "v0.8.10+commit.fc410830", &hex::encode(H256::zero()));
+        stage(&dir, "v0.8.7%2Bcommit.e28d00a7", &valid_hash);
+        stage(&dir, "not-a-version", &valid_hash);
+        std::fs::create_dir_all(dir.join("v0.4.13+commit.0fb4cb1a")).unwrap();
+        LocalFetcher::new(LocalFetcherConfig { dir }, Language::Solidity).expect("directory exists")
+    }
+
+    #[tokio::test]
+    async fn fetch_local_compilers() {
+        let fetcher = fetcher();
+        let mut versions = fetcher.all_versions();
+        versions.sort();
+        let encoded = Version::from_str("0.8.7+commit.e28d00a7").unwrap();
+        let valid = Version::from_str("0.8.9+commit.e5eed63a").unwrap();
+        let tampered = Version::from_str("0.8.10+commit.fc410830").unwrap();
+        assert_eq!(
+            versions,
+            vec![encoded.clone(), valid.clone(), tampered.clone()]
+        );
+
+        let file = fetcher
+            .fetch(&encoded)
+            .await
+            .expect("compiler in the encoded folder should be found");
+        assert_eq!(file, fetcher.dir.join("v0.8.7%2Bcommit.e28d00a7/solc"));
+
+        let file = fetcher
+            .fetch(&valid)
+            .await
+            .expect("compiler should be found");
+        assert_eq!(file, fetcher.dir.join("v0.8.9+commit.e5eed63a/solc"));
+        assert_eq!(
+            fetcher.published_hash(&valid).await.unwrap(),
+            Some(H256::from_slice(&Sha256::digest(BINARY)))
+        );
+
+        assert!(matches!(
+            fetcher.fetch(&tampered).await,
+            Err(FetchError::HashMismatch(_))
+        ));
+        let missing = Version::from_str("0.4.13+commit.0fb4cb1a").unwrap();
+        assert!(matches!(
+            fetcher.fetch(&missing).await,
+            Err(FetchError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn missing_directory() {
+        let dir = std::env::temp_dir().join("blockscout/verification/local_fetcher/missing/");
+        assert!(LocalFetcher::new(LocalFetcherConfig { dir }, Language::Solidity).is_err());
+    }
+}
@@ -1,11 +1,30 @@
 mod compilers;
 mod download_cache;
+mod evm_versions;
 mod fetcher;
+mod gcs_fetcher;
+mod github_fetcher;
+mod http_fetcher;
+mod language;
 mod list_fetcher;
+mod list_history;
+mod local;
+mod local_fetcher;
+mod refreshable_versions;
+mod scratch_dir;
 mod version;
 
 pub use compilers::{Compilers, Error};
-pub use download_cache::DownloadCache;
-pub use fetcher::Fetcher;
-pub use list_fetcher::ListFetcher;
+pub use download_cache::{CompilerHandle, DownloadCache};
+pub use evm_versions::{default_evm_version, supported_evm_versions};
+pub use fetcher::{FetchError, Fetcher};
+pub use gcs_fetcher::GcsFetcher;
+pub use github_fetcher::GithubFetcher;
+pub use http_fetcher::HttpFetcher;
+pub use language::Language;
+pub use list_fetcher::{ChecksumKind, ListFetcher};
+pub(crate) use list_history::ListHistory;
+pub use local::validate_local_compiler;
+pub use local_fetcher::LocalFetcher;
+pub use scratch_dir::ScratchDir;
 pub use version::Version;
@@ -0,0 +1,233 @@
+use crate::scheduler::{self, BackoffSchedule};
+use futures::Future;
+use parking_lot::{Mutex, RwLock, RwLockReadGuard};
+use prometheus::IntGauge;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::Instant,
+};
+
+/// Collection of compiler versions, which size is reported to the metrics.
+pub(super) trait Len {
+    fn len(&self) -> usize;
+}
+
+impl<K, V> Len for HashMap<K, V> {
+    fn len(&self) -> usize {
+        HashMap::len(self)
+    }
+}
+
+impl<T> Len for HashSet<T> {
+    fn len(&self) -> usize {
+        HashSet::len(self)
+    }
+}
+
+/// Compiler versions offered by a fetcher, which are replaced on every refresh.
+/// Shared by all fetchers, so that every one of them updates the versions the same way.
+pub(super) struct RefreshableVersions<T> {
+    versions: Arc<RwLock<T>>,
+    /// Set to the number of known versions on every refresh
+    size_metric: IntGauge,
+    /// Time and result of the last refresh. The initial fetch counts as a successful one
+    last_refresh: Arc<Mutex<(Instant, Result<(), String>)>>,
+    /// Held while a refresh is running, so that refreshes of the same versions never overlap
+    refreshing: Arc<tokio::sync::Mutex<()>>,
+}
+
+impl<T> Clone for RefreshableVersions<T> {
+    fn clone(&self) -> Self {
+        Self {
+            versions: self.versions.clone(),
+            size_metric: self.size_metric.clone(),
+            last_refresh: self.last_refresh.clone(),
+            refreshing: self.refreshing.clone(),
+        }
+    }
+}
+
+impl<T> RefreshableVersions<T>
+where
+    T: Len + PartialEq + Send + Sync + 'static,
+{
+    pub fn new(versions: T, size_metric: IntGauge) -> Self {
+        size_metric.set(versions.len() as i64);
+        Self {
+            versions: Arc::new(RwLock::new(versions)),
+            size_metric,
+            last_refresh: Arc::new(Mutex::new((Instant::now(), Ok(())))),
+            refreshing: Default::default(),
+        }
+    }
+
+    pub fn last_refresh(&self) -> (Instant, Result<(), String>) {
+        self.last_refresh.lock().clone()
+    }
+
+    pub fn read(&self) -> RwLockReadGuard<'_, T> {
+        self.versions.read()
+    }
+
+    /// Replaces the versions with the `fetched` ones, if they differ.
+    /// The size metric is set even if the versions have not changed.
+    pub fn update(&self, fetched: T) {
+        let need_to_update = {
+            let versions = self.versions.read();
+            fetched != *versions
+        };
+        let new_len = if need_to_update {
+            let (old_len, new_len) = {
+                // we don't need to check condition again,
+                // we can just override the value
+                let mut versions = self.versions.write();
+                let old_len = versions.len();
+                *versions = fetched;
+                (old_len, versions.len())
+            };
+            log::info!(
+                "found new compiler versions. old length: {}, new length: {}",
+                old_len,
+                new_len,
+            );
+            new_len
+        } else {
+            let len = self.versions.read().len();
+            log::info!("no new versions found. length: {}", len);
+            len
+        };
+        self.size_metric.set(new_len as i64);
+    }
+
+    /// Spawns the job updating the versions with the result of `fetch` on schedule.
+    /// `fetch` returns `None` if the versions are known to be unchanged (e.g. on `304`),
+    /// which counts as a successful refresh. Ticks coming while another refresh
+    /// of the versions is running are skipped.
+    pub fn spawn_refresh_job<F, Fut>(self, schedule: BackoffSchedule, mut fetch: F)
+    where
+        F: (FnMut() -> Fut) + Send + 'static,
+        Fut: Future<Output = anyhow::Result<Option<T>>> + Send + 'static,
+    {
+        log::info!("spawn version refresh job");
+        scheduler::spawn_job(schedule, "refresh compiler versions", move || {
+            let versions = self.clone();
+            let refreshing = versions.refreshing.clone().try_lock_owned();
+            let fetched = refreshing.ok().map(|guard| (guard, fetch()));
+            async move {
+                let (_guard, fetched) = match fetched {
+                    Some(fetched) => fetched,
+                    None => {
+                        log::warn!(
+                            "skipping compiler versions refresh, the previous one is still running"
+                        );
+                        return Ok(());
+                    }
+                };
+                log::info!("looking for new compilers versions");
+                let result = fetched.await.map(|fetched| match fetched {
+                    Some(fetched) => versions.update(fetched),
+                    None => log::info!("compiler versions not modified"),
+                });
+                let status = result.as_ref().map_err(|err| format!("{:#}", err));
+                *versions.last_refresh.lock() = (Instant::now(), status.copied());
+                result
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cron::Schedule;
+    use pretty_assertions::assert_eq;
+    use std::{
+        str::FromStr,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    fn size_metric() -> IntGauge {
+        IntGauge::new("compiler_list_size", "test").unwrap()
+    }
+
+    #[test]
+    fn update_replaces_versions() {
+        let size_metric = size_metric();
+        let versions = RefreshableVersions::new(HashSet::from([1]), size_metric.clone());
+        assert_eq!(size_metric.get(), 1);
+
+        versions.update(HashSet::from([1, 2, 3]));
+        assert_eq!(*versions.read(), HashSet::from([1, 2, 3]));
+        assert_eq!(size_metric.get(), 3);
+
+        // the metric is set even if the versions have not changed
+        size_metric.set(0);
+        versions.update(HashSet::from([1, 2, 3]));
+        assert_eq!(size_metric.get(), 3);
+    }
+
+    #[tokio::test]
+    async fn refresh_job_updates_versions() {
+        let versions = RefreshableVersions::new(HashMap::new(), size_metric());
+        let calls = Arc::new(AtomicUsize::new(0));
+        let schedule = BackoffSchedule {
+            schedule: Schedule::from_str("* * * * * * *").unwrap(),
+            max_skipped_ticks: 0,
+            max_jitter: std::time::Duration::ZERO,
+        };
+        {
+            let calls = calls.clone();
+            versions.clone().spawn_refresh_job(schedule, move || {
+                let call = calls.fetch_add(1, Ordering::SeqCst);
+                async move {
+                    match call {
+                        0 => Ok(Some(HashMap::from([("v0.8.7", ())]))),
+                        _ => anyhow::bail!("list is unavailable"),
+                    }
+                }
+            });
+        }
+
+        tokio::time::sleep(std::time::Duration::from_millis(2500)).await;
+        assert!(calls.load(Ordering::SeqCst) >= 2);
+        // failed refreshes keep the previously fetched versions
+        assert_eq!(*versions.read(), HashMap::from([("v0.8.7", ())]));
+        let (_, result) = versions.last_refresh();
+        assert_eq!(result, Err("list is unavailable".to_string()));
+    }
+
+    #[tokio::test]
+    async fn refreshes_never_overlap() {
+        let versions = RefreshableVersions::new(HashSet::from([1]), size_metric());
+        let calls = Arc::new(AtomicUsize::new(0));
+        let running = Arc::new(AtomicUsize::new(0));
+        let max_running = Arc::new(AtomicUsize::new(0));
+        // both jobs tick every second, while a refresh takes longer
+        for _ in 0..2 {
+            let schedule = BackoffSchedule {
+                schedule: Schedule::from_str("* * * * * * *").unwrap(),
+                max_skipped_ticks: 0,
+                max_jitter: std::time::Duration::ZERO,
+            };
+            let (calls, running, max_running) =
+                (calls.clone(), running.clone(), max_running.clone());
+            versions.clone().spawn_refresh_job(schedule, move || {
+                calls.fetch_add(1, Ordering::SeqCst);
+                let (running, max_running) = (running.clone(), max_running.clone());
+                async move {
+                    let now_running = running.fetch_add(1, Ordering::SeqCst) + 1;
+                    max_running.fetch_max(now_running, Ordering::SeqCst);
+                    tokio::time::sleep(std::time::Duration::from_millis(1500)).await;
+                    running.fetch_sub(1, Ordering::SeqCst);
+                    Ok(Some(HashSet::from([1, 2])))
+                }
+            });
+        }
+
+        tokio::time::sleep(std::time::Duration::from_millis(3500)).await;
+        assert!(calls.load(Ordering::SeqCst) >= 1);
+        assert_eq!(max_running.load(Ordering::SeqCst), 1);
+        assert_eq!(*versions.read(), HashSet::from([1, 2]));
+    }
+}
@@ -0,0 +1,84 @@
+use anyhow::Context;
+use std::{
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+/// Directory compilers are run in. Every compilation gets its own
+/// subdirectory, which is removed as soon as the compilation finishes.
+///
+/// Could be pointed at a RAM-backed mount (e.g. tmpfs) to speed up compiler I/O.
+#[derive(Debug)]
+pub struct ScratchDir {
+    root: PathBuf,
+    counter: AtomicU64,
+}
+
+impl ScratchDir {
+    /// Creates the directory if it does not exist
+    /// and checks that files could be written into it.
+    pub fn new(root: PathBuf) -> anyhow::Result<Self> {
+        std::fs::create_dir_all(&root)
+            .with_context(|| format!("cannot create scratch directory {:?}", root))?;
+        let probe = root.join(format!(".probe-{}", std::process::id()));
+        std::fs::write(&probe, [])
+            .and_then(|_| std::fs::remove_file(&probe))
+            .with_context(|| format!("scratch directory {:?} is not writable", root))?;
+        Ok(Self {
+            root,
+            counter: AtomicU64::new(0),
+        })
+    }
+
+    /// Creates an empty subdirectory for a single compilation.
+    pub(crate) fn create(&self) -> std::io::Result<CompilationDir> {
+        let id = self.counter.fetch_add(1, Ordering::Relaxed);
+        let path = self
+            .root
+            .join(format!("solc-{}-{}", std::process::id(), id));
+        if path.exists() {
+            std::fs::remove_dir_all(&path)?;
+        }
+        std::fs::create_dir(&path)?;
+        Ok(CompilationDir(path))
+    }
+}
+
+/// Subdirectory of the [`ScratchDir`] removed on drop.
+pub(crate) struct CompilationDir(PathBuf);
+
+impl CompilationDir {
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for CompilationDir {
+    fn drop(&mut self) {
+        if let Err(err) = std::fs::remove_dir_all(&self.0) {
+            log::warn!("cannot remove compilation directory {:?}: {}", self.0, err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env::temp_dir;
+
+    #[test]
+    fn compilation_dirs_are_cleaned() {
+        let root = temp_dir().join("blockscout/verification/scratch_dir/test/");
+        let scratch_dir = ScratchDir::new(root.clone()).expect("scratch dir should be created");
+        assert!(root.is_dir());
+
+        let first = scratch_dir.create().unwrap();
+        let second = scratch_dir.create().unwrap();
+        assert_ne!(first.path(), second.path());
+        std::fs::write(first.path().join("source.sol"), "contract A {}").unwrap();
+        let path = first.path().to_path_buf();
+        drop(first);
+        assert!(!path.exists());
+        assert!(second.path().is_dir());
+    }
+}
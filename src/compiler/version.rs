@@ -1,9 +1,14 @@
 use chrono::NaiveDate;
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
 use std::{cmp::Ordering, fmt::Display, str::FromStr};
 use thiserror::Error;
 
 const DATE_FORMAT: &str = "%Y.%-m.%-d";
 
+/// Characters not encoded when the version is used as a path segment.
+/// All other special characters (e.g. `+`) are percent-encoded.
+pub(super) const PATH_SEGMENT: &AsciiSet = &NON_ALPHANUMERIC.remove(b'.').remove(b'-').remove(b'_');
+
 #[derive(Error, Debug)]
 pub enum ParseError {
     #[error("error parsing the string: {0}")]
@@ -12,8 +17,39 @@ pub enum ParseError {
     CommitHash(hex::FromHexError),
 }
 
+/// Splits the vendor prefix off the version string, e.g. `zksolc` of `zksolc-v1.3.5+commit.0ab2c1f8`.
+/// The prefix starts with a letter, consists of alphanumeric characters and underscores,
+/// and is separated from the version by a dash.
+fn split_vendor(s: &str) -> (Option<String>, &str) {
+    let is_vendor = |vendor: &str| {
+        vendor.starts_with(|c: char| c.is_ascii_alphabetic())
+            && vendor
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_')
+    };
+    let is_version = |version: &str| {
+        let version = version.strip_prefix('v').unwrap_or(version);
+        version.starts_with(|c: char| c.is_ascii_digit())
+    };
+    match s.split_once('-') {
+        Some((vendor, version)) if is_vendor(vendor) && is_version(version) => {
+            (Some(vendor.to_string()), version)
+        }
+        _ => (None, s),
+    }
+}
+
+/// Formats the vendor prefix followed by the separating dash, if any.
+fn vendor_prefix(vendor: Option<&str>) -> String {
+    vendor
+        .map(|vendor| format!("{}-", vendor))
+        .unwrap_or_default()
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct ReleaseVersion {
+    /// Prefix of the compilers built by third parties, e.g. `zksolc`
+    pub vendor: Option<String>,
     pub version: semver::Version,
     pub commit: [u8; 4],
 }
@@ -22,10 +58,12 @@ impl FromStr for ReleaseVersion {
     type Err = ParseError;
 
     /// Parses release version from string formated as
-    /// `(v)*VERSION*+commit.*COMMITHASH*`, examples:
+    /// `(*VENDOR*-)(v)*VERSION*+commit.*COMMITHASH*`, examples:
     /// `v0.8.9+commit.e5eed63a`
     /// `0.8.4+commit.dea1b9ec`
+    /// `zksolc-v1.3.5+commit.0ab2c1f8`
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (vendor, s) = split_vendor(s);
         let (_prefix, major, minor, patch, commit_hash) = sscanf::scanf!(
             s,
             "{:/v?/}{}.{}.{}+commit.{}",
@@ -39,18 +77,30 @@ impl FromStr for ReleaseVersion {
         let version = semver::Version::new(major, minor, patch);
         let mut commit = [0; 4];
         hex::decode_to_slice(&commit_hash, &mut commit).map_err(ParseError::CommitHash)?;
-        Ok(Self { version, commit })
+        Ok(Self {
+            vendor,
+            version,
+            commit,
+        })
     }
 }
 
 impl Display for ReleaseVersion {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "v{}+commit.{}", self.version, hex::encode(self.commit))
+        write!(
+            f,
+            "{}v{}+commit.{}",
+            vendor_prefix(self.vendor.as_deref()),
+            self.version,
+            hex::encode(self.commit)
+        )
     }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct NightlyVersion {
+    /// Prefix of the compilers built by third parties, e.g. `zksolc`
+    pub vendor: Option<String>,
     pub version: semver::Version,
     pub date: NaiveDate,
     pub commit: [u8; 4],
@@ -60,10 +110,11 @@ impl FromStr for NightlyVersion {
     type Err = ParseError;
 
     /// Parses nigthly version from string formated as
-    /// `(v)*VERSION*-nightly.*DATE*+commit.*COMMITHASH*`, examples:
+    /// `(*VENDOR*-)(v)*VERSION*-nightly.*DATE*+commit.*COMMITHASH*`, examples:
     /// `v0.8.8-nightly.2021.9.9+commit.dea1b9ec`
     /// `0.8.4-nightly.2021.9.9+commit.e5eed63a`
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (vendor, s) = split_vendor(s);
         let (_prefix, major, minor, patch, date, commit_hash) = sscanf::scanf!(
             s,
             "{:/v?/}{}.{}.{}-nightly.{}+commit.{}",
@@ -81,6 +132,7 @@ impl FromStr for NightlyVersion {
         let mut commit = [0; 4];
         hex::decode_to_slice(&commit_hash, &mut commit).map_err(ParseError::CommitHash)?;
         Ok(Self {
+            vendor,
             version,
             date,
             commit,
@@ -92,7 +144,8 @@ impl Display for NightlyVersion {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "v{}-nightly.{}+commit.{}",
+            "{}v{}-nightly.{}+commit.{}",
+            vendor_prefix(self.vendor.as_deref()),
             self.version,
             self.date.format(DATE_FORMAT),
             hex::encode(self.commit)
@@ -107,6 +160,13 @@ pub enum Version {
 }
 
 impl Version {
+    pub fn vendor(&self) -> Option<&str> {
+        match self {
+            Version::Nightly(v) => v.vendor.as_deref(),
+            Version::Release(v) => v.vendor.as_deref(),
+        }
+    }
+
     pub fn version(&self) -> &semver::Version {
         match self {
             Version::Nightly(v) => &v.version,
@@ -131,6 +191,23 @@ impl Version {
             Version::Release(v) => &v.commit,
         }
     }
+
+    /// Encodes the version to be used as a path segment
+    /// (e.g. of an url, an object key, or a folder name).
+    /// Should be used by all fetchers, so that listing and getting
+    /// versions never mismatch due to different encodings.
+    pub fn to_path_segment(&self) -> String {
+        utf8_percent_encode(&self.to_string(), PATH_SEGMENT).to_string()
+    }
+
+    /// Parses the version from a path segment.
+    /// Both percent-encoded and not encoded segments are accepted.
+    pub fn from_path_segment(segment: &str) -> Result<Self, ParseError> {
+        let decoded = percent_decode_str(segment)
+            .decode_utf8()
+            .map_err(|e| ParseError::Parse(e.to_string()))?;
+        Self::from_str(&decoded)
+    }
 }
 
 impl FromStr for Version {
@@ -164,12 +241,14 @@ impl Ord for Version {
             self.is_release(),
             self.date(),
             self.commit(),
+            self.vendor(),
         )
             .cmp(&(
                 other.version(),
                 other.is_release(),
                 other.date(),
                 other.commit(),
+                other.vendor(),
             ))
     }
 }
@@ -190,8 +269,7 @@ mod tests {
     where
         <T as std::str::FromStr>::Err: std::fmt::Debug,
     {
-        let ver = T::from_str(ver_str).unwrap();
-        ver
+        T::from_str(ver_str).unwrap()
     }
 
     #[test]
@@ -204,6 +282,31 @@ mod tests {
         check_parsing::<ReleaseVersion>("v123456789.987654321.0+commit.ffffffff");
         check_parsing::<ReleaseVersion>("v1.2.3+commit.01234567");
         check_parsing::<ReleaseVersion>("v3.2.1+commit.89abcdef");
+        assert_eq!(ver.vendor, None);
+    }
+
+    #[test]
+    fn parse_vendor_prefixed() {
+        let ver = check_parsing::<ReleaseVersion>("zksolc-v1.3.5+commit.0ab2c1f8");
+        assert_eq!(ver.vendor.as_deref(), Some("zksolc"));
+        assert_eq!(ver.version, semver::Version::new(1, 3, 5));
+        assert_eq!(ver.to_string(), "zksolc-v1.3.5+commit.0ab2c1f8");
+        let ver =
+            check_parsing::<NightlyVersion>("zk_vyper2-0.8.9-nightly.2021.9.11+commit.e5eed63a");
+        assert_eq!(ver.vendor.as_deref(), Some("zk_vyper2"));
+        assert_eq!(
+            ver.to_string(),
+            "zk_vyper2-v0.8.9-nightly.2021.9.11+commit.e5eed63a"
+        );
+        assert_ne!(
+            check_parsing::<Version>("zksolc-v1.3.5+commit.0ab2c1f8"),
+            check_parsing::<Version>("v1.3.5+commit.0ab2c1f8")
+        );
+
+        ReleaseVersion::from_str("zk solc-v1.3.5+commit.0ab2c1f8").unwrap_err();
+        ReleaseVersion::from_str("1zksolc-v1.3.5+commit.0ab2c1f8").unwrap_err();
+        ReleaseVersion::from_str("zksolc-vendor-v1.3.5+commit.0ab2c1f8").unwrap_err();
+        ReleaseVersion::from_str("zksolc-+commit.0ab2c1f8").unwrap_err();
     }
 
     #[test]
@@ -271,6 +374,32 @@ mod tests {
         );
     }
 
+    #[test]
+    fn path_segment_round_trip() {
+        for (version, segment) in [
+            ("v0.4.10+commit.f0d539ae", "v0.4.10%2Bcommit.f0d539ae"),
+            (
+                "v0.8.9-nightly.2021.9.11+commit.e5eed63a",
+                "v0.8.9-nightly.2021.9.11%2Bcommit.e5eed63a",
+            ),
+            (
+                "zksolc-v1.3.5+commit.0ab2c1f8",
+                "zksolc-v1.3.5%2Bcommit.0ab2c1f8",
+            ),
+        ] {
+            let version = check_parsing::<Version>(version);
+            assert_eq!(version.to_path_segment(), segment);
+            assert_eq!(Version::from_path_segment(segment).unwrap(), version);
+            // not encoded segments are accepted as well
+            assert_eq!(
+                Version::from_path_segment(&version.to_string()).unwrap(),
+                version
+            );
+        }
+        Version::from_path_segment("v0.4.10%2commit.f0d539ae").unwrap_err();
+        Version::from_path_segment("v0.4.10%FF%2Bcommit.f0d539ae").unwrap_err();
+    }
+
     #[test]
     fn order_versions() {
         let ver = check_parsing::<Version>;
@@ -1,52 +1,464 @@
-use crate::consts::DEFAULT_COMPILER_LIST;
-use config::{Config as LibConfig, File};
+use crate::{
+    compiler::{self, ChecksumKind},
+    consts::DEFAULT_COMPILER_LIST,
+    solidity::{BytecodeMatchPolicy, MatchStrategy, SourceTransformKind},
+};
+use config::{Config as LibConfig, ConfigError, File, FileFormat, ValueKind};
 use cron::Schedule;
-use serde::Deserialize;
-use std::{net::SocketAddr, num::NonZeroUsize, path::PathBuf, str::FromStr};
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+use serde_with::{serde_as, DisplayFromStr};
+use std::{collections::BTreeMap, net::SocketAddr, num::NonZeroUsize, path::PathBuf, str::FromStr};
 use url::Url;
 
-#[derive(Deserialize, Clone, Default)]
+#[derive(Deserialize, Serialize, Clone, Default)]
 #[serde(default)]
 pub struct Config {
     pub server: ServerConfiguration,
     pub solidity: SolidityConfiguration,
+    pub vyper: VyperConfiguration,
     pub sourcify: SourcifyConfiguration,
+    pub features: FeaturesConfiguration,
+    pub dead_letter: DeadLetterConfiguration,
+    pub events: EventsConfiguration,
+    pub uploads: UploadsConfiguration,
+    pub admin: AdminConfiguration,
+    pub metrics: MetricsConfiguration,
 }
 
-#[derive(Deserialize, Clone)]
+#[derive(Deserialize, Serialize, Clone)]
 #[serde(default)]
 pub struct ServerConfiguration {
     pub addr: SocketAddr,
+    /// Number of seconds in-flight requests are waited for on SIGTERM or SIGINT
+    /// (and running compiler version refreshes after that) before being dropped.
+    /// Set to `30` by default.
+    pub shutdown_timeout: u64,
+    /// Maximum size (in bytes) of the request bodies; larger requests are rejected with `413`.
+    /// Chunked uploads are limited by their own `max_size`. Set to 10 MiB by default.
+    pub max_request_size: usize,
+    pub cors: CorsConfiguration,
 }
 
 impl Default for ServerConfiguration {
     fn default() -> Self {
         Self {
             addr: SocketAddr::from_str("0.0.0.0:8043").expect("should be valid url"),
+            shutdown_timeout: 30,
+            max_request_size: 10 * 1024 * 1024,
+            cors: Default::default(),
         }
     }
 }
 
-#[derive(Deserialize, Clone)]
+#[derive(Deserialize, Serialize, Clone, Default)]
+#[serde(default)]
+pub struct CorsConfiguration {
+    /// Origins browsers are allowed to call the service from, or `*` for any origin.
+    /// CORS headers are not added if empty. Empty by default.
+    pub allowed_origins: Vec<String>,
+}
+
+#[serde_as]
+#[derive(Deserialize, Serialize, Clone)]
 #[serde(default)]
 pub struct SolidityConfiguration {
     pub enabled: bool,
     pub compilers_list_url: Url,
+    /// Mirrors of `compilers_list_url` tried in order if it fails. Compilers referenced
+    /// by relative paths in the list are downloaded from the mirrors as well. Empty by default.
+    pub compilers_list_mirrors: Vec<Url>,
+    /// Hashsums of the list the downloaded compilers are checked against.
+    /// Set to `Sha256` by default.
+    pub compilers_list_checksum: ChecksumKind,
     #[serde(with = "serde_with::rust::display_fromstr")]
     pub refresh_versions_schedule: Schedule,
+    /// After consecutive failures of the versions refresh, up to this number
+    /// of scheduled refreshes are skipped in a row. `0` disables the backoff.
+    /// Set to `16` by default.
+    pub refresh_versions_max_skipped_ticks: u32,
+    /// Scheduled refreshes are delayed by a random number of seconds up to this one,
+    /// so that replicas sharing the schedule do not hit the fetcher at once.
+    /// Set to `0` by default.
+    pub refresh_versions_max_jitter: u64,
+    /// If enabled, model checker settings are removed from standard json inputs
+    /// before compilation, otherwise such inputs are rejected.
+    /// Set to `true` by default.
+    pub strip_model_checker_settings: bool,
+    /// Number of the newest release versions downloaded in the background on startup.
+    /// Set to `0` by default.
+    pub prefetch_latest: usize,
+    /// Versions downloaded in the background on startup in addition to the newest ones,
+    /// e.g. the versions popular among the verified contracts. Empty by default.
+    #[serde_as(as = "Vec<DisplayFromStr>")]
+    pub prefetch_versions: Vec<compiler::Version>,
+    /// Source the compilers are fetched from.
+    /// Set to `List` by default, which uses `compilers_list_url`.
+    pub fetcher: FetcherConfig,
+    /// Strategies applied in order until the contract is matched,
+    /// if not specified in the request. Set to `["exact"]` by default.
+    pub match_strategies: Vec<MatchStrategy>,
+    /// Which of the provided creation and deployed bytecodes must match
+    /// for the contract to be verified. Set to `Either` by default.
+    pub bytecode_match_policy: BytecodeMatchPolicy,
+    /// Transforms applied in order to the sources before compilation.
+    /// Set to `[]` by default, so the sources are compiled as is.
+    pub source_transforms: Vec<SourceTransformKind>,
+    /// Maps deprecated version strings to the canonical compiler versions
+    /// requested versions are rewritten to. Empty by default.
+    pub version_aliases: BTreeMap<String, String>,
+    /// Maximum number of settings variants (e.g. bytecode hashes) of a single request
+    /// compiled concurrently; once one of them matches, the others are cancelled.
+    /// Set to `1` by default, so the variants are compiled one by one.
+    pub parallel_compile_attempts: NonZeroUsize,
+    /// Directory compilers are run in; every compilation uses its own subdirectory
+    /// removed afterwards. Set to the system temp directory by default.
+    pub scratch_dir: PathBuf,
+    /// JSON-RPC endpoint of the chain used to read implementation addresses
+    /// of the proxies, which failed to be verified. Not set by default.
+    #[serde(serialize_with = "serialize_redacted")]
+    pub rpc_url: Option<Url>,
+    /// If enabled, the contracts failed to be verified are looked up among the contracts
+    /// verified by Sourcify on the chain of the request, using the `[sourcify]` api settings.
+    /// Requests with the contract address must specify the chain id. Set to `false` by default.
+    pub sourcify_fallback: bool,
+    pub list_history: ListHistoryConfiguration,
+    pub auto_version: AutoVersionConfiguration,
+    pub jobs: JobsConfiguration,
+    /// If set, cached compilers are checked against the hashsum currently published
+    /// by the fetcher once they were validated longer than the number of seconds ago,
+    /// and downloaded again if the hashsum has changed. Not set by default.
+    pub cache_max_age: Option<u64>,
+    /// If enabled, downloaded compilers are run with `--version` before being cached,
+    /// and are removed if they fail or report another version.
+    /// Compilers of the local fetcher are not checked.
+    pub check_compiler_binaries: bool,
+    /// If set, fetches of compilers taking longer than the number of seconds are aborted.
+    /// Not set by default.
+    pub fetch_timeout: Option<u64>,
+    /// Versions not found by the fetcher are not fetched again for the number of seconds,
+    /// unless the versions are refreshed in the meantime. `0` disables the caching.
+    /// Set to `60` by default.
+    pub not_found_cache_ttl: u64,
+    /// If set, the least recently used downloaded compilers are removed
+    /// once the compilers occupy more bytes on disk. Not set by default.
+    pub max_cache_bytes: Option<u64>,
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct ListHistoryConfiguration {
+    /// If enabled, a snapshot of the compiler versions with their hashsums
+    /// is appended to the `file` on every compiler list refresh.
+    /// Set to `false` by default.
+    pub enabled: bool,
+    /// Json lines file the snapshots are appended to.
+    pub file: PathBuf,
+    /// Number of days the snapshots are kept for.
+    pub retention_days: u64,
+}
+
+impl Default for ListHistoryConfiguration {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            file: "compiler_list_history.jsonl".into(),
+            retention_days: 365,
+        }
+    }
+}
+
+/// Limits of the verifications looking up the compiler version.
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct AutoVersionConfiguration {
+    /// Maximum number of the newest matching compiler versions tried per request.
+    /// Set to `10` by default.
+    pub max_versions: usize,
+    /// Number of compiler versions tried concurrently; once one of them matches,
+    /// the others are cancelled. Set to `2` by default.
+    pub parallel_versions: NonZeroUsize,
+}
+
+impl Default for AutoVersionConfiguration {
+    fn default() -> Self {
+        Self {
+            max_versions: 10,
+            parallel_versions: NonZeroUsize::new(2).expect("non zero"),
+        }
+    }
+}
+
+/// Results of the asynchronous verifications kept in memory.
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct JobsConfiguration {
+    /// Number of seconds the results of finished verifications are kept for.
+    /// Set to `3600` by default.
+    pub ttl: u64,
+    /// Number of seconds between the removals of expired results.
+    /// Set to `60` by default.
+    pub sweep_interval: u64,
+    /// Number of seconds after which verifications still running are reported as failed.
+    /// Set to `600` by default.
+    pub pending_timeout: u64,
+    /// Maximum number of verifications running in the background.
+    /// New verifications are rejected once reached. Set to `64` by default.
+    pub max_pending: usize,
+}
+
+impl Default for JobsConfiguration {
+    fn default() -> Self {
+        Self {
+            ttl: 3600,
+            sweep_interval: 60,
+            pending_timeout: 600,
+            max_pending: 64,
+        }
+    }
+}
+
+/// Besides the tables of the variants, `"list"` is accepted as the list fetcher
+/// with the default configuration.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case", remote = "Self")]
+pub enum FetcherConfig {
+    /// Compilers are described by the solc list located at `compilers_list_url`
+    List(ListFetcherConfig),
+    /// Compilers are downloaded from a flat mirror with templated urls
+    Http(HttpFetcherConfig),
+    /// Compilers are downloaded from a Google Cloud Storage bucket
+    Gcs(GcsFetcherConfig),
+    /// Compilers are pre-staged in a local directory
+    Local(LocalFetcherConfig),
+    /// Compilers are downloaded from the assets of GitHub releases
+    Github(GithubFetcherConfig),
+}
+
+impl Serialize for FetcherConfig {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        FetcherConfig::serialize(self, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for FetcherConfig {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = config::Value::deserialize(deserializer)?;
+        match value.kind {
+            ValueKind::String(name) if name == "list" => {
+                Ok(FetcherConfig::List(ListFetcherConfig::default()))
+            }
+            ValueKind::String(name) => Err(D::Error::custom(format!(
+                "fetcher {} must be configured with a table",
+                name
+            ))),
+            _ => FetcherConfig::deserialize(value).map_err(D::Error::custom),
+        }
+    }
+}
+
+#[serde_as]
+#[derive(Deserialize, Serialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ListFetcherConfig {
+    /// Overrides `refresh_versions_schedule` of the language for this fetcher. Not set by default.
+    #[serde_as(as = "Option<DisplayFromStr>")]
+    #[serde(default)]
+    pub refresh_versions_schedule: Option<Schedule>,
+}
+
+#[serde_as]
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct HttpFetcherConfig {
+    /// Url returning a json array of available version strings
+    pub index_url: Url,
+    /// Url of the compiler binary, where `{version}` is replaced with the compiler version
+    pub binary_url_template: String,
+    /// Url of the compiler sha256 hashsum, where `{version}` is replaced with the compiler version
+    pub hash_url_template: String,
+    /// Overrides `refresh_versions_schedule` of the language for this fetcher. Not set by default.
+    #[serde_as(as = "Option<DisplayFromStr>")]
+    #[serde(default)]
+    pub refresh_versions_schedule: Option<Schedule>,
+}
+
+#[serde_as]
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct GcsFetcherConfig {
+    /// Bucket containing a folder named after every compiler version,
+    /// with `solc` binary and `sha256.hash` hashsum files inside
+    pub bucket: String,
+    /// Path to the json key of the service account with read access to the bucket
+    pub service_account_key: PathBuf,
+    /// Folder inside the bucket containing version folders. Not set by default.
+    pub prefix: Option<String>,
+    /// Custom GCS endpoint (e.g. of an emulator). Not set by default.
+    pub endpoint: Option<Url>,
+    /// Name of the compiler binary inside version folders. Set to `solc` by default.
+    #[serde(default = "default_binary_name")]
+    pub binary_name: String,
+    /// Name of the hashsum file inside version folders. Set to `sha256.hash` by default.
+    #[serde(default = "default_hash_name")]
+    pub hash_name: String,
+    /// Number of attempts to download a file, if the bucket returns
+    /// server errors or is unreachable. Set to `3` by default.
+    #[serde(default = "default_download_attempts")]
+    pub download_attempts: NonZeroUsize,
+    /// Delay (in milliseconds) before the first retry, doubled on every next one.
+    /// Set to `500` by default.
+    #[serde(default = "default_download_retry_delay")]
+    pub download_retry_delay: u64,
+    /// Overrides `refresh_versions_schedule` of the language for this fetcher. Not set by default.
+    #[serde_as(as = "Option<DisplayFromStr>")]
+    #[serde(default)]
+    pub refresh_versions_schedule: Option<Schedule>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct LocalFetcherConfig {
+    /// Directory containing a folder named after every compiler version,
+    /// with `solc` binary and `sha256.hash` hashsum files inside
+    pub dir: PathBuf,
+}
+
+#[serde_as]
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct GithubFetcherConfig {
+    /// Repository, which releases contain the compilers. Set to `ethereum/solidity` by default.
+    #[serde(default = "default_github_repo")]
+    pub repo: String,
+    /// Token used to authenticate the requests, which raises the API rate limit.
+    /// Not set by default.
+    #[serde(serialize_with = "serialize_redacted")]
+    pub token: Option<String>,
+    /// Name of the release asset containing the compiler binary.
+    /// Set to `solc-static-linux` by default.
+    #[serde(default = "default_github_asset_name")]
+    pub asset_name: String,
+    /// Custom API url (e.g. of a GitHub Enterprise server). Not set by default.
+    pub api_url: Option<Url>,
+    /// Overrides `refresh_versions_schedule` of the language for this fetcher. Not set by default.
+    #[serde_as(as = "Option<DisplayFromStr>")]
+    #[serde(default)]
+    pub refresh_versions_schedule: Option<Schedule>,
+}
+
+fn default_github_repo() -> String {
+    "ethereum/solidity".into()
+}
+
+fn default_github_asset_name() -> String {
+    "solc-static-linux".into()
+}
+
+fn default_binary_name() -> String {
+    "solc".into()
+}
+
+fn default_hash_name() -> String {
+    "sha256.hash".into()
+}
+
+fn default_download_attempts() -> NonZeroUsize {
+    NonZeroUsize::new(3).expect("non zero")
+}
+
+fn default_download_retry_delay() -> u64 {
+    500
+}
+
+impl SolidityConfiguration {
+    /// Schedule the versions of the `fetcher` are refreshed on.
+    pub fn fetcher_refresh_schedule(&self) -> &Schedule {
+        self.fetcher
+            .refresh_versions_schedule()
+            .unwrap_or(&self.refresh_versions_schedule)
+    }
 }
 
 impl Default for SolidityConfiguration {
     fn default() -> Self {
         Self {
             compilers_list_url: Url::try_from(DEFAULT_COMPILER_LIST).expect("valid url"),
+            compilers_list_mirrors: vec![],
+            compilers_list_checksum: ChecksumKind::Sha256,
             enabled: true,
             refresh_versions_schedule: Schedule::from_str("0 0 * * * * *").unwrap(), // every hour
+            refresh_versions_max_skipped_ticks: 16,
+            refresh_versions_max_jitter: 0,
+            strip_model_checker_settings: true,
+            prefetch_latest: 0,
+            prefetch_versions: vec![],
+            fetcher: FetcherConfig::List(ListFetcherConfig::default()),
+            match_strategies: vec![MatchStrategy::Exact],
+            bytecode_match_policy: BytecodeMatchPolicy::Either,
+            source_transforms: vec![],
+            version_aliases: BTreeMap::new(),
+            parallel_compile_attempts: NonZeroUsize::new(1).expect("non zero"),
+            scratch_dir: std::env::temp_dir(),
+            rpc_url: None,
+            sourcify_fallback: false,
+            list_history: Default::default(),
+            auto_version: Default::default(),
+            jobs: Default::default(),
+            cache_max_age: None,
+            check_compiler_binaries: false,
+            fetch_timeout: None,
+            not_found_cache_ttl: 60,
+            max_cache_bytes: None,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct VyperConfiguration {
+    /// Set to `false` by default.
+    pub enabled: bool,
+    /// Source the compilers are fetched from; must be set if vyper verification is enabled.
+    /// Only `Http` and `Local` fetchers are supported, as there is no vyper compilers list.
+    pub fetcher: Option<FetcherConfig>,
+    #[serde(with = "serde_with::rust::display_fromstr")]
+    pub refresh_versions_schedule: Schedule,
+    /// After consecutive failures of the versions refresh, up to this number
+    /// of scheduled refreshes are skipped in a row. `0` disables the backoff.
+    /// Set to `16` by default.
+    pub refresh_versions_max_skipped_ticks: u32,
+    /// Scheduled refreshes are delayed by a random number of seconds up to this one,
+    /// so that replicas sharing the schedule do not hit the fetcher at once.
+    /// Set to `0` by default.
+    pub refresh_versions_max_jitter: u64,
+    /// Strategies applied in order until the contract is matched,
+    /// if not specified in the request. Set to `["exact"]` by default.
+    pub match_strategies: Vec<MatchStrategy>,
+    /// Directory compilers are run in; every compilation uses its own subdirectory
+    /// removed afterwards. Set to the system temp directory by default.
+    pub scratch_dir: PathBuf,
+}
+
+impl VyperConfiguration {
+    /// Schedule the versions of the `fetcher` are refreshed on.
+    pub fn fetcher_refresh_schedule(&self) -> &Schedule {
+        self.fetcher
+            .as_ref()
+            .and_then(FetcherConfig::refresh_versions_schedule)
+            .unwrap_or(&self.refresh_versions_schedule)
+    }
+}
+
+impl Default for VyperConfiguration {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            fetcher: None,
+            refresh_versions_schedule: Schedule::from_str("0 0 * * * * *").unwrap(), // every hour
+            refresh_versions_max_skipped_ticks: 16,
+            refresh_versions_max_jitter: 0,
+            match_strategies: vec![MatchStrategy::Exact],
+            scratch_dir: std::env::temp_dir(),
         }
     }
 }
 
-#[derive(Deserialize, Clone)]
+#[derive(Deserialize, Serialize, Clone)]
 #[serde(default)]
 pub struct SourcifyConfiguration {
     pub enabled: bool,
@@ -68,16 +480,474 @@ impl Default for SourcifyConfiguration {
     }
 }
 
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct DeadLetterConfiguration {
+    /// If enabled, failed verification requests are stored for offline analysis.
+    /// Set to `false` by default.
+    pub enabled: bool,
+    /// Directory where failed verification requests are stored.
+    pub dir: PathBuf,
+    /// Maximum size (in bytes) of a single stored request. Source contents
+    /// are removed from larger requests; requests which are still too large are not stored.
+    pub max_entry_size: usize,
+    /// Number of days the stored requests are kept for.
+    pub retention_days: u64,
+}
+
+impl Default for DeadLetterConfiguration {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            dir: "dead_letters/".into(),
+            max_entry_size: 1024 * 1024,
+            retention_days: 30,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct EventsConfiguration {
+    /// If enabled, outcomes of completed verifications are published to the NATS `subject`.
+    /// Set to `false` by default.
+    pub enabled: bool,
+    /// Credentials in the url are redacted in the admin config endpoint.
+    #[serde(serialize_with = "serialize_without_credentials")]
+    pub nats_url: Url,
+    pub subject: String,
+    /// Chain included into the published events. Not set by default.
+    pub chain_id: Option<String>,
+    /// Maximum number of events waiting to be published.
+    pub buffer_size: NonZeroUsize,
+    /// What happens to new events when the buffer is full.
+    /// Set to `Drop` by default.
+    pub backpressure: BackpressurePolicy,
+    /// Number of seconds sending an event to the broker may take
+    /// before the event is dropped and the connection is reset. Set to `5` by default.
+    pub publish_timeout: u64,
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BackpressurePolicy {
+    /// New events are dropped with a warning
+    Drop,
+    /// Verification responses wait until the buffer has free space
+    Block,
+}
+
+impl Default for EventsConfiguration {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            nats_url: Url::from_str("nats://127.0.0.1:4222").expect("valid url"),
+            subject: "verification.results".into(),
+            chain_id: None,
+            buffer_size: NonZeroUsize::new(1024).expect("non zero"),
+            backpressure: BackpressurePolicy::Drop,
+            publish_timeout: 5,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct UploadsConfiguration {
+    /// If enabled, large standard json inputs could be uploaded in chunks
+    /// and referenced by the upload id in verification requests.
+    /// Set to `false` by default.
+    pub enabled: bool,
+    /// Maximum total size (in bytes) of a single upload.
+    pub max_size: usize,
+    /// Maximum number of uploads in progress. New uploads are rejected once reached.
+    pub max_uploads: usize,
+    /// Maximum size (in bytes) of all uploads in progress together.
+    /// Chunks exceeding it are rejected.
+    pub max_total_size: usize,
+    /// Number of seconds after the last appended chunk the upload is removed in.
+    pub expiry: u64,
+}
+
+impl Default for UploadsConfiguration {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_size: 50 * 1024 * 1024,
+            max_uploads: 64,
+            max_total_size: 512 * 1024 * 1024,
+            expiry: 3600,
+        }
+    }
+}
+
+/// Map from experimental feature names to the flag indicating whether the feature is enabled.
+/// Features not specified in the map are disabled.
+#[derive(Deserialize, Serialize, Clone, Default, Debug, PartialEq, Eq)]
+#[serde(transparent)]
+pub struct FeaturesConfiguration(pub BTreeMap<String, bool>);
+
+impl FeaturesConfiguration {
+    pub fn is_enabled(&self, feature: &str) -> bool {
+        self.0.get(feature).copied().unwrap_or_default()
+    }
+
+    /// Returns the names of configured features not present in `known` list.
+    pub fn unknown<'a>(&'a self, known: &'a [&str]) -> impl Iterator<Item = &'a str> {
+        self.0
+            .keys()
+            .map(String::as_str)
+            .filter(|feature| !known.contains(feature))
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Default)]
+#[serde(default)]
+pub struct AdminConfiguration {
+    /// If enabled, admin endpoints are available for requests
+    /// authorized with the `token` (via `Authorization: Bearer <token>` header).
+    /// Set to `false` by default.
+    pub enabled: bool,
+    #[serde(serialize_with = "serialize_redacted")]
+    pub token: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Default)]
+#[serde(default)]
+pub struct MetricsConfiguration {
+    /// If enabled, prometheus metrics are available at `/metrics`.
+    /// Set to `false` by default.
+    pub enabled: bool,
+}
+
+/// Serializes secret values so that they never appear in the output
+/// (e.g. responses of admin endpoints), only indicating whether the value is set.
+fn serialize_redacted<S: Serializer, T>(
+    secret: &Option<T>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    secret.as_ref().map(|_| "[REDACTED]").serialize(serializer)
+}
+
+fn serialize_without_credentials<S: Serializer>(
+    url: &Url,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let mut url = url.clone();
+    if url.password().is_some() {
+        let _ = url.set_password(Some("[REDACTED]"));
+    }
+    // either the user name or the token in the `nats://token@host` form
+    if !url.username().is_empty() {
+        let _ = url.set_username("[REDACTED]");
+    }
+    url.as_str().serialize(serializer)
+}
+
 impl Config {
-    pub fn from_file(file: PathBuf) -> Result<Self, config::ConfigError> {
+    /// Reads the config from the `file` (if it exists) in the format selected by its extension:
+    /// `.toml`, `.yaml`, `.yml` or `.json`. Options could be overridden by environment variables.
+    pub fn from_file(file: PathBuf) -> Result<Self, ConfigError> {
         let mut builder =
             LibConfig::builder().add_source(config::Environment::with_prefix("VERIFICATION"));
         if file.exists() {
-            builder = builder.add_source(File::from(file));
+            let format = file_format(&file)?;
+            builder = builder.add_source(File::from(file).format(format));
         }
-        builder
-            .build()
-            .expect("Failed to build config")
-            .try_deserialize()
+        builder.build()?.try_deserialize()
+    }
+
+    /// Checks the invariants between the options, which are not enforced by their types.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let invalid = |message: &str| Err(ConfigError::Message(message.to_string()));
+        if !self.solidity.enabled && !self.vyper.enabled && !self.sourcify.enabled {
+            return invalid("at least one of solidity, vyper or sourcify must be enabled");
+        }
+        if self.solidity.enabled {
+            self.solidity.fetcher.validate("solidity")?;
+            for (alias, target) in &self.solidity.version_aliases {
+                if let Err(err) = compiler::Version::from_str(target) {
+                    return Err(ConfigError::Message(format!(
+                        "solidity.version_aliases: target {} of {} is not a valid compiler version: {}",
+                        target, alias, err
+                    )));
+                }
+            }
+        }
+        if self.vyper.enabled {
+            match &self.vyper.fetcher {
+                None => return invalid("vyper.fetcher must be set if vyper is enabled"),
+                Some(fetcher @ (FetcherConfig::Http(_) | FetcherConfig::Local(_))) => {
+                    fetcher.validate("vyper")?
+                }
+                Some(_) => return invalid("only http and local fetchers are supported for vyper"),
+            }
+        }
+        let sourcify_used =
+            self.sourcify.enabled || (self.solidity.enabled && self.solidity.sourcify_fallback);
+        if sourcify_used && !matches!(self.sourcify.api_url.scheme(), "http" | "https") {
+            return invalid("sourcify.api_url must be an http or https url");
+        }
+        if self.admin.enabled && self.admin.token.is_none() {
+            return invalid("admin.token must be set if admin is enabled");
+        }
+        Ok(())
+    }
+}
+
+impl FetcherConfig {
+    /// Schedule of the versions refresh configured for the fetcher itself, if any.
+    /// The `local` fetcher has no schedule of its own.
+    pub fn refresh_versions_schedule(&self) -> Option<&Schedule> {
+        match self {
+            FetcherConfig::List(config) => config.refresh_versions_schedule.as_ref(),
+            FetcherConfig::Http(config) => config.refresh_versions_schedule.as_ref(),
+            FetcherConfig::Gcs(config) => config.refresh_versions_schedule.as_ref(),
+            FetcherConfig::Github(config) => config.refresh_versions_schedule.as_ref(),
+            FetcherConfig::Local(_) => None,
+        }
+    }
+
+    fn validate(&self, language: &str) -> Result<(), ConfigError> {
+        let invalid = |message: String| Err(ConfigError::Message(message));
+        match self {
+            FetcherConfig::Http(config) => {
+                for (name, template) in [
+                    ("binary_url_template", &config.binary_url_template),
+                    ("hash_url_template", &config.hash_url_template),
+                ] {
+                    if !template.contains("{version}") {
+                        return invalid(format!(
+                            "{}.fetcher.http.{} must contain {{version}}",
+                            language, name
+                        ));
+                    }
+                }
+            }
+            FetcherConfig::Gcs(config) if config.bucket.is_empty() => {
+                return invalid(format!("{}.fetcher.gcs.bucket must be set", language));
+            }
+            _ => (),
+        }
+        Ok(())
+    }
+}
+
+fn file_format(file: &std::path::Path) -> Result<FileFormat, ConfigError> {
+    match file.extension().and_then(|extension| extension.to_str()) {
+        Some("toml") => Ok(FileFormat::Toml),
+        Some("yaml" | "yml") => Ok(FileFormat::Yaml),
+        Some("json") => Ok(FileFormat::Json),
+        _ => Err(ConfigError::Message(format!(
+            "unsupported format of the config file {}: expected .toml, .yaml, .yml or .json",
+            file.display()
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::env::temp_dir;
+
+    fn load(extension: &str, content: &str) -> Result<Config, ConfigError> {
+        let path = temp_dir().join(format!(
+            "verification_config_{}.{}",
+            rand::random::<u64>(),
+            extension
+        ));
+        std::fs::write(&path, content).unwrap();
+        let config = Config::from_file(path.clone());
+        std::fs::remove_file(path).unwrap();
+        config
+    }
+
+    fn to_json(config: Config) -> serde_json::Value {
+        serde_json::to_value(config).unwrap()
+    }
+
+    #[test]
+    fn loads_equivalent_configs_in_any_format() {
+        let toml = load(
+            "toml",
+            r#"
+            [server]
+            addr = "127.0.0.1:8050"
+            shutdown_timeout = 5
+
+            [solidity]
+            enabled = false
+            "#,
+        )
+        .unwrap();
+        let yaml = load(
+            "yml",
+            "server:\n  addr: 127.0.0.1:8050\n  shutdown_timeout: 5\nsolidity:\n  enabled: false\n",
+        )
+        .unwrap();
+        let json = load(
+            "json",
+            r#"{"server": {"addr": "127.0.0.1:8050", "shutdown_timeout": 5}, "solidity": {"enabled": false}}"#,
+        )
+        .unwrap();
+        assert_eq!(toml.server.shutdown_timeout, 5);
+        assert!(!toml.solidity.enabled);
+        let toml = to_json(toml);
+        assert_eq!(to_json(yaml), toml);
+        assert_eq!(to_json(json), toml);
+    }
+
+    #[test]
+    fn fetchers_override_refresh_schedule() {
+        let config = load(
+            "toml",
+            r#"
+            [solidity]
+            refresh_versions_schedule = "0 0 0 * * * *"
+
+            [solidity.fetcher.http]
+            index_url = "https://example.com/list.json"
+            binary_url_template = "https://example.com/{version}/solc"
+            hash_url_template = "https://example.com/{version}/solc.sha256"
+            refresh_versions_schedule = "0 0 * * * * *"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.solidity.fetcher_refresh_schedule(),
+            &Schedule::from_str("0 0 * * * * *").unwrap()
+        );
+
+        let mut config = config.solidity;
+        config.fetcher = FetcherConfig::List(ListFetcherConfig::default());
+        assert_eq!(
+            config.fetcher_refresh_schedule(),
+            &Schedule::from_str("0 0 0 * * * *").unwrap()
+        );
+
+        let config = load(
+            "toml",
+            r#"
+            [solidity.fetcher.list]
+            refresh_versions_schedule = "0 0 * * * * *"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.solidity.fetcher_refresh_schedule(),
+            &Schedule::from_str("0 0 * * * * *").unwrap()
+        );
+    }
+
+    #[test]
+    fn list_fetcher_could_be_named() {
+        let config = load(
+            "toml",
+            r#"
+            [solidity]
+            fetcher = "list"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.solidity.fetcher,
+            FetcherConfig::List(ListFetcherConfig::default())
+        );
+
+        let err = load(
+            "toml",
+            r#"
+            [solidity]
+            fetcher = "local"
+            "#,
+        )
+        .err()
+        .expect("local fetcher requires a directory");
+        assert!(err.to_string().contains("must be configured"), "{}", err);
+    }
+
+    #[test]
+    fn validates_config() {
+        assert!(Config::default().validate().is_ok());
+
+        let mut config = Config::default();
+        config.solidity.enabled = false;
+        config.sourcify.enabled = false;
+        assert!(config.validate().is_err());
+
+        let mut config = Config::default();
+        config.vyper.enabled = true;
+        assert!(config.validate().is_err());
+        config.vyper.fetcher = Some(FetcherConfig::List(ListFetcherConfig::default()));
+        assert!(config.validate().is_err());
+        config.vyper.fetcher = Some(FetcherConfig::Local(LocalFetcherConfig {
+            dir: "compilers/".into(),
+        }));
+        assert!(config.validate().is_ok());
+
+        let mut config = Config::default();
+        config.solidity.fetcher = FetcherConfig::Gcs(GcsFetcherConfig {
+            bucket: "".into(),
+            service_account_key: "key.json".into(),
+            prefix: None,
+            endpoint: None,
+            binary_name: default_binary_name(),
+            hash_name: default_hash_name(),
+            download_attempts: default_download_attempts(),
+            download_retry_delay: default_download_retry_delay(),
+            refresh_versions_schedule: None,
+        });
+        let err = config.validate().unwrap_err();
+        assert_eq!(err.to_string(), "solidity.fetcher.gcs.bucket must be set");
+
+        let mut config = Config::default();
+        config.solidity.fetcher = FetcherConfig::Http(HttpFetcherConfig {
+            index_url: Url::from_str("https://example.com/list.json").unwrap(),
+            binary_url_template: "https://example.com/solc".into(),
+            hash_url_template: "https://example.com/{version}.sha256".into(),
+            refresh_versions_schedule: None,
+        });
+        assert!(config.validate().is_err());
+
+        let mut config = Config::default();
+        config.sourcify.api_url = Url::from_str("ftp://sourcify.dev/").unwrap();
+        assert!(config.validate().is_err());
+        config.sourcify.enabled = false;
+        assert!(config.validate().is_ok());
+
+        let mut config = Config::default();
+        config.admin.enabled = true;
+        assert!(config.validate().is_err());
+
+        let mut config = Config::default();
+        config.solidity.version_aliases = BTreeMap::from([(
+            "v0.8.7-nightly.2021.8.9+commit.74c804d8".to_string(),
+            "v0.8.7+commit.e28d00a7".to_string(),
+        )]);
+        assert!(config.validate().is_ok());
+        config
+            .solidity
+            .version_aliases
+            .insert("old".to_string(), "not-a-version".to_string());
+        let err = config.validate().unwrap_err();
+        assert!(
+            err.to_string()
+                .starts_with("solidity.version_aliases: target not-a-version of old"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn unknown_config_format_is_rejected() {
+        let err = load("ini", "")
+            .err()
+            .expect("unknown format should be rejected");
+        assert!(
+            err.to_string().contains("unsupported format"),
+            "unexpected error: {}",
+            err
+        );
     }
 }
@@ -6,3 +6,7 @@ pub const DEFAULT_COMPILER_LIST: &str =
 pub const DEFAULT_COMPILER_LIST: &str = "https://solc-bin.ethereum.org/macosx-amd64/list.json";
 #[cfg(target_os = "windows")]
 pub const DEFAULT_COMPILER_LIST: &str = "https://solc-bin.ethereum.org/windows-amd64/list.json";
+
+/// Names of experimental features which routes are registered
+/// only if enabled in `[features]` config section.
+pub const EXPERIMENTAL_FEATURES: &[&str] = &[];
@@ -0,0 +1,321 @@
+use crate::{
+    compiler::Version, config::DeadLetterConfiguration, VerificationResponse, VerificationStatus,
+};
+use ethers_solc::{artifacts::Source, CompilerInput};
+use serde::Serialize;
+use std::{
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, SystemTime},
+};
+
+/// Full context of a failed verification request stored for offline analysis.
+///
+/// Only the data sent in the request body is recorded.
+/// No information about the requester (addresses, headers) is stored.
+#[derive(Clone, Debug, Serialize)]
+pub(crate) struct DeadLetter {
+    pub compiler_version: String,
+    pub creation_tx_input: String,
+    pub deployed_bytecode: String,
+    pub compiler_input: CompilerInput,
+    /// Indicates that source contents were removed
+    /// as the entry exceeded the maximum allowed size.
+    pub sources_truncated: bool,
+    pub failure_reason: String,
+}
+
+impl DeadLetter {
+    pub fn new(
+        compiler_version: &Version,
+        compiler_input: &CompilerInput,
+        creation_tx_input: &str,
+        deployed_bytecode: &str,
+    ) -> Self {
+        Self {
+            compiler_version: compiler_version.to_string(),
+            creation_tx_input: creation_tx_input.to_string(),
+            deployed_bytecode: deployed_bytecode.to_string(),
+            compiler_input: compiler_input.clone(),
+            sources_truncated: false,
+            failure_reason: String::new(),
+        }
+    }
+
+    fn truncate_sources(&mut self) {
+        for source in self.compiler_input.sources.values_mut() {
+            *source = Source {
+                content: String::new(),
+            };
+        }
+        self.sources_truncated = true;
+    }
+}
+
+/// Stores failed verification requests as separate json files in the configured directory.
+/// Entries older than the retention period are removed on each new record.
+pub(crate) struct DeadLetterSink {
+    dir: PathBuf,
+    max_entry_size: usize,
+    retention: Duration,
+    counter: AtomicU64,
+}
+
+impl DeadLetterSink {
+    pub fn new(config: DeadLetterConfiguration) -> std::io::Result<Self> {
+        std::fs::create_dir_all(&config.dir)?;
+        Ok(Self {
+            dir: config.dir,
+            max_entry_size: config.max_entry_size,
+            retention: Duration::from_secs(config.retention_days * 24 * 60 * 60),
+            counter: AtomicU64::new(0),
+        })
+    }
+
+    /// Records the `dead_letter` if the `response` indicates a failed verification.
+    /// Errors are only logged, as recording must not affect the response.
+    pub async fn record_if_failed(
+        &self,
+        mut dead_letter: DeadLetter,
+        response: &VerificationResponse,
+    ) {
+        if response.status != VerificationStatus::Failed {
+            return;
+        }
+        dead_letter.failure_reason = response.message.clone();
+
+        let content = match self.serialize(dead_letter) {
+            Some(content) => content,
+            None => {
+                log::warn!(target: "dead_letter", "failed verification exceeds maximum entry size and is not recorded");
+                return;
+            }
+        };
+
+        let timestamp = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis();
+        let counter = self.counter.fetch_add(1, Ordering::Relaxed);
+        let file = self.dir.join(format!("{}-{}.json", timestamp, counter));
+        let dir = self.dir.clone();
+        let retention = self.retention;
+        let result = tokio::task::spawn_blocking(move || -> std::io::Result<()> {
+            std::fs::write(file, content)?;
+            remove_expired(&dir, retention);
+            Ok(())
+        })
+        .await
+        .map_err(std::io::Error::from)
+        .and_then(|result| result);
+        if let Err(err) = result {
+            log::warn!(target: "dead_letter", "failed to record failed verification: {}", err)
+        }
+    }
+
+    /// Serializes the entry, removing source contents if the entry is too large.
+    /// Returns `None` if the entry exceeds the maximum size even without sources.
+    fn serialize(&self, mut dead_letter: DeadLetter) -> Option<String> {
+        let content = serde_json::to_string(&dead_letter).expect("serialization is infallible");
+        if content.len() <= self.max_entry_size {
+            return Some(content);
+        }
+        dead_letter.truncate_sources();
+        let content = serde_json::to_string(&dead_letter).expect("serialization is infallible");
+        (content.len() <= self.max_entry_size).then_some(content)
+    }
+}
+
+/// Returns whether the file is named as the entries recorded by [`DeadLetterSink`],
+/// i.e. `<timestamp>-<counter>.json`.
+fn is_dead_letter(path: &Path) -> bool {
+    let is_number = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_digit());
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .and_then(|name| name.strip_suffix(".json"))
+        .and_then(|stem| stem.split_once('-'))
+        .map_or(false, |(timestamp, counter)| {
+            is_number(timestamp) && is_number(counter)
+        })
+}
+
+/// Removes the expired entries from the `dir`; other files are never touched.
+/// Errors are logged per entry, so that one broken file does not block the cleanup.
+fn remove_expired(dir: &Path, retention: Duration) {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) => {
+            log::warn!(target: "dead_letter", "cannot read directory {:?}: {}", dir, err);
+            return;
+        }
+    };
+    let now = SystemTime::now();
+    for entry in entries {
+        let path = match entry {
+            Ok(entry) => entry.path(),
+            Err(err) => {
+                log::warn!(target: "dead_letter", "cannot read directory entry: {}", err);
+                continue;
+            }
+        };
+        if !is_dead_letter(&path) {
+            continue;
+        }
+        let expired = std::fs::metadata(&path)
+            .and_then(|metadata| metadata.modified())
+            .map(|modified| {
+                now.duration_since(modified)
+                    .map(|age| age > retention)
+                    .unwrap_or_default()
+            });
+        let result = match expired {
+            Ok(true) => std::fs::remove_file(&path),
+            Ok(false) => Ok(()),
+            Err(err) => Err(err),
+        };
+        if let Err(err) = result {
+            log::warn!(target: "dead_letter", "cannot remove expired entry {:?}: {}", path, err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers_solc::artifacts::Sources;
+    use pretty_assertions::assert_eq;
+    use std::str::FromStr;
+
+    fn sink(max_entry_size: usize) -> DeadLetterSink {
+        let dir = std::env::temp_dir().join(format!("dead_letter_{}", rand::random::<u64>()));
+        DeadLetterSink::new(DeadLetterConfiguration {
+            enabled: true,
+            dir,
+            max_entry_size,
+            retention_days: 30,
+        })
+        .expect("couldn't create dead letter directory")
+    }
+
+    fn dead_letter() -> DeadLetter {
+        let compiler_input = CompilerInput {
+            language: "Solidity".to_string(),
+            sources: Sources::from([(
+                "source.sol".into(),
+                Source {
+                    content: "contract A {}".to_string(),
+                },
+            )]),
+            settings: Default::default(),
+        };
+        DeadLetter::new(
+            &Version::from_str("v0.8.9+commit.e5eed63a").unwrap(),
+            &compiler_input,
+            "0x6001",
+            "0x6002",
+        )
+    }
+
+    fn records(sink: &DeadLetterSink) -> Vec<serde_json::Value> {
+        std::fs::read_dir(&sink.dir)
+            .unwrap()
+            .map(|entry| {
+                let content = std::fs::read_to_string(entry.unwrap().path()).unwrap();
+                serde_json::from_str(&content).unwrap()
+            })
+            .collect()
+    }
+
+    fn failure() -> VerificationResponse {
+        VerificationResponse::err("No contract could be verified with provided data")
+    }
+
+    #[tokio::test]
+    async fn records_only_failed_verifications() {
+        let sink = sink(1024 * 1024);
+
+        let success = VerificationResponse {
+            message: "OK".to_string(),
+            result: None,
+            status: VerificationStatus::FullMatch,
+            error_code: None,
+            compiler_diagnostics: None,
+            optimizer_likely_enabled: None,
+            proxy: None,
+            model_checker_stripped: false,
+            bytecode_matches: None,
+            debug: None,
+        };
+        sink.record_if_failed(dead_letter(), &success).await;
+        assert!(
+            records(&sink).is_empty(),
+            "successful verification recorded"
+        );
+
+        sink.record_if_failed(dead_letter(), &failure()).await;
+        let records = records(&sink);
+        assert_eq!(records.len(), 1);
+        let record = &records[0];
+        assert_eq!(
+            record["failure_reason"],
+            "No contract could be verified with provided data"
+        );
+        assert_eq!(record["compiler_version"], "v0.8.9+commit.e5eed63a");
+        assert_eq!(record["creation_tx_input"], "0x6001");
+        assert_eq!(record["deployed_bytecode"], "0x6002");
+        assert_eq!(
+            record["compiler_input"]["sources"]["source.sol"]["content"],
+            "contract A {}"
+        );
+        assert_eq!(record["sources_truncated"], false);
+    }
+
+    #[tokio::test]
+    async fn truncates_large_entries() {
+        let mut full_entry = dead_letter();
+        full_entry.failure_reason = failure().message;
+        let full_size = serde_json::to_string(&full_entry).unwrap().len();
+        let sink = sink(full_size - 1);
+        sink.record_if_failed(dead_letter(), &failure()).await;
+        let records = records(&sink);
+        assert_eq!(records.len(), 1);
+        assert_eq!(
+            records[0]["compiler_input"]["sources"]["source.sol"]["content"],
+            ""
+        );
+        assert_eq!(records[0]["sources_truncated"], true);
+
+        let sink = self::sink(10);
+        sink.record_if_failed(dead_letter(), &failure()).await;
+        assert!(self::records(&sink).is_empty(), "too large entry recorded");
+    }
+
+    #[test]
+    fn removes_only_expired_entries() {
+        let dir = std::env::temp_dir().join(format!("dead_letter_{}", rand::random::<u64>()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for name in [
+            "1-0.json",
+            "2-1.json",
+            "notes.json",
+            "1-0.json.bak",
+            "3-a.json",
+        ] {
+            std::fs::write(dir.join(name), "{}").unwrap();
+        }
+        // cannot be removed as a file, must not stop the cleanup
+        std::fs::create_dir(dir.join("0-0.json")).unwrap();
+        std::thread::sleep(Duration::from_millis(10));
+
+        remove_expired(&dir, Duration::ZERO);
+        let mut remaining: Vec<_> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        remaining.sort();
+        assert_eq!(
+            remaining,
+            ["0-0.json", "1-0.json.bak", "3-a.json", "notes.json"]
+        );
+    }
+}
@@ -0,0 +1,238 @@
+use crate::config::CorsConfiguration;
+use actix_web::{
+    body::EitherBody,
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    http::{
+        header::{self, HeaderMap, HeaderValue},
+        Method,
+    },
+    Error, HttpResponse,
+};
+use futures::future::{ready, LocalBoxFuture, Ready};
+use std::rc::Rc;
+
+const ALLOWED_METHODS: &str = "GET, POST, PATCH, OPTIONS";
+const ALLOWED_HEADERS: &str = "content-type, authorization, upload-offset";
+/// Seconds browsers could cache the preflight responses for
+const MAX_AGE: &str = "3600";
+
+/// Adds CORS headers to the responses to the configured origins, and answers their preflight
+/// requests before routing, so that preflights are supported by every route (e.g. `/verify/*`).
+/// Requests from other origins are passed through without the headers, so browsers block them.
+pub struct Cors {
+    allowed_origins: Rc<Vec<String>>,
+}
+
+impl Cors {
+    pub fn new(config: &CorsConfiguration) -> Self {
+        Self {
+            allowed_origins: Rc::new(config.allowed_origins.clone()),
+        }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for Cors
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = CorsMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(CorsMiddleware {
+            service: Rc::new(service),
+            allowed_origins: self.allowed_origins.clone(),
+        }))
+    }
+}
+
+pub struct CorsMiddleware<S> {
+    service: Rc<S>,
+    allowed_origins: Rc<Vec<String>>,
+}
+
+impl<S> CorsMiddleware<S> {
+    /// Returns the origin of the request, if it is allowed.
+    fn allowed_origin(&self, req: &ServiceRequest) -> Option<HeaderValue> {
+        let origin = req.headers().get(header::ORIGIN)?;
+        let allowed = self
+            .allowed_origins
+            .iter()
+            .any(|allowed| allowed == "*" || origin.to_str().is_ok_and(|origin| origin == allowed));
+        allowed.then(|| origin.clone())
+    }
+}
+
+/// Adds `Origin` to the `Vary` header, keeping the values already set by the handler.
+fn vary_by_origin(headers: &mut HeaderMap) {
+    let varies = headers
+        .get_all(header::VARY)
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .any(|value| value == "*" || value.eq_ignore_ascii_case("origin"));
+    if !varies {
+        headers.append(header::VARY, HeaderValue::from_static("Origin"));
+    }
+}
+
+impl<S, B> Service<ServiceRequest> for CorsMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let origin = match self.allowed_origin(&req) {
+            Some(origin) => origin,
+            None => {
+                let response = self.service.call(req);
+                return Box::pin(async move { Ok(response.await?.map_into_left_body()) });
+            }
+        };
+        let is_preflight = req.method() == Method::OPTIONS
+            && req
+                .headers()
+                .contains_key(header::ACCESS_CONTROL_REQUEST_METHOD);
+        if is_preflight {
+            let response = HttpResponse::Ok()
+                .insert_header((header::ACCESS_CONTROL_ALLOW_ORIGIN, origin))
+                .insert_header((header::ACCESS_CONTROL_ALLOW_METHODS, ALLOWED_METHODS))
+                .insert_header((header::ACCESS_CONTROL_ALLOW_HEADERS, ALLOWED_HEADERS))
+                .insert_header((header::ACCESS_CONTROL_MAX_AGE, MAX_AGE))
+                .insert_header((header::VARY, "Origin"))
+                .finish();
+            return Box::pin(ready(Ok(req.into_response(response).map_into_right_body())));
+        }
+
+        let response = self.service.call(req);
+        Box::pin(async move {
+            let mut response = response.await?;
+            let headers = response.headers_mut();
+            headers.insert(header::ACCESS_CONTROL_ALLOW_ORIGIN, origin);
+            vary_by_origin(headers);
+            Ok(response.map_into_left_body())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{
+        dev::ServiceResponse,
+        http::StatusCode,
+        test::{self, TestRequest},
+        web, App,
+    };
+    use pretty_assertions::assert_eq;
+
+    const VERIFY_ROUTE: &str = "/api/v1/solidity/verify/multiple-files";
+
+    async fn send(allowed_origins: &[&str], request: TestRequest) -> ServiceResponse<impl Sized> {
+        let config = CorsConfiguration {
+            allowed_origins: allowed_origins
+                .iter()
+                .map(|origin| origin.to_string())
+                .collect(),
+        };
+        let app = test::init_service(
+            App::new()
+                .wrap(Cors::new(&config))
+                .route(VERIFY_ROUTE, web::post().to(HttpResponse::Ok)),
+        )
+        .await;
+        request.send_request(&app).await
+    }
+
+    fn allowed_origin<B>(response: &ServiceResponse<B>) -> Option<&str> {
+        response
+            .headers()
+            .get(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+            .map(|origin| origin.to_str().unwrap())
+    }
+
+    fn preflight(origin: &str) -> TestRequest {
+        TestRequest::default()
+            .method(Method::OPTIONS)
+            .uri(VERIFY_ROUTE)
+            .insert_header((header::ORIGIN, origin))
+            .insert_header((header::ACCESS_CONTROL_REQUEST_METHOD, "POST"))
+    }
+
+    #[actix_rt::test]
+    async fn answers_preflight_of_allowed_origins() {
+        let response = send(
+            &["https://blockscout.com"],
+            preflight("https://blockscout.com"),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(allowed_origin(&response), Some("https://blockscout.com"));
+        assert_eq!(
+            response.headers().get(header::ACCESS_CONTROL_ALLOW_METHODS),
+            Some(&HeaderValue::from_static(ALLOWED_METHODS))
+        );
+
+        let response = send(&["https://blockscout.com"], preflight("https://evil.com")).await;
+        assert_eq!(allowed_origin(&response), None);
+        assert_ne!(response.status(), StatusCode::OK);
+    }
+
+    #[actix_rt::test]
+    async fn adds_allowed_origin_to_responses() {
+        let request = |origin| {
+            TestRequest::post()
+                .uri(VERIFY_ROUTE)
+                .insert_header((header::ORIGIN, origin))
+        };
+        let response = send(&["*"], request("https://blockscout.com")).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(allowed_origin(&response), Some("https://blockscout.com"));
+
+        let response = send(&["https://blockscout.com"], request("https://evil.com")).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(allowed_origin(&response), None);
+    }
+
+    #[actix_rt::test]
+    async fn keeps_vary_of_handlers() {
+        let config = CorsConfiguration {
+            allowed_origins: vec!["*".to_string()],
+        };
+        let app = test::init_service(App::new().wrap(Cors::new(&config)).route(
+            VERIFY_ROUTE,
+            web::post().to(|| async {
+                HttpResponse::Ok()
+                    .insert_header((header::VARY, "Accept-Encoding"))
+                    .finish()
+            }),
+        ))
+        .await;
+        let response = TestRequest::post()
+            .uri(VERIFY_ROUTE)
+            .insert_header((header::ORIGIN, "https://blockscout.com"))
+            .send_request(&app)
+            .await;
+        let vary: Vec<_> = response
+            .headers()
+            .get_all(header::VARY)
+            .map(|value| value.to_str().unwrap())
+            .collect();
+        assert_eq!(vary, vec!["Accept-Encoding", "Origin"]);
+
+        let mut headers = HeaderMap::new();
+        headers.insert(header::VARY, HeaderValue::from_static("accept, origin"));
+        vary_by_origin(&mut headers);
+        assert_eq!(headers.get_all(header::VARY).count(), 1);
+    }
+}
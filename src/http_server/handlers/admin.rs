@@ -0,0 +1,122 @@
+use crate::config::Config;
+use actix_web::{
+    error,
+    http::header,
+    web::{self, Json},
+    Error, HttpRequest,
+};
+use sha2::{Digest, Sha256};
+
+/// Token required to access admin endpoints.
+///
+/// Only the hash of the token is kept, and hashes of the provided tokens are compared
+/// in constant time, so that the token could not be guessed from the response times.
+pub struct AdminToken([u8; 32]);
+
+impl AdminToken {
+    pub fn new(token: String) -> Self {
+        Self(Sha256::digest(token).into())
+    }
+
+    fn matches(&self, token: &str) -> bool {
+        let digest: [u8; 32] = Sha256::digest(token).into();
+        digest
+            .iter()
+            .zip(self.0.iter())
+            .fold(0, |difference, (a, b)| difference | (a ^ b))
+            == 0
+    }
+
+    /// Checks that the request carries `Authorization: Bearer <token>` header
+    /// with the configured admin token.
+    pub fn authorize(&self, request: &HttpRequest) -> Result<(), Error> {
+        let token = request
+            .headers()
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "));
+        match token {
+            Some(token) if self.matches(token) => Ok(()),
+            _ => Err(error::ErrorUnauthorized("invalid admin token")),
+        }
+    }
+}
+
+/// Returns the configuration the server has been started with.
+/// Secrets are redacted during serialization.
+pub async fn config(
+    request: HttpRequest,
+    token: web::Data<AdminToken>,
+    config: web::Data<Config>,
+) -> Result<Json<Config>, Error> {
+    token.authorize(&request)?;
+    Ok(Json(config.get_ref().clone()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{
+        http::StatusCode,
+        test::{self, read_body_json, TestRequest},
+        App,
+    };
+    use pretty_assertions::assert_eq;
+
+    const TOKEN: &str = "admin-secret";
+    const NATS_TOKEN: &str = "nats-secret";
+
+    #[actix_rt::test]
+    async fn config_secrets_are_redacted() {
+        let mut config = Config::default();
+        config.admin.enabled = true;
+        config.admin.token = Some(TOKEN.into());
+        config.events.nats_url = format!("nats://{}@127.0.0.1:4222", NATS_TOKEN)
+            .parse()
+            .unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(AdminToken::new(TOKEN.into())))
+                .app_data(web::Data::new(config))
+                .route("/admin/config", web::get().to(super::config)),
+        )
+        .await;
+
+        let response = TestRequest::get()
+            .uri("/admin/config")
+            .send_request(&app)
+            .await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let response = TestRequest::get()
+            .uri("/admin/config")
+            .insert_header((header::AUTHORIZATION, "Bearer invalid"))
+            .send_request(&app)
+            .await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let response = TestRequest::get()
+            .uri("/admin/config")
+            .insert_header((header::AUTHORIZATION, format!("Bearer {}", TOKEN)))
+            .send_request(&app)
+            .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body: serde_json::Value = read_body_json(response).await;
+        assert_eq!(body["admin"]["token"], "[REDACTED]");
+        assert_eq!(body["server"]["addr"], "0.0.0.0:8043");
+        assert_eq!(
+            body["events"]["nats_url"],
+            "nats://%5BREDACTED%5D@127.0.0.1:4222"
+        );
+        assert!(
+            !body.to_string().contains(TOKEN),
+            "admin token is present in the output: {}",
+            body
+        );
+        assert!(
+            !body.to_string().contains(NATS_TOKEN),
+            "nats token is present in the output: {}",
+            body
+        );
+    }
+}
@@ -0,0 +1,273 @@
+use crate::{
+    config::JobsConfiguration, http_server::handlers::verification::VerificationResponse, scheduler,
+};
+use actix_web::{
+    error,
+    http::StatusCode,
+    web::{self, Json},
+    Error,
+};
+use serde::Serialize;
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum JobError {
+    #[error("job not found or expired: {0}")]
+    NotFound(String),
+    #[error("too many verifications in progress, the maximum is {0}")]
+    TooMany(usize),
+}
+
+impl From<JobError> for Error {
+    fn from(err: JobError) -> Self {
+        let status = match err {
+            JobError::NotFound(_) => StatusCode::NOT_FOUND,
+            JobError::TooMany(_) => StatusCode::SERVICE_UNAVAILABLE,
+        };
+        error::InternalError::new(err, status).into()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum JobStatus {
+    Pending,
+    /// The verification has been completed, successfully or not
+    Done {
+        response: Box<VerificationResponse>,
+    },
+    /// The verification could not be completed, e.g. due to invalid bytecodes
+    Error {
+        message: String,
+    },
+}
+
+struct Job {
+    status: JobStatus,
+    updated_at: Instant,
+    /// The job is failed if still pending at this moment
+    deadline: Instant,
+}
+
+impl Job {
+    fn fail_if_overdue(&mut self, now: Instant) {
+        if self.status == JobStatus::Pending && self.deadline <= now {
+            self.status = JobStatus::Error {
+                message: "verification has not completed in time".to_string(),
+            };
+            self.updated_at = now;
+        }
+    }
+}
+
+/// Verifications run in the background, kept in memory.
+/// Finished jobs are removed once their results are older than the ttl;
+/// pending ones are failed once they run longer than the pending timeout.
+pub struct JobStore {
+    jobs: parking_lot::Mutex<HashMap<String, Job>>,
+    ttl: Duration,
+    pending_timeout: Duration,
+    max_pending: usize,
+}
+
+impl JobStore {
+    pub fn new(config: &JobsConfiguration) -> Self {
+        Self {
+            jobs: Default::default(),
+            ttl: Duration::from_secs(config.ttl),
+            pending_timeout: Duration::from_secs(config.pending_timeout),
+            max_pending: config.max_pending,
+        }
+    }
+
+    /// Registers a new pending job and returns its id.
+    /// Fails if the maximum number of jobs is already pending.
+    pub fn start(&self) -> Result<String, JobError> {
+        let now = Instant::now();
+        let mut jobs = self.jobs.lock();
+        jobs.values_mut().for_each(|job| job.fail_if_overdue(now));
+        let pending = jobs
+            .values()
+            .filter(|job| job.status == JobStatus::Pending)
+            .count();
+        if pending >= self.max_pending {
+            return Err(JobError::TooMany(self.max_pending));
+        }
+        let id = hex::encode(rand::random::<[u8; 16]>());
+        jobs.insert(
+            id.clone(),
+            Job {
+                status: JobStatus::Pending,
+                updated_at: now,
+                deadline: now + self.pending_timeout,
+            },
+        );
+        Ok(id)
+    }
+
+    /// Records the result of the pending job.
+    /// Results of jobs already failed by the pending timeout are ignored.
+    pub fn finish(&self, id: &str, status: JobStatus) {
+        let now = Instant::now();
+        if let Some(job) = self.jobs.lock().get_mut(id) {
+            job.fail_if_overdue(now);
+            if job.status == JobStatus::Pending {
+                job.status = status;
+                job.updated_at = now;
+            }
+        }
+    }
+
+    pub fn status(&self, id: &str) -> Result<JobStatus, JobError> {
+        let now = Instant::now();
+        let mut jobs = self.jobs.lock();
+        let not_found = || JobError::NotFound(id.to_string());
+        let job = jobs.get_mut(id).ok_or_else(not_found)?;
+        job.fail_if_overdue(now);
+        if self.is_expired(job) {
+            return Err(not_found());
+        }
+        Ok(job.status.clone())
+    }
+
+    pub fn remove_expired(&self) {
+        let now = Instant::now();
+        self.jobs.lock().retain(|_, job| {
+            job.fail_if_overdue(now);
+            !self.is_expired(job)
+        });
+    }
+
+    fn is_expired(&self, job: &Job) -> bool {
+        job.status != JobStatus::Pending && job.updated_at.elapsed() >= self.ttl
+    }
+
+    /// Removes expired jobs every `interval` until the service is stopped.
+    pub fn spawn_sweep(self: std::sync::Arc<Self>, interval: Duration) {
+        scheduler::spawn_periodic_job(interval, "remove expired verification jobs", move || {
+            self.remove_expired()
+        });
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct JobResponse {
+    pub job_id: String,
+}
+
+pub async fn status(
+    store: web::Data<JobStore>,
+    job_id: web::Path<String>,
+) -> Result<Json<JobStatus>, Error> {
+    Ok(Json(store.status(&job_id)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn store(ttl: u64) -> JobStore {
+        JobStore::new(&JobsConfiguration {
+            ttl,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn reports_job_status() {
+        let store = store(60);
+        let id = store.start().unwrap();
+        assert_eq!(store.status(&id), Ok(JobStatus::Pending));
+        let status = JobStatus::Done {
+            response: Box::new(VerificationResponse::err("No contract could be verified")),
+        };
+        store.finish(&id, status.clone());
+        assert_eq!(store.status(&id), Ok(status));
+        assert_eq!(
+            store.status("unknown"),
+            Err(JobError::NotFound("unknown".to_string()))
+        );
+    }
+
+    #[test]
+    fn finished_jobs_expire() {
+        let store = store(0);
+        let id = store.start().unwrap();
+        store.remove_expired();
+        assert_eq!(
+            store.status(&id),
+            Ok(JobStatus::Pending),
+            "pending jobs should never expire"
+        );
+        store.finish(
+            &id,
+            JobStatus::Error {
+                message: "invalid bytecode".to_string(),
+            },
+        );
+        assert_eq!(store.status(&id), Err(JobError::NotFound(id.clone())));
+        store.remove_expired();
+        assert!(store.jobs.lock().is_empty());
+    }
+
+    #[test]
+    fn overdue_jobs_fail() {
+        let store = JobStore::new(&JobsConfiguration {
+            pending_timeout: 0,
+            ..Default::default()
+        });
+        let id = store.start().unwrap();
+        let failed = JobStatus::Error {
+            message: "verification has not completed in time".to_string(),
+        };
+        assert_eq!(store.status(&id), Ok(failed.clone()));
+        // late results do not override the failure
+        store.finish(
+            &id,
+            JobStatus::Done {
+                response: Box::new(VerificationResponse::err("No contract could be verified")),
+            },
+        );
+        assert_eq!(store.status(&id), Ok(failed));
+    }
+
+    #[test]
+    fn limits_pending_jobs() {
+        let store = JobStore::new(&JobsConfiguration {
+            max_pending: 2,
+            ..Default::default()
+        });
+        let first = store.start().unwrap();
+        store.start().unwrap();
+        assert_eq!(store.start(), Err(JobError::TooMany(2)));
+
+        // finished jobs free the place
+        store.finish(
+            &first,
+            JobStatus::Error {
+                message: "invalid bytecode".to_string(),
+            },
+        );
+        store.start().expect("job should be started");
+    }
+
+    #[test]
+    fn serializes_status() {
+        let status = JobStatus::Error {
+            message: "invalid bytecode".to_string(),
+        };
+        assert_eq!(
+            serde_json::to_value(status).unwrap(),
+            serde_json::json!({"status": "error", "message": "invalid bytecode"})
+        );
+        assert_eq!(
+            serde_json::to_value(JobStatus::Pending).unwrap(),
+            serde_json::json!({"status": "pending"})
+        );
+    }
+}
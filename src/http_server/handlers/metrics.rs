@@ -0,0 +1,13 @@
+use actix_web::{error, web, Error, HttpResponse};
+use prometheus::{Encoder, Registry, TextEncoder};
+
+pub async fn metrics(registry: web::Data<Registry>) -> Result<HttpResponse, Error> {
+    let encoder = TextEncoder::new();
+    let mut buffer = Vec::new();
+    encoder
+        .encode(&registry.gather(), &mut buffer)
+        .map_err(error::ErrorInternalServerError)?;
+    Ok(HttpResponse::Ok()
+        .content_type(encoder.format_type())
+        .body(buffer))
+}
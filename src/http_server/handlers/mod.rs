@@ -1,7 +1,12 @@
+pub mod admin;
+pub mod jobs;
+pub mod metrics;
+pub mod schema;
 pub mod status;
+pub mod uploads;
 pub mod verification;
 
 pub use self::verification::{
-    solidity::{multi_part, standard_json, version_list},
+    solidity::{artifact, multi_part, multi_part_auto, standard_json, version_list},
     sourcify,
 };
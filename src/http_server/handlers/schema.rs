@@ -0,0 +1,144 @@
+use crate::{
+    http_server::handlers::verification::solidity::{
+        AutoVerificationRequest, MultiPartFiles, StandardJson, VerificationRequest,
+    },
+    schema::root_schema,
+    VerificationResponse,
+};
+use actix_web::{
+    error,
+    web::{self, Json},
+    Error,
+};
+use serde_json::Value;
+
+/// Names of the types, which schemas are available.
+pub const SCHEMA_TYPES: [&str; 4] = [
+    "multi-part-request",
+    "multi-part-auto-request",
+    "standard-json-request",
+    "verification-response",
+];
+
+pub async fn get(schema_type: web::Path<String>) -> Result<Json<Value>, Error> {
+    let schema = match schema_type.as_str() {
+        "multi-part-request" => root_schema::<VerificationRequest<MultiPartFiles>>(
+            "Multi-part files verification request",
+        ),
+        "multi-part-auto-request" => root_schema::<AutoVerificationRequest>(
+            "Multi-part files verification request looking up the compiler version",
+        ),
+        "standard-json-request" => root_schema::<VerificationRequest<StandardJson>>(
+            "Standard json input verification request",
+        ),
+        "verification-response" => root_schema::<VerificationResponse>("Verification response"),
+        other => {
+            return Err(error::ErrorNotFound(format!(
+                "unknown schema type {}, expected one of {:?}",
+                other, SCHEMA_TYPES
+            )))
+        }
+    };
+    Ok(Json(schema))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::{property_names, validate};
+    use actix_web::{
+        http::StatusCode,
+        test::{self, read_body_json, TestRequest},
+        App,
+    };
+    use serde_json::json;
+    use std::collections::BTreeSet;
+
+    async fn get_schema(schema_type: &str) -> (StatusCode, Value) {
+        let app = test::init_service(App::new().route("/schema/{type}", web::get().to(get))).await;
+        let response = TestRequest::get()
+            .uri(&format!("/schema/{}", schema_type))
+            .send_request(&app)
+            .await;
+        let status = response.status();
+        match status.is_success() {
+            true => (status, read_body_json(response).await),
+            false => (status, Value::Null),
+        }
+    }
+
+    #[actix_rt::test]
+    async fn returns_schemas() {
+        for schema_type in SCHEMA_TYPES {
+            let (status, schema) = get_schema(schema_type).await;
+            assert_eq!(status, StatusCode::OK, "{}", schema_type);
+            assert_eq!(
+                schema["$schema"], "http://json-schema.org/draft-07/schema#",
+                "{}",
+                schema_type
+            );
+            assert_eq!(schema["type"], "object", "{}", schema_type);
+            let properties = schema["properties"].as_object().expect("properties");
+            for required in schema["required"].as_array().expect("required fields") {
+                assert!(
+                    properties.contains_key(required.as_str().unwrap()),
+                    "{}: required field {} is not described",
+                    schema_type,
+                    required
+                );
+            }
+        }
+        assert_eq!(get_schema("unknown").await.0, StatusCode::NOT_FOUND);
+    }
+
+    #[actix_rt::test]
+    async fn request_schemas_describe_requests() {
+        let common = json!({
+            "deployed_bytecode": "0x6001",
+            "creation_bytecode": "0x6001",
+            "compiler_version": "v0.8.14+commit.80d49f37",
+            "match_strategies": ["exact", "metadata_insensitive"],
+            "contract_file": "A.sol",
+            "contract_name": "A",
+            "local_compiler": {"path": "/usr/local/bin/solc", "sha256": null},
+            "contract_address": "0x000000000000000000000000000000000000cafe",
+            "chain_id": "1",
+            "ignore_language_mismatch": false,
+        });
+        let keys = |request: &Value| -> BTreeSet<String> {
+            request.as_object().unwrap().keys().cloned().collect()
+        };
+        let with = |fields: Value| {
+            let mut request = common.clone();
+            request
+                .as_object_mut()
+                .unwrap()
+                .extend(fields.as_object().unwrap().clone());
+            request
+        };
+
+        let multi_part = with(json!({
+            "sources": {"A.sol": "contract A {}"},
+            "evm_version": "default",
+            "optimization_runs": 0,
+            "contract_libraries": {"MyLib": "0x123123"},
+            "libraries": {"A.sol": {"OtherLib": "0x456456"}},
+            "verification_metadata": null,
+        }));
+        serde_json::from_value::<VerificationRequest<MultiPartFiles>>(multi_part.clone())
+            .expect("valid request");
+        let (_, schema) = get_schema("multi-part-request").await;
+        assert_eq!(validate(&multi_part, &schema), Ok(()));
+        assert_eq!(property_names(&schema), keys(&multi_part));
+
+        let standard_json = with(json!({
+            "input": {"language": "Solidity", "sources": {}, "settings": {"optimizer": {"enabled": false}}},
+            "upload_id": null,
+        }));
+        serde_json::from_value::<VerificationRequest<StandardJson>>(standard_json.clone())
+            .expect("valid request");
+        let (_, schema) = get_schema("standard-json-request").await;
+        assert_eq!(validate(&standard_json, &schema), Ok(()));
+        assert_eq!(property_names(&schema), keys(&standard_json));
+    }
+}
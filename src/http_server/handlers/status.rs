@@ -1,5 +1,144 @@
-use actix_web::{HttpResponse, Responder};
+use crate::{
+    compiler::{Compilers, Language},
+    config::SolidityConfiguration,
+    scheduler,
+};
+use actix_web::{web, HttpResponse, Responder};
+use serde::Serialize;
+use std::{
+    collections::BTreeMap,
+    time::{Duration, Instant},
+};
 
 pub async fn status() -> impl Responder {
     HttpResponse::Ok().finish()
 }
+
+/// Compilers of the enabled languages, the readiness of the service is checked by.
+pub struct Readiness(pub Vec<(Language, web::Data<Compilers>)>);
+
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct ReadyStatus {
+    pub ready: bool,
+    /// Number of the available compiler versions by the enabled languages
+    pub versions: BTreeMap<&'static str, usize>,
+}
+
+impl ReadyStatus {
+    /// The service is ready once the versions of every enabled language are known.
+    fn new(versions: BTreeMap<&'static str, usize>) -> Self {
+        Self {
+            ready: versions.values().all(|count| *count > 0),
+            versions,
+        }
+    }
+}
+
+/// Reports whether compiler versions have been loaded for all the enabled languages.
+pub async fn ready(readiness: web::Data<Readiness>) -> impl Responder {
+    let versions = readiness
+        .0
+        .iter()
+        .map(|(language, compilers)| (language.as_str(), compilers.all_versions().len()))
+        .collect();
+    let status = ReadyStatus::new(versions);
+    match status.ready {
+        true => HttpResponse::Ok().json(status),
+        false => HttpResponse::ServiceUnavailable().json(status),
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct RefreshStatus {
+    pub healthy: bool,
+    /// Seconds passed since the last refresh, `None` if the fetcher does not refresh versions
+    pub seconds_since_last_refresh: Option<u64>,
+    pub error: Option<String>,
+}
+
+impl RefreshStatus {
+    /// The refresh is healthy if the last one succeeded no longer than `max_age` ago.
+    /// Fetchers, which do not refresh the versions, are always healthy.
+    fn new(last_refresh: Option<(Instant, Result<(), String>)>, max_age: Option<Duration>) -> Self {
+        let (refreshed_at, result) = match last_refresh {
+            Some(last_refresh) => last_refresh,
+            None => {
+                return Self {
+                    healthy: true,
+                    seconds_since_last_refresh: None,
+                    error: None,
+                }
+            }
+        };
+        let elapsed = refreshed_at.elapsed();
+        let outdated = max_age.is_some_and(|max_age| elapsed > max_age);
+        let error = match result {
+            Err(err) => Some(err),
+            Ok(()) if outdated => Some(format!(
+                "versions have not been refreshed for {}s",
+                elapsed.as_secs()
+            )),
+            Ok(()) => None,
+        };
+        Self {
+            healthy: error.is_none(),
+            seconds_since_last_refresh: Some(elapsed.as_secs()),
+            error,
+        }
+    }
+}
+
+/// Reports whether the last refresh of the compiler versions succeeded
+/// within two intervals of the refresh schedule.
+pub async fn refresh(
+    compilers: web::Data<Compilers>,
+    config: web::Data<SolidityConfiguration>,
+) -> impl Responder {
+    let max_age =
+        scheduler::interval(config.fetcher_refresh_schedule()).map(|interval| interval * 2);
+    let status = RefreshStatus::new(compilers.last_refresh(), max_age);
+    match status.healthy {
+        true => HttpResponse::Ok().json(status),
+        false => HttpResponse::ServiceUnavailable().json(status),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn refresh_status() {
+        let max_age = Some(Duration::from_secs(60));
+        let status = |last_refresh| RefreshStatus::new(last_refresh, max_age);
+
+        assert!(status(None).healthy);
+        assert!(status(Some((Instant::now(), Ok(())))).healthy);
+
+        let failed = status(Some((Instant::now(), Err("list is unavailable".into()))));
+        assert_eq!(
+            failed,
+            RefreshStatus {
+                healthy: false,
+                seconds_since_last_refresh: Some(0),
+                error: Some("list is unavailable".into()),
+            }
+        );
+
+        let long_ago = Instant::now() - Duration::from_secs(120);
+        let outdated = status(Some((long_ago, Ok(()))));
+        assert!(!outdated.healthy);
+        assert_eq!(outdated.seconds_since_last_refresh, Some(120));
+        // without the schedule interval the age is not limited
+        assert!(RefreshStatus::new(Some((long_ago, Ok(()))), None).healthy);
+    }
+
+    #[test]
+    fn ready_status() {
+        assert!(ReadyStatus::new(BTreeMap::new()).ready);
+        assert!(ReadyStatus::new(BTreeMap::from([("solidity", 3), ("vyper", 1)])).ready);
+        let not_loaded = ReadyStatus::new(BTreeMap::from([("solidity", 3), ("vyper", 0)]));
+        assert!(!not_loaded.ready);
+    }
+}
@@ -0,0 +1,275 @@
+use crate::config::UploadsConfiguration;
+use actix_web::{
+    error,
+    http::StatusCode,
+    web::{self, Bytes, Json},
+    Error, HttpRequest, HttpResponse,
+};
+use serde::Serialize;
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+use thiserror::Error;
+
+/// Header carrying the size of the upload the chunk is appended at.
+/// Chunks sent at another offset are rejected, so that retried chunks are never appended twice.
+pub const UPLOAD_OFFSET_HEADER: &str = "Upload-Offset";
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum UploadError {
+    #[error("upload not found or expired: {0}")]
+    NotFound(String),
+    #[error("chunk offset {found} does not match the upload size {expected}")]
+    OffsetMismatch { expected: usize, found: usize },
+    #[error("upload exceeds the maximum size of {0} bytes")]
+    TooLarge(usize),
+    #[error("too many uploads in progress, the maximum is {0}")]
+    TooMany(usize),
+    #[error("uploads exceed the maximum total size of {0} bytes")]
+    StorageFull(usize),
+}
+
+impl From<UploadError> for Error {
+    fn from(err: UploadError) -> Self {
+        let status = match err {
+            UploadError::NotFound(_) => StatusCode::NOT_FOUND,
+            UploadError::OffsetMismatch { .. } => StatusCode::CONFLICT,
+            UploadError::TooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            UploadError::TooMany(_) | UploadError::StorageFull(_) => {
+                StatusCode::SERVICE_UNAVAILABLE
+            }
+        };
+        error::InternalError::new(err, status).into()
+    }
+}
+
+struct Upload {
+    data: Vec<u8>,
+    updated_at: Instant,
+}
+
+/// Uploads assembled from chunks kept in memory.
+/// Uploads are removed once consumed by a verification;
+/// uploads not appended to within the expiry period are removed as well.
+pub struct UploadStore {
+    uploads: parking_lot::Mutex<HashMap<String, Upload>>,
+    max_size: usize,
+    max_uploads: usize,
+    max_total_size: usize,
+    expiry: Duration,
+}
+
+impl UploadStore {
+    pub fn new(config: UploadsConfiguration) -> Self {
+        Self {
+            uploads: Default::default(),
+            max_size: config.max_size,
+            max_uploads: config.max_uploads,
+            max_total_size: config.max_total_size,
+            expiry: Duration::from_secs(config.expiry),
+        }
+    }
+
+    fn remove_expired(&self, uploads: &mut HashMap<String, Upload>) {
+        uploads.retain(|_, upload| upload.updated_at.elapsed() < self.expiry);
+    }
+
+    /// Starts a new empty upload and returns its id.
+    /// Fails if the maximum number of uploads are in progress.
+    pub fn start(&self) -> Result<String, UploadError> {
+        let id = hex::encode(rand::random::<[u8; 16]>());
+        let mut uploads = self.uploads.lock();
+        self.remove_expired(&mut uploads);
+        if uploads.len() >= self.max_uploads {
+            return Err(UploadError::TooMany(self.max_uploads));
+        }
+        uploads.insert(
+            id.clone(),
+            Upload {
+                data: vec![],
+                updated_at: Instant::now(),
+            },
+        );
+        Ok(id)
+    }
+
+    /// Appends the `chunk` to the upload and returns the new upload size.
+    /// If `offset` is specified, it must be equal to the current upload size.
+    /// Chunks exceeding the maximum total size of all uploads are rejected.
+    pub fn append(
+        &self,
+        id: &str,
+        offset: Option<usize>,
+        chunk: &[u8],
+    ) -> Result<usize, UploadError> {
+        let mut uploads = self.uploads.lock();
+        self.remove_expired(&mut uploads);
+        let total_size: usize = uploads.values().map(|upload| upload.data.len()).sum();
+        let upload = uploads
+            .get_mut(id)
+            .ok_or_else(|| UploadError::NotFound(id.to_string()))?;
+        match offset {
+            Some(offset) if offset != upload.data.len() => {
+                return Err(UploadError::OffsetMismatch {
+                    expected: upload.data.len(),
+                    found: offset,
+                })
+            }
+            _ => {}
+        }
+        if upload.data.len() + chunk.len() > self.max_size {
+            return Err(UploadError::TooLarge(self.max_size));
+        }
+        if total_size + chunk.len() > self.max_total_size {
+            return Err(UploadError::StorageFull(self.max_total_size));
+        }
+        upload.data.extend_from_slice(chunk);
+        upload.updated_at = Instant::now();
+        Ok(upload.data.len())
+    }
+
+    /// Returns the current upload size, from which the upload should be resumed.
+    pub fn size(&self, id: &str) -> Result<usize, UploadError> {
+        let mut uploads = self.uploads.lock();
+        self.remove_expired(&mut uploads);
+        uploads
+            .get(id)
+            .map(|upload| upload.data.len())
+            .ok_or_else(|| UploadError::NotFound(id.to_string()))
+    }
+
+    /// Removes the upload and returns the content assembled so far.
+    pub fn take(&self, id: &str) -> Result<Vec<u8>, UploadError> {
+        let mut uploads = self.uploads.lock();
+        self.remove_expired(&mut uploads);
+        uploads
+            .remove(id)
+            .map(|upload| upload.data)
+            .ok_or_else(|| UploadError::NotFound(id.to_string()))
+    }
+
+    pub fn max_size(&self) -> usize {
+        self.max_size
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct UploadResponse {
+    pub id: String,
+    pub size: usize,
+}
+
+pub async fn start(store: web::Data<UploadStore>) -> Result<HttpResponse, Error> {
+    let id = store.start()?;
+    Ok(HttpResponse::Created().json(UploadResponse { id, size: 0 }))
+}
+
+pub async fn append(
+    request: HttpRequest,
+    store: web::Data<UploadStore>,
+    id: web::Path<String>,
+    chunk: Bytes,
+) -> Result<Json<UploadResponse>, Error> {
+    let offset = request
+        .headers()
+        .get(UPLOAD_OFFSET_HEADER)
+        .map(|value| {
+            value
+                .to_str()
+                .ok()
+                .and_then(|value| value.parse().ok())
+                .ok_or_else(|| error::ErrorBadRequest("invalid upload offset"))
+        })
+        .transpose()?;
+    let id = id.into_inner();
+    let size = store.append(&id, offset, &chunk)?;
+    Ok(Json(UploadResponse { id, size }))
+}
+
+pub async fn status(
+    store: web::Data<UploadStore>,
+    id: web::Path<String>,
+) -> Result<Json<UploadResponse>, Error> {
+    let id = id.into_inner();
+    let size = store.size(&id)?;
+    Ok(Json(UploadResponse { id, size }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn store(max_size: usize, expiry: u64) -> UploadStore {
+        UploadStore::new(UploadsConfiguration {
+            enabled: true,
+            max_size,
+            expiry,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn assembles_chunks() {
+        let store = store(10, 60);
+        let id = store.start().unwrap();
+        assert_eq!(store.append(&id, Some(0), b"abc"), Ok(3));
+        // a retried chunk is not appended twice
+        assert_eq!(
+            store.append(&id, Some(0), b"abc"),
+            Err(UploadError::OffsetMismatch {
+                expected: 3,
+                found: 0
+            })
+        );
+        assert_eq!(store.append(&id, None, b"def"), Ok(6));
+        assert_eq!(store.size(&id), Ok(6));
+
+        assert_eq!(
+            store.append(&id, Some(6), b"ghijk"),
+            Err(UploadError::TooLarge(10))
+        );
+        assert_eq!(store.size(&id), Ok(6), "rejected chunk should be discarded");
+
+        assert_eq!(store.take(&id).unwrap(), b"abcdef");
+        assert_eq!(store.size(&id), Err(UploadError::NotFound(id.clone())));
+    }
+
+    #[test]
+    fn limits_all_uploads() {
+        let store = UploadStore::new(UploadsConfiguration {
+            enabled: true,
+            max_size: 10,
+            max_uploads: 2,
+            max_total_size: 15,
+            expiry: 60,
+        });
+        let first = store.start().unwrap();
+        let second = store.start().unwrap();
+        assert_eq!(store.start(), Err(UploadError::TooMany(2)));
+
+        assert_eq!(store.append(&first, None, b"0123456789"), Ok(10));
+        assert_eq!(
+            store.append(&second, None, b"abcdef"),
+            Err(UploadError::StorageFull(15))
+        );
+        assert_eq!(store.append(&second, None, b"abcde"), Ok(5));
+
+        // consumed uploads free the space
+        store.take(&first).unwrap();
+        store.start().expect("upload should be started");
+        assert_eq!(store.append(&second, None, b"fghij"), Ok(10));
+    }
+
+    #[test]
+    fn uploads_expire() {
+        let store = store(10, 0);
+        let id = store.start().unwrap();
+        assert_eq!(
+            store.append(&id, None, b"abc"),
+            Err(UploadError::NotFound(id.clone()))
+        );
+        assert!(store.uploads.lock().is_empty());
+    }
+}
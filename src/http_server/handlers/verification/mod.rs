@@ -1,33 +1,280 @@
 #![allow(dead_code)]
 
-use ethers_solc::CompilerInput;
+use ethabi::{
+    param_type::{ParamType, Writer},
+    Token,
+};
+use ethers_core::types::{Address, I256};
+use ethers_solc::{artifacts::Severity, CompilerInput};
+use primitive_types::H256;
+use sha2::{Digest, Sha256};
 use std::{collections::BTreeMap, fmt::Display};
 
-use crate::{compiler::Version, solidity::VerificationSuccess, DisplayBytes};
+use crate::{
+    compiler::{self, Version},
+    schema::{AddressDef, BytesDef, H256Def, JsonSchema},
+    solidity::{BytecodeMatches, VerificationFailure, VerificationSuccess},
+    DisplayBytes,
+};
 use serde::{Deserialize, Serialize};
 
 pub mod solidity;
 pub mod sourcify;
+pub mod vyper;
 
-#[derive(Debug, Deserialize, Serialize, PartialEq)]
+/// [`VerificationResult::match_source`] of the contracts compiled by the service itself
+pub const LOCAL_MATCH: &str = "local";
+/// [`VerificationResult::match_source`] of the contracts verified by Sourcify
+pub const SOURCIFY_MATCH: &str = "sourcify";
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, JsonSchema)]
 pub struct VerificationResponse {
     pub message: String,
+    /// Set for successful verifications only
     pub result: Option<VerificationResult>,
     pub status: VerificationStatus,
+    /// Reason of the failure clients could branch on, e.g. to retry timed out verifications.
+    /// Set for failed verifications of the service itself.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_code: Option<VerificationErrorCode>,
+    /// Errors, warnings and info messages reported by the compiler.
+    /// Set for failed compilations only.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compiler_diagnostics: Option<Vec<CompilerDiagnostic>>,
+    /// Heuristic guess whether the deployed bytecode was compiled with the optimizer enabled.
+    /// Advisory only; set for failed verifications if the guess could be made.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub optimizer_likely_enabled: Option<bool>,
+    /// Set for failed verifications if the bytecode belongs to a proxy,
+    /// in which case the implementation should be verified instead.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<ProxyInfo>,
+    /// Indicates that model checker settings were removed from the input before compilation.
+    /// Set for failed verifications only, verified contracts report it in the `result`.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub model_checker_stripped: bool,
+    /// Outcomes of the creation and deployed bytecodes compared independently.
+    /// Set for contracts verified by the service itself.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bytecode_matches: Option<BytecodeMatches>,
+    /// Bytecodes compared during the verification. Set only if requested
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub debug: Option<DebugInfo>,
+}
+
+/// Error, warning or info message reported by the compiler.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, JsonSchema)]
+pub struct CompilerDiagnostic {
+    /// One of "error", "warning" or "info"
+    pub severity: String,
+    /// Kind of the message reported by the compiler, e.g. "ParserError"
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub message: String,
+    /// Message with the source snippet formatted by the compiler
+    pub formatted_message: Option<String>,
+    pub source_location: Option<SourceLocation>,
+}
+
+/// Range of bytes of the source file the diagnostic refers to.
+/// Offsets are `-1` if unknown to the compiler.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, JsonSchema)]
+pub struct SourceLocation {
+    pub file: String,
+    pub start: i32,
+    pub end: i32,
+}
+
+impl From<&ethers_solc::artifacts::Error> for CompilerDiagnostic {
+    fn from(error: &ethers_solc::artifacts::Error) -> Self {
+        let severity = match error.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Info => "info",
+        };
+        Self {
+            severity: severity.to_string(),
+            kind: error.r#type.clone(),
+            message: error.message.clone(),
+            formatted_message: error.formatted_message.clone(),
+            source_location: error
+                .source_location
+                .as_ref()
+                .map(|location| SourceLocation {
+                    file: location.file.clone(),
+                    start: location.start,
+                    end: location.end,
+                }),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, JsonSchema)]
+pub struct DebugInfo {
+    /// Bytecodes of the matched contract; `None` if no contract has been matched
+    #[schemars(with = "Option<BytesDef>")]
+    pub compiled_creation: Option<DisplayBytes>,
+    #[schemars(with = "Option<BytesDef>")]
+    pub compiled_deployed: Option<DisplayBytes>,
+    #[schemars(with = "BytesDef")]
+    pub onchain_creation: DisplayBytes,
+    #[schemars(with = "BytesDef")]
+    pub onchain_deployed: DisplayBytes,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, JsonSchema)]
+pub struct ProxyInfo {
+    /// Proxy standard detected from the bytecode. Only "eip1967" for now
+    pub kind: String,
+    /// Read from the chain if RPC is configured and the contract address is provided
+    #[schemars(with = "Option<AddressDef>")]
+    pub implementation_address: Option<Address>,
 }
 
-#[derive(Debug, Deserialize, Serialize, PartialEq)]
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, JsonSchema)]
 pub struct VerificationResult {
     pub file_name: String,
     pub contract_name: String,
+    /// Language of the sources, e.g. "Solidity"
+    pub language: String,
+    /// One of "contract", "library", "interface" or "abstract".
+    /// `None` if the kind could not be extracted from the compiler output.
+    pub contract_kind: Option<String>,
     pub compiler_version: String,
+    /// Version requested by the client, if it was rewritten
+    /// to `compiler_version` by a configured alias or resolved from a pattern (e.g. `latest`)
+    pub compiler_version_alias: Option<String>,
     pub evm_version: String,
+    #[schemars(with = "Option<BytesDef>")]
     pub constructor_arguments: Option<DisplayBytes>,
+    /// `constructor_arguments` decoded with the constructor parameters from the abi
+    pub decoded_constructor_arguments: Option<Vec<ConstructorArgument>>,
     pub optimization: Option<bool>,
     pub optimization_runs: Option<usize>,
     pub contract_libraries: BTreeMap<String, String>,
     pub abi: String,
     pub sources: BTreeMap<String, String>,
+    /// Fingerprint of `sources` computed by [`sources_hash`]
+    #[schemars(with = "H256Def")]
+    pub sources_hash: H256,
+    /// Indicates that model checker settings were removed from the input before compilation
+    pub model_checker_stripped: bool,
+    /// Names of the transforms applied in order to the sources before compilation;
+    /// `sources` contain the transformed sources
+    pub source_transforms: Vec<String>,
+    /// Indicates that the values of `immutable` variables were masked
+    /// in the deployed bytecodes before comparison
+    pub immutables_masked: bool,
+    /// Canonical signatures of the contract functions mapped to their 4-byte selectors
+    pub function_selectors: BTreeMap<String, String>,
+    /// Canonical signatures of the contract events mapped to their topic hashes.
+    /// Anonymous events have no topic and are not included.
+    pub event_topics: BTreeMap<String, String>,
+    /// The first strategy from the requested ones the contract has been matched with.
+    /// `None` if the contract was not matched by the service itself.
+    pub match_strategy: Option<String>,
+    /// Service the contract has been matched by: [`LOCAL_MATCH`] for the compilation by the service,
+    /// [`SOURCIFY_MATCH`] for the contracts verified by Sourcify
+    pub match_source: String,
+    /// IPFS hash (CIDv0) of the metadata file returned by Sourcify, computed by the service.
+    /// Not set for metadata files larger than 256 KiB, which IPFS splits into several chunks
+    pub metadata_ipfs_hash: Option<String>,
+    /// Compiler settings the contract has been verified with in the "verification metadata" format.
+    /// Could be used to re-verify the contract later.
+    pub verification_metadata: Option<String>,
+}
+
+/// Constructor argument decoded from the data appended to the creation bytecode.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, JsonSchema)]
+pub struct ConstructorArgument {
+    pub name: String,
+    /// Canonical abi type, e.g. "uint256"
+    #[serde(rename = "type")]
+    pub kind: String,
+    /// Addresses and bytes are "0x"-prefixed hex strings, integers are decimal strings,
+    /// while arrays and tuples are json arrays of their values
+    pub value: serde_json::Value,
+}
+
+/// Pairs the decoded constructor arguments with the constructor parameters of the `abi`.
+pub(crate) fn constructor_arguments(
+    abi: &ethabi::Contract,
+    tokens: &[Token],
+) -> Vec<ConstructorArgument> {
+    let params = abi
+        .constructor()
+        .map(|constructor| constructor.inputs.as_slice());
+    params
+        .unwrap_or_default()
+        .iter()
+        .zip(tokens)
+        .map(|(param, token)| ConstructorArgument {
+            name: param.name.clone(),
+            kind: Writer::write(&param.kind),
+            value: token_value(token),
+        })
+        .collect()
+}
+
+fn token_value(token: &Token) -> serde_json::Value {
+    match token {
+        Token::Address(address) => format!("{:?}", address).into(),
+        Token::Bytes(bytes) | Token::FixedBytes(bytes) => {
+            format!("0x{}", hex::encode(bytes)).into()
+        }
+        Token::Uint(value) => value.to_string().into(),
+        Token::Int(value) => I256::from_raw(*value).to_string().into(),
+        Token::Bool(value) => (*value).into(),
+        Token::String(value) => value.clone().into(),
+        Token::Array(tokens) | Token::FixedArray(tokens) | Token::Tuple(tokens) => {
+            tokens.iter().map(token_value).collect()
+        }
+    }
+}
+
+fn canonical_signature<'a>(name: &str, params: impl Iterator<Item = &'a ParamType>) -> String {
+    let types: Vec<_> = params.map(Writer::write).collect();
+    format!("{}({})", name, types.join(","))
+}
+
+pub(crate) fn function_selectors(abi: &ethabi::Contract) -> BTreeMap<String, String> {
+    abi.functions()
+        .map(|function| {
+            let signature =
+                canonical_signature(&function.name, function.inputs.iter().map(|p| &p.kind));
+            let selector = format!("0x{}", hex::encode(function.short_signature()));
+            (signature, selector)
+        })
+        .collect()
+}
+
+pub(crate) fn event_topics(abi: &ethabi::Contract) -> BTreeMap<String, String> {
+    abi.events()
+        .filter(|event| !event.anonymous)
+        .map(|event| {
+            let signature = canonical_signature(&event.name, event.inputs.iter().map(|p| &p.kind));
+            let topic = format!("0x{}", hex::encode(event.signature()));
+            (signature, topic)
+        })
+        .collect()
+}
+
+/// Stable fingerprint of the sources map. Caches keyed by the sources must use it
+/// as well, so clients could predict the cache behavior.
+///
+/// Sources are normalized by ordering them by file name (byte-wise lexicographically);
+/// neither names nor contents are modified otherwise. Each source contributes
+/// its name and content to the sha256 hash, both prefixed by their byte length
+/// encoded as a big-endian u64, so different maps never produce the same input.
+pub fn sources_hash(sources: &BTreeMap<String, String>) -> H256 {
+    let mut hasher = Sha256::new();
+    for (name, content) in sources {
+        for part in [name, content] {
+            hasher.update((part.len() as u64).to_be_bytes());
+            hasher.update(part.as_bytes());
+        }
+    }
+    H256::from_slice(&hasher.finalize())
 }
 
 impl From<(CompilerInput, Version, VerificationSuccess)> for VerificationResult {
@@ -38,15 +285,31 @@ impl From<(CompilerInput, Version, VerificationSuccess)> for VerificationResult
             VerificationSuccess,
         ),
     ) -> Self {
+        let verification_metadata =
+            solidity::VerificationMetadata::from(&compiler_input.settings).emit();
+        let sources: BTreeMap<_, _> = compiler_input
+            .sources
+            .into_iter()
+            .map(|(path, source)| (path.to_string_lossy().to_string(), source.content))
+            .collect();
         VerificationResult {
             file_name: verification_success.file_path,
             contract_name: verification_success.contract_name,
+            language: compiler_input.language,
+            contract_kind: verification_success
+                .contract_kind
+                .map(|kind| kind.to_string()),
             compiler_version: compiler_version.to_string(),
-            evm_version: compiler_input
-                .settings
-                .evm_version
-                .map(|v| v.to_string())
-                .unwrap_or_else(|| "default".to_string()),
+            // the compiler default is reported as the version actually used
+            evm_version: match compiler_input.settings.evm_version {
+                Some(evm_version) => evm_version.to_string(),
+                None => compiler::default_evm_version(&compiler_version)
+                    .unwrap_or("default")
+                    .to_string(),
+            },
+            decoded_constructor_arguments: verification_success
+                .constructor_args_decoded
+                .map(|tokens| constructor_arguments(&verification_success.abi, &tokens)),
             constructor_arguments: verification_success.constructor_args,
             optimization: compiler_input.settings.optimizer.enabled,
             optimization_runs: compiler_input.settings.optimizer.runs,
@@ -54,34 +317,106 @@ impl From<(CompilerInput, Version, VerificationSuccess)> for VerificationResult
                 .settings
                 .libraries
                 .libs
-                .into_iter()
-                .flat_map(|(_path, libs)| libs)
+                .into_values()
+                .flatten()
                 .collect(),
+            verification_metadata: Some(verification_metadata),
+            match_strategy: Some(verification_success.match_strategy.to_string()),
+            match_source: LOCAL_MATCH.to_string(),
+            metadata_ipfs_hash: None,
+            function_selectors: function_selectors(&verification_success.abi),
+            event_topics: event_topics(&verification_success.abi),
             abi: serde_json::to_string(&verification_success.abi)
                 .expect("Is result of local compilation and, thus, should be always valid"),
-            sources: compiler_input
-                .sources
-                .into_iter()
-                .map(|(path, source)| (path.to_string_lossy().to_string(), source.content))
-                .collect(),
+            sources_hash: sources_hash(&sources),
+            sources,
+            model_checker_stripped: false,
+            source_transforms: vec![],
+            immutables_masked: verification_success.immutables_masked,
+            compiler_version_alias: None,
         }
     }
 }
 
-#[derive(Debug, Deserialize, Serialize, PartialEq)]
+/// "0" if fully matched, "2" if partially matched, "1" otherwise
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, JsonSchema)]
 pub enum VerificationStatus {
+    /// Both the bytecode and the metadata hash appended to it match.
+    /// Serialized as the status of verified contracts used before partial matches were introduced
     #[serde(rename = "0")]
-    Ok,
+    FullMatch,
+    /// The bytecode matches, but the metadata hash differs,
+    /// e.g. due to changed comments or file names
+    #[serde(rename = "2")]
+    PartialMatch,
     #[serde(rename = "1")]
     Failed,
 }
 
+/// Machine-readable reason of a failed verification.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum VerificationErrorCode {
+    /// The requested compiler version is not offered by the fetcher
+    CompilerNotFound,
+    CompilationError,
+    /// No compiled contract matches the provided bytecodes
+    BytecodeMismatch,
+    /// The input has been rejected by the checks of the service,
+    /// e.g. Vyper sources sent to the Solidity endpoints
+    InvalidInput,
+    /// The bytecode matches, but the data appended to the creation bytecode
+    /// does not fit the constructor parameters
+    ConstructorArgsMismatch,
+    /// The compiler has not been fetched in time; the verification could be retried
+    Timeout,
+}
+
+impl From<&VerificationFailure> for VerificationErrorCode {
+    fn from(failure: &VerificationFailure) -> Self {
+        match failure {
+            VerificationFailure::NoMatchingContracts => Self::BytecodeMismatch,
+            VerificationFailure::InvalidConstructorArguments { .. } => {
+                Self::ConstructorArgsMismatch
+            }
+            VerificationFailure::ContractNotFound(_) => Self::InvalidInput,
+        }
+    }
+}
+
+impl VerificationStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            VerificationStatus::FullMatch => "full_match",
+            VerificationStatus::PartialMatch => "partial_match",
+            VerificationStatus::Failed => "failed",
+        }
+    }
+}
+
 impl VerificationResponse {
+    /// Response of a fully matched contract.
     pub fn ok(result: VerificationResult) -> Self {
+        Self::verified(result, true)
+    }
+
+    /// Response of a verified contract, which metadata hash matches if `full_match` is set.
+    pub fn verified(result: VerificationResult, full_match: bool) -> Self {
+        let status = match full_match {
+            true => VerificationStatus::FullMatch,
+            false => VerificationStatus::PartialMatch,
+        };
         Self {
             message: "OK".to_string(),
             result: Some(result),
-            status: VerificationStatus::Ok,
+            status,
+            error_code: None,
+            compiler_diagnostics: None,
+            optimizer_likely_enabled: None,
+            proxy: None,
+            model_checker_stripped: false,
+            bytecode_matches: None,
+            debug: None,
         }
     }
 
@@ -90,49 +425,162 @@ impl VerificationResponse {
             message: message.to_string(),
             result: None,
             status: VerificationStatus::Failed,
+            error_code: None,
+            compiler_diagnostics: None,
+            optimizer_likely_enabled: None,
+            proxy: None,
+            model_checker_stripped: false,
+            bytecode_matches: None,
+            debug: None,
         }
     }
+
+    /// Response of a failed verification with the reason derived from the `failure`.
+    pub(crate) fn failed(failure: &VerificationFailure) -> Self {
+        Self::err(failure).with_error_code(failure.into())
+    }
+
+    pub fn with_error_code(mut self, error_code: VerificationErrorCode) -> Self {
+        self.error_code = Some(error_code);
+        self
+    }
+
+    /// Response of a failed compilation, containing the messages reported by the compiler.
+    pub(crate) fn compilation_failed(err: &compiler::Error) -> Self {
+        let diagnostics = match err {
+            compiler::Error::Compilation { diagnostics, .. } => {
+                Some(diagnostics.iter().map(CompilerDiagnostic::from).collect())
+            }
+            _ => None,
+        };
+        Self {
+            compiler_diagnostics: diagnostics,
+            ..Self::err(err).with_error_code(VerificationErrorCode::CompilationError)
+        }
+    }
+
+    pub fn with_optimizer_likely_enabled(mut self, optimizer_likely_enabled: Option<bool>) -> Self {
+        self.optimizer_likely_enabled = optimizer_likely_enabled;
+        self
+    }
+
+    pub fn with_proxy(mut self, proxy: Option<ProxyInfo>) -> Self {
+        self.proxy = proxy;
+        self
+    }
+
+    pub fn with_model_checker_stripped(mut self, model_checker_stripped: bool) -> Self {
+        self.model_checker_stripped = model_checker_stripped;
+        self
+    }
+
+    pub fn with_bytecode_matches(mut self, bytecode_matches: Option<BytecodeMatches>) -> Self {
+        self.bytecode_matches = bytecode_matches;
+        self
+    }
+
+    pub fn with_debug(mut self, debug: Option<DebugInfo>) -> Self {
+        self.debug = debug;
+        self
+    }
 }
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use super::*;
-    use crate::tests::parse::test_serialize_json_ok;
+    use crate::{
+        schema::{property_names, root_schema, validate},
+        tests::parse::test_serialize_json_ok,
+    };
     use serde_json::json;
 
+    pub(crate) fn verification_result() -> VerificationResult {
+        let sources = BTreeMap::from([("source.sol".to_string(), "content".to_string())]);
+        VerificationResult {
+            file_name: "File.sol".to_string(),
+            contract_name: "contract_name".to_string(),
+            language: "Solidity".to_string(),
+            contract_kind: Some("library".to_string()),
+            compiler_version: "compiler_version".to_string(),
+            evm_version: "evm_version".to_string(),
+            constructor_arguments: Some(DisplayBytes::from([0xca, 0xfe])),
+            decoded_constructor_arguments: Some(vec![ConstructorArgument {
+                name: "owner".to_string(),
+                kind: "address".to_string(),
+                value: json!("0x0000000000000000000000000000000000000001"),
+            }]),
+            optimization: Some(false),
+            optimization_runs: Some(200),
+            contract_libraries: BTreeMap::from([("some_library".into(), "some_address".into())]),
+            abi: "abi".to_string(),
+            sources_hash: sources_hash(&sources),
+            sources,
+            model_checker_stripped: false,
+            source_transforms: vec![],
+            immutables_masked: false,
+            compiler_version_alias: None,
+            function_selectors: BTreeMap::from([("retrieve()".into(), "0x2e64cec1".into())]),
+            event_topics: BTreeMap::new(),
+            verification_metadata: Some(r#"{"v":1,"opt":{}}"#.to_string()),
+            match_strategy: Some("exact".to_string()),
+            match_source: LOCAL_MATCH.to_string(),
+            metadata_ipfs_hash: None,
+        }
+    }
+
+    #[test]
+    fn decodes_constructor_arguments() {
+        let abi: ethabi::Contract = serde_json::from_value(json!([{
+            "type": "constructor",
+            "stateMutability": "nonpayable",
+            "inputs": [
+                {"name": "owner", "type": "address"},
+                {"name": "delta", "type": "int8"},
+                {"name": "limits", "type": "tuple", "components": [{"name": "", "type": "uint256"}, {"name": "", "type": "bytes2"}]},
+            ]
+        }]))
+        .unwrap();
+        let tokens = [
+            Token::Address(Address::from_low_u64_be(1)),
+            Token::Int(I256::from(-1).into_raw()),
+            Token::Tuple(vec![
+                Token::Uint(200.into()),
+                Token::FixedBytes(vec![0xca, 0xfe]),
+            ]),
+        ];
+        let arguments = constructor_arguments(&abi, &tokens);
+        assert_eq!(
+            serde_json::to_value(arguments).unwrap(),
+            json!([
+                {"name": "owner", "type": "address", "value": "0x0000000000000000000000000000000000000001"},
+                {"name": "delta", "type": "int8", "value": "-1"},
+                {"name": "limits", "type": "(uint256,bytes2)", "value": ["200", "0xcafe"]},
+            ])
+        );
+    }
+
     #[test]
     fn parse_response() {
         test_serialize_json_ok(vec![
             (
-                VerificationResponse::ok(VerificationResult {
-                    file_name: "File.sol".to_string(),
-                    contract_name: "contract_name".to_string(),
-                    compiler_version: "compiler_version".to_string(),
-                    evm_version: "evm_version".to_string(),
-                    constructor_arguments: Some(DisplayBytes::from([0xca, 0xfe])),
-                    optimization: Some(false),
-                    optimization_runs: Some(200),
-                    contract_libraries: BTreeMap::from([(
-                        "some_library".into(),
-                        "some_address".into(),
-                    )]),
-                    abi: "abi".to_string(),
-                    sources: serde_json::from_str(
-                        r#"{
-                            "source.sol": "content"
-                        }"#,
-                    )
-                    .unwrap(),
-                }),
+                VerificationResponse::ok(verification_result()),
                 json!({
                     "message": "OK",
                     "status": "0",
                     "result": {
                         "file_name": "File.sol",
                         "contract_name": "contract_name",
+                        "language": "Solidity",
+                        "contract_kind": "library",
                         "compiler_version": "compiler_version",
+                        "compiler_version_alias": null,
                         "evm_version": "evm_version",
                         "constructor_arguments": "0xcafe",
+                        "decoded_constructor_arguments": [{
+                            "name": "owner",
+                            "type": "address",
+                            "value": "0x0000000000000000000000000000000000000001",
+                        }],
                         "contract_libraries": {
                             "some_library": "some_address",
                         },
@@ -142,6 +590,18 @@ mod tests {
                         "sources": {
                             "source.sol": "content",
                         },
+                        "sources_hash": "0xea7b8309442fab8d0e1cc381dee4f57024fb405c883fe9bc39110ee5cf5722fb",
+                        "model_checker_stripped": false,
+                        "source_transforms": [],
+                        "immutables_masked": false,
+                        "function_selectors": {
+                            "retrieve()": "0x2e64cec1",
+                        },
+                        "event_topics": {},
+                        "verification_metadata": "{\"v\":1,\"opt\":{}}",
+                        "match_strategy": "exact",
+                        "match_source": "local",
+                        "metadata_ipfs_hash": null,
                     },
 
                 }),
@@ -154,6 +614,192 @@ mod tests {
                     "result": null,
                 }),
             ),
+            (
+                VerificationResponse::failed(&VerificationFailure::NoMatchingContracts)
+                    .with_optimizer_likely_enabled(Some(true)),
+                json!({
+                    "message": "No contract could be verified with provided data",
+                    "status": "1",
+                    "result": null,
+                    "error_code": "bytecode_mismatch",
+                    "optimizer_likely_enabled": true,
+                }),
+            ),
+            (
+                VerificationResponse::err("No contract could be verified with provided data")
+                    .with_proxy(Some(ProxyInfo {
+                        kind: "eip1967".into(),
+                        implementation_address: Some(Address::from_low_u64_be(0xcafe)),
+                    })),
+                json!({
+                    "message": "No contract could be verified with provided data",
+                    "status": "1",
+                    "result": null,
+                    "proxy": {
+                        "kind": "eip1967",
+                        "implementation_address": "0x000000000000000000000000000000000000cafe",
+                    },
+                }),
+            ),
+            (
+                VerificationResponse::failed(&VerificationFailure::NoMatchingContracts)
+                    .with_model_checker_stripped(true),
+                json!({
+                    "message": "No contract could be verified with provided data",
+                    "status": "1",
+                    "result": null,
+                    "error_code": "bytecode_mismatch",
+                    "model_checker_stripped": true,
+                }),
+            ),
         ])
     }
+
+    fn compilation_error() -> compiler::Error {
+        let diagnostics = serde_json::from_value(json!([
+            {
+                "sourceLocation": {"file": "Main.sol", "start": 25, "end": 29},
+                "type": "ParserError",
+                "component": "general",
+                "severity": "error",
+                "errorCode": "2314",
+                "message": "Expected ';' but got '{'",
+                "formattedMessage": "ParserError: Expected ';' but got '{'",
+            },
+            {
+                "type": "Warning",
+                "component": "general",
+                "severity": "warning",
+                "message": "SPDX license identifier not provided in source file.",
+            },
+        ]))
+        .unwrap();
+        compiler::Error::Compilation {
+            errors: vec!["ParserError: Expected ';' but got '{'".to_string()],
+            diagnostics,
+        }
+    }
+
+    #[test]
+    fn returns_compiler_diagnostics() {
+        let response = VerificationResponse::compilation_failed(&compilation_error());
+        assert_eq!(
+            serde_json::to_value(response).unwrap(),
+            json!({
+                "message": "Compilation error: [\"ParserError: Expected ';' but got '{'\"]",
+                "status": "1",
+                "result": null,
+                "error_code": "compilation_error",
+                "compiler_diagnostics": [
+                    {
+                        "severity": "error",
+                        "type": "ParserError",
+                        "message": "Expected ';' but got '{'",
+                        "formatted_message": "ParserError: Expected ';' but got '{'",
+                        "source_location": {"file": "Main.sol", "start": 25, "end": 29},
+                    },
+                    {
+                        "severity": "warning",
+                        "type": "Warning",
+                        "message": "SPDX license identifier not provided in source file.",
+                        "formatted_message": null,
+                        "source_location": null,
+                    },
+                ],
+            })
+        );
+    }
+
+    #[test]
+    fn responses_match_schema() {
+        let schema = root_schema::<VerificationResponse>("VerificationResponse");
+        let bytecode_matches =
+            serde_json::from_value(json!({"creation": "not_provided", "deployed": "matched"}))
+                .unwrap();
+        let responses = [
+            VerificationResponse::ok(verification_result())
+                .with_bytecode_matches(Some(bytecode_matches)),
+            VerificationResponse::compilation_failed(&compilation_error()),
+            VerificationResponse::err("No contract could be verified with provided data")
+                .with_error_code(VerificationErrorCode::Timeout)
+                .with_optimizer_likely_enabled(Some(false))
+                .with_proxy(Some(ProxyInfo {
+                    kind: "eip1967".into(),
+                    implementation_address: None,
+                }))
+                .with_debug(Some(DebugInfo {
+                    compiled_creation: Some(DisplayBytes::from([0x60, 0x80])),
+                    compiled_deployed: None,
+                    onchain_creation: DisplayBytes::from([0x60, 0x80]),
+                    onchain_deployed: DisplayBytes::from([0x60]),
+                })),
+        ];
+        for response in responses {
+            let value = serde_json::to_value(&response).unwrap();
+            assert_eq!(validate(&value, &schema), Ok(()), "{}", value);
+        }
+    }
+
+    #[test]
+    fn result_schema_describes_all_fields() {
+        let schema = root_schema::<VerificationResult>("VerificationResult");
+        let value = serde_json::to_value(verification_result()).unwrap();
+        let keys: std::collections::BTreeSet<_> =
+            value.as_object().unwrap().keys().cloned().collect();
+        assert_eq!(property_names(&schema), keys);
+    }
+
+    #[test]
+    fn sources_hash_ignores_key_order() {
+        let ordered = BTreeMap::from([
+            ("A.sol".to_string(), "contract A {}".to_string()),
+            ("B.sol".to_string(), "contract B {}".to_string()),
+        ]);
+        let reversed: BTreeMap<String, String> =
+            serde_json::from_str(r#"{"B.sol": "contract B {}", "A.sol": "contract A {}"}"#)
+                .unwrap();
+        assert_eq!(sources_hash(&ordered), sources_hash(&reversed));
+
+        let moved = BTreeMap::from([
+            (
+                "A.sol".to_string(),
+                "contract A {}contract B {}".to_string(),
+            ),
+            ("B.sol".to_string(), String::new()),
+        ]);
+        assert_ne!(sources_hash(&ordered), sources_hash(&moved));
+    }
+
+    #[test]
+    fn selectors_from_abi() {
+        let abi: ethabi::Contract = serde_json::from_value(json!([
+            {"type": "function", "name": "transfer", "stateMutability": "nonpayable",
+             "inputs": [{"name": "to", "type": "address"}, {"name": "amount", "type": "uint256"}],
+             "outputs": [{"name": "", "type": "bool"}]},
+            {"type": "function", "name": "balanceOf", "stateMutability": "view",
+             "inputs": [{"name": "account", "type": "address"}],
+             "outputs": [{"name": "", "type": "uint256"}]},
+            {"type": "event", "name": "Transfer", "anonymous": false,
+             "inputs": [{"name": "from", "type": "address", "indexed": true},
+                        {"name": "to", "type": "address", "indexed": true},
+                        {"name": "value", "type": "uint256", "indexed": false}]},
+            {"type": "event", "name": "Anonymous", "anonymous": true, "inputs": []},
+        ]))
+        .unwrap();
+
+        assert_eq!(
+            function_selectors(&abi),
+            BTreeMap::from([
+                ("balanceOf(address)".into(), "0x70a08231".into()),
+                ("transfer(address,uint256)".into(), "0xa9059cbb".into()),
+            ])
+        );
+        assert_eq!(
+            event_topics(&abi),
+            BTreeMap::from([(
+                "Transfer(address,address,uint256)".into(),
+                "0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef".into()
+            )])
+        );
+    }
 }
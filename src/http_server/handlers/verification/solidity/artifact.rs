@@ -0,0 +1,83 @@
+use super::types::{ArtifactRequest, VerificationQuery};
+use crate::{
+    compiler::Compilers,
+    config::SolidityConfiguration,
+    http_server::handlers::verification::{
+        solidity::contract_verifier::{
+            compile_and_verify_handler, resolve_compiler_version, Input, VerificationContext,
+        },
+        VerificationResponse,
+    },
+};
+use actix_web::{
+    error,
+    web::{self, Json},
+    Error, HttpRequest,
+};
+
+pub async fn verify(
+    request: HttpRequest,
+    compilers: web::Data<Compilers>,
+    config: web::Data<SolidityConfiguration>,
+    query: web::Query<VerificationQuery>,
+    params: Json<ArtifactRequest>,
+) -> Result<Json<VerificationResponse>, Error> {
+    let params = params.into_inner();
+
+    // without the creation bytecode only the deployed one is compared
+    let creation_bytecode = params
+        .creation_bytecode
+        .as_deref()
+        .or_else(|| params.artifact.bytecode.as_ref().map(|b| b.hex()))
+        .unwrap_or_default()
+        .to_string();
+    let deployed_bytecode = params.artifact.deployed_bytecode.hex().to_string();
+
+    // settings pinned by verification metadata already contain the correct bytecode hash
+    let bruteforce_bytecode_hashes = !params.content.has_verification_metadata();
+    let compiler_input = params.content.try_into().map_err(error::ErrorBadRequest)?;
+    let (compiler_version, compiler_version_alias) = resolve_compiler_version(
+        &config.version_aliases,
+        || compilers.all_versions(),
+        |version| compilers.is_cached(version),
+        &params.compiler_version,
+    )?;
+    let input = Input {
+        compiler_version,
+        compiler_input,
+        creation_tx_input: &creation_bytecode,
+        deployed_bytecode: &deployed_bytecode,
+        match_strategies: params
+            .match_strategies
+            .unwrap_or_else(|| config.match_strategies.clone()),
+        bytecode_match_policy: config.bytecode_match_policy,
+        contract_file: None,
+        contract_name: None,
+        local_compiler: None,
+        contract_address: None,
+        chain_id: None,
+        check_language: true,
+        debug: query.debug,
+        source_transforms: config
+            .source_transforms
+            .iter()
+            .map(|kind| kind.transform())
+            .collect(),
+        parallel_compile_attempts: config.parallel_compile_attempts,
+    };
+    let mut response = compile_and_verify_handler(
+        &compilers,
+        input,
+        bruteforce_bytecode_hashes,
+        VerificationContext::from_request(&request),
+    )
+    .await?;
+    if let Some(result) = response.result.as_mut() {
+        result.compiler_version_alias = compiler_version_alias;
+        // Report the abi as it was specified in the artifact
+        if let Some(abi) = params.artifact.abi {
+            result.abi = abi.to_string();
+        }
+    }
+    Ok(Json(response))
+}
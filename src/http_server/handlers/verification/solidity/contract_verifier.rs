@@ -1,83 +1,462 @@
+use super::types::LocalCompiler;
 use crate::{
-    compiler::{self, Compilers},
-    solidity::{VerificationSuccess, Verifier},
-    VerificationResponse, VerificationResult,
+    compiler::{self, Compilers, FetchError, Language},
+    dead_letter::{DeadLetter, DeadLetterSink},
+    events::{EventPublisher, VerificationEvent},
+    http_server::handlers::{
+        admin::AdminToken,
+        verification::{
+            sourcify::SourcifyFallback, DebugInfo, ProxyInfo, VerificationErrorCode,
+            VerificationStatus,
+        },
+    },
+    metrics,
+    rpc::RpcClient,
+    solidity::{
+        apply_transforms, is_eip1967_proxy, link_libraries, looks_like_vyper,
+        optimizer_likely_enabled, validate_settings, BytecodeMatchPolicy, MatchStrategy,
+        SourceTransform, VerificationFailure, VerificationSuccess, Verifier,
+        EIP1967_IMPLEMENTATION_SLOT,
+    },
+    DisplayBytes, VerificationResponse, VerificationResult,
 };
-use actix_web::error;
+use actix_web::{error, web, HttpRequest};
+use ethers_core::types::Address;
 use ethers_solc::{
     artifacts::{BytecodeHash, SettingsMetadata},
     CompilerInput,
 };
+use futures::StreamExt;
 use semver::VersionReq;
-use std::fmt::Debug;
+use std::{
+    collections::BTreeMap, fmt::Debug, num::NonZeroUsize, path::PathBuf, str::FromStr,
+    time::Instant,
+};
 use thiserror::Error;
 
+const LANGUAGE_MISMATCH: &str = "Sources look like Vyper rather than Solidity; use the Vyper verification endpoint instead (set `ignore_language_mismatch` to skip this check)";
+
 const BYTECODE_HASHES: [BytecodeHash; 3] =
     [BytecodeHash::Ipfs, BytecodeHash::None, BytecodeHash::Bzzr1];
 
 pub struct Input<'a> {
     pub compiler_version: compiler::Version,
     pub compiler_input: CompilerInput,
+    /// Either of the bytecodes may be empty, if not available
     pub creation_tx_input: &'a str,
     pub deployed_bytecode: &'a str,
+    pub match_strategies: Vec<MatchStrategy>,
+    /// Which of the provided bytecodes must match for the contract to be verified
+    pub bytecode_match_policy: BytecodeMatchPolicy,
+    /// If set, only contracts from the file are compared
+    pub contract_file: Option<String>,
+    /// If set, only contracts with the name are compared
+    pub contract_name: Option<String>,
+    /// If present, the compiler is used instead of the fetched one
+    pub local_compiler: Option<PathBuf>,
+    /// Address the contract is deployed at, if known
+    pub contract_address: Option<Address>,
+    /// Chain the contract is deployed on, if known
+    pub chain_id: Option<String>,
+    /// If set, sources looking like another language are rejected before compilation
+    pub check_language: bool,
+    /// If set, compared bytecodes are returned in the response
+    pub debug: bool,
+    /// Applied in order to the sources before compilation
+    pub source_transforms: Vec<Box<dyn SourceTransform>>,
+    /// Maximum number of bytecode hash variants compiled concurrently
+    pub parallel_compile_attempts: NonZeroUsize,
+}
+
+/// Optional collaborators of the verification; the ones not set are skipped.
+#[derive(Clone, Copy, Default)]
+pub(crate) struct VerificationContext<'a> {
+    /// Records the failed verifications
+    pub dead_letter_sink: Option<&'a DeadLetterSink>,
+    /// Reads the implementation addresses of proxies
+    pub rpc_client: Option<&'a RpcClient>,
+    /// Publishes the outcomes of the verifications
+    pub event_publisher: Option<&'a EventPublisher>,
+    /// Looks up the contracts failed to be verified in Sourcify
+    pub sourcify_fallback: Option<&'a SourcifyFallback>,
+}
+
+impl<'a> VerificationContext<'a> {
+    /// Collects the collaborators registered as the app data.
+    pub fn from_request(request: &'a HttpRequest) -> Self {
+        Self {
+            dead_letter_sink: app_data(request),
+            rpc_client: app_data(request),
+            event_publisher: app_data(request),
+            sourcify_fallback: app_data(request),
+        }
+    }
+}
+
+fn app_data<T: 'static>(request: &HttpRequest) -> Option<&T> {
+    request
+        .app_data::<web::Data<T>>()
+        .map(|data| data.get_ref())
+}
+
+/// Parses the requested compiler version, rewriting configured aliases to canonical versions,
+/// and resolving patterns (`latest` or e.g. `0.8.x`) to the newest matching release
+/// of the `available` ones. Releases of vendors are matched only by the patterns
+/// prefixed with the vendor name (e.g. `zksolc-latest`). Of the equally newest releases
+/// (e.g. the same version built from different commits), the cached one is preferred.
+/// Returns the requested version string as well, if it was rewritten.
+pub(crate) fn resolve_compiler_version(
+    aliases: &BTreeMap<String, String>,
+    available: impl FnOnce() -> Vec<compiler::Version>,
+    is_cached: impl Fn(&compiler::Version) -> bool,
+    requested: &str,
+) -> Result<(compiler::Version, Option<String>), actix_web::Error> {
+    let (version, alias) = match aliases.get(requested) {
+        Some(canonical) => (canonical.as_str(), Some(requested.to_string())),
+        None => (requested, None),
+    };
+    let (vendor, prefix) = match version_pattern(version) {
+        Some(pattern) => pattern,
+        None => {
+            let version = compiler::Version::from_str(version).map_err(error::ErrorBadRequest)?;
+            return Ok((version, alias));
+        }
+    };
+    let releases: Vec<_> = available()
+        .into_iter()
+        .filter(|version| version.is_release() && version.vendor() == vendor)
+        .collect();
+    let matching = releases.iter().filter(|version| {
+        let version = version.version();
+        [version.major, version.minor, version.patch].starts_with(&prefix)
+    });
+    // releases differing only by the commit are ordered by whether they are cached,
+    // so that a needless download is avoided
+    let newest =
+        matching.max_by(|a, b| (a.version(), is_cached(a), a).cmp(&(b.version(), is_cached(b), b)));
+    match newest {
+        Some(resolved) => Ok((resolved.clone(), Some(requested.to_string()))),
+        None => {
+            let range = match (releases.iter().min(), releases.iter().max()) {
+                (Some(min), Some(max)) => format!("available versions are {} to {}", min, max),
+                _ => "no versions are available".to_string(),
+            };
+            Err(error::ErrorBadRequest(format!(
+                "no compiler version matches {}; {}",
+                version, range
+            )))
+        }
+    }
+}
+
+/// Parses `latest` or a partial version ending with a wildcard (e.g. `0.8.x` or `v0.*`),
+/// optionally prefixed with the vendor name (e.g. `zksolc-latest`), into the vendor
+/// and the version components every matching version starts with.
+fn version_pattern(pattern: &str) -> Option<(Option<&str>, Vec<u64>)> {
+    let (vendor, pattern) = match pattern.split_once('-') {
+        Some((vendor, pattern)) => (Some(vendor), pattern),
+        None => (None, pattern),
+    };
+    if pattern == "latest" {
+        return Some((vendor, vec![]));
+    }
+    let pattern = pattern.strip_prefix('v').unwrap_or(pattern);
+    let prefix = pattern
+        .strip_suffix(".x")
+        .or_else(|| pattern.strip_suffix(".*"))?;
+    let prefix = prefix
+        .split('.')
+        .map(|component| component.parse().ok())
+        .collect::<Option<Vec<u64>>>()?;
+    (prefix.len() <= 2).then_some((vendor, prefix))
+}
+
+/// Parses the chain id of the request. Only decimal ids are accepted,
+/// as the id becomes a part of the Sourcify url.
+fn parse_chain_id(chain_id: &str) -> Result<u64, actix_web::Error> {
+    chain_id
+        .bytes()
+        .all(|b| b.is_ascii_digit())
+        .then(|| chain_id.parse().ok())
+        .flatten()
+        .ok_or_else(|| error::ErrorBadRequest("chain_id must be a decimal number"))
+}
+
+/// Validates the externally provided compiler, which could be used by admins only.
+pub(crate) fn local_compiler_path(
+    request: &HttpRequest,
+    admin_token: Option<&AdminToken>,
+    local_compiler: Option<LocalCompiler>,
+) -> Result<Option<PathBuf>, actix_web::Error> {
+    let local_compiler = match local_compiler {
+        Some(local_compiler) => local_compiler,
+        None => return Ok(None),
+    };
+    let admin_token = admin_token.ok_or_else(|| {
+        error::ErrorForbidden("local compilers are allowed only if admin endpoints are enabled")
+    })?;
+    admin_token.authorize(request)?;
+    compiler::validate_local_compiler(&local_compiler.path, local_compiler.sha256)
+        .map_err(error::ErrorBadRequest)?;
+    Ok(Some(local_compiler.path))
 }
 
 #[derive(Error, Debug)]
 enum CompileAndVerifyError {
     #[error("{0:#}")]
     Compilation(#[from] compiler::Error),
-    #[error("No contract could be verified with provided data")]
-    NoMatchingContracts,
+    #[error("{0}")]
+    NotVerified(#[from] VerificationFailure),
 }
 
 pub(crate) async fn compile_and_verify_handler(
+    compilers: &Compilers,
+    input: Input<'_>,
+    bruteforce_bytecode_hashes: bool,
+    context: VerificationContext<'_>,
+) -> Result<VerificationResponse, actix_web::Error> {
+    let VerificationContext {
+        dead_letter_sink,
+        rpc_client,
+        event_publisher,
+        sourcify_fallback,
+    } = context;
+    let started_at = Instant::now();
+    let contract_address = input.contract_address;
+    if sourcify_fallback.is_some() && contract_address.is_some() && input.chain_id.is_none() {
+        return Err(error::ErrorBadRequest(
+            "chain_id is required to look up the contract in Sourcify",
+        ));
+    }
+    let chain_id = input.chain_id.as_deref().map(parse_chain_id).transpose()?;
+    let compiler_version = input.compiler_version.to_string();
+    let dead_letter = dead_letter_sink.map(|sink| {
+        let dead_letter = DeadLetter::new(
+            &input.compiler_version,
+            &input.compiler_input,
+            input.creation_tx_input,
+            input.deployed_bytecode,
+        );
+        (sink, dead_letter)
+    });
+    let response =
+        compile_and_verify_all(compilers, input, bruteforce_bytecode_hashes, rpc_client).await?;
+    let response = match (sourcify_fallback, contract_address, chain_id) {
+        (Some(fallback), Some(address), Some(chain_id))
+            if response.status == VerificationStatus::Failed =>
+        {
+            fallback.lookup(chain_id, address).await.unwrap_or(response)
+        }
+        _ => response,
+    };
+    metrics::count_verify_contract(Language::Solidity, &response.status);
+    if let Some((sink, dead_letter)) = dead_letter {
+        sink.record_if_failed(dead_letter, &response).await;
+    }
+    if let Some(publisher) = event_publisher {
+        let event = VerificationEvent::new(
+            contract_address,
+            compiler_version,
+            &response,
+            started_at.elapsed(),
+        );
+        publisher.publish(event).await;
+    }
+    Ok(response)
+}
+
+/// Verifies the contract with the compiler version of the `input`. Unlike
+/// [`compile_and_verify_handler`], the verification is neither recorded nor published.
+pub(crate) async fn compile_and_verify_all(
     compilers: &Compilers,
     mut input: Input<'_>,
     bruteforce_bytecode_hashes: bool,
+    rpc_client: Option<&RpcClient>,
 ) -> Result<VerificationResponse, actix_web::Error> {
+    if input.match_strategies.is_empty() {
+        return Err(error::ErrorBadRequest(
+            "at least one match strategy must be specified",
+        ));
+    }
     let verifier = Verifier::new(input.creation_tx_input, input.deployed_bytecode)
+        .map_err(error::ErrorBadRequest)?
+        .with_contract(input.contract_file.clone(), input.contract_name.clone());
+    validate_settings(&input.compiler_input.settings, &input.compiler_version)
         .map_err(error::ErrorBadRequest)?;
+    if input.check_language && looks_like_vyper(&input.compiler_input.sources) {
+        return Ok(VerificationResponse::err(LANGUAGE_MISMATCH)
+            .with_error_code(VerificationErrorCode::InvalidInput));
+    }
+
+    let sources = std::mem::take(&mut input.compiler_input.sources);
+    let (sources, source_transforms) =
+        apply_transforms(&input.source_transforms, sources).map_err(error::ErrorBadRequest)?;
+    input.compiler_input.sources = sources;
 
     let bruteforce_metadata = settings_metadata(&input, bruteforce_bytecode_hashes);
 
-    for metadata in bruteforce_metadata {
-        input.compiler_input.settings.metadata = metadata;
-        match compile_and_verify(compilers, &verifier, &input).await {
-            Ok(verification_success) => {
-                let verification_result = VerificationResult::from((
-                    input.compiler_input,
-                    input.compiler_version,
-                    verification_success,
-                ));
-                return Ok(VerificationResponse::ok(verification_result));
+    let matched = {
+        // Attempts are run concurrently up to the limit; the first matching one wins,
+        // and dropping the rest kills their compiler processes
+        let attempts = bruteforce_metadata.into_iter().enumerate();
+        let mut attempts = futures::stream::iter(attempts.map(|(priority, metadata)| {
+            let mut compiler_input = input.compiler_input.clone();
+            compiler_input.settings.metadata = metadata;
+            let (verifier, input) = (&verifier, &input);
+            async move {
+                let result = compile_and_verify(compilers, verifier, input, &compiler_input).await;
+                (priority, compiler_input, result)
             }
-            err @ Err(CompileAndVerifyError::Compilation(compiler::Error::Compilation(_))) => {
-                return Ok(VerificationResponse::err(err.unwrap_err()))
+        }))
+        .buffer_unordered(input.parallel_compile_attempts.get());
+        let mut matched = Err(VerificationFailure::NoMatchingContracts);
+        let mut failure_priority = usize::MAX;
+        while let Some((priority, compiler_input, result)) = attempts.next().await {
+            match result {
+                Ok(verification_success) => {
+                    matched = Ok((compiler_input, verification_success));
+                    break;
+                }
+                Err(CompileAndVerifyError::Compilation(
+                    err @ compiler::Error::Compilation { .. },
+                )) => return Ok(VerificationResponse::compilation_failed(&err)),
+                // failures clients could act upon are returned as failed verifications
+                Err(CompileAndVerifyError::Compilation(err)) => {
+                    let error_code = match &err {
+                        compiler::Error::Fetch(FetchError::NotFound(_)) => {
+                            VerificationErrorCode::CompilerNotFound
+                        }
+                        compiler::Error::Fetch(FetchError::Timeout(_)) => {
+                            VerificationErrorCode::Timeout
+                        }
+                        _ => return Err(error::ErrorInternalServerError(err)),
+                    };
+                    return Ok(
+                        VerificationResponse::err(format!("{:#}", err)).with_error_code(error_code)
+                    );
+                }
+                // Try other bytecode hashes if there is no matching contracts,
+                // keeping the reason of the attempt which matched the bytecode.
+                // Attempts complete in any order, so the reason of the first
+                // of them in the order of the bytecode hashes is kept
+                Err(CompileAndVerifyError::NotVerified(failure)) => {
+                    if failure != VerificationFailure::NoMatchingContracts
+                        && priority < failure_priority
+                    {
+                        failure_priority = priority;
+                        matched = Err(failure);
+                    }
+                }
             }
-            Err(CompileAndVerifyError::Compilation(err)) => {
-                return Err(error::ErrorInternalServerError(err))
-            }
-            // Try other bytecode hashes if there is no matching contracts
-            Err(CompileAndVerifyError::NoMatchingContracts) => {}
         }
-    }
+        matched
+    };
+    let failure = match matched {
+        Ok((compiler_input, verification_success)) => {
+            let debug = debug_info(
+                &input,
+                Some(&verification_success.compiled_creation),
+                Some(&verification_success.compiled_deployed),
+            );
+            let full_match = verifier.metadata_matches(&verification_success.compiled_deployed);
+            let bytecode_matches = verification_success.bytecode_matches;
+            let mut verification_result = VerificationResult::from((
+                compiler_input,
+                input.compiler_version,
+                verification_success,
+            ));
+            verification_result.source_transforms = source_transforms;
+            return Ok(
+                VerificationResponse::verified(verification_result, full_match)
+                    .with_bytecode_matches(Some(bytecode_matches))
+                    .with_debug(debug),
+            );
+        }
+        Err(failure) => failure,
+    };
     // In case of any other error the execution will not get to this point
-    Ok(VerificationResponse::err(
-        CompileAndVerifyError::NoMatchingContracts,
-    ))
+    let deployed_bytecode = verifier.deployed_bytecode();
+    let proxy = detect_proxy(&deployed_bytecode, input.contract_address, rpc_client).await;
+    Ok(VerificationResponse::failed(&failure)
+        .with_optimizer_likely_enabled(optimizer_likely_enabled(&deployed_bytecode))
+        .with_proxy(proxy)
+        .with_debug(debug_info(&input, None, None)))
+}
+
+/// Returns the compiled bytecodes alongside the on-chain ones, if requested.
+fn debug_info(
+    input: &Input<'_>,
+    compiled_creation: Option<&DisplayBytes>,
+    compiled_deployed: Option<&DisplayBytes>,
+) -> Option<DebugInfo> {
+    // on-chain bytecodes are valid hex, as they have been already parsed by the verifier
+    let onchain = |bytecode: &str| DisplayBytes::from_str(bytecode).unwrap_or_default();
+    input.debug.then(|| DebugInfo {
+        compiled_creation: compiled_creation.cloned(),
+        compiled_deployed: compiled_deployed.cloned(),
+        onchain_creation: onchain(input.creation_tx_input),
+        onchain_deployed: onchain(input.deployed_bytecode),
+    })
+}
+
+/// Checks whether the bytecode belongs to an EIP-1967 proxy and, if possible,
+/// reads the implementation address from the chain.
+async fn detect_proxy(
+    deployed_bytecode: &[u8],
+    contract_address: Option<Address>,
+    rpc_client: Option<&RpcClient>,
+) -> Option<ProxyInfo> {
+    if !is_eip1967_proxy(deployed_bytecode) {
+        return None;
+    }
+    let implementation_address = match (rpc_client, contract_address) {
+        (Some(rpc_client), Some(address)) => {
+            match rpc_client
+                .storage_at(address, EIP1967_IMPLEMENTATION_SLOT)
+                .await
+            {
+                Ok(value) => Some(Address::from(value)).filter(|address| !address.is_zero()),
+                Err(err) => {
+                    log::warn!(
+                        "cannot read proxy implementation of {:?}: {:#}",
+                        address,
+                        err
+                    );
+                    None
+                }
+            }
+        }
+        _ => None,
+    };
+    Some(ProxyInfo {
+        kind: "eip1967".into(),
+        implementation_address,
+    })
 }
 
 async fn compile_and_verify(
     compilers: &Compilers,
     verifier: &Verifier,
     input: &Input<'_>,
+    compiler_input: &CompilerInput,
 ) -> Result<VerificationSuccess, CompileAndVerifyError> {
-    let compiler_output = compilers
-        .compile(&input.compiler_version, &input.compiler_input)
-        .await?;
-    verifier
-        .verify(compiler_output)
-        .ok_or(CompileAndVerifyError::NoMatchingContracts)
+    let mut compiler_output = match &input.local_compiler {
+        Some(path) => {
+            let compile_time = metrics::compile_time(Language::Solidity, &input.compiler_version);
+            metrics::timed(compile_time, compilers.compile_with(path, compiler_input)).await?
+        }
+        None => {
+            compilers
+                .compile(&input.compiler_version, compiler_input)
+                .await?
+        }
+    };
+    link_libraries(&mut compiler_output, &compiler_input.settings.libraries);
+    Ok(verifier.verify(
+        compiler_output,
+        &input.match_strategies,
+        input.bytecode_match_policy,
+    )?)
 }
 
 /// Iterates through possible bytecode if required and creates
@@ -106,3 +485,690 @@ fn settings_metadata(
             .into()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        compiler::{FetchError, Fetcher},
+        http_server::handlers::verification::sourcify::SourcifyApiClient,
+    };
+    use actix_web::{http::header, test::TestRequest};
+    use async_trait::async_trait;
+    use primitive_types::H256;
+    use std::{env::temp_dir, os::unix::fs::PermissionsExt, path::Path, str::FromStr, sync::Arc};
+
+    /// Outputs an empty compilation result regardless of the input.
+    const STUB_COMPILER: &str =
+        "#!/bin/sh\ncat > /dev/null\necho '{\"contracts\":{},\"sources\":{}}'\n";
+
+    /// Fails the verification if the compiler is fetched instead of the local one.
+    struct UnreachableFetcher;
+
+    #[async_trait]
+    impl Fetcher for UnreachableFetcher {
+        async fn fetch(&self, ver: &compiler::Version) -> Result<PathBuf, FetchError> {
+            Err(FetchError::NotFound(ver.clone()))
+        }
+
+        fn all_versions(&self) -> Vec<compiler::Version> {
+            vec![]
+        }
+    }
+
+    fn stub_compiler() -> PathBuf {
+        let path = temp_dir().join(format!("stub_solc_{}", rand::random::<u64>()));
+        std::fs::write(&path, STUB_COMPILER).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        path
+    }
+
+    fn read_contract_file(name: &str) -> String {
+        let path = Path::new("tests/contracts/simple_storage").join(name);
+        std::fs::read_to_string(path).unwrap().trim().to_string()
+    }
+
+    /// Input with empty sources compiled by the fetched compiler;
+    /// tests override the fields they need.
+    fn input<'a>(creation_tx_input: &'a str, deployed_bytecode: &'a str) -> Input<'a> {
+        Input {
+            compiler_version: compiler::Version::from_str("v0.8.7+commit.e28d00a7").unwrap(),
+            compiler_input: CompilerInput {
+                language: "Solidity".to_string(),
+                sources: Default::default(),
+                settings: Default::default(),
+            },
+            creation_tx_input,
+            deployed_bytecode,
+            match_strategies: vec![MatchStrategy::Exact],
+            bytecode_match_policy: BytecodeMatchPolicy::Either,
+            contract_file: None,
+            contract_name: None,
+            local_compiler: None,
+            contract_address: None,
+            chain_id: None,
+            check_language: true,
+            debug: false,
+            source_transforms: vec![],
+            parallel_compile_attempts: NonZeroUsize::new(1).unwrap(),
+        }
+    }
+
+    #[tokio::test]
+    async fn compile_with_local_compiler() {
+        let compilers = Compilers::new(
+            Arc::new(UnreachableFetcher),
+            compiler::ScratchDir::new(temp_dir()).unwrap(),
+        );
+        let creation_tx_input = read_contract_file("creation_tx_input");
+        let deployed_bytecode = read_contract_file("deployed_bytecode");
+        let input = Input {
+            local_compiler: Some(stub_compiler()),
+            ..input(&creation_tx_input, &deployed_bytecode)
+        };
+
+        let response =
+            compile_and_verify_handler(&compilers, input, false, VerificationContext::default())
+                .await
+                .expect("stub compiler should be run");
+        assert!(response.result.is_none());
+        assert_eq!(
+            response.message,
+            VerificationFailure::NoMatchingContracts.to_string()
+        );
+        assert_eq!(
+            response.error_code,
+            Some(VerificationErrorCode::BytecodeMismatch)
+        );
+    }
+
+    #[tokio::test]
+    async fn missing_compiler_is_reported() {
+        let compilers = Compilers::new(
+            Arc::new(UnreachableFetcher),
+            compiler::ScratchDir::new(temp_dir()).unwrap(),
+        );
+        let creation_tx_input = read_contract_file("creation_tx_input");
+        let deployed_bytecode = read_contract_file("deployed_bytecode");
+        let input = input(&creation_tx_input, &deployed_bytecode);
+
+        let response =
+            compile_and_verify_handler(&compilers, input, false, VerificationContext::default())
+                .await
+                .expect("missing compiler should fail the verification");
+        assert_eq!(response.status, VerificationStatus::Failed);
+        assert_eq!(
+            response.error_code,
+            Some(VerificationErrorCode::CompilerNotFound)
+        );
+    }
+
+    #[tokio::test]
+    async fn sourcify_fallback_requires_chain_id() {
+        let compilers = Compilers::new(
+            Arc::new(UnreachableFetcher),
+            compiler::ScratchDir::new(temp_dir()).unwrap(),
+        );
+        let fallback = SourcifyFallback::new(SourcifyApiClient::new(
+            "http://localhost/".parse().unwrap(),
+            1,
+            NonZeroUsize::new(1).unwrap(),
+        ));
+        let input = Input {
+            contract_address: Some(Address::from_low_u64_be(1)),
+            ..input("0x6001", "0x6001")
+        };
+        let context = VerificationContext {
+            sourcify_fallback: Some(&fallback),
+            ..Default::default()
+        };
+
+        let err = compile_and_verify_handler(&compilers, input, false, context)
+            .await
+            .expect_err("request without chain id should be rejected");
+        assert_eq!(
+            err.as_response_error().status_code(),
+            actix_web::http::StatusCode::BAD_REQUEST
+        );
+    }
+
+    #[tokio::test]
+    async fn sourcify_fallback_uses_chain_of_request() {
+        let compilers = Compilers::new(
+            Arc::new(UnreachableFetcher),
+            compiler::ScratchDir::new(temp_dir()).unwrap(),
+        );
+        let address = Address::from_low_u64_be(1);
+        let metadata = serde_json::json!({
+            "compiler": {"version": "0.8.14+commit.80d49f37"},
+            "language": "Solidity",
+            "output": {"abi": []},
+            "settings": {
+                "compilationTarget": {"example.sol": "Example"},
+                "libraries": {},
+                "optimizer": {"enabled": false, "runs": 200}
+            }
+        });
+        let server = wiremock::MockServer::start().await;
+        wiremock::Mock::given(wiremock::matchers::path(format!(
+            "/files/any/77/{}",
+            ethers_core::utils::to_checksum(&address, None)
+        )))
+        .respond_with(
+            wiremock::ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "status": "full",
+                "files": [
+                    {"name": "metadata.json", "content": metadata.to_string()},
+                    {"name": "example.sol", "content": "contract Example {}"},
+                ],
+            })),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+        let fallback = SourcifyFallback::new(SourcifyApiClient::new(
+            format!("{}/", server.uri()).parse().unwrap(),
+            1,
+            NonZeroUsize::new(1).unwrap(),
+        ));
+        let creation_tx_input = read_contract_file("creation_tx_input");
+        let deployed_bytecode = read_contract_file("deployed_bytecode");
+        let input = Input {
+            contract_address: Some(address),
+            chain_id: Some("77".into()),
+            ..input(&creation_tx_input, &deployed_bytecode)
+        };
+        let context = VerificationContext {
+            sourcify_fallback: Some(&fallback),
+            ..Default::default()
+        };
+
+        let response = compile_and_verify_handler(&compilers, input, false, context)
+            .await
+            .expect("failed verification should be looked up in Sourcify");
+        assert_eq!(response.status, VerificationStatus::FullMatch);
+        assert_eq!(response.result.unwrap().contract_name, "Example");
+    }
+
+    #[tokio::test]
+    async fn invalid_chain_id_is_rejected() {
+        let compilers = Compilers::new(
+            Arc::new(UnreachableFetcher),
+            compiler::ScratchDir::new(temp_dir()).unwrap(),
+        );
+        for chain_id in ["../..", "1/..", "", "0x1", "+1"] {
+            let input = Input {
+                contract_address: Some(Address::from_low_u64_be(1)),
+                chain_id: Some(chain_id.into()),
+                ..input("0x6001", "0x6001")
+            };
+            let err = compile_and_verify_handler(
+                &compilers,
+                input,
+                false,
+                VerificationContext::default(),
+            )
+            .await
+            .expect_err("invalid chain id should be rejected");
+            assert_eq!(
+                err.as_response_error().status_code(),
+                actix_web::http::StatusCode::BAD_REQUEST,
+                "{}",
+                chain_id
+            );
+        }
+    }
+
+    /// Writes the compilation result containing the simple storage contract.
+    fn simple_storage_output(creation_tx_input: &str, deployed_bytecode: &str) -> PathBuf {
+        let output = serde_json::json!({
+            "contracts": {"Main.sol": {"SimpleStorage": {
+                "abi": [],
+                "evm": {
+                    "bytecode": {"object": creation_tx_input},
+                    "deployedBytecode": {"object": deployed_bytecode},
+                },
+            }}},
+            "sources": {"Main.sol": {"id": 0}},
+        });
+        let output_path = temp_dir().join(format!("stub_output_{}.json", rand::random::<u64>()));
+        std::fs::write(&output_path, output.to_string()).unwrap();
+        output_path
+    }
+
+    /// Outputs the simple storage contract for the sources without imports,
+    /// and an empty compilation result otherwise.
+    fn flattened_only_compiler(creation_tx_input: &str, deployed_bytecode: &str) -> PathBuf {
+        let output_path = simple_storage_output(creation_tx_input, deployed_bytecode);
+        let path = stub_compiler();
+        let script = format!(
+            "#!/bin/sh\ninput=$(cat)\ncase \"$input\" in\n  *import*) echo '{{\"contracts\":{{}},\"sources\":{{}}}}' ;;\n  *) cat {:?} ;;\nesac\n",
+            output_path
+        );
+        std::fs::write(&path, script).unwrap();
+        path
+    }
+
+    #[tokio::test]
+    async fn verifies_flattened_sources() {
+        let compilers = Compilers::new(
+            Arc::new(UnreachableFetcher),
+            compiler::ScratchDir::new(temp_dir()).unwrap(),
+        );
+        let creation_tx_input = read_contract_file("creation_tx_input");
+        let deployed_bytecode = read_contract_file("deployed_bytecode");
+        let compiler = flattened_only_compiler(&creation_tx_input, &deployed_bytecode);
+        let sources = [
+            (
+                "Main.sol",
+                "import \"./Storage.sol\";\ncontract SimpleStorage is Storage {}",
+            ),
+            ("Storage.sol", "contract Storage {}"),
+        ]
+        .into_iter()
+        .map(|(path, content)| {
+            (
+                PathBuf::from(path),
+                ethers_solc::artifacts::Source {
+                    content: content.into(),
+                },
+            )
+        })
+        .collect();
+        let transformed = |source_transforms| {
+            let mut input = Input {
+                local_compiler: Some(compiler.clone()),
+                source_transforms,
+                ..input(&creation_tx_input, &deployed_bytecode)
+            };
+            input.compiler_input.sources = Clone::clone(&sources);
+            input
+        };
+
+        let response = compile_and_verify_handler(
+            &compilers,
+            transformed(vec![]),
+            false,
+            VerificationContext::default(),
+        )
+        .await
+        .unwrap();
+        assert!(response.result.is_none(), "sources should not be flattened");
+
+        let flatten = vec![crate::solidity::SourceTransformKind::Flatten.transform()];
+        let response = compile_and_verify_handler(
+            &compilers,
+            transformed(flatten),
+            false,
+            VerificationContext::default(),
+        )
+        .await
+        .unwrap();
+        let result = response
+            .result
+            .expect("flattened sources should be verified");
+        assert_eq!(result.source_transforms, vec!["flatten".to_string()]);
+        assert_eq!(
+            result.sources,
+            std::collections::BTreeMap::from([(
+                "Main.sol".to_string(),
+                "contract Storage {}\ncontract SimpleStorage is Storage {}".to_string()
+            )])
+        );
+    }
+
+    /// Whether the process exists and is not a zombie.
+    fn is_running(pid: &str) -> bool {
+        std::fs::read_to_string(format!("/proc/{}/stat", pid))
+            .map_or(false, |stat| !stat.contains(") Z "))
+    }
+
+    #[tokio::test]
+    async fn cancels_losing_attempts() {
+        let compilers = Compilers::new(
+            Arc::new(UnreachableFetcher),
+            compiler::ScratchDir::new(temp_dir()).unwrap(),
+        );
+        let creation_tx_input = read_contract_file("creation_tx_input");
+        let deployed_bytecode = read_contract_file("deployed_bytecode");
+        let output_path = simple_storage_output(&creation_tx_input, &deployed_bytecode);
+        // only the variant without the bytecode hash matches; it completes once
+        // the others have recorded their pids, and the others never complete
+        let started = temp_dir().join(format!("losing_attempts_{}", rand::random::<u64>()));
+        std::fs::create_dir_all(&started).unwrap();
+        let losing_attempts = BYTECODE_HASHES.len() - 1;
+        let compiler = stub_compiler();
+        let script = format!(
+            "#!/bin/sh\ninput=$(cat)\ncase \"$input\" in\n  *'\"bytecodeHash\":\"none\"'*) while [ $(ls {dir:?} | wc -l) -lt {losing_attempts} ]; do sleep 0.01; done; cat {output:?} ;;\n  *) echo > {dir:?}/$$; exec sleep 600 ;;\nesac\n",
+            dir = started,
+            losing_attempts = losing_attempts,
+            output = output_path,
+        );
+        std::fs::write(&compiler, script).unwrap();
+        let input = Input {
+            local_compiler: Some(compiler),
+            parallel_compile_attempts: NonZeroUsize::new(BYTECODE_HASHES.len()).unwrap(),
+            ..input(&creation_tx_input, &deployed_bytecode)
+        };
+
+        let response = tokio::time::timeout(
+            std::time::Duration::from_secs(60),
+            compile_and_verify_handler(&compilers, input, true, VerificationContext::default()),
+        )
+        .await
+        .expect("losing attempts should not be waited for")
+        .unwrap();
+        assert!(response.result.is_some(), "contract should be verified");
+
+        let pids: Vec<_> = std::fs::read_dir(&started)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        assert_eq!(pids.len(), losing_attempts);
+        for pid in pids {
+            tokio::time::timeout(std::time::Duration::from_secs(10), async {
+                while is_running(&pid) {
+                    tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+                }
+            })
+            .await
+            .expect("losing compilations should be killed");
+        }
+    }
+
+    #[tokio::test]
+    async fn reports_bytecode_matches() {
+        let compilers = Compilers::new(
+            Arc::new(UnreachableFetcher),
+            compiler::ScratchDir::new(temp_dir()).unwrap(),
+        );
+        let creation_tx_input = read_contract_file("creation_tx_input");
+        let deployed_bytecode = read_contract_file("deployed_bytecode");
+        let output_path = simple_storage_output(&creation_tx_input, &deployed_bytecode);
+        let compiler = stub_compiler();
+        let script = format!("#!/bin/sh\ncat > /dev/null\ncat {:?}\n", output_path);
+        std::fs::write(&compiler, script).unwrap();
+        let mismatching_creation_tx_input = format!("0x00{}", &creation_tx_input[2..]);
+        let verify = |creation_tx_input, bytecode_match_policy| {
+            let input = Input {
+                bytecode_match_policy,
+                local_compiler: Some(compiler.clone()),
+                ..input(creation_tx_input, &deployed_bytecode)
+            };
+            compile_and_verify_handler(&compilers, input, false, VerificationContext::default())
+        };
+        let bytecode_matches = |response: &VerificationResponse| {
+            serde_json::to_value(response.bytecode_matches).unwrap()
+        };
+
+        let response = verify("", BytecodeMatchPolicy::Either).await.unwrap();
+        assert_eq!(response.status, VerificationStatus::FullMatch);
+        assert_eq!(
+            bytecode_matches(&response),
+            serde_json::json!({"creation": "not_provided", "deployed": "matched"})
+        );
+
+        let response = verify(&mismatching_creation_tx_input, BytecodeMatchPolicy::Either)
+            .await
+            .unwrap();
+        assert_eq!(response.status, VerificationStatus::FullMatch);
+        assert_eq!(
+            bytecode_matches(&response),
+            serde_json::json!({"creation": "mismatched", "deployed": "matched"})
+        );
+
+        let response = verify(&mismatching_creation_tx_input, BytecodeMatchPolicy::Both)
+            .await
+            .unwrap();
+        assert_eq!(response.status, VerificationStatus::Failed);
+        assert_eq!(response.bytecode_matches, None);
+    }
+
+    #[tokio::test]
+    async fn verifies_with_compiler_fetched_by_encoded_version() {
+        use sha2::{Digest, Sha256};
+
+        let creation_tx_input = read_contract_file("creation_tx_input");
+        let deployed_bytecode = read_contract_file("deployed_bytecode");
+        let output_path = simple_storage_output(&creation_tx_input, &deployed_bytecode);
+        let script = format!("#!/bin/sh\ncat > /dev/null\ncat {:?}\n", output_path);
+        let dir = temp_dir().join(format!("encoded_compilers_{}", rand::random::<u64>()));
+        let folder = dir.join("v0.8.7%2Bcommit.e28d00a7");
+        std::fs::create_dir_all(&folder).unwrap();
+        std::fs::write(folder.join("solc"), &script).unwrap();
+        std::fs::set_permissions(folder.join("solc"), std::fs::Permissions::from_mode(0o755))
+            .unwrap();
+        std::fs::write(
+            folder.join("sha256.hash"),
+            hex::encode(Sha256::digest(script.as_bytes())),
+        )
+        .unwrap();
+        let fetcher = compiler::LocalFetcher::new(
+            crate::config::LocalFetcherConfig { dir },
+            Language::Solidity,
+        )
+        .unwrap();
+        let compilers = Compilers::new(
+            Arc::new(fetcher),
+            compiler::ScratchDir::new(temp_dir()).unwrap(),
+        );
+
+        let input = input(&creation_tx_input, &deployed_bytecode);
+        assert_eq!(input.compiler_version.to_string(), "v0.8.7+commit.e28d00a7");
+        let response =
+            compile_and_verify_handler(&compilers, input, false, VerificationContext::default())
+                .await
+                .unwrap();
+        assert_eq!(response.status, VerificationStatus::FullMatch);
+        assert_eq!(response.result.unwrap().contract_name, "SimpleStorage");
+    }
+
+    #[tokio::test]
+    async fn reads_proxy_implementation() {
+        let proxy = hex::decode("363d3d373d3d363d7f360894a13ba1a3210667c828492db98dca3e2076cc3735a920a3ca505d382bbc545af43d6000803e6038573d6000fd5b3d6000f3").unwrap();
+        let implementation = Address::from_low_u64_be(0xcafe);
+        let mock_server = wiremock::MockServer::start().await;
+        wiremock::Mock::given(wiremock::matchers::method("POST"))
+            .respond_with(wiremock::ResponseTemplate::new(200).set_body_json(
+                serde_json::json!({"jsonrpc": "2.0", "id": 1, "result": H256::from(implementation)}),
+            ))
+            .mount(&mock_server)
+            .await;
+        let rpc_client = RpcClient::new(
+            mock_server.uri().parse().unwrap(),
+            std::time::Duration::from_secs(1),
+        )
+        .unwrap();
+        let address = Some(Address::from_low_u64_be(1));
+
+        let detected = detect_proxy(&proxy, address, Some(&rpc_client)).await;
+        assert_eq!(
+            detected,
+            Some(ProxyInfo {
+                kind: "eip1967".into(),
+                implementation_address: Some(implementation),
+            })
+        );
+        let without_rpc = detect_proxy(&proxy, address, None).await;
+        assert_eq!(without_rpc.unwrap().implementation_address, None);
+        let simple_storage =
+            hex::decode(read_contract_file("deployed_bytecode").trim_start_matches("0x")).unwrap();
+        assert_eq!(
+            detect_proxy(&simple_storage, address, Some(&rpc_client)).await,
+            None
+        );
+    }
+
+    #[test]
+    fn local_compiler_requires_admin() {
+        let path = stub_compiler();
+        let local_compiler = || {
+            Some(LocalCompiler {
+                path: path.clone(),
+                sha256: None,
+            })
+        };
+        let token = AdminToken::new("secret".into());
+        let authorized = TestRequest::default()
+            .insert_header((header::AUTHORIZATION, "Bearer secret"))
+            .to_http_request();
+        let unauthorized = TestRequest::default().to_http_request();
+
+        assert_eq!(
+            local_compiler_path(&unauthorized, None, None).unwrap(),
+            None
+        );
+        assert_eq!(
+            local_compiler_path(&authorized, Some(&token), local_compiler()).unwrap(),
+            Some(path.clone())
+        );
+        let status = |result: Result<_, actix_web::Error>| {
+            result.unwrap_err().as_response_error().status_code()
+        };
+        assert_eq!(
+            status(local_compiler_path(&authorized, None, local_compiler())),
+            403
+        );
+        assert_eq!(
+            status(local_compiler_path(
+                &unauthorized,
+                Some(&token),
+                local_compiler()
+            )),
+            401
+        );
+        let mismatching = LocalCompiler {
+            path: path.clone(),
+            sha256: Some(H256::zero()),
+        };
+        assert_eq!(
+            status(local_compiler_path(
+                &authorized,
+                Some(&token),
+                Some(mismatching)
+            )),
+            400
+        );
+    }
+
+    #[test]
+    fn resolves_version_aliases() {
+        let aliases = BTreeMap::from([(
+            "v0.8.7-nightly.2021.8.9+commit.74c804d8".to_string(),
+            "v0.8.7+commit.e28d00a7".to_string(),
+        )]);
+        let (version, alias) = resolve_compiler_version(
+            &aliases,
+            Vec::new,
+            |_| false,
+            "v0.8.7-nightly.2021.8.9+commit.74c804d8",
+        )
+        .unwrap();
+        assert_eq!(
+            version,
+            compiler::Version::from_str("v0.8.7+commit.e28d00a7").unwrap()
+        );
+        assert_eq!(
+            alias.as_deref(),
+            Some("v0.8.7-nightly.2021.8.9+commit.74c804d8")
+        );
+
+        let (version, alias) =
+            resolve_compiler_version(&aliases, Vec::new, |_| false, "v0.8.9+commit.e5eed63a")
+                .unwrap();
+        assert_eq!(
+            version,
+            compiler::Version::from_str("v0.8.9+commit.e5eed63a").unwrap()
+        );
+        assert_eq!(alias, None);
+
+        assert!(resolve_compiler_version(&aliases, Vec::new, |_| false, "not-a-version").is_err());
+    }
+
+    #[test]
+    fn resolves_version_patterns() {
+        let available = || {
+            [
+                "v0.8.15-nightly.2022.5.27+commit.095cc647",
+                "zksolc-v1.3.5+commit.0ab2c1f8",
+                "v0.8.14+commit.80d49f37",
+                "v0.8.9+commit.e5eed63a",
+                "v0.7.6+commit.7338295f",
+                "v0.4.24+commit.e67f0147",
+            ]
+            .iter()
+            .map(|version| compiler::Version::from_str(version).unwrap())
+            .collect()
+        };
+        let aliases = BTreeMap::new();
+        for (pattern, expected) in [
+            ("latest", "v0.8.14+commit.80d49f37"),
+            ("0.8.x", "v0.8.14+commit.80d49f37"),
+            ("v0.7.*", "v0.7.6+commit.7338295f"),
+            ("0.x", "v0.8.14+commit.80d49f37"),
+            // releases of vendors are selected only if the vendor is named
+            ("zksolc-latest", "zksolc-v1.3.5+commit.0ab2c1f8"),
+            ("zksolc-v1.x", "zksolc-v1.3.5+commit.0ab2c1f8"),
+        ] {
+            let (version, alias) =
+                resolve_compiler_version(&aliases, available, |_| false, pattern).unwrap();
+            assert_eq!(
+                version,
+                compiler::Version::from_str(expected).unwrap(),
+                "{}",
+                pattern
+            );
+            assert_eq!(alias.as_deref(), Some(pattern));
+        }
+
+        let err = resolve_compiler_version(&aliases, available, |_| false, "0.6.x").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "no compiler version matches 0.6.x; \
+            available versions are v0.4.24+commit.e67f0147 to v0.8.14+commit.80d49f37"
+        );
+        assert!(resolve_compiler_version(&aliases, available, |_| false, "0.8.1.x").is_err());
+        assert!(resolve_compiler_version(&aliases, available, |_| false, "1.x").is_err());
+        assert!(resolve_compiler_version(&aliases, available, |_| false, "zksolc-0.8.x").is_err());
+    }
+
+    #[test]
+    fn resolves_version_patterns_to_cached_equivalents() {
+        let version = |version: &str| compiler::Version::from_str(version).unwrap();
+        // the same release built from different commits by several mirrors
+        let available = || {
+            vec![
+                version("v0.8.14+commit.80d49f37"),
+                version("v0.8.14+commit.00000001"),
+                version("v0.8.9+commit.e5eed63a"),
+            ]
+        };
+        let aliases = BTreeMap::new();
+        let resolve = |cached: &[&str]| {
+            let cached: Vec<_> = cached.iter().map(|cached| version(cached)).collect();
+            resolve_compiler_version(
+                &aliases,
+                available,
+                |version| cached.contains(version),
+                "0.8.x",
+            )
+            .unwrap()
+            .0
+        };
+
+        assert_eq!(resolve(&[]), version("v0.8.14+commit.80d49f37"));
+        assert_eq!(
+            resolve(&["v0.8.14+commit.00000001"]),
+            version("v0.8.14+commit.00000001")
+        );
+        assert_eq!(
+            resolve(&["v0.8.14+commit.00000001", "v0.8.14+commit.80d49f37"]),
+            version("v0.8.14+commit.80d49f37")
+        );
+        // older releases are not preferred even if cached
+        assert_eq!(
+            resolve(&["v0.8.9+commit.e5eed63a"]),
+            version("v0.8.14+commit.80d49f37")
+        );
+    }
+}
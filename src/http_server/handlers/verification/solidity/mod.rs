@@ -1,6 +1,14 @@
 mod contract_verifier;
 mod types;
+mod verification_metadata;
 
+pub mod artifact;
 pub mod multi_part;
+pub mod multi_part_auto;
 pub mod standard_json;
 pub mod version_list;
+
+pub(crate) use types::{
+    AutoVerificationRequest, MultiPartFiles, StandardJson, VerificationRequest,
+};
+pub(crate) use verification_metadata::VerificationMetadata;
@@ -1,34 +1,80 @@
-use super::types::{MultiPartFiles, VerificationRequest};
+use super::types::{MultiPartFiles, VerificationQuery, VerificationRequest};
 use crate::{
-    compiler::{Compilers, Version},
-    http_server::handlers::verification::{
-        solidity::contract_verifier::{compile_and_verify_handler, Input},
-        VerificationResponse,
+    compiler::Compilers,
+    config::SolidityConfiguration,
+    http_server::handlers::{
+        admin::AdminToken,
+        verification::{
+            solidity::contract_verifier::{
+                compile_and_verify_handler, local_compiler_path, resolve_compiler_version, Input,
+                VerificationContext,
+            },
+            VerificationResponse,
+        },
     },
 };
 use actix_web::{
     error,
     web::{self, Json},
-    Error,
+    Error, HttpRequest,
 };
-use std::str::FromStr;
 
 pub async fn verify(
+    request: HttpRequest,
+    admin_token: Option<web::Data<AdminToken>>,
     compilers: web::Data<Compilers>,
+    config: web::Data<SolidityConfiguration>,
+    query: web::Query<VerificationQuery>,
     params: Json<VerificationRequest<MultiPartFiles>>,
 ) -> Result<Json<VerificationResponse>, Error> {
     let params = params.into_inner();
 
+    // settings pinned by verification metadata already contain the correct bytecode hash
+    let bruteforce_bytecode_hashes = !params.content.has_verification_metadata();
     let compiler_input = params.content.try_into().map_err(error::ErrorBadRequest)?;
-    let compiler_version =
-        Version::from_str(&params.compiler_version).map_err(error::ErrorBadRequest)?;
+    let (compiler_version, compiler_version_alias) = resolve_compiler_version(
+        &config.version_aliases,
+        || compilers.all_versions(),
+        |version| compilers.is_cached(version),
+        &params.compiler_version,
+    )?;
+    let local_compiler = local_compiler_path(
+        &request,
+        admin_token.as_ref().map(|token| token.get_ref()),
+        params.local_compiler,
+    )?;
     let input = Input {
         compiler_version,
         compiler_input,
         creation_tx_input: &params.creation_bytecode,
         deployed_bytecode: &params.deployed_bytecode,
+        match_strategies: params
+            .match_strategies
+            .unwrap_or_else(|| config.match_strategies.clone()),
+        bytecode_match_policy: config.bytecode_match_policy,
+        contract_file: params.contract_file,
+        contract_name: params.contract_name,
+        local_compiler,
+        contract_address: params.contract_address,
+        chain_id: params.chain_id,
+        check_language: !params.ignore_language_mismatch,
+        debug: query.debug,
+        source_transforms: config
+            .source_transforms
+            .iter()
+            .map(|kind| kind.transform())
+            .collect(),
+        parallel_compile_attempts: config.parallel_compile_attempts,
     };
-    compile_and_verify_handler(&compilers, input, true)
-        .await
-        .map(Json)
+    let mut response = compile_and_verify_handler(
+        &compilers,
+        input,
+        bruteforce_bytecode_hashes,
+        VerificationContext::from_request(&request),
+    )
+    .await?;
+    if let Some(result) = response.result.as_mut() {
+        result.compiler_version_alias = compiler_version_alias;
+    }
+    Ok(Json(response))
 }
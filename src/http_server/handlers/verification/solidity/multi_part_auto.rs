@@ -0,0 +1,223 @@
+use super::{
+    contract_verifier::{compile_and_verify_all, Input},
+    types::{AutoVerificationRequest, VerificationQuery},
+};
+use crate::{
+    compiler::{self, Compilers, Language},
+    config::SolidityConfiguration,
+    http_server::handlers::verification::{VerificationErrorCode, VerificationResponse},
+    metrics,
+    rpc::RpcClient,
+    solidity::{validate_settings, Verifier},
+};
+use actix_web::{
+    error,
+    web::{self, Json},
+    Error,
+};
+use ethers_solc::{artifacts::Settings, CompilerInput};
+use futures::{Stream, StreamExt};
+use semver::VersionReq;
+use std::str::FromStr;
+
+/// Verifies multi-part files with the newest release versions matching the requested range,
+/// returning the first matched one in the result.
+pub async fn verify(
+    compilers: web::Data<Compilers>,
+    rpc_client: Option<web::Data<RpcClient>>,
+    config: web::Data<SolidityConfiguration>,
+    query: web::Query<VerificationQuery>,
+    params: Json<AutoVerificationRequest>,
+) -> Result<Json<VerificationResponse>, Error> {
+    let params = params.into_inner();
+    let range = params
+        .compiler_versions
+        .as_deref()
+        .map(VersionReq::from_str)
+        .transpose()
+        .map_err(error::ErrorBadRequest)?;
+    // settings pinned by verification metadata already contain the correct bytecode hash
+    let bruteforce_bytecode_hashes = !params.content.has_verification_metadata();
+    let compiler_input: CompilerInput =
+        params.content.try_into().map_err(error::ErrorBadRequest)?;
+    let match_strategies = params
+        .match_strategies
+        .unwrap_or_else(|| config.match_strategies.clone());
+    // errors of the request itself are reported as is,
+    // as they would fail the attempts of every version
+    if match_strategies.is_empty() {
+        return Err(error::ErrorBadRequest(
+            "at least one match strategy must be specified",
+        ));
+    }
+    Verifier::new(&params.creation_bytecode, &params.deployed_bytecode)
+        .map_err(error::ErrorBadRequest)?;
+
+    let versions = candidate_versions(
+        compilers.all_versions(),
+        range.as_ref(),
+        &compiler_input.settings,
+        config.auto_version.max_versions,
+    );
+    if versions.is_empty() {
+        return Err(error::ErrorBadRequest(
+            "no available compiler versions match the requested range and settings",
+        ));
+    }
+    let rpc_client = rpc_client.as_ref().map(|client| client.get_ref());
+    let tried = versions.len();
+
+    // Versions are run concurrently up to the limit, but the results are taken in order,
+    // so the newest matching version wins; dropping the rest kills their compiler processes
+    let mut attempts = futures::stream::iter(versions.into_iter().map(|compiler_version| {
+        let input = Input {
+            compiler_version,
+            compiler_input: compiler_input.clone(),
+            creation_tx_input: &params.creation_bytecode,
+            deployed_bytecode: &params.deployed_bytecode,
+            match_strategies: match_strategies.clone(),
+            bytecode_match_policy: config.bytecode_match_policy,
+            contract_file: None,
+            contract_name: None,
+            local_compiler: None,
+            contract_address: params.contract_address,
+            // versions are not looked up in Sourcify
+            chain_id: None,
+            check_language: !params.ignore_language_mismatch,
+            debug: query.debug,
+            source_transforms: config
+                .source_transforms
+                .iter()
+                .map(|kind| kind.transform())
+                .collect(),
+            parallel_compile_attempts: config.parallel_compile_attempts,
+        };
+        compile_and_verify_all(&compilers, input, bruteforce_bytecode_hashes, rpc_client)
+    }))
+    .buffered(config.auto_version.parallel_versions.get());
+    let response = first_verified(&mut attempts).await.unwrap_or_else(|| {
+        VerificationResponse::err(format!(
+            "No contract could be verified with any of the {} compiler versions tried",
+            tried
+        ))
+        .with_error_code(VerificationErrorCode::BytecodeMismatch)
+    });
+    metrics::count_verify_contract(Language::Solidity, &response.status);
+    Ok(Json(response))
+}
+
+/// Returns the first verified response of the `attempts` taken in order.
+/// Attempts failed with an error (e.g. the compiler of the version could not be fetched)
+/// are logged and treated as not verified, so that the other versions are still tried.
+async fn first_verified(
+    attempts: &mut (impl Stream<Item = Result<VerificationResponse, Error>> + Unpin),
+) -> Option<VerificationResponse> {
+    while let Some(attempt) = attempts.next().await {
+        match attempt {
+            Ok(response) if response.result.is_some() => return Some(response),
+            Ok(_) => (),
+            Err(err) => log::warn!("automatic verification attempt failed: {}", err),
+        }
+    }
+    None
+}
+
+/// Versions matching the `range` (any release, if not set) and compatible with the `settings`
+/// in descending order, limited to `max_versions`.
+fn candidate_versions(
+    mut versions: Vec<compiler::Version>,
+    range: Option<&VersionReq>,
+    settings: &Settings,
+    max_versions: usize,
+) -> Vec<compiler::Version> {
+    // pre-release (nightly) versions are matched only by ranges mentioning them explicitly
+    versions.retain(|version| match range {
+        Some(range) => range.matches(version.version()),
+        None => version.is_release(),
+    });
+    versions.retain(|version| validate_settings(settings, version).is_ok());
+    versions.sort_unstable_by(|a, b| b.cmp(a));
+    versions.truncate(max_versions);
+    versions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn versions(versions: &[&str]) -> Vec<compiler::Version> {
+        versions
+            .iter()
+            .map(|version| compiler::Version::from_str(version).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn candidates_are_newest_matching_releases() {
+        let available = versions(&[
+            "v0.8.9+commit.e5eed63a",
+            "v0.8.15-nightly.2022.5.27+commit.095cc647",
+            "v0.4.24+commit.e67f0147",
+            "v0.8.14+commit.80d49f37",
+            "v0.8.10+commit.fc410830",
+        ]);
+        assert_eq!(
+            candidate_versions(available.clone(), None, &Settings::default(), 3),
+            versions(&[
+                "v0.8.14+commit.80d49f37",
+                "v0.8.10+commit.fc410830",
+                "v0.8.9+commit.e5eed63a",
+            ])
+        );
+        let range = VersionReq::from_str(">=0.4.0, <0.8.10").unwrap();
+        assert_eq!(
+            candidate_versions(available.clone(), Some(&range), &Settings::default(), 10),
+            versions(&["v0.8.9+commit.e5eed63a", "v0.4.24+commit.e67f0147"])
+        );
+        let range = VersionReq::from_str(">=0.8.15-nightly").unwrap();
+        assert_eq!(
+            candidate_versions(available, Some(&range), &Settings::default(), 10),
+            versions(&["v0.8.15-nightly.2022.5.27+commit.095cc647"])
+        );
+    }
+
+    #[test]
+    fn incompatible_candidates_are_skipped() {
+        let available = versions(&[
+            "v0.8.6+commit.11564f7e",
+            "v0.8.9+commit.e5eed63a",
+            "v0.8.4+commit.c7e474f2",
+        ]);
+        let settings = Settings {
+            evm_version: Some(ethers_solc::EvmVersion::London),
+            ..Default::default()
+        };
+        // london is supported since 0.8.7, so older versions do not take the slots
+        assert_eq!(
+            candidate_versions(available, None, &settings, 2),
+            versions(&["v0.8.9+commit.e5eed63a"])
+        );
+    }
+
+    #[tokio::test]
+    async fn failed_attempts_do_not_abort_the_search() {
+        let verified = || {
+            let mut response = VerificationResponse::err("OK");
+            response.result =
+                Some(crate::http_server::handlers::verification::tests::verification_result());
+            response
+        };
+        let mut attempts = futures::stream::iter([
+            Err(error::ErrorInternalServerError(
+                "compiler cannot be fetched",
+            )),
+            Ok(VerificationResponse::err("no match")),
+            Ok(verified()),
+        ]);
+        assert_eq!(first_verified(&mut attempts).await, Some(verified()));
+
+        let mut attempts = futures::stream::iter([Err(error::ErrorBadRequest("unsupported"))]);
+        assert_eq!(first_verified(&mut attempts).await, None);
+    }
+}
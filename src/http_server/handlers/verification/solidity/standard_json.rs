@@ -1,37 +1,223 @@
-use super::types::VerificationRequest;
+use super::types::{VerificationQuery, VerificationRequest};
 use crate::{
-    compiler::{Compilers, Version},
-    http_server::handlers::verification::{
-        solidity::{
-            contract_verifier::{compile_and_verify_handler, Input},
-            types::StandardJson,
+    compiler::{self, Compilers},
+    config::SolidityConfiguration,
+    dead_letter::DeadLetterSink,
+    events::EventPublisher,
+    http_server::handlers::{
+        admin::AdminToken,
+        jobs::{JobResponse, JobStatus, JobStore},
+        uploads::UploadStore,
+        verification::{
+            solidity::{
+                contract_verifier::{
+                    compile_and_verify_handler, local_compiler_path, resolve_compiler_version,
+                    Input, VerificationContext,
+                },
+                types::StandardJson,
+            },
+            sourcify::SourcifyFallback,
+            VerificationResponse,
         },
-        VerificationResponse,
     },
+    rpc::RpcClient,
+    solidity::MatchStrategy,
 };
 use actix_web::{
     error,
     web::{self, Json},
-    Error,
+    Error, HttpRequest, HttpResponse,
 };
-use std::str::FromStr;
+use ethers_core::types::Address;
+use ethers_solc::CompilerInput;
+use std::path::PathBuf;
 
 pub async fn verify(
+    request: HttpRequest,
+    admin_token: Option<web::Data<AdminToken>>,
     compilers: web::Data<Compilers>,
+    config: web::Data<SolidityConfiguration>,
+    query: web::Query<VerificationQuery>,
     params: Json<VerificationRequest<StandardJson>>,
 ) -> Result<Json<VerificationResponse>, Error> {
-    let params = params.into_inner();
+    let verification = Verification::new(
+        &request,
+        admin_token,
+        &compilers,
+        &config,
+        query.into_inner(),
+        params.into_inner(),
+    )?;
+    let response = verification
+        .run(
+            &compilers,
+            &config,
+            VerificationContext::from_request(&request),
+        )
+        .await?;
+    Ok(Json(response))
+}
+
+/// Checks the request and returns the id of the job the verification is run in.
+/// Invalid requests are rejected immediately, without starting a job.
+pub async fn verify_async(
+    request: HttpRequest,
+    admin_token: Option<web::Data<AdminToken>>,
+    compilers: web::Data<Compilers>,
+    rpc_client: Option<web::Data<RpcClient>>,
+    config: web::Data<SolidityConfiguration>,
+    jobs: web::Data<JobStore>,
+    query: web::Query<VerificationQuery>,
+    params: Json<VerificationRequest<StandardJson>>,
+) -> Result<HttpResponse, Error> {
+    let verification = Verification::new(
+        &request,
+        admin_token,
+        &compilers,
+        &config,
+        query.into_inner(),
+        params.into_inner(),
+    )?;
+    let dead_letter_sink = request.app_data::<web::Data<DeadLetterSink>>().cloned();
+    let event_publisher = request.app_data::<web::Data<EventPublisher>>().cloned();
+    let sourcify_fallback = request.app_data::<web::Data<SourcifyFallback>>().cloned();
+    let job_id = jobs.start()?;
+    {
+        let job_id = job_id.clone();
+        // run on the worker of the request, as the verification errors are not `Send`
+        actix_web::rt::spawn(async move {
+            let status = match verification
+                .run(
+                    &compilers,
+                    &config,
+                    VerificationContext {
+                        dead_letter_sink: dead_letter_sink.as_ref().map(|data| data.get_ref()),
+                        rpc_client: rpc_client.as_ref().map(|data| data.get_ref()),
+                        event_publisher: event_publisher.as_ref().map(|data| data.get_ref()),
+                        sourcify_fallback: sourcify_fallback.as_ref().map(|data| data.get_ref()),
+                    },
+                )
+                .await
+            {
+                Ok(response) => JobStatus::Done {
+                    response: Box::new(response),
+                },
+                Err(err) => JobStatus::Error {
+                    message: err.to_string(),
+                },
+            };
+            jobs.finish(&job_id, status);
+        });
+    }
+    Ok(HttpResponse::Accepted().json(JobResponse { job_id }))
+}
+
+/// Standard json verification request checked and converted into the compiler input.
+struct Verification {
+    compiler_input: CompilerInput,
+    compiler_version: compiler::Version,
+    compiler_version_alias: Option<String>,
+    creation_bytecode: String,
+    deployed_bytecode: String,
+    match_strategies: Vec<MatchStrategy>,
+    contract_file: Option<String>,
+    contract_name: Option<String>,
+    local_compiler: Option<PathBuf>,
+    contract_address: Option<Address>,
+    chain_id: Option<String>,
+    check_language: bool,
+    debug: bool,
+    model_checker_stripped: bool,
+}
+
+impl Verification {
+    fn new(
+        request: &HttpRequest,
+        admin_token: Option<web::Data<AdminToken>>,
+        compilers: &Compilers,
+        config: &SolidityConfiguration,
+        query: VerificationQuery,
+        mut params: VerificationRequest<StandardJson>,
+    ) -> Result<Self, Error> {
+        params.content.load_upload(
+            request
+                .app_data::<web::Data<UploadStore>>()
+                .map(|uploads| uploads.get_ref()),
+        )?;
+        let model_checker_stripped = params.content.take_model_checker_settings().is_some();
+        if model_checker_stripped && !config.strip_model_checker_settings {
+            return Err(error::ErrorBadRequest(
+                "model checker settings are not allowed in the input",
+            ));
+        }
+
+        let compiler_input = params.content.try_into().map_err(error::ErrorBadRequest)?;
+        let (compiler_version, compiler_version_alias) = resolve_compiler_version(
+            &config.version_aliases,
+            || compilers.all_versions(),
+            |version| compilers.is_cached(version),
+            &params.compiler_version,
+        )?;
+        let local_compiler = local_compiler_path(
+            request,
+            admin_token.as_ref().map(|token| token.get_ref()),
+            params.local_compiler,
+        )?;
+        Ok(Self {
+            compiler_input,
+            compiler_version,
+            compiler_version_alias,
+            creation_bytecode: params.creation_bytecode,
+            deployed_bytecode: params.deployed_bytecode,
+            match_strategies: params
+                .match_strategies
+                .unwrap_or_else(|| config.match_strategies.clone()),
+            contract_file: params.contract_file,
+            contract_name: params.contract_name,
+            local_compiler,
+            contract_address: params.contract_address,
+            chain_id: params.chain_id,
+            check_language: !params.ignore_language_mismatch,
+            debug: query.debug,
+            model_checker_stripped,
+        })
+    }
 
-    let compiler_input = params.content.into();
-    let compiler_version =
-        Version::from_str(&params.compiler_version).map_err(error::ErrorBadRequest)?;
-    let input = Input {
-        compiler_version,
-        compiler_input,
-        creation_tx_input: &params.creation_bytecode,
-        deployed_bytecode: &params.deployed_bytecode,
-    };
-    compile_and_verify_handler(&compilers, input, false)
-        .await
-        .map(Json)
+    async fn run(
+        self,
+        compilers: &Compilers,
+        config: &SolidityConfiguration,
+        context: VerificationContext<'_>,
+    ) -> Result<VerificationResponse, Error> {
+        let input = Input {
+            compiler_version: self.compiler_version,
+            compiler_input: self.compiler_input,
+            creation_tx_input: &self.creation_bytecode,
+            deployed_bytecode: &self.deployed_bytecode,
+            match_strategies: self.match_strategies,
+            bytecode_match_policy: config.bytecode_match_policy,
+            contract_file: self.contract_file,
+            contract_name: self.contract_name,
+            local_compiler: self.local_compiler,
+            contract_address: self.contract_address,
+            chain_id: self.chain_id,
+            check_language: self.check_language,
+            debug: self.debug,
+            source_transforms: config
+                .source_transforms
+                .iter()
+                .map(|kind| kind.transform())
+                .collect(),
+            parallel_compile_attempts: config.parallel_compile_attempts,
+        };
+        let mut response = compile_and_verify_handler(compilers, input, false, context).await?;
+        match response.result.as_mut() {
+            Some(result) => {
+                result.model_checker_stripped = self.model_checker_stripped;
+                result.compiler_version_alias = self.compiler_version_alias;
+            }
+            None => response = response.with_model_checker_stripped(self.model_checker_stripped),
+        }
+        Ok(response)
+    }
 }
@@ -1,26 +1,168 @@
+use super::verification_metadata::VerificationMetadata;
+use crate::{
+    http_server::handlers::uploads::UploadStore,
+    schema::{AddressDef, CompilerInputDef, H256Def, JsonSchema},
+    solidity::MatchStrategy,
+};
+use actix_web::error;
+use ethers_core::types::Address;
 use ethers_solc::{
-    artifacts::{Libraries, Settings, Source, Sources},
+    artifacts::{Libraries, ModelCheckerSettings, Settings, Source, Sources},
     CompilerInput, EvmVersion,
 };
+use primitive_types::H256;
 use serde::{Deserialize, Serialize};
 use std::{collections::BTreeMap, path::PathBuf, str::FromStr};
 
-#[derive(Debug, Deserialize, PartialEq)]
+#[derive(Debug, Deserialize, PartialEq, JsonSchema)]
 pub struct VerificationRequest<T> {
+    /// Either of the bytecodes may be omitted or empty, if not available
+    #[serde(default)]
     pub deployed_bytecode: String,
+    #[serde(default)]
     pub creation_bytecode: String,
+    /// Compiler version used to compile the contract
     pub compiler_version: String,
+    /// Overrides the strategies specified in the config if present
+    #[serde(default)]
+    pub match_strategies: Option<Vec<MatchStrategy>>,
+    /// If set, only contracts from the source file are compared
+    #[serde(default)]
+    pub contract_file: Option<String>,
+    /// If set, only contracts with the name are compared; otherwise
+    /// the first matching contract of the sources is verified
+    #[serde(default)]
+    pub contract_name: Option<String>,
+    /// Compiler used instead of the fetched one. Allowed for admins only
+    #[serde(default)]
+    pub local_compiler: Option<LocalCompiler>,
+    /// Address the contract is deployed at. Used to read the implementation
+    /// address if the contract turns out to be a proxy
+    #[serde(default)]
+    #[schemars(with = "Option<AddressDef>")]
+    pub contract_address: Option<Address>,
+    /// Chain the contract is deployed on. Required to look up the contracts
+    /// failed to be verified in Sourcify, if the fallback is enabled
+    #[serde(default)]
+    pub chain_id: Option<String>,
+    /// Disables rejection of sources looking like another language
+    #[serde(default)]
+    pub ignore_language_mismatch: bool,
 
     #[serde(flatten)]
     pub content: T,
 }
 
+/// Verification request, which compiler version is looked up among the available ones.
+#[derive(Debug, Deserialize, PartialEq, JsonSchema)]
+pub struct AutoVerificationRequest {
+    /// Either of the bytecodes may be omitted or empty, if not available
+    #[serde(default)]
+    pub deployed_bytecode: String,
+    #[serde(default)]
+    pub creation_bytecode: String,
+    /// Semver range the tried versions are limited to, e.g. `>=0.8.0, <0.8.10`.
+    /// Release versions are tried in descending order, starting from the newest
+    pub compiler_versions: Option<String>,
+    /// Overrides the strategies specified in the config if present
+    #[serde(default)]
+    pub match_strategies: Option<Vec<MatchStrategy>>,
+    /// Address the contract is deployed at. Used to read the implementation
+    /// address if the contract turns out to be a proxy
+    #[serde(default)]
+    #[schemars(with = "Option<AddressDef>")]
+    pub contract_address: Option<Address>,
+    /// Disables rejection of sources looking like another language
+    #[serde(default)]
+    pub ignore_language_mismatch: bool,
+
+    #[serde(flatten)]
+    pub content: MultiPartFiles,
+}
+
+/// Query parameters accepted by the verification endpoints.
+#[derive(Debug, Default, Deserialize, PartialEq, Eq)]
+pub struct VerificationQuery {
+    /// If set, compiled and on-chain bytecodes are returned in the response
+    #[serde(default)]
+    pub debug: bool,
+}
+
+/// Externally provided compiler located on the server.
+#[derive(Debug, Deserialize, PartialEq, Eq, JsonSchema)]
+pub struct LocalCompiler {
+    pub path: PathBuf,
+    /// If present, the compiler file must match the hashsum
+    #[schemars(with = "Option<H256Def>")]
+    pub sha256: Option<H256>,
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+pub struct ArtifactRequest {
+    pub compiler_version: String,
+    /// If not specified, the creation bytecode from the artifact is used
+    pub creation_bytecode: Option<String>,
+    pub artifact: Artifact,
+    /// Overrides the strategies specified in the config if present
+    #[serde(default)]
+    pub match_strategies: Option<Vec<MatchStrategy>>,
+
+    #[serde(flatten)]
+    pub content: MultiPartFiles,
+}
+
+/// Per-contract artifact json emitted by Hardhat or Foundry.
+/// Only the fields used in verification are parsed.
 #[derive(Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Artifact {
+    pub abi: Option<serde_json::Value>,
+    pub bytecode: Option<ArtifactBytecode>,
+    pub deployed_bytecode: ArtifactBytecode,
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(untagged)]
+pub enum ArtifactBytecode {
+    /// Hardhat stores the bytecode as a hex string
+    Hex(String),
+    /// Foundry stores the bytecode inside the object with additional information
+    Object { object: String },
+}
+
+impl ArtifactBytecode {
+    pub fn hex(&self) -> &str {
+        match self {
+            ArtifactBytecode::Hex(hex) => hex,
+            ArtifactBytecode::Object { object } => object,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, PartialEq, JsonSchema)]
 pub struct MultiPartFiles {
+    /// Map from a source file name to the actual source code
     sources: BTreeMap<PathBuf, String>,
-    evm_version: String,
+    /// Version of the EVM to compile for. The compiler default
+    /// is used if not set or set to "default"
+    evm_version: Option<String>,
+    /// The optimizer is disabled if not set. `0` is a valid number of runs,
+    /// which enables the optimizer and is passed to the compiler as is.
     optimization_runs: Option<usize>,
+    /// Library names mapped to their addresses, specified for every source file
     contract_libraries: Option<BTreeMap<String, String>>,
+    /// Source files mapped to the libraries defined in them, which take precedence
+    /// over `contract_libraries` of the same names
+    libraries: Option<BTreeMap<PathBuf, BTreeMap<String, String>>>,
+    /// If present, the settings pinned by the verification metadata
+    /// override all other settings of the request.
+    verification_metadata: Option<String>,
+}
+
+impl MultiPartFiles {
+    pub fn has_verification_metadata(&self) -> bool {
+        self.verification_metadata.is_some()
+    }
 }
 
 impl TryFrom<MultiPartFiles> for CompilerInput {
@@ -35,18 +177,26 @@ impl TryFrom<MultiPartFiles> for CompilerInput {
             // so we assume that every file MAY contains all libraries
             let libs = multi_part
                 .sources
-                .iter()
-                .map(|(filename, _)| (PathBuf::from(filename), libs.clone()))
+                .keys()
+                .map(|filename| (PathBuf::from(filename), libs.clone()))
                 .collect();
             settings.libraries = Libraries { libs };
         }
+        for (filename, libs) in multi_part.libraries.into_iter().flatten() {
+            let file_libs = settings.libraries.libs.entry(filename).or_default();
+            file_libs.extend(libs);
+        }
 
-        if multi_part.evm_version != "default" {
-            settings.evm_version =
-                Some(EvmVersion::from_str(&multi_part.evm_version).map_err(anyhow::Error::msg)?);
-        } else {
+        settings.evm_version = match multi_part.evm_version.as_deref() {
             // `Settings::default()` sets the value to the latest available evm version (`Some(London)` for now)
-            settings.evm_version = None
+            None | Some("default") => None,
+            Some(evm_version) => {
+                Some(EvmVersion::from_str(evm_version).map_err(anyhow::Error::msg)?)
+            }
+        };
+
+        if let Some(verification_metadata) = multi_part.verification_metadata {
+            VerificationMetadata::parse(&verification_metadata)?.apply(&mut settings);
         }
 
         let sources: Sources = multi_part
@@ -62,15 +212,129 @@ impl TryFrom<MultiPartFiles> for CompilerInput {
     }
 }
 
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize, JsonSchema)]
 pub struct StandardJson {
-    input: CompilerInput,
+    /// May be omitted if the input is referenced by `upload_id`
+    #[serde(default)]
+    #[schemars(with = "Option<CompilerInputDef>")]
+    input: Option<CompilerInput>,
+    /// Upload containing the compiler input json, for inputs too large for a single request
+    #[serde(default)]
+    upload_id: Option<String>,
+}
+
+impl StandardJson {
+    /// Replaces the referenced upload with the compiler input parsed from its content.
+    pub fn load_upload(&mut self, uploads: Option<&UploadStore>) -> Result<(), actix_web::Error> {
+        let upload_id = match self.upload_id.take() {
+            None => return Ok(()),
+            Some(_) if self.input.is_some() => {
+                return Err(error::ErrorBadRequest(
+                    "only one of `input` and `upload_id` can be specified",
+                ))
+            }
+            Some(upload_id) => upload_id,
+        };
+        let uploads = uploads.ok_or_else(|| error::ErrorBadRequest("uploads are disabled"))?;
+        let content = uploads.take(&upload_id)?;
+        let input = serde_json::from_slice(&content).map_err(|err| {
+            error::ErrorBadRequest(format!("upload is not a valid compiler input: {}", err))
+        })?;
+        self.input = Some(input);
+        Ok(())
+    }
+
+    /// Removes model checker settings from the input and returns them if were present.
+    ///
+    /// Model checker (SMTChecker) does not affect the resultant bytecode,
+    /// but may slow down the compilation significantly.
+    pub fn take_model_checker_settings(&mut self) -> Option<ModelCheckerSettings> {
+        self.input.as_mut()?.settings.model_checker.take()
+    }
+}
+
+impl TryFrom<StandardJson> for CompilerInput {
+    type Error = anyhow::Error;
+
+    fn try_from(input: StandardJson) -> Result<Self, Self::Error> {
+        if input.upload_id.is_some() {
+            anyhow::bail!("upload must be loaded first");
+        }
+        let mut input = input
+            .input
+            .ok_or_else(|| anyhow::anyhow!("either `input` or `upload_id` must be specified"))?;
+        validate_compiler_input(&mut input)?;
+        Ok(input)
+    }
 }
 
-impl From<StandardJson> for CompilerInput {
-    fn from(input: StandardJson) -> Self {
-        input.input
+/// Outputs the verification cannot be done without. Whole bytecode objects
+/// are requested, as the link references are required to link the libraries.
+const REQUIRED_OUTPUTS: [&str; 4] = ["abi", "metadata", "evm.bytecode", "evm.deployedBytecode"];
+
+/// Checks the standard json input could be verified, before invoking the compiler.
+///
+/// Missing output selection is replaced by the one used for multi-part files. The outputs
+/// required for the verification are merged into the selections of the contracts,
+/// including the `*` selection of all contracts, which is added if not specified.
+/// The rest of the settings are kept as is.
+fn validate_compiler_input(input: &mut CompilerInput) -> anyhow::Result<()> {
+    if input.language != "Solidity" {
+        anyhow::bail!(
+            "unsupported language `{}`, only `Solidity` inputs are verified",
+            input.language
+        );
+    }
+    if input.sources.is_empty() {
+        anyhow::bail!("input must contain at least one source file");
     }
+    let output_selection = &mut input.settings.output_selection.0;
+    if output_selection.is_empty() {
+        *output_selection = Settings::default().output_selection.0;
+    }
+    output_selection
+        .entry("*".to_string())
+        .or_default()
+        .entry("*".to_string())
+        .or_default();
+    for file_selection in output_selection.values_mut() {
+        // the "" key selects file-level outputs (e.g. the ast), the rest are contracts
+        if file_selection.keys().all(|contract| contract.is_empty()) {
+            file_selection.insert("*".to_string(), vec![]);
+        }
+        let contract_selections = file_selection
+            .iter_mut()
+            .filter(|(contract, _)| !contract.is_empty());
+        for (_, outputs) in contract_selections {
+            for required in REQUIRED_OUTPUTS {
+                if !is_output_selected(outputs, required) {
+                    outputs.push(required.to_string());
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Checks whether the output is selected either directly, or by a wildcard
+/// or one of its parents (e.g. `evm` selects `evm.bytecode`)
+fn is_output_selected(outputs: &[String], output: &str) -> bool {
+    outputs.iter().any(|selected| {
+        selected == "*"
+            || output
+                .strip_prefix(selected.as_str())
+                .map_or(false, |rest| rest.is_empty() || rest.starts_with('.'))
+    })
+}
+
+/// Filters of the versions list. Bounds are inclusive semver versions (e.g. `0.8.15`)
+#[derive(Debug, Default, Deserialize)]
+pub struct VersionsQuery {
+    /// If set, nightly versions are excluded
+    #[serde(default)]
+    pub stable_only: bool,
+    pub min: Option<String>,
+    pub max: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -78,10 +342,15 @@ pub struct VersionsResponse {
     pub versions: Vec<String>,
 }
 
+#[derive(Debug, Serialize)]
+pub struct EvmVersionsResponse {
+    pub evm_versions: Vec<String>,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::tests::parse::test_deserialize_ok;
+    use crate::{config::UploadsConfiguration, tests::parse::test_deserialize_ok};
     use pretty_assertions::assert_eq;
 
     fn sources(sources: &[(&str, &str)]) -> BTreeMap<PathBuf, String> {
@@ -109,11 +378,20 @@ mod tests {
                     deployed_bytecode: "0x6001".into(),
                     creation_bytecode: "0x6001".into(),
                     compiler_version: "0.8.3".into(),
+                    match_strategies: None,
+                    contract_file: None,
+                    contract_name: None,
+                    local_compiler: None,
+                    contract_address: None,
+                    chain_id: None,
+                    ignore_language_mismatch: false,
                     content: MultiPartFiles {
                         sources: sources(&[("source.sol", "pragma")]),
-                        evm_version: format!("{}", ethers_solc::EvmVersion::London),
+                        evm_version: Some(ethers_solc::EvmVersion::London.to_string()),
                         optimization_runs: Some(200),
                         contract_libraries: None,
+                        libraries: None,
+                        verification_metadata: None,
                     },
                 },
             ),
@@ -122,6 +400,7 @@ mod tests {
                     "deployed_bytecode": "0x6001",
                     "creation_bytecode": "0x6001",
                     "compiler_version": "0.8.3",
+                    "contract_name": "A",
                     "sources": {
                         "source.sol": "source",
                         "A.sol": "A",
@@ -131,12 +410,22 @@ mod tests {
                     "evm_version": "spuriousDragon",
                     "contract_libraries": {
                         "Lib.sol": "0x1234567890123456789012345678901234567890"
+                    },
+                    "libraries": {
+                        "A.sol": {"Math": "0x0000000000000000000000000000000000000001"}
                     }
                 }"#,
                 VerificationRequest::<MultiPartFiles> {
                     deployed_bytecode: "0x6001".into(),
                     creation_bytecode: "0x6001".into(),
                     compiler_version: "0.8.3".into(),
+                    match_strategies: None,
+                    contract_file: None,
+                    contract_name: Some("A".into()),
+                    local_compiler: None,
+                    contract_address: None,
+                    chain_id: None,
+                    ignore_language_mismatch: false,
                     content: MultiPartFiles {
                         sources: sources(&[
                             ("source.sol", "source"),
@@ -144,12 +433,20 @@ mod tests {
                             ("B", "B"),
                             ("metadata.json", "metadata"),
                         ]),
-                        evm_version: format!("{}", ethers_solc::EvmVersion::SpuriousDragon),
+                        evm_version: Some(ethers_solc::EvmVersion::SpuriousDragon.to_string()),
                         optimization_runs: None,
                         contract_libraries: Some(BTreeMap::from([(
                             "Lib.sol".into(),
                             "0x1234567890123456789012345678901234567890".into(),
                         )])),
+                        libraries: Some(BTreeMap::from([(
+                            "A.sol".into(),
+                            BTreeMap::from([(
+                                "Math".into(),
+                                "0x0000000000000000000000000000000000000001".into(),
+                            )]),
+                        )])),
+                        verification_metadata: None,
                     },
                 },
             ),
@@ -167,39 +464,158 @@ mod tests {
     fn multi_part_to_input() {
         let mutli_part = MultiPartFiles {
             sources: sources(&[("source.sol", "pragma")]),
-            evm_version: format!("{}", ethers_solc::EvmVersion::London),
+            evm_version: Some(ethers_solc::EvmVersion::London.to_string()),
             optimization_runs: Some(200),
             contract_libraries: Some(BTreeMap::from([(
                 "some_library".into(),
                 "some_address".into(),
             )])),
+            libraries: Some(BTreeMap::from([
+                (
+                    "source.sol".into(),
+                    BTreeMap::from([("some_library".into(), "file_address".into())]),
+                ),
+                (
+                    "lib.sol".into(),
+                    BTreeMap::from([("other_library".into(), "other_address".into())]),
+                ),
+            ])),
+            verification_metadata: None,
         };
-        let expected = r#"{"language":"Solidity","sources":{"source.sol":{"content":"pragma"}},"settings":{"optimizer":{"enabled":true,"runs":200},"outputSelection":{"*":{"":["ast"],"*":["abi","evm.bytecode","evm.deployedBytecode","evm.methodIdentifiers"]}},"evmVersion":"london","libraries":{"source.sol":{"some_library":"some_address"}}}}"#;
+        let expected = r#"{"language":"Solidity","sources":{"source.sol":{"content":"pragma"}},"settings":{"optimizer":{"enabled":true,"runs":200},"outputSelection":{"*":{"":["ast"],"*":["abi","evm.bytecode","evm.deployedBytecode","evm.methodIdentifiers"]}},"evmVersion":"london","libraries":{"lib.sol":{"other_library":"other_address"},"source.sol":{"some_library":"file_address"}}}}"#;
         test_to_input(mutli_part, expected);
         let multi_part = MultiPartFiles {
             sources: sources(&[("source.sol", "")]),
-            evm_version: format!("{}", ethers_solc::EvmVersion::SpuriousDragon),
+            evm_version: Some(ethers_solc::EvmVersion::SpuriousDragon.to_string()),
             optimization_runs: None,
             contract_libraries: None,
+            libraries: None,
+            verification_metadata: None,
         };
         let expected = r#"{"language":"Solidity","sources":{"source.sol":{"content":""}},"settings":{"optimizer":{"enabled":false},"outputSelection":{"*":{"":["ast"],"*":["abi","evm.bytecode","evm.deployedBytecode","evm.methodIdentifiers"]}},"evmVersion":"spuriousDragon","libraries":{}}}"#;
         test_to_input(multi_part, expected);
     }
 
+    #[test]
+    fn zero_optimization_runs() {
+        let multi_part = MultiPartFiles {
+            sources: sources(&[("source.sol", "pragma")]),
+            evm_version: Some("default".to_string()),
+            optimization_runs: Some(0),
+            contract_libraries: None,
+            libraries: None,
+            verification_metadata: None,
+        };
+        let expected = r#"{"language":"Solidity","sources":{"source.sol":{"content":"pragma"}},"settings":{"optimizer":{"enabled":true,"runs":0},"outputSelection":{"*":{"":["ast"],"*":["abi","evm.bytecode","evm.deployedBytecode","evm.methodIdentifiers"]}},"libraries":{}}}"#;
+        test_to_input(multi_part, expected);
+    }
+
     #[test]
     // 'default' should result in None in CompilerInput
     fn default_evm_version() {
         let multi_part = MultiPartFiles {
             sources: BTreeMap::new(),
-            evm_version: "default".to_string(),
+            evm_version: Some("default".to_string()),
             optimization_runs: None,
             contract_libraries: None,
+            libraries: None,
+            verification_metadata: None,
         };
         let compiler_input = CompilerInput::try_from(multi_part).expect("Structure is valid");
         assert_eq!(
             None, compiler_input.settings.evm_version,
             "'default' should result in `None`"
+        );
+
+        let multi_part: MultiPartFiles = serde_json::from_value(
+            serde_json::json!({"sources": {}, "settings": {"optimizer": {}}}),
+        )
+        .expect("valid request");
+        let compiler_input = CompilerInput::try_from(multi_part).expect("Structure is valid");
+        assert_eq!(
+            None, compiler_input.settings.evm_version,
+            "omitted version should result in `None`"
+        );
+    }
+
+    #[test]
+    fn unknown_evm_version() {
+        let multi_part: MultiPartFiles =
+            serde_json::from_value(serde_json::json!({"sources": {}, "evm_version": "future"}))
+                .expect("valid request");
+        let err = CompilerInput::try_from(multi_part).expect_err("version is unknown");
+        assert_eq!(err.to_string(), "Unknown evm version: future");
+    }
+
+    #[test]
+    fn verification_metadata_overrides_settings() {
+        let multi_part = MultiPartFiles {
+            sources: sources(&[("source.sol", "pragma")]),
+            evm_version: Some(ethers_solc::EvmVersion::London.to_string()),
+            optimization_runs: None,
+            contract_libraries: None,
+            libraries: None,
+            verification_metadata: Some(
+                r#"{"v":1,"evm":"istanbul","opt":{"enabled":true,"runs":1000},"meta":{"bytecodeHash":"none"}}"#
+                    .into(),
+            ),
+        };
+        let expected = r#"{"language":"Solidity","sources":{"source.sol":{"content":"pragma"}},"settings":{"optimizer":{"enabled":true,"runs":1000},"metadata":{"bytecodeHash":"none"},"outputSelection":{"*":{"":["ast"],"*":["abi","evm.bytecode","evm.deployedBytecode","evm.methodIdentifiers"]}},"evmVersion":"istanbul","libraries":{}}}"#;
+        test_to_input(multi_part, expected);
+    }
+
+    const FOUNDRY_ARTIFACT: &str = r#"{
+        "abi": [{"type": "function", "name": "retrieve", "inputs": [], "outputs": [{"name": "", "type": "uint256", "internalType": "uint256"}], "stateMutability": "view"}],
+        "bytecode": {"object": "0x6080", "sourceMap": "", "linkReferences": {}},
+        "deployedBytecode": {"object": "0x6001", "sourceMap": "", "linkReferences": {}},
+        "methodIdentifiers": {"retrieve()": "2e64cec1"},
+        "id": 0
+    }"#;
+
+    #[test]
+    fn parse_artifact() {
+        let foundry: Artifact = serde_json::from_str(FOUNDRY_ARTIFACT).expect("valid artifact");
+        assert_eq!(foundry.deployed_bytecode.hex(), "0x6001");
+        assert_eq!(foundry.bytecode.as_ref().map(|b| b.hex()), Some("0x6080"));
+        assert!(foundry.abi.is_some());
+
+        let hardhat: Artifact = serde_json::from_str(
+            r#"{"_format": "hh-sol-artifact-1", "contractName": "Storage", "abi": [], "bytecode": "0x6080", "deployedBytecode": "0x6001"}"#,
         )
+        .expect("valid artifact");
+        assert_eq!(hardhat.deployed_bytecode.hex(), "0x6001");
+        assert_eq!(hardhat.bytecode.as_ref().map(|b| b.hex()), Some("0x6080"));
+
+        let err = serde_json::from_str::<Artifact>(r#"{"abi": [], "bytecode": "0x6080"}"#)
+            .expect_err("artifact without deployed bytecode should be rejected");
+        assert!(err.to_string().contains("deployedBytecode"), "{}", err);
+    }
+
+    #[test]
+    fn parse_artifact_request() {
+        let input = format!(
+            r#"{{
+                "compiler_version": "0.8.3",
+                "artifact": {},
+                "sources": {{"source.sol": "pragma"}},
+                "evm_version": "london"
+            }}"#,
+            FOUNDRY_ARTIFACT
+        );
+        let request: ArtifactRequest = serde_json::from_str(&input).expect("valid request");
+        assert_eq!(request.creation_bytecode, None);
+        assert_eq!(request.artifact.deployed_bytecode.hex(), "0x6001");
+        assert_eq!(
+            request.content,
+            MultiPartFiles {
+                sources: sources(&[("source.sol", "pragma")]),
+                evm_version: Some("london".into()),
+                optimization_runs: None,
+                contract_libraries: None,
+                libraries: None,
+                verification_metadata: None,
+            }
+        );
     }
 
     #[test]
@@ -212,7 +628,7 @@ mod tests {
         }"#;
 
         let deserialized: VerificationRequest<StandardJson> =
-            serde_json::from_str(&input).expect("Valid json");
+            serde_json::from_str(input).expect("Valid json");
         assert_eq!(
             deserialized.deployed_bytecode, "0x6001",
             "Invalid deployed bytecode"
@@ -228,8 +644,189 @@ mod tests {
 
         let expected_compiler_input = r#"{"language":"Solidity","sources":{"./src/contracts/Foo.sol":{"content":"pragma solidity ^0.8.2;\n\ncontract Foo {\n    function bar() external pure returns (uint256) {\n        return 42;\n    }\n}\n"}},"settings":{"optimizer":{"enabled":true,"runs":200},"metadata":{"useLiteralContent":true},"outputSelection":{"*":{"":["id","ast"],"*":["abi","evm.bytecode","evm.deployedBytecode","evm.methodIdentifiers"]}},"libraries":{}}}"#;
 
+        let actual_compiler_input = serde_json::to_string(&deserialized.content.input.unwrap())
+            .expect("Actual deserialization");
+        assert_eq!(actual_compiler_input, expected_compiler_input);
+    }
+
+    #[test]
+    fn strip_model_checker_settings() {
+        let input = r#"{
+            "input":{"language":"Solidity","sources":{"Foo.sol":{"content":"pragma solidity ^0.8.2;"}},"settings":{"modelChecker":{"engine":"all","timeout":100000,"targets":["assert","overflow"]},"optimizer":{"enabled":false}}}
+        }"#;
+        let mut standard_json: StandardJson = serde_json::from_str(input).expect("Valid json");
+
+        let stripped = standard_json.take_model_checker_settings();
+        assert!(stripped.is_some(), "Model checker settings should be found");
+        assert_eq!(
+            None,
+            standard_json.input.as_ref().unwrap().settings.model_checker
+        );
+        assert!(
+            standard_json.take_model_checker_settings().is_none(),
+            "Settings should be stripped only once"
+        );
+
+        let expected_compiler_input = r#"{"language":"Solidity","sources":{"Foo.sol":{"content":"pragma solidity ^0.8.2;"}},"settings":{"optimizer":{"enabled":false},"outputSelection":{"*":{"":["ast"],"*":["abi","evm.bytecode","evm.deployedBytecode","evm.methodIdentifiers","metadata"]}},"libraries":{}}}"#;
         let actual_compiler_input =
-            serde_json::to_string(&deserialized.content.input).expect("Actual deserialization");
+            serde_json::to_string(&CompilerInput::try_from(standard_json).unwrap())
+                .expect("Actual deserialization");
         assert_eq!(actual_compiler_input, expected_compiler_input);
     }
+
+    #[test]
+    fn standard_json_from_upload() {
+        let uploads = UploadStore::new(UploadsConfiguration::default());
+        let upload_id = uploads.start().unwrap();
+        let input = r#"{"language":"Solidity","sources":{"Foo.sol":{"content":"pragma solidity ^0.8.2;"}},"settings":{"optimizer":{"enabled":false},"outputSelection":{"*":{"*":["*"]}},"libraries":{}}}"#;
+        let (first, second) = input.as_bytes().split_at(input.len() / 2);
+        uploads.append(&upload_id, None, first).unwrap();
+        uploads.append(&upload_id, None, second).unwrap();
+
+        let request = format!(r#"{{"upload_id":"{}"}}"#, upload_id);
+        let mut standard_json: StandardJson = serde_json::from_str(&request).expect("Valid json");
+        standard_json
+            .load_upload(Some(&uploads))
+            .expect("upload should be loaded");
+        let compiler_input = CompilerInput::try_from(standard_json).unwrap();
+        assert_eq!(serde_json::to_string(&compiler_input).unwrap(), input);
+
+        let mut consumed: StandardJson = serde_json::from_str(&request).unwrap();
+        assert!(
+            consumed.load_upload(Some(&uploads)).is_err(),
+            "upload should be removed once loaded"
+        );
+
+        let mut disabled: StandardJson = serde_json::from_str(&request).unwrap();
+        assert!(disabled.load_upload(None).is_err());
+
+        let empty: StandardJson = serde_json::from_str("{}").unwrap();
+        assert!(CompilerInput::try_from(empty).is_err());
+    }
+
+    fn compiler_input(input: serde_json::Value) -> anyhow::Result<CompilerInput> {
+        let standard_json: StandardJson =
+            serde_json::from_value(serde_json::json!({ "input": input })).expect("Valid json");
+        CompilerInput::try_from(standard_json)
+    }
+
+    #[test]
+    fn validate_standard_json() {
+        let sources = serde_json::json!({"Foo.sol": {"content": "pragma solidity ^0.8.2;"}});
+        let err = compiler_input(
+            serde_json::json!({"language": "Vyper", "sources": sources, "settings": {"optimizer": {}}}),
+        )
+        .unwrap_err();
+        assert!(
+            err.to_string().contains("unsupported language `Vyper`"),
+            "{}",
+            err
+        );
+        let err = compiler_input(
+            serde_json::json!({"language": "Solidity", "sources": {}, "settings": {"optimizer": {}}}),
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("at least one source"), "{}", err);
+
+        let input = compiler_input(serde_json::json!({
+            "language": "Solidity",
+            "sources": sources,
+            "settings": {
+                "optimizer": {"enabled": true, "runs": 200},
+                "outputSelection": {
+                    "Foo.sol": {"": ["ast"], "Foo": ["abi", "evm.bytecode.object"]},
+                    "Bar.sol": {"": ["ast"]},
+                    "*": {"*": ["*"]}
+                }
+            }
+        }))
+        .unwrap();
+        assert_eq!(input.settings.optimizer.runs, Some(200));
+        assert_eq!(
+            serde_json::to_value(&input.settings.output_selection).unwrap(),
+            serde_json::json!({
+                "Foo.sol": {
+                    "": ["ast"],
+                    "Foo": [
+                        "abi",
+                        "evm.bytecode.object",
+                        "metadata",
+                        "evm.bytecode",
+                        "evm.deployedBytecode"
+                    ]
+                },
+                "Bar.sol": {
+                    "": ["ast"],
+                    "*": ["abi", "metadata", "evm.bytecode", "evm.deployedBytecode"]
+                },
+                "*": {"*": ["*"]}
+            })
+        );
+
+        let input = compiler_input(
+            serde_json::json!({"language": "Solidity", "sources": sources, "settings": {"optimizer": {}}}),
+        )
+        .unwrap();
+        assert_eq!(
+            serde_json::to_value(&input.settings.output_selection).unwrap(),
+            serde_json::json!({
+                "*": {
+                    "": ["ast"],
+                    "*": [
+                        "abi",
+                        "evm.bytecode",
+                        "evm.deployedBytecode",
+                        "evm.methodIdentifiers",
+                        "metadata"
+                    ]
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn merge_required_output_selection() {
+        let sources = serde_json::json!({"Foo.sol": {"content": "pragma solidity ^0.8.2;"}});
+        let input = compiler_input(serde_json::json!({
+            "language": "Solidity",
+            "sources": sources,
+            "settings": {
+                "optimizer": {"enabled": true, "runs": 1000},
+                "evmVersion": "london",
+                "outputSelection": {}
+            }
+        }))
+        .unwrap();
+        assert_eq!(input.settings.optimizer.enabled, Some(true));
+        assert_eq!(input.settings.optimizer.runs, Some(1000));
+        assert_eq!(input.settings.evm_version, Some(EvmVersion::London));
+        let selection = &input.settings.output_selection.0["*"]["*"];
+        for required in REQUIRED_OUTPUTS {
+            assert!(
+                selection.iter().any(|output| output == required),
+                "{} should be selected: {:?}",
+                required,
+                selection
+            );
+        }
+
+        let input = compiler_input(serde_json::json!({
+            "language": "Solidity",
+            "sources": sources,
+            "settings": {
+                "optimizer": {},
+                "outputSelection": {
+                    "Foo.sol": {"Foo": ["evm", "abi", "storageLayout"]}
+                }
+            }
+        }))
+        .unwrap();
+        assert_eq!(
+            serde_json::to_value(&input.settings.output_selection).unwrap(),
+            serde_json::json!({
+                "Foo.sol": {"Foo": ["evm", "abi", "storageLayout", "metadata"]},
+                "*": {"*": ["abi", "metadata", "evm.bytecode", "evm.deployedBytecode"]}
+            })
+        );
+    }
 }
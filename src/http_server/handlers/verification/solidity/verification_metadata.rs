@@ -0,0 +1,169 @@
+//! Compact "verification metadata" format used by Blockscout to store
+//! the compiler settings a contract has been verified with.
+//!
+//! The format is a json object containing only the settings
+//! that affect the resultant bytecode, e.g.:
+//! `{"v":1,"evm":"london","opt":{"enabled":true,"runs":200},"meta":{"bytecodeHash":"ipfs"}}`.
+//! Being stored together with the sources, it allows to re-verify the contract later.
+
+use ethers_solc::{
+    artifacts::{serde_helpers, Libraries, Optimizer, Settings, SettingsMetadata},
+    remappings::Remapping,
+    EvmVersion,
+};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+const FORMAT_VERSION: u8 = 1;
+
+#[derive(Debug, Error)]
+pub enum ParseError {
+    #[error("invalid verification metadata: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("unsupported verification metadata version: {0}")]
+    UnsupportedVersion(u8),
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct VerificationMetadata {
+    #[serde(rename = "v")]
+    format_version: u8,
+    #[serde(
+        rename = "evm",
+        default,
+        with = "serde_helpers::display_from_str_opt",
+        skip_serializing_if = "Option::is_none"
+    )]
+    evm_version: Option<EvmVersion>,
+    #[serde(rename = "opt")]
+    optimizer: Optimizer,
+    #[serde(rename = "ir", default, skip_serializing_if = "Option::is_none")]
+    via_ir: Option<bool>,
+    #[serde(rename = "meta", default, skip_serializing_if = "Option::is_none")]
+    metadata: Option<SettingsMetadata>,
+    #[serde(rename = "libs", default, skip_serializing_if = "Libraries::is_empty")]
+    libraries: Libraries,
+    #[serde(rename = "remap", default, skip_serializing_if = "Vec::is_empty")]
+    remappings: Vec<Remapping>,
+}
+
+impl VerificationMetadata {
+    pub fn parse(metadata: &str) -> Result<Self, ParseError> {
+        let metadata: Self = serde_json::from_str(metadata)?;
+        if metadata.format_version != FORMAT_VERSION {
+            return Err(ParseError::UnsupportedVersion(metadata.format_version));
+        }
+        Ok(metadata)
+    }
+
+    pub fn emit(&self) -> String {
+        serde_json::to_string(self).expect("serialization is infallible")
+    }
+
+    /// Overrides corresponding values of the `settings` with the pinned ones.
+    pub fn apply(self, settings: &mut Settings) {
+        settings.evm_version = self.evm_version;
+        settings.optimizer = self.optimizer;
+        settings.via_ir = self.via_ir;
+        settings.metadata = self.metadata;
+        settings.libraries = self.libraries;
+        settings.remappings = self.remappings;
+    }
+}
+
+impl From<&Settings> for VerificationMetadata {
+    fn from(settings: &Settings) -> Self {
+        Self {
+            format_version: FORMAT_VERSION,
+            evm_version: settings.evm_version,
+            optimizer: settings.optimizer.clone(),
+            via_ir: settings.via_ir,
+            metadata: settings.metadata.clone(),
+            libraries: settings.libraries.clone(),
+            remappings: settings.remappings.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers_solc::artifacts::BytecodeHash;
+    use pretty_assertions::assert_eq;
+    use std::{collections::BTreeMap, str::FromStr};
+
+    fn settings() -> Settings {
+        let mut settings = Settings {
+            evm_version: Some(EvmVersion::Istanbul),
+            via_ir: Some(true),
+            metadata: Some(SettingsMetadata::from(BytecodeHash::None)),
+            ..Default::default()
+        };
+        settings.optimizer.enabled = Some(true);
+        settings.optimizer.runs = Some(1000);
+        settings.libraries = Libraries {
+            libs: BTreeMap::from([(
+                "source.sol".into(),
+                BTreeMap::from([(
+                    "SafeMath".into(),
+                    "0xFBe36e5cAD207d5fDee40E6568bb276a351f6713".into(),
+                )]),
+            )]),
+        };
+        settings.remappings =
+            vec![Remapping::from_str("@openzeppelin/=lib/openzeppelin/").unwrap()];
+        settings
+    }
+
+    #[test]
+    fn round_trip() {
+        let settings = settings();
+        let emitted = VerificationMetadata::from(&settings).emit();
+        let parsed = VerificationMetadata::parse(&emitted).expect("emitted metadata should parse");
+
+        let mut restored = Settings::default();
+        parsed.apply(&mut restored);
+        assert_eq!(
+            serde_json::to_value(&restored).unwrap(),
+            serde_json::to_value(&settings).unwrap()
+        );
+    }
+
+    #[test]
+    fn parse_minimal() {
+        let parsed = VerificationMetadata::parse(r#"{"v":1,"opt":{"enabled":false}}"#)
+            .expect("minimal metadata should parse");
+        let mut settings = settings();
+        parsed.apply(&mut settings);
+        assert_eq!(settings.evm_version, None);
+        assert_eq!(settings.optimizer.enabled, Some(false));
+        assert_eq!(settings.optimizer.runs, None);
+        assert_eq!(settings.metadata, None);
+        assert!(settings.libraries.is_empty());
+    }
+
+    #[test]
+    fn zero_optimizer_runs() {
+        let mut settings = Settings::default();
+        settings.optimizer.enabled = Some(true);
+        settings.optimizer.runs = Some(0);
+        let emitted = VerificationMetadata::from(&settings).emit();
+        assert!(emitted.contains(r#""opt":{"enabled":true,"runs":0}"#));
+
+        let mut restored = Settings::default();
+        VerificationMetadata::parse(&emitted)
+            .unwrap()
+            .apply(&mut restored);
+        assert_eq!(restored.optimizer.enabled, Some(true));
+        assert_eq!(restored.optimizer.runs, Some(0));
+    }
+
+    #[test]
+    fn parse_unsupported_version() {
+        assert!(matches!(
+            VerificationMetadata::parse(r#"{"v":2,"opt":{}}"#),
+            Err(ParseError::UnsupportedVersion(2))
+        ));
+        VerificationMetadata::parse(r#"{"opt":{}}"#).expect_err("version is required");
+    }
+}
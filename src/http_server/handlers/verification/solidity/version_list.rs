@@ -1,18 +1,116 @@
-use super::types::VersionsResponse;
-use crate::compiler::Compilers;
+use super::types::{EvmVersionsResponse, VersionsQuery, VersionsResponse};
+use crate::compiler::{self, Compilers, Version};
 
 use actix_web::{
+    error,
     web::{self, Json},
     Error,
 };
+use std::str::FromStr;
 
 pub async fn get_version_list(
     compilers: web::Data<Compilers>,
+    query: web::Query<VersionsQuery>,
 ) -> Result<Json<VersionsResponse>, Error> {
-    let mut versions = compilers.all_versions();
-    // sort in descending order
-    versions.sort_by(|x, y| x.cmp(y).reverse());
+    let versions = filter_versions(compilers.all_versions(), &query)?;
     let versions = versions.into_iter().map(|v| v.to_string()).collect();
 
     Ok(Json(VersionsResponse { versions }))
 }
+
+/// Returns the EVM versions supported by the available compiler version.
+pub async fn get_evm_versions(
+    compilers: web::Data<Compilers>,
+    version: web::Path<String>,
+) -> Result<Json<EvmVersionsResponse>, Error> {
+    let not_found = || error::ErrorNotFound(format!("compiler version {} not found", version));
+    let version = Version::from_path_segment(&version).map_err(|_| not_found())?;
+    if !compilers.all_versions().contains(&version) {
+        return Err(not_found());
+    }
+    let evm_versions = compiler::supported_evm_versions(&version)
+        .iter()
+        .map(|evm_version| evm_version.to_string())
+        .collect();
+    Ok(Json(EvmVersionsResponse { evm_versions }))
+}
+
+/// Returns the versions matching the query in descending order
+fn filter_versions(
+    mut versions: Vec<Version>,
+    query: &VersionsQuery,
+) -> Result<Vec<Version>, Error> {
+    let parse_bound = |bound: &Option<String>| {
+        bound
+            .as_deref()
+            .map(semver::Version::from_str)
+            .transpose()
+            .map_err(error::ErrorBadRequest)
+    };
+    let min = parse_bound(&query.min)?;
+    let max = parse_bound(&query.max)?;
+    versions.retain(|version| {
+        (!query.stable_only || version.is_release())
+            && min.as_ref().is_none_or(|min| version.version() >= min)
+            && max.as_ref().is_none_or(|max| version.version() <= max)
+    });
+    // sort in descending order
+    versions.sort_by(|x, y| x.cmp(y).reverse());
+    Ok(versions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn versions(versions: &[&str]) -> Vec<Version> {
+        versions
+            .iter()
+            .map(|version| Version::from_str(version).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn versions_are_sorted_and_filtered() {
+        let available = versions(&[
+            "v0.4.24+commit.e67f0147",
+            "v0.8.15-nightly.2022.5.27+commit.095cc647",
+            "v0.8.9+commit.e5eed63a",
+            "v0.8.15-nightly.2022.5.25+commit.fdc3c8ee",
+            "v0.8.14+commit.80d49f37",
+            "v0.5.0+commit.1d4f565a",
+        ]);
+        assert_eq!(
+            filter_versions(available.clone(), &VersionsQuery::default()).unwrap(),
+            versions(&[
+                "v0.8.15-nightly.2022.5.27+commit.095cc647",
+                "v0.8.15-nightly.2022.5.25+commit.fdc3c8ee",
+                "v0.8.14+commit.80d49f37",
+                "v0.8.9+commit.e5eed63a",
+                "v0.5.0+commit.1d4f565a",
+                "v0.4.24+commit.e67f0147",
+            ])
+        );
+
+        let query = VersionsQuery {
+            stable_only: true,
+            min: Some("0.5.0".into()),
+            max: Some("0.8.99".into()),
+        };
+        assert_eq!(
+            filter_versions(available.clone(), &query).unwrap(),
+            versions(&[
+                "v0.8.14+commit.80d49f37",
+                "v0.8.9+commit.e5eed63a",
+                "v0.5.0+commit.1d4f565a",
+            ])
+        );
+
+        let query = VersionsQuery {
+            max: Some("0.8".into()),
+            ..Default::default()
+        };
+        assert!(filter_versions(available, &query).is_err());
+    }
+}
@@ -1,10 +1,10 @@
 use crate::{VerificationResponse, VerificationResult};
 use actix_web::{error, error::Error};
 use futures::Future;
-use reqwest::Url;
+use reqwest::{StatusCode, Url};
 use std::{num::NonZeroUsize, sync::Arc};
 
-use super::types::{ApiFilesResponse, ApiRequest, ApiVerificationResponse, Files};
+use super::types::{ApiFilesResponse, ApiRequest, ApiVerificationResponse, Files, MatchStatus};
 
 #[async_trait::async_trait]
 pub(super) trait SourcifyApi {
@@ -13,10 +13,13 @@ pub(super) trait SourcifyApi {
         params: &ApiRequest,
     ) -> Result<ApiVerificationResponse, reqwest::Error>;
 
-    async fn source_files_request(
+    /// Returns the files of the contract verified by Sourcify, or `None` if it is not verified.
+    /// Only transport and server errors are retried, as not verified contracts are not errors.
+    async fn lookup_request(
         &self,
-        params: &ApiRequest,
-    ) -> Result<ApiFilesResponse, reqwest::Error>;
+        chain: &str,
+        address: &str,
+    ) -> Result<Option<ApiFilesResponse>, reqwest::Error>;
 }
 
 pub struct SourcifyApiClient {
@@ -71,18 +74,26 @@ impl SourcifyApi for SourcifyApiClient {
         .await
     }
 
-    async fn source_files_request(
+    async fn lookup_request(
         &self,
-        params: &ApiRequest,
-    ) -> Result<ApiFilesResponse, reqwest::Error> {
+        chain: &str,
+        address: &str,
+    ) -> Result<Option<ApiFilesResponse>, reqwest::Error> {
         make_retrying_request(self.verification_attempts, || async {
             let url = self
                 .host
-                .join(format!("files/any/{}/{}", &params.chain, &params.address).as_str())
+                .join(format!("files/any/{}/{}", chain, address).as_str())
                 .expect("should be valid url");
-            let resp = reqwest::get(url).await?;
-
-            resp.json().await
+            let resp = reqwest::Client::builder()
+                .timeout(std::time::Duration::from_secs(self.request_timeout))
+                .build()?
+                .get(url)
+                .send()
+                .await?;
+            if resp.status() == StatusCode::NOT_FOUND {
+                return Ok(None);
+            }
+            resp.error_for_status()?.json().await.map(Some)
         })
         .await
     }
@@ -98,15 +109,19 @@ pub(super) async fn verify_using_sourcify_client(
         .map_err(error::ErrorInternalServerError)?;
 
     match response {
-        ApiVerificationResponse::Verified { result: _ } => {
+        ApiVerificationResponse::Verified { result: items } => {
             let api_files_response = sourcify_client
-                .source_files_request(&params)
+                .lookup_request(&params.chain, &params.address)
                 .await
-                .map_err(error::ErrorInternalServerError)?;
+                .map_err(error::ErrorInternalServerError)?
+                .ok_or_else(|| {
+                    error::ErrorInternalServerError("files of the verified contract not found")
+                })?;
             let files =
                 Files::try_from(api_files_response).map_err(error::ErrorInternalServerError)?;
             let result = VerificationResult::try_from(files).map_err(error::ErrorBadRequest)?;
-            Ok(VerificationResponse::ok(result))
+            let full_match = items.iter().all(|item| item.status == MatchStatus::Perfect);
+            Ok(VerificationResponse::verified(result, full_match))
         }
         ApiVerificationResponse::Error { error } => Ok(VerificationResponse::err(error)),
         ApiVerificationResponse::ValidationErrors { message, errors } => {
@@ -115,3 +130,137 @@ pub(super) async fn verify_using_sourcify_client(
         }
     }
 }
+
+/// Returns the contract verified by Sourcify on the chain, if any.
+pub(super) async fn lookup_using_sourcify_client(
+    sourcify_client: &impl SourcifyApi,
+    chain: &str,
+    address: &str,
+) -> Result<Option<VerificationResponse>, anyhow::Error> {
+    let api_files_response = match sourcify_client.lookup_request(chain, address).await? {
+        Some(response) => response,
+        None => return Ok(None),
+    };
+    let full_match = api_files_response.status == MatchStatus::Perfect;
+    let files = Files::try_from(api_files_response)?;
+    let result = VerificationResult::try_from(files)?;
+    Ok(Some(VerificationResponse::verified(result, full_match)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http_server::handlers::verification::{VerificationStatus, SOURCIFY_MATCH};
+    use pretty_assertions::assert_eq;
+
+    const METADATA: &str = r#"{
+        "compiler": {"version": "0.8.14+commit.80d49f37"},
+        "language": "Solidity",
+        "output": {"abi": []},
+        "settings": {
+            "compilationTarget": {"example.sol": "Example"},
+            "libraries": {},
+            "optimizer": {"enabled": false, "runs": 200}
+        }
+    }"#;
+
+    /// Knows the contracts at the `verified` addresses only
+    struct MockApi {
+        verified: Vec<(&'static str, &'static str)>,
+    }
+
+    // only lookups are mocked, verifications always fail
+    #[async_trait::async_trait]
+    impl SourcifyApi for MockApi {
+        async fn verification_request(
+            &self,
+            _params: &ApiRequest,
+        ) -> Result<ApiVerificationResponse, reqwest::Error> {
+            Ok(ApiVerificationResponse::Error {
+                error: "verification is not supported by the mock".into(),
+            })
+        }
+
+        async fn lookup_request(
+            &self,
+            _chain: &str,
+            address: &str,
+        ) -> Result<Option<ApiFilesResponse>, reqwest::Error> {
+            let response = serde_json::json!({
+                "files": [
+                    {"name": "metadata.json", "content": METADATA},
+                    {"name": "example.sol", "content": "contract Example {}"},
+                ],
+            });
+            Ok(self
+                .verified
+                .iter()
+                .find(|(verified, _)| *verified == address)
+                .map(|(_, status)| {
+                    let mut response = response.clone();
+                    response["status"] = serde_json::json!(status);
+                    serde_json::from_value(response).unwrap()
+                }))
+        }
+    }
+
+    #[tokio::test]
+    async fn looks_up_verified_contracts() {
+        let client = MockApi {
+            verified: vec![("0x01", "full"), ("0x02", "partial")],
+        };
+        let response = lookup_using_sourcify_client(&client, "1", "0x01")
+            .await
+            .unwrap()
+            .expect("contract should be verified");
+        assert_eq!(response.status, VerificationStatus::FullMatch);
+        let result = response.result.unwrap();
+        assert_eq!(result.contract_name, "Example");
+        assert_eq!(result.match_source, SOURCIFY_MATCH);
+
+        let response = lookup_using_sourcify_client(&client, "1", "0x02")
+            .await
+            .unwrap()
+            .expect("contract should be verified");
+        assert_eq!(response.status, VerificationStatus::PartialMatch);
+
+        let response = lookup_using_sourcify_client(&client, "1", "0x03")
+            .await
+            .unwrap();
+        assert_eq!(response, None);
+    }
+
+    fn client(server: &wiremock::MockServer) -> SourcifyApiClient {
+        let host = Url::parse(&format!("{}/server/", server.uri())).unwrap();
+        SourcifyApiClient::new(host, 1, NonZeroUsize::new(3).unwrap())
+    }
+
+    #[tokio::test]
+    async fn not_verified_contracts_are_not_retried() {
+        let server = wiremock::MockServer::start().await;
+        wiremock::Mock::given(wiremock::matchers::path("/server/files/any/1/0x01"))
+            .respond_with(
+                wiremock::ResponseTemplate::new(404)
+                    .set_body_json(serde_json::json!({"error": "Files have not been found!"})),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+        let response = client(&server).lookup_request("1", "0x01").await.unwrap();
+        assert!(response.is_none());
+    }
+
+    #[tokio::test]
+    async fn server_errors_are_retried() {
+        let server = wiremock::MockServer::start().await;
+        wiremock::Mock::given(wiremock::matchers::path("/server/files/any/1/0x01"))
+            .respond_with(wiremock::ResponseTemplate::new(502))
+            .expect(3)
+            .mount(&server)
+            .await;
+        client(&server)
+            .lookup_request("1", "0x01")
+            .await
+            .expect_err("server error should be returned after all attempts");
+    }
+}
@@ -2,18 +2,28 @@ use std::collections::BTreeMap;
 
 use ethers_solc::EvmVersion;
 use serde::Deserialize;
+use sha2::{Digest, Sha256};
 
-use crate::VerificationResult;
+use crate::{
+    http_server::handlers::verification::{
+        event_topics, function_selectors, sources_hash, SOURCIFY_MATCH,
+    },
+    VerificationResult,
+};
 
 use super::types::Files;
 
 const METADATA_FILE_NAME: &str = "metadata.json";
+/// Larger files are split by IPFS into a tree of chunks, which is not supported
+const IPFS_CHUNK_SIZE: usize = 256 * 1024;
+const BASE58_ALPHABET: &[u8] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
 
 // There is struct for metadata in ethers_solc::artifacts::Metadata
 // however it is for standard json input of compiler and
 // has different `libraries` field structure
 #[derive(Debug, PartialEq, Deserialize)]
 pub struct Metadata {
+    pub language: String,
     pub settings: MetadataSettings,
     pub compiler: Compiler,
     pub output: Output,
@@ -65,10 +75,67 @@ impl Files {
     }
 }
 
+/// IPFS hash (CIDv0) of the `content` added as a file, the way solc computes
+/// the hash of the metadata. `None` for contents exceeding a single chunk.
+fn ipfs_hash(content: &[u8]) -> Option<String> {
+    fn varint(mut value: usize, out: &mut Vec<u8>) {
+        while value >= 0x80 {
+            out.push((value as u8) | 0x80);
+            value >>= 7;
+        }
+        out.push(value as u8);
+    }
+    if content.len() > IPFS_CHUNK_SIZE {
+        return None;
+    }
+    // unixfs `Data` message of the "file" type; the data field is omitted for empty files
+    let mut unixfs = vec![0x08, 0x02];
+    if !content.is_empty() {
+        unixfs.push(0x12);
+        varint(content.len(), &mut unixfs);
+        unixfs.extend_from_slice(content);
+    }
+    unixfs.push(0x18);
+    varint(content.len(), &mut unixfs);
+    // dag-pb `PBNode` message without links
+    let mut node = vec![0x0a];
+    varint(unixfs.len(), &mut node);
+    node.extend_from_slice(&unixfs);
+
+    // sha2-256 multihash
+    let mut multihash = vec![0x12, 0x20];
+    multihash.extend_from_slice(&Sha256::digest(&node));
+    // base58 digits in little-endian order
+    let mut digits: Vec<u8> = vec![];
+    for byte in &multihash {
+        let mut carry = *byte as usize;
+        for digit in digits.iter_mut() {
+            carry += (*digit as usize) << 8;
+            *digit = (carry % 58) as u8;
+            carry /= 58;
+        }
+        while carry > 0 {
+            digits.push((carry % 58) as u8);
+            carry /= 58;
+        }
+    }
+    Some(
+        digits
+            .iter()
+            .rev()
+            .map(|digit| BASE58_ALPHABET[*digit as usize] as char)
+            .collect(),
+    )
+}
+
 impl TryFrom<Files> for VerificationResult {
     type Error = anyhow::Error;
 
     fn try_from(files: Files) -> Result<Self, Self::Error> {
+        let metadata_ipfs_hash = files
+            .0
+            .get(METADATA_FILE_NAME)
+            .and_then(|content| ipfs_hash(content.as_bytes()));
         let (metadata, source_files) = files.extract_metadata_and_source_files()?;
 
         let compiler_version = metadata.compiler.version;
@@ -87,19 +154,41 @@ impl TryFrom<Files> for VerificationResult {
         let optimization_runs = metadata.settings.optimizer.runs;
         let contract_libraries: BTreeMap<String, String> = metadata.settings.libraries;
         let abi = serde_json::to_string(&metadata.output.abi)?;
+        // Selectors are auxiliary data, so unsupported abi should not fail the verification
+        let parsed_abi: Option<ethabi::Contract> = serde_json::from_value(metadata.output.abi).ok();
+        let function_selectors = parsed_abi
+            .as_ref()
+            .map(function_selectors)
+            .unwrap_or_default();
+        let event_topics = parsed_abi.as_ref().map(event_topics).unwrap_or_default();
 
         Ok(VerificationResult {
             file_name,
             contract_name,
+            language: metadata.language,
+            // Sourcify does not return the output the kind could be extracted from
+            contract_kind: None,
             compiler_version,
             evm_version,
             // TODO: extract args
             constructor_arguments: None,
+            decoded_constructor_arguments: None,
             contract_libraries,
             optimization,
             optimization_runs,
             abi,
+            sources_hash: sources_hash(&source_files),
             sources: source_files,
+            model_checker_stripped: false,
+            source_transforms: vec![],
+            immutables_masked: false,
+            compiler_version_alias: None,
+            function_selectors,
+            event_topics,
+            verification_metadata: None,
+            match_strategy: None,
+            match_source: SOURCIFY_MATCH.to_string(),
+            metadata_ipfs_hash,
         })
     }
 }
@@ -113,6 +202,7 @@ mod tests {
         "compiler": {
             "version": "0.8.14+commit.80d49f37"
         },
+        "language": "Solidity",
         "output": {
             "abi": [{"inputs":[],"name":"retrieve","outputs":[{"internalType":"uint256","name":"","type":"uint256"}],"stateMutability":"view","type":"function"}]
         },
@@ -165,14 +255,28 @@ mod tests {
             VerificationResult {
                 file_name: "example.sol".into(),
                 contract_name: "Example".into(),
+                language: "Solidity".into(),
+                contract_kind: None,
                 compiler_version: "0.8.14+commit.80d49f37".into(),
                 evm_version: "london".into(),
                 constructor_arguments: None,
+                decoded_constructor_arguments: None,
                 contract_libraries: BTreeMap::from([("SafeMath".into(), "0xFBe36e5cAD207d5fDee40E6568bb276a351f6713".into())]),
                 optimization: Some(false),
                 optimization_runs: Some(200),
                 abi: r#"[{"inputs":[],"name":"retrieve","outputs":[{"internalType":"uint256","name":"","type":"uint256"}],"stateMutability":"view","type":"function"}]"#.into(),
                 sources: BTreeMap::from([("source.sol".into(), "content".into())]),
+                sources_hash: sources_hash(&BTreeMap::from([("source.sol".into(), "content".into())])),
+                model_checker_stripped: false,
+                source_transforms: vec![],
+                immutables_masked: false,
+                compiler_version_alias: None,
+                function_selectors: BTreeMap::from([("retrieve()".into(), "0x2e64cec1".into())]),
+                event_topics: BTreeMap::new(),
+                verification_metadata: None,
+                match_strategy: None,
+                match_source: SOURCIFY_MATCH.to_string(),
+                metadata_ipfs_hash: ipfs_hash(DEFAULT_METADATA.as_bytes()),
             }
         );
 
@@ -180,4 +284,18 @@ mod tests {
         VerificationResult::try_from(files)
             .expect_err("Parsing files without metadata should fail");
     }
+
+    #[test]
+    fn computes_ipfs_hash() {
+        // hashes returned by `ipfs add`
+        assert_eq!(
+            ipfs_hash(b"hello world\n").as_deref(),
+            Some("QmT78zSuBmuS4z925WZfrqQ1qHaJ56DQaTfyMUF7F8ff5o")
+        );
+        assert_eq!(
+            ipfs_hash(b"").as_deref(),
+            Some("QmbFMke1KXqnYyBBWxB74N4c5SBnJMVAiMNRcGu6x1AwQH")
+        );
+        assert_eq!(ipfs_hash(&vec![0; IPFS_CHUNK_SIZE + 1]), None);
+    }
 }
@@ -6,8 +6,34 @@ pub use self::api::SourcifyApiClient;
 
 use self::types::ApiRequest;
 use actix_web::{error::Error, web, web::Json};
+use ethers_core::{types::Address, utils::to_checksum};
 
 use super::VerificationResponse;
+use crate::{compiler::Language, metrics};
+
+/// Looks up the contracts failed to be verified by the service
+/// among the ones verified by Sourcify.
+pub struct SourcifyFallback {
+    client: SourcifyApiClient,
+}
+
+impl SourcifyFallback {
+    pub fn new(client: SourcifyApiClient) -> Self {
+        Self { client }
+    }
+
+    /// Returns the Sourcify verification of the contract on the chain, if any.
+    /// Failed lookups are logged and treated as not verified contracts.
+    pub async fn lookup(&self, chain_id: u64, address: Address) -> Option<VerificationResponse> {
+        let address = to_checksum(&address, None);
+        api::lookup_using_sourcify_client(&self.client, &chain_id.to_string(), &address)
+            .await
+            .unwrap_or_else(|err| {
+                log::warn!("sourcify lookup of {} failed: {:#}", address, err);
+                None
+            })
+    }
+}
 
 pub async fn verify(
     sourcify_client: web::Data<SourcifyApiClient>,
@@ -16,5 +42,6 @@ pub async fn verify(
     let response =
         api::verify_using_sourcify_client(sourcify_client.into_inner(), params.into_inner())
             .await?;
+    metrics::count_verify_contract(Language::Solidity, &response.status);
     Ok(Json(response))
 }
@@ -32,10 +32,19 @@ pub(super) enum ApiVerificationResponse {
     },
 }
 
+/// Match reported by Sourcify: "perfect" by the verification endpoint, "full" by the files lookup
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub(super) enum MatchStatus {
+    #[serde(alias = "full")]
+    Perfect,
+    Partial,
+}
+
 #[derive(Deserialize)]
 pub(super) struct ResultItem {
     pub address: String,
-    pub status: String,
+    pub status: MatchStatus,
     #[serde(rename = "storageTimestamp")]
     pub storage_timestamp: Option<String>,
 }
@@ -48,6 +57,7 @@ pub(super) struct FieldError {
 
 #[derive(Deserialize, Debug)]
 pub(super) struct ApiFilesResponse {
+    pub status: MatchStatus,
     pub files: Vec<FileItem>,
 }
 
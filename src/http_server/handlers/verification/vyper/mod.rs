@@ -0,0 +1,3 @@
+mod types;
+
+pub mod multi_part;
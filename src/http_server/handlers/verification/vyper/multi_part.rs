@@ -0,0 +1,51 @@
+use super::types::{VerificationRequest, VyperInput};
+use crate::{
+    compiler::{self, Compilers, Language},
+    config::VyperConfiguration,
+    http_server::handlers::verification::VerificationResponse,
+    metrics,
+    solidity::{BytecodeMatchPolicy, Verifier},
+};
+use actix_web::{
+    error,
+    web::{self, Json},
+    Error,
+};
+use std::str::FromStr;
+
+pub async fn verify(
+    compilers: web::Data<Compilers>,
+    config: web::Data<VyperConfiguration>,
+    params: Json<VerificationRequest>,
+) -> Result<Json<VerificationResponse>, Error> {
+    let params = params.into_inner();
+    let compiler_version =
+        compiler::Version::from_str(&params.compiler_version).map_err(error::ErrorBadRequest)?;
+    let verifier = Verifier::new(&params.creation_bytecode, &params.deployed_bytecode)
+        .map_err(error::ErrorBadRequest)?;
+    let input = VyperInput::try_from(params.content).map_err(error::ErrorBadRequest)?;
+
+    let output = match compilers.compile(&compiler_version, &input).await {
+        Ok(output) => output,
+        Err(err @ compiler::Error::Compilation { .. }) => {
+            return Ok(Json(VerificationResponse::compilation_failed(&err)))
+        }
+        Err(err) => return Err(error::ErrorInternalServerError(err)),
+    };
+    let match_strategies = params
+        .match_strategies
+        .unwrap_or_else(|| config.match_strategies.clone());
+    let response = match verifier.verify(output, &match_strategies, BytecodeMatchPolicy::default())
+    {
+        Ok(verification_success) => {
+            let full_match = verifier.metadata_matches(&verification_success.compiled_deployed);
+            let bytecode_matches = verification_success.bytecode_matches;
+            let result = (input, compiler_version, verification_success).into();
+            VerificationResponse::verified(result, full_match)
+                .with_bytecode_matches(Some(bytecode_matches))
+        }
+        Err(failure) => VerificationResponse::failed(&failure),
+    };
+    metrics::count_verify_contract(Language::Vyper, &response.status);
+    Ok(Json(response))
+}
@@ -0,0 +1,210 @@
+use crate::{
+    compiler::Version,
+    http_server::handlers::verification::{
+        constructor_arguments, event_topics, function_selectors, sources_hash, VerificationResult,
+        LOCAL_MATCH,
+    },
+    solidity::{MatchStrategy, VerificationSuccess},
+};
+use ethers_solc::EvmVersion;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, path::PathBuf, str::FromStr};
+
+#[derive(Debug, Deserialize, PartialEq)]
+pub struct VerificationRequest {
+    pub deployed_bytecode: String,
+    pub creation_bytecode: String,
+    pub compiler_version: String,
+    /// Overrides the strategies specified in the config if present
+    #[serde(default)]
+    pub match_strategies: Option<Vec<MatchStrategy>>,
+
+    #[serde(flatten)]
+    pub content: MultiPartFiles,
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+pub struct MultiPartFiles {
+    sources: BTreeMap<PathBuf, String>,
+    /// Version of the EVM to compile for, or "default"
+    evm_version: String,
+    /// The compiler default is used if not set
+    optimize: Option<bool>,
+}
+
+/// Standard json input of the vyper compiler
+/// (https://docs.vyperlang.org/en/stable/compiling-a-contract.html#input-json-description).
+#[derive(Debug, Serialize, PartialEq)]
+pub struct VyperInput {
+    language: String,
+    sources: BTreeMap<String, Source>,
+    settings: Settings,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+struct Source {
+    content: String,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct Settings {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    evm_version: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    optimize: Option<bool>,
+    output_selection: BTreeMap<String, Vec<String>>,
+}
+
+/// Outputs required for the verification; vyper rejects unknown output names,
+/// so default solidity selection could not be used.
+const OUTPUT_SELECTION: [&str; 3] = ["abi", "evm.bytecode.object", "evm.deployedBytecode.object"];
+
+impl TryFrom<MultiPartFiles> for VyperInput {
+    type Error = anyhow::Error;
+
+    fn try_from(multi_part: MultiPartFiles) -> Result<Self, Self::Error> {
+        let evm_version = match multi_part.evm_version.as_str() {
+            "default" => None,
+            evm_version => {
+                let evm_version = EvmVersion::from_str(evm_version).map_err(anyhow::Error::msg)?;
+                Some(evm_version.to_string())
+            }
+        };
+        let sources = multi_part
+            .sources
+            .into_iter()
+            .map(|(name, content)| (name.to_string_lossy().to_string(), Source { content }))
+            .collect();
+        let output_selection = BTreeMap::from([(
+            "*".to_string(),
+            OUTPUT_SELECTION
+                .iter()
+                .map(|output| output.to_string())
+                .collect(),
+        )]);
+        Ok(VyperInput {
+            language: "Vyper".to_string(),
+            sources,
+            settings: Settings {
+                evm_version,
+                optimize: multi_part.optimize,
+                output_selection,
+            },
+        })
+    }
+}
+
+impl From<(VyperInput, Version, VerificationSuccess)> for VerificationResult {
+    fn from(
+        (input, compiler_version, verification_success): (VyperInput, Version, VerificationSuccess),
+    ) -> Self {
+        let sources: BTreeMap<_, _> = input
+            .sources
+            .into_iter()
+            .map(|(path, source)| (path, source.content))
+            .collect();
+        VerificationResult {
+            file_name: verification_success.file_path,
+            contract_name: verification_success.contract_name,
+            language: input.language,
+            // vyper modules are always contracts, the kind is reported for solidity only
+            contract_kind: None,
+            compiler_version: compiler_version.to_string(),
+            compiler_version_alias: None,
+            evm_version: input
+                .settings
+                .evm_version
+                .unwrap_or_else(|| "default".to_string()),
+            decoded_constructor_arguments: verification_success
+                .constructor_args_decoded
+                .map(|tokens| constructor_arguments(&verification_success.abi, &tokens)),
+            constructor_arguments: verification_success.constructor_args,
+            optimization: input.settings.optimize,
+            optimization_runs: None,
+            contract_libraries: BTreeMap::new(),
+            function_selectors: function_selectors(&verification_success.abi),
+            event_topics: event_topics(&verification_success.abi),
+            abi: serde_json::to_string(&verification_success.abi)
+                .expect("Is result of local compilation and, thus, should be always valid"),
+            sources_hash: sources_hash(&sources),
+            sources,
+            model_checker_stripped: false,
+            source_transforms: vec![],
+            immutables_masked: verification_success.immutables_masked,
+            match_strategy: Some(verification_success.match_strategy.to_string()),
+            match_source: LOCAL_MATCH.to_string(),
+            metadata_ipfs_hash: None,
+            // the format describes solidity settings only
+            verification_metadata: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::parse::{test_deserialize_ok, test_serialize_json_ok};
+    use serde_json::json;
+
+    fn multi_part(evm_version: &str, optimize: Option<bool>) -> MultiPartFiles {
+        MultiPartFiles {
+            sources: BTreeMap::from([("token.vy".into(), "# @version 0.3.7".into())]),
+            evm_version: evm_version.into(),
+            optimize,
+        }
+    }
+
+    #[test]
+    fn parse_multi_part() {
+        test_deserialize_ok(vec![(
+            r##"{
+                    "deployed_bytecode": "0x6001",
+                    "creation_bytecode": "0x6001",
+                    "compiler_version": "v0.3.7+commit.6020b8bb",
+                    "sources": {
+                        "token.vy": "# @version 0.3.7"
+                    },
+                    "evm_version": "london",
+                    "optimize": false
+                }"##,
+            VerificationRequest {
+                deployed_bytecode: "0x6001".into(),
+                creation_bytecode: "0x6001".into(),
+                compiler_version: "v0.3.7+commit.6020b8bb".into(),
+                match_strategies: None,
+                content: multi_part("london", Some(false)),
+            },
+        )]);
+    }
+
+    #[test]
+    fn multi_part_to_input() {
+        let output_selection = json!({
+            "*": ["abi", "evm.bytecode.object", "evm.deployedBytecode.object"]
+        });
+        test_serialize_json_ok(vec![
+            (
+                VyperInput::try_from(multi_part("london", Some(false))).unwrap(),
+                json!({
+                    "language": "Vyper",
+                    "sources": {"token.vy": {"content": "# @version 0.3.7"}},
+                    "settings": {
+                        "evmVersion": "london",
+                        "optimize": false,
+                        "outputSelection": output_selection,
+                    },
+                }),
+            ),
+            (
+                VyperInput::try_from(multi_part("default", None)).unwrap(),
+                json!({
+                    "language": "Vyper",
+                    "sources": {"token.vy": {"content": "# @version 0.3.7"}},
+                    "settings": {"outputSelection": output_selection},
+                }),
+            ),
+        ]);
+        assert!(VyperInput::try_from(multi_part("unknown", None)).is_err());
+    }
+}
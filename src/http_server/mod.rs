@@ -1,22 +1,39 @@
+mod cors;
 pub mod handlers;
 mod routers;
 
-pub use self::routers::{configure_router, AppRouter, Router};
+pub use self::routers::{configure_experimental_router, configure_router, AppRouter, Router};
 
-use crate::config::Config;
-use actix_web::{App, HttpServer};
-use std::sync::Arc;
+use crate::{config::Config, scheduler};
+use actix_web::{middleware::Condition, App, HttpServer};
+use std::{sync::Arc, time::Duration};
 
+/// Runs the server until SIGTERM or SIGINT is received. On the signal, the server stops accepting
+/// connections and waits for in-flight requests up to the shutdown timeout; background jobs are
+/// stopped afterwards.
 pub async fn run(config: Config) -> std::io::Result<()> {
     let socket_addr = config.server.addr;
+    let shutdown_timeout = config.server.shutdown_timeout;
+    let cors = config.server.cors.clone();
     log::info!("Verification server is starting at {}", socket_addr);
     let app_router = Arc::new(
         AppRouter::new(config)
             .await
             .expect("couldn't initialize the app"),
     );
-    HttpServer::new(move || App::new().configure(configure_router(&*app_router)))
-        .bind(socket_addr)?
-        .run()
-        .await
+    let result = HttpServer::new(move || {
+        App::new()
+            .wrap(Condition::new(
+                !cors.allowed_origins.is_empty(),
+                cors::Cors::new(&cors),
+            ))
+            .configure(configure_router(&*app_router))
+    })
+    .shutdown_timeout(shutdown_timeout)
+    .bind(socket_addr)?
+    .run()
+    .await;
+    log::info!("Verification server has stopped, stopping background jobs");
+    scheduler::stop_jobs(Duration::from_secs(shutdown_timeout)).await;
+    result
 }
@@ -0,0 +1,32 @@
+use super::Router;
+use crate::{
+    config::Config,
+    http_server::handlers::admin::{self, AdminToken},
+};
+use actix_web::web;
+
+pub struct AdminRouter {
+    token: web::Data<AdminToken>,
+    config: web::Data<Config>,
+}
+
+impl AdminRouter {
+    pub fn new(config: Config) -> anyhow::Result<Self> {
+        let token = config.admin.token.clone().ok_or_else(|| {
+            anyhow::anyhow!("admin token must be set if admin endpoints are enabled")
+        })?;
+        Ok(Self {
+            token: web::Data::new(AdminToken::new(token)),
+            config: web::Data::new(config),
+        })
+    }
+}
+
+impl Router for AdminRouter {
+    fn register_routes(&self, service_config: &mut web::ServiceConfig) {
+        service_config
+            .app_data(self.token.clone())
+            .app_data(self.config.clone())
+            .route("/config", web::get().to(admin::config));
+    }
+}
@@ -1,34 +1,204 @@
-use super::{configure_router, Router, SolidityRouter, SourcifyRouter};
-use crate::{config::Config, http_server::handlers::status};
-use actix_web::web;
+use super::{
+    configure_router, AdminRouter, MetricsRouter, Router, SolidityRouter, SourcifyRouter,
+    UploadsRouter, VyperRouter,
+};
+use crate::{
+    compiler::Language,
+    config::Config,
+    consts::EXPERIMENTAL_FEATURES,
+    http_server::handlers::{
+        schema,
+        status::{self, Readiness},
+    },
+};
+use actix_web::{error, error::JsonPayloadError, web, HttpRequest, HttpResponse};
 
 pub struct AppRouter {
+    admin: Option<AdminRouter>,
+    metrics: Option<MetricsRouter>,
     solidity: Option<SolidityRouter>,
     sourcify: Option<SourcifyRouter>,
+    uploads: Option<UploadsRouter>,
+    vyper: Option<VyperRouter>,
+    readiness: web::Data<Readiness>,
+    max_request_size: usize,
+}
+
+/// Limits json bodies to `max_request_size`, rejecting larger ones with `413`
+/// and a json body containing the limit, so that clients could detect it.
+fn json_config(max_request_size: usize) -> web::JsonConfig {
+    web::JsonConfig::default()
+        .limit(max_request_size)
+        .error_handler(|err: JsonPayloadError, _: &HttpRequest| match err {
+            JsonPayloadError::OverflowKnownLength { limit, .. }
+            | JsonPayloadError::Overflow { limit } => {
+                let response = HttpResponse::PayloadTooLarge().json(serde_json::json!({
+                    "message": format!("request body exceeds the limit of {} bytes", limit),
+                    "max_request_size": limit,
+                }));
+                error::InternalError::from_response(err, response).into()
+            }
+            err => err.into(),
+        })
 }
 
 impl AppRouter {
     pub async fn new(config: Config) -> anyhow::Result<Self> {
+        let max_request_size = config.server.max_request_size;
+        for feature in config.features.unknown(EXPERIMENTAL_FEATURES) {
+            log::warn!("unknown feature in config: {}", feature);
+        }
+        let admin = match config.admin.enabled {
+            false => None,
+            true => Some(AdminRouter::new(config.clone())?),
+        };
+        let metrics = config
+            .metrics
+            .enabled
+            .then(MetricsRouter::new)
+            .transpose()?;
+        let uploads = config
+            .uploads
+            .enabled
+            .then(|| UploadsRouter::new(config.uploads.clone()));
+        let admin_token = config
+            .admin
+            .enabled
+            .then(|| config.admin.token.clone())
+            .flatten();
+        // routers are boxed to keep the future small, as the futures of the fetchers are large
         let solidity = match config.solidity.enabled {
             false => None,
-            true => Some(SolidityRouter::new(config.solidity).await?),
+            true => Some(
+                Box::pin(SolidityRouter::new(
+                    config.solidity,
+                    config.dead_letter,
+                    config.events,
+                    config.sourcify.clone(),
+                    admin_token,
+                ))
+                .await?,
+            ),
         };
         let sourcify = config
             .sourcify
             .enabled
             .then(|| SourcifyRouter::new(config.sourcify));
-        Ok(Self { solidity, sourcify })
+        let vyper = match config.vyper.enabled {
+            false => None,
+            true => Some(Box::pin(VyperRouter::new(config.vyper)).await?),
+        };
+        let readiness = Readiness(
+            solidity
+                .iter()
+                .map(|router| (Language::Solidity, router.compilers()))
+                .chain(
+                    vyper
+                        .iter()
+                        .map(|router| (Language::Vyper, router.compilers())),
+                )
+                .collect(),
+        );
+        Ok(Self {
+            admin,
+            metrics,
+            solidity,
+            sourcify,
+            uploads,
+            vyper,
+            readiness: web::Data::new(readiness),
+            max_request_size,
+        })
     }
 }
 
 impl Router for AppRouter {
     fn register_routes(&self, service_config: &mut web::ServiceConfig) {
+        if let Some(solidity) = &self.solidity {
+            solidity.register_health_routes(service_config);
+        }
         service_config
+            .app_data(json_config(self.max_request_size))
+            .app_data(web::PayloadConfig::new(self.max_request_size))
             .route("/health", web::get().to(status::status))
+            .service(
+                web::resource("/ready")
+                    .app_data(self.readiness.clone())
+                    .route(web::get().to(status::ready)),
+            )
+            .route("/schema/{type}", web::get().to(schema::get))
+            .configure(configure_router(&self.metrics))
+            .service(web::scope("/admin").configure(configure_router(&self.admin)))
             .service(
                 web::scope("/api/v1")
+                    .configure(configure_router(&self.uploads))
                     .service(web::scope("/solidity").configure(configure_router(&self.solidity)))
-                    .service(web::scope("/sourcify").configure(configure_router(&self.sourcify))),
+                    .service(web::scope("/sourcify").configure(configure_router(&self.sourcify)))
+                    .service(web::scope("/vyper").configure(configure_router(&self.vyper))),
             );
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{FetcherConfig, LocalFetcherConfig};
+    use actix_web::{
+        http::StatusCode,
+        test::{self, TestRequest},
+        web::Json,
+        App,
+    };
+    use pretty_assertions::assert_eq;
+
+    #[actix_rt::test]
+    async fn large_json_bodies_are_rejected() {
+        let app = test::init_service(App::new().app_data(json_config(32)).route(
+            "/echo",
+            web::post().to(|body: Json<serde_json::Value>| async move { body }),
+        ))
+        .await;
+        let send = |body: serde_json::Value| {
+            TestRequest::post()
+                .uri("/echo")
+                .set_json(body)
+                .send_request(&app)
+        };
+
+        let response = send(serde_json::json!({"source": "contract A {}"})).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = send(serde_json::json!({"source": "contract A {} contract B {}"})).await;
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        let body: serde_json::Value = test::read_body_json(response).await;
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "message": "request body exceeds the limit of 32 bytes",
+                "max_request_size": 32,
+            })
+        );
+    }
+
+    #[actix_rt::test]
+    async fn disabled_routers_are_not_constructed() {
+        let mut config = Config::default();
+        config.metrics.enabled = false;
+        // the fetcher would fail to be constructed, as the directory does not exist
+        config.solidity.enabled = false;
+        config.solidity.fetcher = FetcherConfig::Local(LocalFetcherConfig {
+            dir: "/nonexistent/compilers".into(),
+        });
+        config.sourcify.enabled = false;
+        let router = AppRouter::new(config)
+            .await
+            .expect("disabled routers should not be constructed");
+        assert!(router.solidity.is_none() && router.sourcify.is_none());
+
+        let app = test::init_service(App::new().configure(configure_router(&router))).await;
+        for uri in ["/api/v1/solidity/versions", "/api/v1/sourcify/verify"] {
+            let response = TestRequest::get().uri(uri).send_request(&app).await;
+            assert_eq!(response.status(), StatusCode::NOT_FOUND, "{}", uri);
+        }
+    }
+}
@@ -0,0 +1,25 @@
+use super::Router;
+use crate::{http_server::handlers::metrics, metrics::Metrics};
+use actix_web::web;
+use prometheus::Registry;
+
+pub struct MetricsRouter {
+    registry: web::Data<Registry>,
+}
+
+impl MetricsRouter {
+    pub fn new() -> Result<Self, prometheus::Error> {
+        let metrics = Metrics::new()?;
+        Ok(Self {
+            registry: web::Data::new(metrics.registry().clone()),
+        })
+    }
+}
+
+impl Router for MetricsRouter {
+    fn register_routes(&self, service_config: &mut web::ServiceConfig) {
+        service_config
+            .app_data(self.registry.clone())
+            .route("/metrics", web::get().to(metrics::metrics));
+    }
+}
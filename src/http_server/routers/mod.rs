@@ -1,10 +1,18 @@
+mod admin;
 mod app;
+mod metrics;
 mod solidity;
 mod sourcify;
+mod uploads;
+mod vyper;
 
 pub use self::app::AppRouter;
 
-use self::{solidity::SolidityRouter, sourcify::SourcifyRouter};
+use self::{
+    admin::AdminRouter, metrics::MetricsRouter, solidity::SolidityRouter, sourcify::SourcifyRouter,
+    uploads::UploadsRouter, vyper::VyperRouter,
+};
+use crate::config::FeaturesConfiguration;
 
 pub trait Router {
     fn register_routes(&self, service_config: &mut actix_web::web::ServiceConfig);
@@ -23,3 +31,70 @@ pub fn configure_router(
 ) -> impl FnOnce(&mut actix_web::web::ServiceConfig) + '_ {
     |service_config| router.register_routes(service_config)
 }
+
+/// Registers routes of the `router` only if experimental `feature` is enabled.
+/// Otherwise, the routes are absent and requests to them result in 404.
+pub fn configure_experimental_router<'a>(
+    features: &'a FeaturesConfiguration,
+    feature: &'a str,
+    router: &'a impl Router,
+) -> impl FnOnce(&mut actix_web::web::ServiceConfig) + 'a {
+    move |service_config| {
+        if features.is_enabled(feature) {
+            router.register_routes(service_config)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{
+        http::StatusCode,
+        test::{self, TestRequest},
+        web, App, HttpResponse,
+    };
+    use std::collections::BTreeMap;
+
+    const FEATURE: &str = "experimental_endpoint";
+
+    struct ExperimentalRouter;
+
+    impl Router for ExperimentalRouter {
+        fn register_routes(&self, service_config: &mut web::ServiceConfig) {
+            service_config.route("/experimental", web::get().to(HttpResponse::Ok));
+        }
+    }
+
+    async fn experimental_endpoint_status(features: FeaturesConfiguration) -> StatusCode {
+        let app = test::init_service(App::new().configure(configure_experimental_router(
+            &features,
+            FEATURE,
+            &ExperimentalRouter,
+        )))
+        .await;
+        TestRequest::get()
+            .uri("/experimental")
+            .send_request(&app)
+            .await
+            .status()
+    }
+
+    #[actix_rt::test]
+    async fn experimental_routes_registered_only_if_enabled() {
+        let enabled = FeaturesConfiguration(BTreeMap::from([(FEATURE.into(), true)]));
+        assert_eq!(experimental_endpoint_status(enabled).await, StatusCode::OK);
+
+        let disabled = FeaturesConfiguration(BTreeMap::from([(FEATURE.into(), false)]));
+        assert_eq!(
+            experimental_endpoint_status(disabled).await,
+            StatusCode::NOT_FOUND
+        );
+
+        let missing = FeaturesConfiguration::default();
+        assert_eq!(
+            experimental_endpoint_status(missing).await,
+            StatusCode::NOT_FOUND
+        );
+    }
+}
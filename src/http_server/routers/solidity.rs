@@ -1,44 +1,223 @@
 use super::Router;
 use crate::{
-    compiler::{Compilers, ListFetcher},
-    config::SolidityConfiguration,
-    http_server::handlers::{multi_part, standard_json, version_list},
+    compiler::{
+        Compilers, Fetcher, GcsFetcher, GithubFetcher, HttpFetcher, Language, ListFetcher,
+        ListHistory, LocalFetcher, ScratchDir,
+    },
+    config::{
+        DeadLetterConfiguration, EventsConfiguration, FetcherConfig, SolidityConfiguration,
+        SourcifyConfiguration,
+    },
+    dead_letter::DeadLetterSink,
+    events::EventPublisher,
+    http_server::handlers::{
+        admin::AdminToken,
+        artifact,
+        jobs::{self, JobStore},
+        multi_part, multi_part_auto,
+        sourcify::{SourcifyApiClient, SourcifyFallback},
+        standard_json, status, version_list,
+    },
+    rpc::RpcClient,
+    scheduler::BackoffSchedule,
 };
 use actix_web::web;
-use std::{path::PathBuf, sync::Arc};
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+const RPC_TIMEOUT: Duration = Duration::from_secs(10);
 
 pub struct SolidityRouter {
     compilers: web::Data<Compilers>,
+    config: web::Data<SolidityConfiguration>,
+    dead_letter_sink: Option<web::Data<DeadLetterSink>>,
+    /// Allows admins to verify using local compilers, if set
+    admin_token: Option<web::Data<AdminToken>>,
+    rpc_client: Option<web::Data<RpcClient>>,
+    event_publisher: Option<web::Data<EventPublisher>>,
+    jobs: web::Data<JobStore>,
+    sourcify_fallback: Option<web::Data<SourcifyFallback>>,
 }
 
-impl SolidityRouter {
-    pub async fn new(config: SolidityConfiguration) -> anyhow::Result<Self> {
-        let dir: PathBuf = "compilers/".into();
-        let fetcher = Arc::new(
+async fn new_fetcher(
+    config: &SolidityConfiguration,
+    refresh_versions_schedule: Option<BackoffSchedule>,
+    dir: &Path,
+) -> anyhow::Result<Arc<dyn Fetcher>> {
+    let list_history = match config.list_history.enabled {
+        false => None,
+        true => Some(ListHistory::new(config.list_history.clone())?),
+    };
+    let dir = dir.to_path_buf();
+    let fetcher: Arc<dyn Fetcher> = match config.fetcher.clone() {
+        FetcherConfig::List(_) => Arc::new(
             ListFetcher::new(
-                config.compilers_list_url,
-                Some(config.refresh_versions_schedule),
-                dir.clone(),
+                config.compilers_list_url.clone(),
+                config.compilers_list_mirrors.clone(),
+                refresh_versions_schedule,
+                dir,
+                list_history,
+            )
+            .await?
+            .with_checksum(config.compilers_list_checksum),
+        ),
+        FetcherConfig::Http(http_config) => Arc::new(
+            HttpFetcher::new(
+                http_config,
+                refresh_versions_schedule,
+                dir,
+                Language::Solidity,
             )
             .await?,
-        );
-        let compilers = Compilers::new(fetcher);
-        compilers.load_from_dir(&dir).await;
+        ),
+        FetcherConfig::Gcs(gcs_config) => {
+            Arc::new(GcsFetcher::new(gcs_config, refresh_versions_schedule, dir).await?)
+        }
+        FetcherConfig::Local(local_config) => {
+            Arc::new(LocalFetcher::new(local_config, Language::Solidity)?)
+        }
+        FetcherConfig::Github(github_config) => {
+            Arc::new(GithubFetcher::new(github_config, refresh_versions_schedule, dir).await?)
+        }
+    };
+    Ok(fetcher)
+}
+
+impl SolidityRouter {
+    pub async fn new(
+        config: SolidityConfiguration,
+        dead_letter_config: DeadLetterConfiguration,
+        events_config: EventsConfiguration,
+        sourcify_config: SourcifyConfiguration,
+        admin_token: Option<String>,
+    ) -> anyhow::Result<Self> {
+        let dir: PathBuf = "compilers/".into();
+        let refresh_versions_schedule = Some(BackoffSchedule {
+            schedule: config.fetcher_refresh_schedule().clone(),
+            max_skipped_ticks: config.refresh_versions_max_skipped_ticks,
+            max_jitter: Duration::from_secs(config.refresh_versions_max_jitter),
+        });
+        // boxed to keep the router future small, as the futures of the fetchers are large
+        let fetcher = Box::pin(new_fetcher(&config, refresh_versions_schedule, &dir)).await?;
+        // compilers of the local fetcher are not downloaded, so they are never evicted
+        let max_cache_bytes = match config.fetcher {
+            FetcherConfig::Local(_) => None,
+            _ => config.max_cache_bytes,
+        };
+        let scratch_dir = ScratchDir::new(config.scratch_dir.clone())?;
+        let compilers = Compilers::new(fetcher, scratch_dir)
+            .with_cache_max_age(config.cache_max_age.map(Duration::from_secs))
+            .with_fetch_timeout(config.fetch_timeout.map(Duration::from_secs))
+            .with_not_found_ttl(Duration::from_secs(config.not_found_cache_ttl))
+            .with_max_cache_bytes(max_cache_bytes)
+            .with_index(&dir, Language::Solidity)
+            .with_binary_check(config.check_compiler_binaries);
+        compilers.load_from_dir(&dir, Language::Solidity).await;
+        let compilers = web::Data::new(compilers);
+        if config.prefetch_latest > 0 || !config.prefetch_versions.is_empty() {
+            let compilers = compilers.clone();
+            let count = config.prefetch_latest;
+            let versions = config.prefetch_versions.clone();
+            tokio::spawn(async move {
+                futures::join!(
+                    compilers.prefetch_latest(count),
+                    compilers.prefetch(&versions)
+                )
+            });
+        }
+        let dead_letter_sink = match dead_letter_config.enabled {
+            false => None,
+            true => Some(web::Data::new(DeadLetterSink::new(dead_letter_config)?)),
+        };
+        let rpc_client = match config.rpc_url.clone() {
+            None => None,
+            Some(url) => Some(web::Data::new(RpcClient::new(url, RPC_TIMEOUT)?)),
+        };
+        let event_publisher = events_config
+            .enabled
+            .then(|| web::Data::new(EventPublisher::new(events_config)));
+        let sourcify_fallback = config.sourcify_fallback.then(|| {
+            let client = SourcifyApiClient::new(
+                sourcify_config.api_url,
+                sourcify_config.request_timeout,
+                sourcify_config.verification_attempts,
+            );
+            web::Data::new(SourcifyFallback::new(client))
+        });
+        let jobs = Arc::new(JobStore::new(&config.jobs));
+        jobs.clone()
+            .spawn_sweep(Duration::from_secs(config.jobs.sweep_interval));
         Ok(Self {
-            compilers: web::Data::new(compilers),
+            compilers,
+            config: web::Data::new(config),
+            dead_letter_sink,
+            admin_token: admin_token.map(|token| web::Data::new(AdminToken::new(token))),
+            rpc_client,
+            event_publisher,
+            jobs: web::Data::from(jobs),
+            sourcify_fallback,
         })
     }
+
+    pub fn compilers(&self) -> web::Data<Compilers> {
+        self.compilers.clone()
+    }
+
+    /// Registers the health check of the compiler versions refresh.
+    /// Registered outside of the solidity scope, next to the other health checks.
+    pub fn register_health_routes(&self, service_config: &mut web::ServiceConfig) {
+        service_config.service(
+            web::resource("/health/refresh")
+                .app_data(self.compilers.clone())
+                .app_data(self.config.clone())
+                .route(web::get().to(status::refresh)),
+        );
+    }
 }
 
 impl Router for SolidityRouter {
     fn register_routes(&self, service_config: &mut web::ServiceConfig) {
+        if let Some(dead_letter_sink) = &self.dead_letter_sink {
+            service_config.app_data(dead_letter_sink.clone());
+        }
+        if let Some(admin_token) = &self.admin_token {
+            service_config.app_data(admin_token.clone());
+        }
+        if let Some(rpc_client) = &self.rpc_client {
+            service_config.app_data(rpc_client.clone());
+        }
+        if let Some(event_publisher) = &self.event_publisher {
+            service_config.app_data(event_publisher.clone());
+        }
+        if let Some(sourcify_fallback) = &self.sourcify_fallback {
+            service_config.app_data(sourcify_fallback.clone());
+        }
         service_config
             .app_data(self.compilers.clone())
+            .app_data(self.config.clone())
+            .app_data(self.jobs.clone())
             .service(
                 web::scope("/verify")
                     .route("/multiple-files", web::post().to(multi_part::verify))
-                    .route("/standard-json", web::post().to(standard_json::verify)),
+                    .route(
+                        "/multiple-files/auto",
+                        web::post().to(multi_part_auto::verify),
+                    )
+                    .route("/standard-json", web::post().to(standard_json::verify))
+                    .route(
+                        "/standard-json/async",
+                        web::post().to(standard_json::verify_async),
+                    )
+                    .route("/jobs/{job_id}", web::get().to(jobs::status))
+                    .route("/artifact", web::post().to(artifact::verify)),
             )
-            .route("/versions", web::get().to(version_list::get_version_list));
+            .route("/versions", web::get().to(version_list::get_version_list))
+            .route(
+                "/compiler/{version}/evm-versions",
+                web::get().to(version_list::get_evm_versions),
+            );
     }
 }
@@ -0,0 +1,32 @@
+use super::Router;
+use crate::{
+    config::UploadsConfiguration,
+    http_server::handlers::uploads::{self, UploadStore},
+};
+use actix_web::web;
+
+pub struct UploadsRouter {
+    store: web::Data<UploadStore>,
+}
+
+impl UploadsRouter {
+    pub fn new(config: UploadsConfiguration) -> Self {
+        Self {
+            store: web::Data::new(UploadStore::new(config)),
+        }
+    }
+}
+
+impl Router for UploadsRouter {
+    /// Besides the upload routes, makes the store available to
+    /// the verification routes registered in the same scope.
+    fn register_routes(&self, service_config: &mut web::ServiceConfig) {
+        service_config.app_data(self.store.clone()).service(
+            web::scope("/uploads")
+                .app_data(web::PayloadConfig::new(self.store.max_size()))
+                .route("", web::post().to(uploads::start))
+                .route("/{id}", web::patch().to(uploads::append))
+                .route("/{id}", web::get().to(uploads::status)),
+        );
+    }
+}
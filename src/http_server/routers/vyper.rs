@@ -0,0 +1,64 @@
+use super::Router;
+use crate::{
+    compiler::{Compilers, Fetcher, HttpFetcher, Language, LocalFetcher, ScratchDir},
+    config::{FetcherConfig, VyperConfiguration},
+    http_server::handlers::verification::{solidity::version_list, vyper::multi_part},
+    scheduler::BackoffSchedule,
+};
+use actix_web::web;
+use std::{path::PathBuf, sync::Arc, time::Duration};
+
+pub struct VyperRouter {
+    compilers: web::Data<Compilers>,
+    config: web::Data<VyperConfiguration>,
+}
+
+impl VyperRouter {
+    pub async fn new(config: VyperConfiguration) -> anyhow::Result<Self> {
+        let dir: PathBuf = "compilers/".into();
+        let refresh_versions_schedule = Some(BackoffSchedule {
+            schedule: config.fetcher_refresh_schedule().clone(),
+            max_skipped_ticks: config.refresh_versions_max_skipped_ticks,
+            max_jitter: Duration::from_secs(config.refresh_versions_max_jitter),
+        });
+        let fetcher: Arc<dyn Fetcher> = match config.fetcher.clone() {
+            None => anyhow::bail!("vyper fetcher must be configured if vyper is enabled"),
+            Some(FetcherConfig::Http(http_config)) => Arc::new(
+                HttpFetcher::new(
+                    http_config,
+                    refresh_versions_schedule,
+                    dir.clone(),
+                    Language::Vyper,
+                )
+                .await?,
+            ),
+            Some(FetcherConfig::Local(local_config)) => {
+                Arc::new(LocalFetcher::new(local_config, Language::Vyper)?)
+            }
+            Some(_) => anyhow::bail!("only http and local fetchers are supported for vyper"),
+        };
+        let scratch_dir = ScratchDir::new(config.scratch_dir.clone())?;
+        let compilers = Compilers::new(fetcher, scratch_dir).with_index(&dir, Language::Vyper);
+        compilers.load_from_dir(&dir, Language::Vyper).await;
+        Ok(Self {
+            compilers: web::Data::new(compilers),
+            config: web::Data::new(config),
+        })
+    }
+
+    pub fn compilers(&self) -> web::Data<Compilers> {
+        self.compilers.clone()
+    }
+}
+
+impl Router for VyperRouter {
+    fn register_routes(&self, service_config: &mut web::ServiceConfig) {
+        service_config
+            .app_data(self.compilers.clone())
+            .app_data(self.config.clone())
+            .service(
+                web::scope("/verify").route("/multiple-files", web::post().to(multi_part::verify)),
+            )
+            .route("/versions", web::get().to(version_list::get_version_list));
+    }
+}
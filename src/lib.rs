@@ -2,8 +2,13 @@ mod cli;
 mod compiler;
 mod config;
 mod consts;
+mod dead_letter;
+mod events;
 mod http_server;
+mod metrics;
+mod rpc;
 mod scheduler;
+mod schema;
 mod solidity;
 mod types;
 
@@ -13,7 +18,7 @@ mod tests;
 pub use self::{cli::Args, config::Config};
 pub use ethers_core::types::Bytes as DisplayBytes;
 pub use http_server::{
-    configure_router,
+    configure_experimental_router, configure_router,
     handlers::verification::{VerificationResponse, VerificationResult, VerificationStatus},
     run as run_http_server, AppRouter, Router,
 };
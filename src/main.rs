@@ -4,6 +4,15 @@ use verification::{run_http_server, Args, Config};
 async fn main() -> std::io::Result<()> {
     env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
     let args = Args::default();
-    let config = Config::from_file(args.config_path).expect("Failed to parse config");
+    let config = match Config::from_file(args.config_path).and_then(|config| {
+        config.validate()?;
+        Ok(config)
+    }) {
+        Ok(config) => config,
+        Err(err) => {
+            log::error!("invalid config: {}", err);
+            std::process::exit(1);
+        }
+    };
     run_http_server(config).await
 }
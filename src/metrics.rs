@@ -0,0 +1,194 @@
+use crate::{
+    compiler::{Language, Version},
+    VerificationStatus,
+};
+use futures::Future;
+use lazy_static::lazy_static;
+use prometheus::{
+    core::Collector, Histogram, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge,
+    IntGaugeVec, Opts, Registry,
+};
+use std::time::Instant;
+
+lazy_static! {
+    pub static ref COMPILER_LIST_SIZE: IntGaugeVec = IntGaugeVec::new(
+        Opts::new(
+            "compiler_list_size",
+            "number of compiler versions in the compiler list after the last refresh"
+        ),
+        &["language"]
+    )
+    .expect("metric options are valid");
+    pub static ref CACHED_COMPILERS: IntGaugeVec = IntGaugeVec::new(
+        Opts::new(
+            "cached_compilers",
+            "number of compiler versions in the download cache"
+        ),
+        &["language"]
+    )
+    .expect("metric options are valid");
+    pub static ref COMPILER_CACHE_REQUESTS: IntCounterVec = IntCounterVec::new(
+        Opts::new(
+            "compiler_cache_requests",
+            "number of compiler requests to the download cache by result (hit or miss)"
+        ),
+        &["result"]
+    )
+    .expect("metric options are valid");
+    pub static ref COMPILER_FETCH_TIME: HistogramVec = HistogramVec::new(
+        HistogramOpts::new(
+            "compiler_fetch_time_seconds",
+            "time spent getting compilers from the download cache, including downloads"
+        ),
+        &["language", "version"]
+    )
+    .expect("metric options are valid");
+    pub static ref COMPILE_TIME: HistogramVec = HistogramVec::new(
+        HistogramOpts::new("compile_time_seconds", "time spent running the compiler"),
+        &["language", "version"]
+    )
+    .expect("metric options are valid");
+    pub static ref VERIFICATIONS: IntCounterVec = IntCounterVec::new(
+        Opts::new(
+            "verifications",
+            "number of completed verifications by status (full_match, partial_match or failed)"
+        ),
+        &["language", "status"]
+    )
+    .expect("metric options are valid");
+    pub static ref COMPILER_HASH_SECONDS: Histogram = Histogram::with_opts(HistogramOpts::new(
+        "compiler_hash_seconds",
+        "time spent checking hashsums of compiler binaries"
+    ))
+    .expect("metric options are valid");
+    pub static ref COMPILER_HASHED_BYTES: IntCounter = IntCounter::new(
+        "compiler_hashed_bytes",
+        "total number of compiler binary bytes hashed"
+    )
+    .expect("metric options are valid");
+    pub static ref COMPILER_LIST_NOT_MODIFIED: IntCounter = IntCounter::new(
+        "compiler_list_not_modified",
+        "number of compiler list refreshes answered with 304 Not Modified"
+    )
+    .expect("metric options are valid");
+}
+
+/// Size of the compiler list of the `language`.
+pub fn compiler_list_size(language: Language) -> IntGauge {
+    COMPILER_LIST_SIZE.with_label_values(&[language.as_str()])
+}
+
+/// Number of cached compilers of the `language`.
+pub fn cached_compilers(language: Language) -> IntGauge {
+    CACHED_COMPILERS.with_label_values(&[language.as_str()])
+}
+
+/// Label of the compiler version: its major and minor version, e.g. `0.8`.
+fn version_label(version: &Version) -> String {
+    format!("{}.{}", version.version().major, version.version().minor)
+}
+
+/// Time of getting compilers of the `language` and `version` from the download cache.
+pub fn compiler_fetch_time(language: Language, version: &Version) -> Histogram {
+    COMPILER_FETCH_TIME.with_label_values(&[language.as_str(), &version_label(version)])
+}
+
+/// Time of compilations with compilers of the `language` and `version`.
+pub fn compile_time(language: Language, version: &Version) -> Histogram {
+    COMPILE_TIME.with_label_values(&[language.as_str(), &version_label(version)])
+}
+
+/// Counts the completed verification of a contract in the `language` with the `status`.
+pub fn count_verify_contract(language: Language, status: &VerificationStatus) {
+    VERIFICATIONS
+        .with_label_values(&[language.as_str(), status.as_str()])
+        .inc();
+}
+
+/// Awaits the future and observes its duration in the `histogram`, whether it fails or not.
+/// Nothing is observed if the future is dropped before completion.
+pub async fn timed<F: Future>(histogram: Histogram, future: F) -> F::Output {
+    // unlike `HistogramTimer`, an instant is not observed when dropped
+    let started_at = Instant::now();
+    let output = future.await;
+    histogram.observe(started_at.elapsed().as_secs_f64());
+    output
+}
+
+/// Service metrics exposed via the registry.
+pub struct Metrics {
+    registry: Registry,
+}
+
+impl Metrics {
+    pub fn new() -> Result<Self, prometheus::Error> {
+        Ok(Self {
+            registry: build_registry()?,
+        })
+    }
+
+    pub fn registry(&self) -> &Registry {
+        &self.registry
+    }
+}
+
+/// Creates the registry with all service metrics registered.
+pub fn build_registry() -> Result<Registry, prometheus::Error> {
+    let registry = Registry::new_custom(Some("verification".into()), None)?;
+    register(&registry, COMPILER_LIST_SIZE.clone())?;
+    register(&registry, CACHED_COMPILERS.clone())?;
+    register(&registry, COMPILER_CACHE_REQUESTS.clone())?;
+    register(&registry, COMPILER_FETCH_TIME.clone())?;
+    register(&registry, COMPILE_TIME.clone())?;
+    register(&registry, VERIFICATIONS.clone())?;
+    register(&registry, COMPILER_HASH_SECONDS.clone())?;
+    register(&registry, COMPILER_HASHED_BYTES.clone())?;
+    register(&registry, COMPILER_LIST_NOT_MODIFIED.clone())?;
+    Ok(registry)
+}
+
+/// Registers the collector in the registry.
+/// If the collector is already registered, the existing one is kept.
+fn register<C: Collector + 'static>(registry: &Registry, collector: C) -> prometheus::Result<()> {
+    match registry.register(Box::new(collector)) {
+        Ok(()) | Err(prometheus::Error::AlreadyReg) => Ok(()),
+        Err(err) => Err(err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn create_metrics_twice() {
+        let first = Metrics::new().expect("first metrics should be created");
+        let second = Metrics::new().expect("second metrics should be created");
+        COMPILER_LIST_SIZE.with_label_values(&["test"]).set(1);
+        for metrics in [first, second] {
+            let families = metrics.registry().gather();
+            assert!(families
+                .iter()
+                .any(|family| family.get_name() == "verification_compiler_list_size"));
+        }
+    }
+
+    #[test]
+    fn counts_verifications_by_status() {
+        let partial_matches = VERIFICATIONS.with_label_values(&["solidity", "partial_match"]);
+        let vyper_partial_matches = VERIFICATIONS.with_label_values(&["vyper", "partial_match"]);
+        let before = partial_matches.get();
+        let vyper_before = vyper_partial_matches.get();
+        count_verify_contract(Language::Solidity, &VerificationStatus::PartialMatch);
+        assert!(partial_matches.get() > before);
+        count_verify_contract(Language::Vyper, &VerificationStatus::PartialMatch);
+        assert!(vyper_partial_matches.get() > vyper_before);
+    }
+
+    #[test]
+    fn register_twice() {
+        let registry = build_registry().unwrap();
+        register(&registry, COMPILER_LIST_SIZE.clone())
+            .expect("duplicate registration should be tolerated");
+    }
+}
@@ -0,0 +1,103 @@
+use anyhow::Context;
+use ethers_core::types::Address;
+use primitive_types::H256;
+use serde::Deserialize;
+use serde_json::json;
+use std::time::Duration;
+use url::Url;
+
+/// Minimal JSON-RPC client of the chain the verified contracts are deployed at.
+pub struct RpcClient {
+    url: Url,
+    client: reqwest::Client,
+}
+
+#[derive(Deserialize)]
+struct RpcResponse<T> {
+    result: Option<T>,
+    error: Option<serde_json::Value>,
+}
+
+impl RpcClient {
+    pub fn new(url: Url, timeout: Duration) -> anyhow::Result<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(timeout)
+            .build()
+            .context("cannot build rpc client")?;
+        Ok(Self { url, client })
+    }
+
+    /// Returns the value of the storage `slot` of the `address` at the latest block.
+    pub async fn storage_at(&self, address: Address, slot: H256) -> anyhow::Result<H256> {
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "eth_getStorageAt",
+            "params": [address, slot, "latest"],
+        });
+        let response: RpcResponse<H256> = self
+            .client
+            .post(self.url.clone())
+            .json(&request)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .context("eth_getStorageAt request failed")?
+            .json()
+            .await
+            .context("cannot parse eth_getStorageAt response")?;
+        match (response.result, response.error) {
+            (Some(value), _) => Ok(value),
+            (None, Some(error)) => anyhow::bail!("eth_getStorageAt returned error: {}", error),
+            (None, None) => anyhow::bail!("eth_getStorageAt returned neither result nor error"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+    use wiremock::{
+        matchers::{body_partial_json, method},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    #[tokio::test]
+    async fn reads_storage() {
+        let address = Address::from_str("0xFBe36e5cAD207d5fDee40E6568bb276a351f6713").unwrap();
+        let value = H256::from_low_u64_be(0xcafe);
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(body_partial_json(json!({
+                "method": "eth_getStorageAt",
+                "params": [address, H256::zero(), "latest"],
+            })))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(json!({"jsonrpc": "2.0", "id": 1, "result": value})),
+            )
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(
+                json!({"jsonrpc": "2.0", "id": 1, "error": {"code": -32000, "message": "oops"}}),
+            ))
+            .mount(&mock_server)
+            .await;
+
+        let client = RpcClient::new(
+            Url::parse(&mock_server.uri()).unwrap(),
+            Duration::from_secs(1),
+        )
+        .unwrap();
+        assert_eq!(
+            client.storage_at(address, H256::zero()).await.unwrap(),
+            value
+        );
+        client
+            .storage_at(address, H256::from_low_u64_be(1))
+            .await
+            .expect_err("rpc error should be returned");
+    }
+}
@@ -1,30 +1,152 @@
 use cron::Schedule;
 use futures::Future;
+use lazy_static::lazy_static;
+use rand::Rng;
+use std::time::Duration;
+use tokio::{sync::watch, task::JoinHandle};
 
 use chrono::Utc;
 
-pub fn spawn_job<F, Fut>(
-    schedule: Schedule,
-    job_name: &'static str,
-    mut run: F,
-) -> tokio::task::JoinHandle<()>
+lazy_static! {
+    /// Set once the service stops; jobs are finished before their next tick.
+    static ref SHUTDOWN: watch::Sender<bool> = watch::channel(false).0;
+    /// Jobs spawned with [`spawn_job`] and [`spawn_periodic_job`], awaited by [`stop_jobs`].
+    static ref JOBS: parking_lot::Mutex<Vec<JoinHandle<()>>> = Default::default();
+}
+
+/// Schedule of a job, which skips some of the scheduled ticks after consecutive failures.
+#[derive(Clone, Debug)]
+pub struct BackoffSchedule {
+    pub schedule: Schedule,
+    /// Maximum number of ticks skipped in a row; `0` disables the backoff.
+    pub max_skipped_ticks: u32,
+    /// Every run is delayed from its tick by a random duration up to this one,
+    /// so that replicas sharing the schedule do not run at the same time.
+    pub max_jitter: Duration,
+}
+
+/// Failure state of a job spawned with [`spawn_job`].
+///
+/// After `n` consecutive failures the next `2^(n-1) - 1` ticks (up to the cap) are skipped,
+/// the normal cadence is resumed as soon as the job succeeds.
+#[derive(Debug)]
+pub struct Backoff {
+    max_skipped_ticks: u32,
+    consecutive_failures: u32,
+    ticks_to_skip: u32,
+}
+
+impl Backoff {
+    pub fn new(max_skipped_ticks: u32) -> Self {
+        Self {
+            max_skipped_ticks,
+            consecutive_failures: 0,
+            ticks_to_skip: 0,
+        }
+    }
+
+    pub fn consecutive_failures(&self) -> u32 {
+        self.consecutive_failures
+    }
+
+    /// Returns whether the job should run on the current tick.
+    /// Skipped ticks are consumed.
+    pub fn should_run(&mut self) -> bool {
+        if self.ticks_to_skip > 0 {
+            self.ticks_to_skip -= 1;
+            return false;
+        }
+        true
+    }
+
+    pub fn record_success(&mut self) {
+        self.consecutive_failures = 0;
+        self.ticks_to_skip = 0;
+    }
+
+    pub fn record_failure(&mut self) {
+        self.consecutive_failures = self.consecutive_failures.saturating_add(1);
+        let exponent = (self.consecutive_failures - 1).min(31);
+        self.ticks_to_skip = ((1u32 << exponent) - 1).min(self.max_skipped_ticks);
+    }
+}
+
+/// Runs the job on every tick of the schedule until [`stop_jobs`] is called.
+/// Ticks are skipped after consecutive failures of the job according to [`Backoff`].
+pub fn spawn_job<F, Fut>(schedule: BackoffSchedule, job_name: &'static str, mut run: F)
 where
     F: (FnMut() -> Fut) + Send + 'static,
-    Fut: Future + Send + 'static,
-    <Fut as futures::Future>::Output: Send,
+    Fut: Future<Output = anyhow::Result<()>> + Send + 'static,
 {
-    tokio::spawn(async move {
-        loop {
-            let sleep_duration = time_till_next_call(&schedule);
+    let mut backoff = Backoff::new(schedule.max_skipped_ticks);
+    let mut shutdown = SHUTDOWN.subscribe();
+    let handle = tokio::spawn(async move {
+        while !*shutdown.borrow() {
+            let sleep_duration =
+                time_till_next_call(&schedule.schedule) + jitter(schedule.max_jitter);
             log::debug!(
                 "scheduled next run of '{}' in {:?}",
                 job_name,
                 sleep_duration
             );
-            tokio::time::sleep(sleep_duration).await;
-            run().await;
+            tokio::select! {
+                _ = tokio::time::sleep(sleep_duration) => {}
+                _ = shutdown.changed() => break,
+            }
+            if !backoff.should_run() {
+                log::info!("skipping '{}' after repeated failures", job_name);
+                continue;
+            }
+            match run().await {
+                Ok(()) => backoff.record_success(),
+                Err(err) => {
+                    backoff.record_failure();
+                    log::error!(
+                        "error during '{}' ({} consecutive failures): {:#}",
+                        job_name,
+                        backoff.consecutive_failures(),
+                        err
+                    );
+                }
+            }
         }
-    })
+        log::info!("stopped '{}'", job_name);
+    });
+    JOBS.lock().push(handle);
+}
+
+/// Runs the job every `period` until [`stop_jobs`] is called.
+pub fn spawn_periodic_job<F>(period: Duration, job_name: &'static str, mut run: F)
+where
+    F: FnMut() + Send + 'static,
+{
+    let mut shutdown = SHUTDOWN.subscribe();
+    let handle = tokio::spawn(async move {
+        let mut interval = tokio::time::interval(period);
+        while !*shutdown.borrow() {
+            tokio::select! {
+                _ = interval.tick() => run(),
+                _ = shutdown.changed() => break,
+            }
+        }
+        log::info!("stopped '{}'", job_name);
+    });
+    JOBS.lock().push(handle);
+}
+
+/// Stops the jobs spawned with [`spawn_job`] and [`spawn_periodic_job`], waiting up to
+/// the `timeout` for the running ones to complete their current run.
+/// Jobs still running after the timeout are aborted.
+pub async fn stop_jobs(timeout: Duration) {
+    SHUTDOWN.send_replace(true);
+    let mut jobs: Vec<_> = std::mem::take(&mut *JOBS.lock());
+    if tokio::time::timeout(timeout, futures::future::join_all(jobs.iter_mut()))
+        .await
+        .is_err()
+    {
+        log::warn!("jobs have not stopped in {:?}, aborting", timeout);
+        jobs.iter().for_each(|job| job.abort());
+    }
 }
 
 fn time_till_next_call(schedule: &Schedule) -> std::time::Duration {
@@ -37,6 +159,23 @@ fn time_till_next_call(schedule: &Schedule) -> std::time::Duration {
         .map_or(default, |t| (t - now).to_std().unwrap_or(default))
 }
 
+/// Random duration up to `max_jitter`, chosen anew for every tick. Runs are delayed
+/// from the ticks of the schedule, so the jitter does not accumulate between the runs.
+fn jitter(max_jitter: Duration) -> Duration {
+    if max_jitter.is_zero() {
+        return Duration::ZERO;
+    }
+    rand::thread_rng().gen_range(Duration::ZERO..=max_jitter)
+}
+
+/// Returns the time between the next two ticks of the schedule,
+/// or `None` if the schedule does not tick twice anymore.
+pub fn interval(schedule: &Schedule) -> Option<std::time::Duration> {
+    let mut upcoming = schedule.upcoming(Utc);
+    let (first, second) = (upcoming.next()?, upcoming.next()?);
+    (second - first).to_std().ok()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -62,4 +201,66 @@ mod tests {
                 <= std::time::Duration::from_secs(60 * 60)
         );
     }
+
+    #[test]
+    fn schedule_interval() {
+        let every_15_seconds = Schedule::from_str("0/15 * * * * * *").unwrap();
+        assert_eq!(
+            interval(&every_15_seconds),
+            Some(std::time::Duration::from_secs(15))
+        );
+        let in_the_past = Schedule::from_str("0 0 0 1 1 * 2000").unwrap();
+        assert_eq!(interval(&in_the_past), None);
+    }
+
+    #[test]
+    fn jitter_is_bounded() {
+        assert_eq!(jitter(Duration::ZERO), Duration::ZERO);
+        let max_jitter = Duration::from_secs(5);
+        let jitters: Vec<_> = (0..100).map(|_| jitter(max_jitter)).collect();
+        assert!(jitters.iter().all(|jitter| *jitter <= max_jitter));
+        assert!(
+            jitters.iter().any(|jitter| *jitter != jitters[0]),
+            "jitter should be chosen for every tick"
+        );
+    }
+
+    fn run_ticks(backoff: &mut Backoff, ticks: usize) -> usize {
+        (0..ticks).filter(|_| backoff.should_run()).count()
+    }
+
+    #[test]
+    fn backoff_skips_ticks() {
+        let mut backoff = Backoff::new(4);
+        assert_eq!(run_ticks(&mut backoff, 3), 3);
+
+        // the first failure does not skip anything
+        backoff.record_failure();
+        assert!(backoff.should_run());
+        // 2 consecutive failures skip 1 tick
+        backoff.record_failure();
+        assert_eq!(run_ticks(&mut backoff, 2), 1);
+        // 3 consecutive failures skip 3 ticks
+        backoff.record_failure();
+        assert_eq!(run_ticks(&mut backoff, 4), 1);
+        // number of skipped ticks is capped
+        for _ in 0..10 {
+            backoff.record_failure();
+        }
+        assert_eq!(backoff.consecutive_failures(), 13);
+        assert_eq!(run_ticks(&mut backoff, 5), 1);
+
+        backoff.record_success();
+        assert_eq!(backoff.consecutive_failures(), 0);
+        assert_eq!(run_ticks(&mut backoff, 3), 3);
+    }
+
+    #[test]
+    fn disabled_backoff() {
+        let mut backoff = Backoff::new(0);
+        for _ in 0..5 {
+            backoff.record_failure();
+            assert!(backoff.should_run());
+        }
+    }
 }
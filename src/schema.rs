@@ -0,0 +1,256 @@
+//! JSON Schema of the request and response types served to integrators.
+//!
+//! Schemas are derived from the types with `schemars`, so they cannot drift from
+//! the types. Fields of foreign types are described with the definitions below,
+//! referenced by `#[schemars(with = "...")]` attributes.
+
+use schemars::{
+    gen::{SchemaGenerator, SchemaSettings},
+    schema::{InstanceType, ObjectValidation, Schema, SchemaObject, StringValidation},
+};
+use serde_json::Value;
+
+pub use schemars::JsonSchema;
+
+/// Returns the schema of `T` as a standalone document with all subschemas inlined.
+pub fn root_schema<T: JsonSchema>(title: &str) -> Value {
+    let generator = SchemaSettings::draft07()
+        .with(|settings| settings.inline_subschemas = true)
+        .into_generator();
+    let mut schema = generator.into_root_schema_for::<T>();
+    schema.schema.metadata().title = Some(title.to_string());
+    serde_json::to_value(schema).expect("schemas are always serializable")
+}
+
+fn string_pattern(pattern: &str) -> Schema {
+    SchemaObject {
+        instance_type: Some(InstanceType::String.into()),
+        string: Some(Box::new(StringValidation {
+            pattern: Some(pattern.to_string()),
+            ..Default::default()
+        })),
+        ..Default::default()
+    }
+    .into()
+}
+
+/// "0x"-prefixed hex string, e.g. a bytecode.
+pub struct BytesDef;
+
+impl JsonSchema for BytesDef {
+    fn schema_name() -> String {
+        "Bytes".into()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        string_pattern("^0x([0-9a-fA-F]{2})*$")
+    }
+}
+
+pub struct AddressDef;
+
+impl JsonSchema for AddressDef {
+    fn schema_name() -> String {
+        "Address".into()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        string_pattern("^0x[0-9a-fA-F]{40}$")
+    }
+}
+
+pub struct H256Def;
+
+impl JsonSchema for H256Def {
+    fn schema_name() -> String {
+        "H256".into()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        string_pattern("^0x[0-9a-fA-F]{64}$")
+    }
+}
+
+/// Solidity standard json input; only the top-level fields are described.
+pub struct CompilerInputDef;
+
+impl JsonSchema for CompilerInputDef {
+    fn schema_name() -> String {
+        "CompilerInput".into()
+    }
+
+    fn json_schema(gen: &mut SchemaGenerator) -> Schema {
+        let object = || SchemaObject {
+            instance_type: Some(InstanceType::Object.into()),
+            ..Default::default()
+        };
+        let mut schema = object();
+        schema.metadata().description = Some(
+            "https://docs.soliditylang.org/en/latest/using-the-compiler.html#input-description"
+                .into(),
+        );
+        schema.object = Some(Box::new(ObjectValidation {
+            properties: [
+                ("language".to_string(), gen.subschema_for::<String>()),
+                ("sources".to_string(), object().into()),
+                ("settings".to_string(), object().into()),
+            ]
+            .into_iter()
+            .collect(),
+            required: ["language".to_string(), "sources".to_string()]
+                .into_iter()
+                .collect(),
+            ..Default::default()
+        }));
+        schema.into()
+    }
+}
+
+/// Checks that the `value` conforms to the subset of JSON Schema produced by [`root_schema`].
+/// Used by tests to ensure that the schemas describe the actual values.
+#[cfg(test)]
+pub(crate) fn validate(value: &Value, schema: &Value) -> Result<(), String> {
+    if let Some(variants) = schema["allOf"].as_array() {
+        for variant in variants {
+            validate(value, variant)?;
+        }
+    }
+    for combinator in ["anyOf", "oneOf"] {
+        if let Some(variants) = schema[combinator].as_array() {
+            if !variants
+                .iter()
+                .any(|variant| validate(value, variant).is_ok())
+            {
+                return Err(format!("{} matches none of {:?}", value, variants));
+            }
+        }
+    }
+    if let Some(values) = schema["enum"].as_array() {
+        if !values.contains(value) {
+            return Err(format!("{} is not one of {:?}", value, values));
+        }
+    }
+    let matches_type = |instance_type: &str| match instance_type {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "boolean" => value.is_boolean(),
+        "integer" => value.is_u64() || value.is_i64(),
+        "number" => value.is_number(),
+        "null" => value.is_null(),
+        _ => true,
+    };
+    let matches_type = match &schema["type"] {
+        Value::String(instance_type) => matches_type(instance_type),
+        Value::Array(instance_types) => instance_types
+            .iter()
+            .any(|instance_type| matches_type(instance_type.as_str().unwrap_or_default())),
+        _ => true,
+    };
+    if !matches_type {
+        return Err(format!("{} is not of type {}", value, schema["type"]));
+    }
+    if let Value::Object(object) = value {
+        for name in schema["required"].as_array().into_iter().flatten() {
+            let name = name.as_str().unwrap_or_default();
+            if !object.contains_key(name) {
+                return Err(format!("required field {} is missing", name));
+            }
+        }
+        for (name, field) in object {
+            let field_schema = match (&schema["properties"], &schema["additionalProperties"]) {
+                (Value::Object(properties), _) => properties
+                    .get(name)
+                    .ok_or_else(|| format!("field {} is not described", name))?,
+                (_, Value::Null) => continue,
+                (_, additional) => additional,
+            };
+            validate(field, field_schema).map_err(|err| format!("{}: {}", name, err))?;
+        }
+    }
+    if let (Value::Array(items), Some(item_schema)) = (value, schema.get("items")) {
+        for item in items {
+            validate(item, item_schema)?;
+        }
+    }
+    Ok(())
+}
+
+/// Names of the properties described by the object `schema`.
+#[cfg(test)]
+pub(crate) fn property_names(schema: &Value) -> std::collections::BTreeSet<String> {
+    schema["properties"]
+        .as_object()
+        .map(|properties| properties.keys().cloned().collect())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use serde::Serialize;
+    use serde_json::json;
+
+    #[derive(Serialize, JsonSchema)]
+    struct Inner {
+        count: usize,
+        /// Name of the inner
+        name: Option<String>,
+    }
+
+    #[derive(Serialize, JsonSchema)]
+    struct Outer {
+        enabled: bool,
+        #[schemars(with = "Option<AddressDef>")]
+        owner: Option<ethers_core::types::Address>,
+        #[serde(flatten)]
+        inner: Inner,
+    }
+
+    #[test]
+    fn flattened_object() {
+        let schema = root_schema::<Outer>("Outer");
+        assert_eq!(schema["$schema"], "http://json-schema.org/draft-07/schema#");
+        assert_eq!(schema["title"], "Outer");
+        assert_eq!(
+            property_names(&schema),
+            ["count", "enabled", "name", "owner"]
+                .into_iter()
+                .map(String::from)
+                .collect()
+        );
+        assert_eq!(schema["required"], json!(["count", "enabled"]));
+        assert_eq!(
+            schema["properties"]["name"]["description"],
+            "Name of the inner"
+        );
+    }
+
+    #[test]
+    fn validates_values() {
+        let schema = root_schema::<Outer>("Outer");
+        assert_eq!(
+            validate(&json!({"enabled": true, "count": 1}), &schema),
+            Ok(())
+        );
+        assert_eq!(
+            validate(
+                &json!({"enabled": true, "count": 1, "name": null, "owner": null}),
+                &schema
+            ),
+            Ok(())
+        );
+        assert_eq!(
+            validate(
+                &json!({"enabled": true, "count": 1, "owner": "0x000000000000000000000000000000000000cafe"}),
+                &schema
+            ),
+            Ok(())
+        );
+        assert!(validate(&json!({"enabled": true}), &schema).is_err());
+        assert!(validate(&json!({"enabled": true, "count": "1"}), &schema).is_err());
+        assert!(validate(&json!({"enabled": true, "count": 1, "owner": 1}), &schema).is_err());
+        assert!(validate(&json!({"enabled": true, "count": 1, "unknown": 1}), &schema).is_err());
+    }
+}
@@ -0,0 +1,120 @@
+use ethers_solc::artifacts::{ast::NodeType, SourceFile};
+use std::{collections::BTreeMap, fmt::Display};
+
+/// Kind of the contract as declared in the source code.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum ContractKind {
+    Contract,
+    Library,
+    Interface,
+    Abstract,
+}
+
+impl Display for ContractKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let kind = match self {
+            ContractKind::Contract => "contract",
+            ContractKind::Library => "library",
+            ContractKind::Interface => "interface",
+            ContractKind::Abstract => "abstract",
+        };
+        f.write_str(kind)
+    }
+}
+
+/// Extracts the kind of the contract from the ast of the compiled sources.
+///
+/// Returns `None` if the ast was not requested in the output selection
+/// or the contract definition could not be found in it.
+pub(crate) fn contract_kind(
+    sources: &BTreeMap<String, SourceFile>,
+    file_path: &str,
+    contract_name: &str,
+) -> Option<ContractKind> {
+    let ast = sources.get(file_path)?.ast.as_ref()?;
+    let definition = ast.nodes.iter().find(|node| {
+        node.node_type == NodeType::ContractDefinition
+            && node.other.get("name").and_then(|name| name.as_str()) == Some(contract_name)
+    })?;
+    let is_abstract = definition
+        .other
+        .get("abstract")
+        .and_then(|is_abstract| is_abstract.as_bool())
+        .unwrap_or_default();
+    match definition.other.get("contractKind")?.as_str()? {
+        "contract" if is_abstract => Some(ContractKind::Abstract),
+        "contract" => Some(ContractKind::Contract),
+        "library" => Some(ContractKind::Library),
+        "interface" => Some(ContractKind::Interface),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    fn contract_definition(name: &str, kind: &str, is_abstract: bool) -> serde_json::Value {
+        json!({
+            "id": 1,
+            "nodeType": "ContractDefinition",
+            "src": "0:10:0",
+            "name": name,
+            "contractKind": kind,
+            "abstract": is_abstract,
+            "nodes": [],
+        })
+    }
+
+    fn sources() -> BTreeMap<String, SourceFile> {
+        let ast = json!({
+            "absolutePath": "source.sol",
+            "id": 0,
+            "nodeType": "SourceUnit",
+            "src": "0:100:0",
+            "nodes": [
+                {"id": 2, "nodeType": "PragmaDirective", "src": "0:10:0", "literals": ["solidity"]},
+                contract_definition("Lib", "library", false),
+                contract_definition("Main", "contract", false),
+                contract_definition("Base", "contract", true),
+                contract_definition("IMain", "interface", false),
+            ],
+        });
+        BTreeMap::from([(
+            "source.sol".into(),
+            serde_json::from_value(json!({"id": 0, "ast": ast})).unwrap(),
+        )])
+    }
+
+    #[test]
+    fn extract_contract_kind() {
+        let sources = sources();
+        for (name, expected) in [
+            ("Lib", Some(ContractKind::Library)),
+            ("Main", Some(ContractKind::Contract)),
+            ("Base", Some(ContractKind::Abstract)),
+            ("IMain", Some(ContractKind::Interface)),
+            ("Unknown", None),
+        ] {
+            assert_eq!(
+                contract_kind(&sources, "source.sol", name),
+                expected,
+                "{}",
+                name
+            );
+        }
+        assert_eq!(contract_kind(&sources, "unknown.sol", "Lib"), None);
+        assert_eq!(ContractKind::Library.to_string(), "library");
+    }
+
+    #[test]
+    fn missing_ast() {
+        let sources = BTreeMap::from([(
+            "source.sol".into(),
+            serde_json::from_value(json!({"id": 0})).unwrap(),
+        )]);
+        assert_eq!(contract_kind(&sources, "source.sol", "Lib"), None);
+    }
+}
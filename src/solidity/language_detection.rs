@@ -0,0 +1,118 @@
+//! Lightweight heuristic detecting sources, which are obviously not written in Solidity.
+//!
+//! Vyper sources posted to the Solidity endpoints otherwise fail deep in compilation
+//! with confusing parser errors. The check is advisory and could be skipped in the request.
+
+use ethers_solc::artifacts::Sources;
+
+/// Lines Solidity sources (almost) always contain.
+const SOLIDITY_MARKERS: [&str; 5] = [
+    "pragma solidity",
+    "contract ",
+    "library ",
+    "abstract contract ",
+    "interface ",
+];
+
+/// Version pragmas and function decorators specific to Vyper.
+const VYPER_MARKERS: [&str; 8] = [
+    "# @version",
+    "# pragma version",
+    "#pragma version",
+    "@external",
+    "@internal",
+    "@deploy",
+    "@view",
+    "@pure",
+];
+
+fn has_line_starting_with(content: &str, markers: &[&str]) -> bool {
+    content.lines().any(|line| {
+        let line = line.trim_start();
+        markers.iter().any(|marker| line.starts_with(marker))
+    })
+}
+
+/// Vyper declares interfaces with the same keyword, but the declaration ends with a colon.
+fn has_solidity_line(content: &str) -> bool {
+    content.lines().any(|line| {
+        let line = line.trim();
+        !line.ends_with(':')
+            && SOLIDITY_MARKERS
+                .iter()
+                .any(|marker| line.starts_with(marker))
+    })
+}
+
+/// Returns whether the sources look like Vyper: none of them contains Solidity
+/// declarations, and at least one has a `.vy` extension or Vyper-specific lines.
+pub(crate) fn looks_like_vyper(sources: &Sources) -> bool {
+    let has_solidity = sources
+        .values()
+        .any(|source| has_solidity_line(&source.content));
+    let has_vyper = sources.iter().any(|(path, source)| {
+        path.extension() == Some("vy".as_ref())
+            || has_line_starting_with(&source.content, &VYPER_MARKERS)
+    });
+    !has_solidity && has_vyper
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers_solc::artifacts::Source;
+
+    fn sources(files: &[(&str, &str)]) -> Sources {
+        files
+            .iter()
+            .map(|(path, content)| {
+                (
+                    path.into(),
+                    Source {
+                        content: content.to_string(),
+                    },
+                )
+            })
+            .collect()
+    }
+
+    const VYPER: &str = "# @version 0.3.7\n\nowner: public(address)\n\n@external\ndef __init__():\n    self.owner = msg.sender\n";
+
+    #[test]
+    fn detects_vyper() {
+        assert!(looks_like_vyper(&sources(&[("Owner.vy", VYPER)])));
+        assert!(looks_like_vyper(&sources(&[("Owner.sol", VYPER)])));
+        assert!(looks_like_vyper(&sources(&[("Owner.vy", "x: uint256")])));
+        let with_interface = format!(
+            "interface IOwner:\n    def owner() -> address: view\n\n{}",
+            VYPER
+        );
+        assert!(looks_like_vyper(&sources(&[(
+            "Owner.vy",
+            with_interface.as_str()
+        )])));
+    }
+
+    #[test]
+    fn accepts_solidity() {
+        let solidity = "pragma solidity ^0.8.0;\ncontract A {\n    // @external\n}";
+        assert!(!looks_like_vyper(&sources(&[("A.sol", solidity)])));
+        assert!(!looks_like_vyper(&sources(&[(
+            "A.sol",
+            "contract A { function f() external {} }"
+        )])));
+        // mixed sources are left for the compiler to decide
+        assert!(!looks_like_vyper(&sources(&[
+            ("A.sol", solidity),
+            ("Owner.vy", VYPER)
+        ])));
+        assert!(!looks_like_vyper(&sources(&[("A.sol", "")])));
+        // sources declaring interfaces only
+        let interface =
+            "interface IOwner {\n    function owner() external view returns (address);\n}";
+        assert!(!looks_like_vyper(&sources(&[
+            ("IOwner.sol", interface),
+            ("Owner.vy", VYPER)
+        ])));
+    }
+}
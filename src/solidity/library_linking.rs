@@ -0,0 +1,135 @@
+use ethers_core::types::Address;
+use ethers_solc::{
+    artifacts::{Bytecode, Libraries},
+    CompilerOutput,
+};
+use std::{path::Path, str::FromStr};
+
+/// Substitutes the placeholders of the `libraries` left in the bytecodes of the `output`.
+///
+/// The compiler links only the libraries specified for the file the library is defined in,
+/// so the placeholders of libraries specified for other files remain. Such placeholders
+/// are linked by the library name, if all addresses specified for the name are the same.
+pub(crate) fn link_libraries(output: &mut CompilerOutput, libraries: &Libraries) {
+    let contracts = output
+        .contracts
+        .values_mut()
+        .flat_map(|contracts| contracts.values_mut());
+    for evm in contracts.filter_map(|contract| contract.evm.as_mut()) {
+        let deployed_bytecode = evm
+            .deployed_bytecode
+            .as_mut()
+            .and_then(|deployed_bytecode| deployed_bytecode.bytecode.as_mut());
+        for bytecode in evm.bytecode.iter_mut().chain(deployed_bytecode) {
+            link(bytecode, libraries);
+        }
+    }
+}
+
+fn link(bytecode: &mut Bytecode, libraries: &Libraries) {
+    let references: Vec<(String, String)> = bytecode
+        .link_references
+        .iter()
+        .flat_map(|(file, libs)| libs.keys().map(|lib| (file.clone(), lib.clone())))
+        .collect();
+    for (file, library) in references {
+        if let Some(address) = library_address(libraries, &file, &library) {
+            bytecode.link(file, library, address);
+        }
+    }
+}
+
+fn library_address(libraries: &Libraries, file: &str, library: &str) -> Option<Address> {
+    let specified_for_file = libraries
+        .libs
+        .get(Path::new(file))
+        .and_then(|libs| libs.get(library));
+    let address = match specified_for_file {
+        Some(address) => address,
+        None => {
+            let mut addresses = libraries.libs.values().filter_map(|libs| libs.get(library));
+            let address = addresses.next()?;
+            if addresses.any(|other| !other.eq_ignore_ascii_case(address)) {
+                return None;
+            }
+            address
+        }
+    };
+    Address::from_str(address).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers_solc::{utils::library_hash_placeholder, Artifact};
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+    use std::collections::BTreeMap;
+
+    const ADDRESS: &str = "9bca1bf2810c9b68f25c82e8ebb9dc0a5301e310";
+
+    /// Output with the contract, which bytecodes reference the library `lib.sol:Math`.
+    fn output() -> CompilerOutput {
+        let unlinked = format!("6080__{}__6000", library_hash_placeholder("lib.sol:Math"));
+        let bytecode = json!({
+            "object": unlinked,
+            "linkReferences": {"lib.sol": {"Math": [{"start": 2, "length": 20}]}},
+        });
+        serde_json::from_value(json!({
+            "contracts": {"main.sol": {"Main": {
+                "abi": [],
+                "evm": {"bytecode": bytecode, "deployedBytecode": bytecode},
+            }}}
+        }))
+        .unwrap()
+    }
+
+    fn libraries(libs: &[(&str, &str)]) -> Libraries {
+        let libs = libs
+            .iter()
+            .map(|(file, address)| {
+                let libs = BTreeMap::from([("Math".to_string(), address.to_string())]);
+                (file.into(), libs)
+            })
+            .collect();
+        Libraries { libs }
+    }
+
+    fn linked(libraries: Libraries) -> Option<String> {
+        let mut output = output();
+        link_libraries(&mut output, &libraries);
+        let contract = &output.contracts["main.sol"]["Main"];
+        let creation = contract.get_bytecode_bytes().map(|bytes| bytes.to_string());
+        let deployed = contract
+            .get_deployed_bytecode_bytes()
+            .map(|bytes| bytes.to_string());
+        assert_eq!(creation, deployed);
+        creation
+    }
+
+    #[test]
+    fn links_placeholders() {
+        let expected = Some(format!("0x6080{}6000", ADDRESS));
+        let address = format!("0x{}", ADDRESS);
+        assert_eq!(linked(libraries(&[("lib.sol", &address)])), expected);
+        assert_eq!(
+            linked(libraries(&[
+                ("main.sol", &address),
+                ("other.sol", &address)
+            ])),
+            expected,
+            "should be linked by the library name"
+        );
+    }
+
+    #[test]
+    fn keeps_unknown_placeholders() {
+        assert_eq!(linked(libraries(&[])), None);
+        let another = "0x0000000000000000000000000000000000000001";
+        assert_eq!(
+            linked(libraries(&[("main.sol", ADDRESS), ("other.sol", another)])),
+            None,
+            "addresses specified for the library name are ambiguous"
+        );
+    }
+}
@@ -1,3 +1,19 @@
+mod contract_kind;
+mod language_detection;
+mod library_linking;
+mod optimizer_heuristic;
+mod proxy;
+mod settings_validation;
+mod source_transform;
 mod verifier;
 
-pub(crate) use verifier::{VerificationSuccess, Verifier};
+pub(crate) use language_detection::looks_like_vyper;
+pub(crate) use library_linking::link_libraries;
+pub(crate) use optimizer_heuristic::optimizer_likely_enabled;
+pub(crate) use proxy::{is_eip1967_proxy, EIP1967_IMPLEMENTATION_SLOT};
+pub(crate) use settings_validation::validate_settings;
+pub(crate) use source_transform::{apply_transforms, SourceTransform, SourceTransformKind};
+pub(crate) use verifier::{
+    BytecodeMatchPolicy, BytecodeMatches, MatchStrategy, VerificationFailure, VerificationSuccess,
+    Verifier,
+};
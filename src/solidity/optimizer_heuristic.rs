@@ -0,0 +1,95 @@
+//! Best-effort heuristic guessing whether the bytecode was compiled with the optimizer enabled.
+//!
+//! The legacy (non-optimized) code generator leaves a lot of redundant stack
+//! manipulations in the code, the most common of which is `SWAP1 POP`.
+//! The optimizer removes most of them, so their density among the opcodes
+//! is a good enough hint. The result is advisory only and may be wrong,
+//! e.g. for contracts compiled via IR or with an unusual number of optimizer runs.
+
+const SWAP1: u8 = 0x90;
+const POP: u8 = 0x50;
+const PUSH1: u8 = 0x60;
+const PUSH32: u8 = 0x7f;
+
+/// Bytecodes with fewer opcodes do not provide enough data for the guess.
+const MIN_OPCODES: usize = 64;
+/// Non-optimized bytecodes are expected to have at least one `SWAP1 POP`
+/// sequence per `SWAP1_POP_RATIO` opcodes.
+const SWAP1_POP_RATIO: usize = 100;
+
+/// Returns whether the optimizer was likely enabled when compiling the `bytecode`
+/// (without metadata hash), or `None` if the bytecode is too short to make a guess.
+pub(crate) fn optimizer_likely_enabled(bytecode: &[u8]) -> Option<bool> {
+    let mut opcodes = 0;
+    let mut swap1_pops = 0;
+    let mut previous = None;
+    let mut i = 0;
+    while i < bytecode.len() {
+        let opcode = bytecode[i];
+        if previous == Some(SWAP1) && opcode == POP {
+            swap1_pops += 1;
+        }
+        if (PUSH1..=PUSH32).contains(&opcode) {
+            i += (opcode - PUSH1 + 1) as usize;
+        }
+        previous = Some(opcode);
+        opcodes += 1;
+        i += 1;
+    }
+
+    (opcodes >= MIN_OPCODES).then_some(swap1_pops * SWAP1_POP_RATIO < opcodes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DisplayBytes;
+    use std::str::FromStr;
+
+    fn bytecode(contract_dir: &str) -> DisplayBytes {
+        let path = format!("tests/contracts/{}/deployed_bytecode", contract_dir);
+        let content = std::fs::read_to_string(path).expect("Error while reading bytecode");
+        DisplayBytes::from_str(content.trim()).expect("Invalid bytecode")
+    }
+
+    #[test]
+    fn optimized_bytecodes() {
+        for contract_dir in [
+            "library",
+            "with_immutable_assignment",
+            "issue_5127",
+            "home_bridge",
+        ] {
+            assert_eq!(
+                optimizer_likely_enabled(&bytecode(contract_dir).0),
+                Some(true),
+                "{}",
+                contract_dir
+            );
+        }
+    }
+
+    #[test]
+    fn non_optimized_bytecodes() {
+        for contract_dir in [
+            "simple_storage",
+            "issue_5431",
+            "solidity_0.5.9_smart_contract",
+        ] {
+            assert_eq!(
+                optimizer_likely_enabled(&bytecode(contract_dir).0),
+                Some(false),
+                "{}",
+                contract_dir
+            );
+        }
+    }
+
+    #[test]
+    fn short_bytecode() {
+        assert_eq!(
+            optimizer_likely_enabled(&[0x60, 0x80, 0x60, 0x40, 0x52]),
+            None
+        );
+    }
+}
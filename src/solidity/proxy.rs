@@ -0,0 +1,66 @@
+//! Detection of the proxy contracts, whose code is located at another address.
+//!
+//! EIP-1967 proxies store the address of the implementation in a well-known storage slot
+//! (https://eips.ethereum.org/EIPS/eip-1967), which the proxy bytecode loads on every call,
+//! thus, containing the slot as a `PUSH32` constant.
+
+use primitive_types::H256;
+
+const PUSH1: u8 = 0x60;
+const PUSH32: u8 = 0x7f;
+
+/// `bytes32(uint256(keccak256('eip1967.proxy.implementation')) - 1)`
+pub(crate) const EIP1967_IMPLEMENTATION_SLOT: H256 = H256([
+    0x36, 0x08, 0x94, 0xa1, 0x3b, 0xa1, 0xa3, 0x21, 0x06, 0x67, 0xc8, 0x28, 0x49, 0x2d, 0xb9, 0x8d,
+    0xca, 0x3e, 0x20, 0x76, 0xcc, 0x37, 0x35, 0xa9, 0x20, 0xa3, 0xca, 0x50, 0x5d, 0x38, 0x2b, 0xbc,
+]);
+
+/// Returns whether the `bytecode` pushes the EIP-1967 implementation slot on the stack.
+pub(crate) fn is_eip1967_proxy(bytecode: &[u8]) -> bool {
+    let mut i = 0;
+    while i < bytecode.len() {
+        let opcode = bytecode[i];
+        if opcode == PUSH32
+            && bytecode.get(i + 1..i + 33) == Some(EIP1967_IMPLEMENTATION_SLOT.as_bytes())
+        {
+            return true;
+        }
+        if (PUSH1..=PUSH32).contains(&opcode) {
+            i += (opcode - PUSH1 + 1) as usize;
+        }
+        i += 1;
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Runtime code of the minimal ERC-1967 proxy (used by e.g. solady `ERC1967Factory`)
+    const EIP1967_PROXY: &str = "363d3d373d3d363d7f360894a13ba1a3210667c828492db98dca3e2076cc3735a920a3ca505d382bbc545af43d6000803e6038573d6000fd5b3d6000f3";
+
+    #[test]
+    fn implementation_slot() {
+        let hash = ethers_core::utils::keccak256("eip1967.proxy.implementation");
+        let slot = primitive_types::U256::from_big_endian(&hash) - 1;
+        let mut bytes = [0u8; 32];
+        slot.to_big_endian(&mut bytes);
+        assert_eq!(H256(bytes), EIP1967_IMPLEMENTATION_SLOT);
+    }
+
+    #[test]
+    fn detects_eip1967_proxy() {
+        assert!(is_eip1967_proxy(&hex::decode(EIP1967_PROXY).unwrap()));
+
+        let simple_storage =
+            std::fs::read_to_string("tests/contracts/simple_storage/deployed_bytecode").unwrap();
+        let simple_storage = hex::decode(simple_storage.trim().trim_start_matches("0x")).unwrap();
+        assert!(!is_eip1967_proxy(&simple_storage));
+
+        // `PUSH32 <slot>` is a part of the preceding push data
+        let mut hidden = vec![PUSH1, PUSH32];
+        hidden.extend(EIP1967_IMPLEMENTATION_SLOT.as_bytes());
+        assert!(!is_eip1967_proxy(&hidden));
+    }
+}
@@ -0,0 +1,212 @@
+use crate::compiler::Version;
+use ethers_solc::{artifacts::Settings, EvmVersion};
+use semver::VersionReq;
+use thiserror::Error;
+
+/// Known combinations of compiler version and settings that the compiler
+/// is not able to process. Detecting them before compilation allows
+/// to return a precise error instead of a cryptic compiler failure.
+#[derive(Clone, Debug, PartialEq, Eq, Error)]
+pub(crate) enum IncompatibleSettings {
+    #[error("evm version {evm_version} is not supported by compiler {compiler_version}")]
+    EvmVersion {
+        evm_version: EvmVersion,
+        compiler_version: String,
+    },
+    #[error("`viaIR` setting is not supported by compiler {0} (available since v0.7.5)")]
+    ViaIr(String),
+    #[error(
+        "`metadata.bytecodeHash` setting is not supported by compiler {0} (available since v0.6.0)"
+    )]
+    BytecodeHash(String),
+    #[error("`optimizer.runs` is set to {0} while the optimizer is disabled")]
+    RunsWithoutOptimizer(usize),
+}
+
+type Rule = fn(&Settings, &Version) -> Result<(), IncompatibleSettings>;
+
+/// All incompatibility rules checked by [`validate_settings`].
+/// New rules should be added here.
+const RULES: &[Rule] = &[
+    evm_version_supported,
+    via_ir_supported,
+    bytecode_hash_supported,
+    runs_with_optimizer,
+];
+
+/// Checks that `settings` contain no combinations
+/// known to be incompatible with the `compiler_version`.
+pub(crate) fn validate_settings(
+    settings: &Settings,
+    compiler_version: &Version,
+) -> Result<(), IncompatibleSettings> {
+    RULES
+        .iter()
+        .try_for_each(|rule| rule(settings, compiler_version))
+}
+
+fn version_matches(requirement: &str, compiler_version: &Version) -> bool {
+    VersionReq::parse(requirement)
+        .expect("requirement is valid")
+        .matches(compiler_version.version())
+}
+
+fn evm_version_supported(
+    settings: &Settings,
+    compiler_version: &Version,
+) -> Result<(), IncompatibleSettings> {
+    match settings.evm_version {
+        Some(evm_version)
+            if evm_version.normalize_version(compiler_version.version()) != Some(evm_version) =>
+        {
+            Err(IncompatibleSettings::EvmVersion {
+                evm_version,
+                compiler_version: compiler_version.to_string(),
+            })
+        }
+        _ => Ok(()),
+    }
+}
+
+fn via_ir_supported(
+    settings: &Settings,
+    compiler_version: &Version,
+) -> Result<(), IncompatibleSettings> {
+    if settings.via_ir.unwrap_or_default() && version_matches("<0.7.5", compiler_version) {
+        return Err(IncompatibleSettings::ViaIr(compiler_version.to_string()));
+    }
+    Ok(())
+}
+
+fn bytecode_hash_supported(
+    settings: &Settings,
+    compiler_version: &Version,
+) -> Result<(), IncompatibleSettings> {
+    let bytecode_hash = settings
+        .metadata
+        .as_ref()
+        .and_then(|metadata| metadata.bytecode_hash.as_ref());
+    if bytecode_hash.is_some() && version_matches("<0.6.0", compiler_version) {
+        return Err(IncompatibleSettings::BytecodeHash(
+            compiler_version.to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Number of runs used by the compiler if none is specified.
+/// Default settings of the tools (and of `ethers_solc`) contain it
+/// even with the optimizer disabled, so it is not treated as a conflict.
+const DEFAULT_OPTIMIZER_RUNS: usize = 200;
+
+fn runs_with_optimizer(
+    settings: &Settings,
+    _compiler_version: &Version,
+) -> Result<(), IncompatibleSettings> {
+    match (settings.optimizer.enabled, settings.optimizer.runs) {
+        (Some(false), Some(runs)) if runs != DEFAULT_OPTIMIZER_RUNS => {
+            Err(IncompatibleSettings::RunsWithoutOptimizer(runs))
+        }
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers_solc::artifacts::{BytecodeHash, SettingsMetadata};
+    use std::str::FromStr;
+
+    fn version(version: &str) -> Version {
+        Version::from_str(version).expect("valid version")
+    }
+
+    fn settings(evm_version: Option<EvmVersion>) -> Settings {
+        Settings {
+            evm_version,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn compatible_settings() {
+        let compiler_version = version("v0.8.7+commit.e28d00a7");
+        let mut settings = settings(Some(EvmVersion::London));
+        settings.via_ir = Some(true);
+        settings.metadata = Some(SettingsMetadata::from(BytecodeHash::None));
+        assert_eq!(validate_settings(&settings, &compiler_version), Ok(()));
+
+        let compiler_version = version("v0.4.11+commit.68ef5810");
+        assert_eq!(
+            validate_settings(&self::settings(None), &compiler_version),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn evm_version_too_new() {
+        let compiler_version = version("v0.8.4+commit.c7e474f2");
+        assert_eq!(
+            validate_settings(&settings(Some(EvmVersion::London)), &compiler_version),
+            Err(IncompatibleSettings::EvmVersion {
+                evm_version: EvmVersion::London,
+                compiler_version: compiler_version.to_string(),
+            })
+        );
+
+        // evm version could not be specified before v0.4.21
+        let compiler_version = version("v0.4.11+commit.68ef5810");
+        assert!(matches!(
+            validate_settings(&settings(Some(EvmVersion::Homestead)), &compiler_version),
+            Err(IncompatibleSettings::EvmVersion { .. })
+        ));
+    }
+
+    #[test]
+    fn via_ir_on_old_compiler() {
+        let compiler_version = version("v0.7.4+commit.3f05b770");
+        let mut settings = settings(None);
+        settings.via_ir = Some(true);
+        assert_eq!(
+            validate_settings(&settings, &compiler_version),
+            Err(IncompatibleSettings::ViaIr(compiler_version.to_string()))
+        );
+
+        settings.via_ir = Some(false);
+        assert_eq!(validate_settings(&settings, &compiler_version), Ok(()));
+    }
+
+    #[test]
+    fn bytecode_hash_on_old_compiler() {
+        let compiler_version = version("v0.5.17+commit.d19bba13");
+        let mut settings = settings(None);
+        settings.metadata = Some(SettingsMetadata::from(BytecodeHash::Bzzr1));
+        assert_eq!(
+            validate_settings(&settings, &compiler_version),
+            Err(IncompatibleSettings::BytecodeHash(
+                compiler_version.to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn runs_with_disabled_optimizer() {
+        let compiler_version = version("v0.8.7+commit.e28d00a7");
+        let mut settings = settings(None);
+        settings.optimizer.enabled = Some(false);
+        settings.optimizer.runs = Some(1000);
+        assert_eq!(
+            validate_settings(&settings, &compiler_version),
+            Err(IncompatibleSettings::RunsWithoutOptimizer(1000))
+        );
+
+        settings.optimizer.enabled = Some(true);
+        assert_eq!(validate_settings(&settings, &compiler_version), Ok(()));
+        // the default number of runs is emitted by tools regardless of the optimizer
+        settings.optimizer.enabled = Some(false);
+        settings.optimizer.runs = Some(200);
+        assert_eq!(validate_settings(&settings, &compiler_version), Ok(()));
+        settings.optimizer.runs = None;
+        assert_eq!(validate_settings(&settings, &compiler_version), Ok(()));
+    }
+}
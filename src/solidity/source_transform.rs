@@ -0,0 +1,304 @@
+//! Transforms applied to the sources before compilation.
+//!
+//! Transforms change the sources the contract is compiled from, and, thus,
+//! the metadata hash, so the applied ones are reported in the verification result.
+
+use ethers_solc::artifacts::{Source, Sources};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::{Component, Path, PathBuf},
+};
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum TransformError {
+    #[error("cannot flatten sources: {path:?} imports {import:?}, which is not provided")]
+    MissingImport { path: PathBuf, import: String },
+    #[error("cannot flatten sources: {path:?} renames imported symbols")]
+    AliasedImport { path: PathBuf },
+}
+
+/// Transform of the sources map applied before compilation.
+pub trait SourceTransform: Send + Sync {
+    /// Name of the transform reported in the verification result.
+    fn name(&self) -> &'static str;
+
+    fn apply(&self, sources: Sources) -> Result<Sources, TransformError>;
+}
+
+/// Built-in transforms, which could be selected in the config.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SourceTransformKind {
+    Flatten,
+}
+
+impl SourceTransformKind {
+    pub fn transform(&self) -> Box<dyn SourceTransform> {
+        match self {
+            SourceTransformKind::Flatten => Box::new(Flattener),
+        }
+    }
+}
+
+/// Applies `transforms` in order, returning the transformed sources
+/// with the names of the applied transforms.
+pub(crate) fn apply_transforms(
+    transforms: &[Box<dyn SourceTransform>],
+    mut sources: Sources,
+) -> Result<(Sources, Vec<String>), TransformError> {
+    let mut applied = Vec::with_capacity(transforms.len());
+    for transform in transforms {
+        sources = transform.apply(sources)?;
+        applied.push(transform.name().to_string());
+    }
+    Ok((sources, applied))
+}
+
+/// Merges all sources into a single file, placing every file after the files it imports.
+///
+/// Import statements are removed, as well as all SPDX license identifiers except the first one.
+/// The resultant file is named after the only file not imported by others,
+/// or `flattened.sol` if there are several of them.
+pub struct Flattener;
+
+/// Name of the flattened file if there is no single root file
+const FLATTENED_FILE: &str = "flattened.sol";
+const LICENSE_PREFIX: &str = "// SPDX-License-Identifier:";
+
+struct ParsedSource {
+    imports: Vec<PathBuf>,
+    /// Content with import statements removed
+    body: Vec<String>,
+}
+
+impl Flattener {
+    fn parse(
+        path: &Path,
+        content: &str,
+        sources: &Sources,
+    ) -> Result<ParsedSource, TransformError> {
+        let mut imports = Vec::new();
+        let mut body = Vec::new();
+        let mut statement: Option<String> = None;
+        for line in content.lines() {
+            let trimmed = line.trim_start();
+            if statement.is_none() && !is_import(trimmed) {
+                body.push(line.to_string());
+                continue;
+            }
+            let current = statement.get_or_insert_with(String::new);
+            current.push_str(trimmed);
+            current.push(' ');
+            if trimmed.contains(';') {
+                let import = statement.take().expect("statement is set above");
+                imports.push(Self::resolve(path, &import, sources)?);
+            }
+        }
+        Ok(ParsedSource { imports, body })
+    }
+
+    fn resolve(path: &Path, statement: &str, sources: &Sources) -> Result<PathBuf, TransformError> {
+        if statement.contains(" as ") {
+            return Err(TransformError::AliasedImport {
+                path: path.to_path_buf(),
+            });
+        }
+        let import = statement
+            .split(['"', '\''])
+            .nth(1)
+            .unwrap_or_default()
+            .to_string();
+        let resolved = if import.starts_with("./") || import.starts_with("../") {
+            normalize(&path.parent().unwrap_or_else(|| Path::new("")).join(&import))
+        } else {
+            PathBuf::from(&import)
+        };
+        if !sources.contains_key(&resolved) {
+            return Err(TransformError::MissingImport {
+                path: path.to_path_buf(),
+                import,
+            });
+        }
+        Ok(resolved)
+    }
+
+    fn visit<'a>(
+        path: &'a Path,
+        parsed: &'a BTreeMap<PathBuf, ParsedSource>,
+        visited: &mut BTreeSet<&'a Path>,
+        order: &mut Vec<&'a Path>,
+    ) {
+        // circular imports are allowed; each file is placed only once
+        if !visited.insert(path) {
+            return;
+        }
+        for import in &parsed[path].imports {
+            Self::visit(import, parsed, visited, order);
+        }
+        order.push(path);
+    }
+}
+
+impl SourceTransform for Flattener {
+    fn name(&self) -> &'static str {
+        "flatten"
+    }
+
+    fn apply(&self, sources: Sources) -> Result<Sources, TransformError> {
+        let parsed = sources
+            .iter()
+            .map(|(path, source)| Ok((path.clone(), Self::parse(path, &source.content, &sources)?)))
+            .collect::<Result<BTreeMap<_, _>, _>>()?;
+        let imported: BTreeSet<_> = parsed.values().flat_map(|p| &p.imports).collect();
+        let roots: Vec<_> = parsed.keys().filter(|p| !imported.contains(p)).collect();
+
+        let mut visited = BTreeSet::new();
+        let mut order = Vec::new();
+        // files imported only circularly are not reachable from the roots
+        for path in roots.iter().copied().chain(parsed.keys()) {
+            Self::visit(path, &parsed, &mut visited, &mut order);
+        }
+
+        let mut license_seen = false;
+        let mut lines = Vec::new();
+        for path in order {
+            for line in &parsed[path].body {
+                if line.trim_start().starts_with(LICENSE_PREFIX) {
+                    if license_seen {
+                        continue;
+                    }
+                    license_seen = true;
+                }
+                lines.push(line.as_str());
+            }
+        }
+        let name = match roots.as_slice() {
+            [root] => (*root).clone(),
+            _ => PathBuf::from(FLATTENED_FILE),
+        };
+        let content = lines.join("\n");
+        Ok(BTreeMap::from([(name, Source { content })]))
+    }
+}
+
+fn is_import(line: &str) -> bool {
+    line.strip_prefix("import")
+        .is_some_and(|rest| rest.starts_with([' ', '{', '"', '\'', '*']))
+}
+
+/// Resolves `.` and `..` components without accessing the file system.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn sources(sources: &[(&str, &str)]) -> Sources {
+        sources
+            .iter()
+            .map(|(path, content)| {
+                (
+                    PathBuf::from(path),
+                    Source {
+                        content: content.to_string(),
+                    },
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn flatten_in_import_order() {
+        let input = sources(&[
+            (
+                "contracts/Main.sol",
+                "// SPDX-License-Identifier: MIT\npragma solidity ^0.8.0;\nimport \"./lib/Math.sol\";\nimport {\n    Ownable\n} from \"access/Ownable.sol\";\ncontract Main is Ownable {}",
+            ),
+            (
+                "contracts/lib/Math.sol",
+                "// SPDX-License-Identifier: MIT\npragma solidity ^0.8.0;\nimport '../../access/Ownable.sol';\nlibrary Math {}",
+            ),
+            (
+                "access/Ownable.sol",
+                "// SPDX-License-Identifier: MIT\npragma solidity ^0.8.0;\ncontract Ownable {}",
+            ),
+        ]);
+        let flattened = Flattener.apply(input).unwrap();
+        let expected = sources(&[(
+            "contracts/Main.sol",
+            "// SPDX-License-Identifier: MIT\npragma solidity ^0.8.0;\ncontract Ownable {}\npragma solidity ^0.8.0;\nlibrary Math {}\npragma solidity ^0.8.0;\ncontract Main is Ownable {}",
+        )]);
+        assert_eq!(flattened, expected);
+    }
+
+    #[test]
+    fn flatten_several_roots() {
+        let input = sources(&[("A.sol", "contract A {}"), ("B.sol", "contract B {}")]);
+        let flattened = Flattener.apply(input).unwrap();
+        assert_eq!(
+            flattened,
+            sources(&[(FLATTENED_FILE, "contract A {}\ncontract B {}")])
+        );
+    }
+
+    #[test]
+    fn flatten_circular_imports() {
+        let input = sources(&[
+            ("A.sol", "import \"B.sol\";\ncontract A {}"),
+            ("B.sol", "import \"A.sol\";\ncontract B {}"),
+        ]);
+        let flattened = Flattener.apply(input).unwrap();
+        assert_eq!(
+            flattened,
+            sources(&[(FLATTENED_FILE, "contract B {}\ncontract A {}")])
+        );
+    }
+
+    #[test]
+    fn unflattenable_sources() {
+        let missing = sources(&[("A.sol", "import \"./B.sol\";\ncontract A {}")]);
+        assert_eq!(
+            Flattener.apply(missing),
+            Err(TransformError::MissingImport {
+                path: "A.sol".into(),
+                import: "./B.sol".into()
+            })
+        );
+        let aliased = sources(&[
+            (
+                "A.sol",
+                "import {B as C} from \"B.sol\";\ncontract A is C {}",
+            ),
+            ("B.sol", "contract B {}"),
+        ]);
+        assert_eq!(
+            Flattener.apply(aliased),
+            Err(TransformError::AliasedImport {
+                path: "A.sol".into()
+            })
+        );
+    }
+
+    #[test]
+    fn no_transforms() {
+        let input = sources(&[("A.sol", "import \"B.sol\";"), ("B.sol", "")]);
+        let (output, applied) = apply_transforms(&[], input.clone()).unwrap();
+        assert_eq!(output, input);
+        assert!(applied.is_empty());
+    }
+}
@@ -1,13 +1,17 @@
 #![allow(dead_code, unused)]
 
-use crate::{types::Mismatch, DisplayBytes};
+use super::contract_kind::{contract_kind, ContractKind};
+use crate::{schema::JsonSchema, types::Mismatch, DisplayBytes};
 use bytes::{Buf, Bytes};
 use ethabi::{Constructor, Token};
 use ethers_solc::{artifacts::Contract, Artifact, CompilerOutput};
 use minicbor::{data::Type, Decode, Decoder};
+use serde::{Deserialize, Serialize};
 use std::{
+    borrow::Cow,
     error::Error,
-    fmt::{Debug, Formatter},
+    fmt::{Debug, Display, Formatter},
+    ops::Range,
     str::FromStr,
 };
 use thiserror::Error;
@@ -16,6 +20,8 @@ use thiserror::Error;
 /// with input data provided by the requester.
 #[derive(Clone, Debug, PartialEq, Error)]
 pub(crate) enum InitializationError {
+    #[error("either creation transaction input or deployed bytecode must be provided")]
+    MissingBytecodes,
     #[error("creation transaction input is not a valid hex string")]
     InvalidCreationTxInput(String),
     #[error("deployed bytecode is not a valid hex string: {0}")]
@@ -37,14 +43,118 @@ enum VerificationError {
     BytecodeMismatch(Mismatch<DisplayBytes>),
     #[error("extra data after metadata hash but before constructor args does not match compilation output: {0}")]
     ExtraDataMismatch(Mismatch<DisplayBytes>),
-    #[error("invalid constructor arguments: {0}")]
-    InvalidConstructorArguments(DisplayBytes),
+    #[error("the constructor takes arguments, but no data is appended to the creation bytecode")]
+    MissingConstructorArguments,
+    #[error("the constructor takes no arguments, but {} bytes are appended to the creation bytecode: {0}", .0.as_ref().len())]
+    UnexpectedConstructorArguments(DisplayBytes),
+    #[error("{} bytes appended to the creation bytecode cannot be decoded as constructor arguments ({1}): {0}", .0.as_ref().len())]
+    InvalidConstructorArguments(DisplayBytes, String),
     #[error("library missed")]
     MissedLibrary,
     #[error("internal error: {0}")]
     InternalError(String),
 }
 
+impl VerificationError {
+    fn is_constructor_arguments_error(&self) -> bool {
+        matches!(
+            self,
+            VerificationError::MissingConstructorArguments
+                | VerificationError::UnexpectedConstructorArguments(_)
+                | VerificationError::InvalidConstructorArguments(..)
+        )
+    }
+}
+
+/// Reason no contract has been verified by [`Verifier::verify`].
+#[derive(Clone, Debug, PartialEq, Eq, Error)]
+pub(crate) enum VerificationFailure {
+    #[error("No contract could be verified with provided data")]
+    NoMatchingContracts,
+    /// The bytecode of the contract matches, but the data appended
+    /// to the creation bytecode does not fit its constructor
+    #[error("Bytecode matches contract {contract_name}, but constructor arguments are invalid: {reason}")]
+    InvalidConstructorArguments {
+        contract_name: String,
+        reason: String,
+    },
+    /// The compilation output contains no contract selected by the requester
+    #[error("Contract {0} is not found in the compiled sources")]
+    ContractNotFound(String),
+}
+
+/// Strategy used to compare the compilation result with the data provided by the requester.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchStrategy {
+    /// The bytecodes without metadata hashes are equal and the compiler
+    /// versions encoded into the metadata hashes are the same
+    Exact,
+    /// The bytecodes without metadata hashes are equal;
+    /// the metadata hashes are ignored entirely
+    MetadataInsensitive,
+}
+
+impl Display for MatchStrategy {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let strategy = match self {
+            MatchStrategy::Exact => "exact",
+            MatchStrategy::MetadataInsensitive => "metadata_insensitive",
+        };
+        f.write_str(strategy)
+    }
+}
+
+/// Policy deciding which of the provided bytecodes must match for the contract to be verified.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BytecodeMatchPolicy {
+    /// Either the creation transaction input or the deployed bytecode matches
+    #[default]
+    Either,
+    /// Every provided bytecode matches; the ones not provided are not required
+    Both,
+}
+
+/// Outcome of the comparison of one of the bytecodes provided by the requester.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BytecodeMatch {
+    Matched,
+    Mismatched,
+    /// The bytecode is empty or absent in the request, so it has not been compared
+    NotProvided,
+}
+
+impl BytecodeMatch {
+    fn of<T, E>(comparison: &Option<Result<T, E>>) -> Self {
+        match comparison {
+            Some(Ok(_)) => BytecodeMatch::Matched,
+            Some(Err(_)) => BytecodeMatch::Mismatched,
+            None => BytecodeMatch::NotProvided,
+        }
+    }
+}
+
+/// Outcomes of the creation and deployed bytecodes compared independently.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
+pub struct BytecodeMatches {
+    /// Outcome of the creation transaction input
+    pub creation: BytecodeMatch,
+    /// Outcome of the deployed bytecode
+    pub deployed: BytecodeMatch,
+}
+
+impl BytecodeMatches {
+    fn satisfy(&self, policy: BytecodeMatchPolicy) -> bool {
+        let outcomes = [self.creation, self.deployed];
+        match policy {
+            BytecodeMatchPolicy::Either => outcomes.contains(&BytecodeMatch::Matched),
+            BytecodeMatchPolicy::Both => !outcomes.contains(&BytecodeMatch::Mismatched),
+        }
+    }
+}
+
 /// The structure returned as a result when verification successes.
 /// Contains data needed to be sent back as a verification response.
 #[derive(Clone, Debug, PartialEq)]
@@ -53,6 +163,18 @@ pub(crate) struct VerificationSuccess {
     pub contract_name: String,
     pub abi: ethabi::Contract,
     pub constructor_args: Option<DisplayBytes>,
+    /// `constructor_args` decoded with the constructor parameter types
+    pub constructor_args_decoded: Option<Vec<Token>>,
+    /// `None` if the ast was not included into the compiler output
+    pub contract_kind: Option<ContractKind>,
+    /// The first strategy the contract has been matched with
+    pub match_strategy: MatchStrategy,
+    pub compiled_creation: DisplayBytes,
+    pub compiled_deployed: DisplayBytes,
+    /// Outcomes of the provided bytecodes, which satisfy the requested policy
+    pub bytecode_matches: BytecodeMatches,
+    /// The values of `immutable` variables have been masked in the compared deployed bytecodes
+    pub immutables_masked: bool,
 }
 
 /// Parsed metadata hash
@@ -90,7 +212,7 @@ impl<'b, C> Decode<'b, C> for MetadataHash {
         for _ in 0..number_of_elements {
             // try to parse the key
             match d.str() {
-                Ok(s) if s == "solc" => {
+                Ok("solc") => {
                     if solc.is_some() {
                         // duplicate keys are not allowed in CBOR (RFC 8949)
                         return Err(Error::custom(ParseMetadataHashError::DuplicateKeys));
@@ -389,67 +511,262 @@ impl Bytecode<CreationTxInput> {
     }
 }
 
+/// Constructor arguments appended to the creation bytecode, both encoded and decoded.
+type ConstructorArgs = (Bytes, Vec<Token>);
+
+/// Creation transaction input provided by the requester.
+#[derive(Clone, Debug)]
+enum OnchainCreationTxInput {
+    /// Split by the metadata hash of the deployed bytecode provided alongside
+    Parsed(Bytecode<CreationTxInput>),
+    /// Split by the metadata hash of every compiled contract,
+    /// as there is no deployed bytecode to take the hash from
+    Unparsed(Bytes),
+}
+
 /// Verifier used in contract verification.
 ///
 /// Contains input data provided by the requester that will
 /// further be used in verification process.
 #[derive(Clone, Debug)]
 pub(crate) struct Verifier {
-    /// Bytecode used on the contract creation transaction
-    bc_creation_tx_input: Bytecode<CreationTxInput>,
-    /// Bytecode stored in the chain and being used by EVM
-    bc_deployed_bytecode: DeployedBytecode,
+    /// Bytecode used on the contract creation transaction; `None` if not provided
+    bc_creation_tx_input: Option<OnchainCreationTxInput>,
+    /// Bytecode stored in the chain and being used by EVM; `None` if not provided
+    bc_deployed_bytecode: Option<DeployedBytecode>,
+    /// If set, only contracts from the file are compared
+    contract_file: Option<String>,
+    /// If set, only contracts with the name are compared
+    contract_name: Option<String>,
+}
+
+/// Byte ranges of the deployed bytecode the values of `immutable` variables are written to
+/// on contract creation, as reported in `evm.deployedBytecode.immutableReferences`.
+fn immutable_references(contract: &Contract) -> Vec<Range<usize>> {
+    let deployed_bytecode = contract
+        .evm
+        .as_ref()
+        .and_then(|evm| evm.deployed_bytecode.as_ref());
+    deployed_bytecode
+        .map(|deployed_bytecode| {
+            deployed_bytecode
+                .immutable_references
+                .values()
+                .flatten()
+                .map(|offsets| {
+                    let start = offsets.start as usize;
+                    start..start + offsets.length as usize
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Zeroes the `ranges` of the bytecode, as the compiler leaves them in the compiled one.
+/// Ranges out of the bytecode bounds are left as is, so the bytecodes of different
+/// lengths still differ.
+fn mask(bytecode: &[u8], ranges: &[Range<usize>]) -> Vec<u8> {
+    let mut masked = bytecode.to_vec();
+    for range in ranges {
+        if let Some(bytes) = masked.get_mut(range.clone()) {
+            bytes.fill(0);
+        }
+    }
+    masked
+}
+
+/// Empty strings and bare "0x" prefixes are treated as not provided bytecodes.
+fn is_provided(bytecode: &str) -> bool {
+    !bytecode.strip_prefix("0x").unwrap_or(bytecode).is_empty()
 }
 
 impl Verifier {
     /// Instantiates a new verifier instance with input data provided by the requester.
+    /// Either `creation_tx_input` or `deployed_bytecode` may be empty, but not both.
     ///
     /// Returns [`InitializationError`] inside [`Err`] if either `deployed_bytecode` or `creation_tx_input` are invalid.
     pub fn new(
         creation_tx_input: &str,
         deployed_bytecode: &str,
     ) -> Result<Self, InitializationError> {
-        let deployed_bytecode = DeployedBytecode::from_str(deployed_bytecode)?;
-        let bytecode = Bytecode::from_str(creation_tx_input, &deployed_bytecode)?;
+        if !is_provided(creation_tx_input) && !is_provided(deployed_bytecode) {
+            return Err(InitializationError::MissingBytecodes);
+        }
+        let deployed_bytecode = is_provided(deployed_bytecode)
+            .then(|| DeployedBytecode::from_str(deployed_bytecode))
+            .transpose()?;
+        let bytecode = if !is_provided(creation_tx_input) {
+            None
+        } else if let Some(deployed_bytecode) = &deployed_bytecode {
+            let bytecode = Bytecode::from_str(creation_tx_input, deployed_bytecode)?;
+            Some(OnchainCreationTxInput::Parsed(bytecode))
+        } else {
+            let bytes = DisplayBytes::from_str(creation_tx_input).map_err(|_| {
+                InitializationError::InvalidCreationTxInput(creation_tx_input.to_string())
+            })?;
+            Some(OnchainCreationTxInput::Unparsed(bytes.0))
+        };
 
         Ok(Self {
             bc_deployed_bytecode: deployed_bytecode,
             bc_creation_tx_input: bytecode,
+            contract_file: None,
+            contract_name: None,
         })
     }
 
+    /// Restricts the compared contracts to the ones located in `contract_file`
+    /// and named `contract_name`, if either is specified.
+    pub fn with_contract(
+        mut self,
+        contract_file: Option<String>,
+        contract_name: Option<String>,
+    ) -> Self {
+        self.contract_file = contract_file;
+        self.contract_name = contract_name;
+        self
+    }
+
+    /// Checks whether the contract `name` located at `path` has been selected for comparison.
+    fn selects(&self, path: &str, name: &str) -> bool {
+        self.contract_file
+            .as_deref()
+            .map_or(true, |file| file == path)
+            && self
+                .contract_name
+                .as_deref()
+                .map_or(true, |selected| selected == name)
+    }
+
+    /// Describes the selected contract as `file:name`, omitting the parts not specified.
+    fn selected_contract(&self) -> Option<String> {
+        match (&self.contract_file, &self.contract_name) {
+            (None, None) => None,
+            (Some(file), None) => Some(file.clone()),
+            (None, Some(name)) => Some(name.clone()),
+            (Some(file), Some(name)) => Some(format!("{}:{}", file, name)),
+        }
+    }
+
+    /// Returns the deployed bytecode provided on initialization without metadata hash,
+    /// or empty bytes if it has not been provided.
+    pub fn deployed_bytecode(&self) -> bytes::Bytes {
+        self.bc_deployed_bytecode
+            .as_ref()
+            .map(DeployedBytecode::bytecode)
+            .unwrap_or_default()
+    }
+
+    /// Checks whether the metadata hash appended to the `compiled_deployed` bytecode
+    /// equals the on-chain one, i.e. the contract is matched fully rather than partially.
+    ///
+    /// Without the on-chain deployed bytecode the contract could be matched only by
+    /// the creation transaction input containing the compiled metadata hash, so it is full.
+    pub fn metadata_matches(&self, compiled_deployed: &DisplayBytes) -> bool {
+        let compiled = match DeployedBytecode::try_from(compiled_deployed.0.clone()) {
+            Ok(compiled) => compiled,
+            Err(_) => return false,
+        };
+        match &self.bc_deployed_bytecode {
+            Some(deployed_bytecode) => {
+                compiled.encoded_metadata_hash_with_length()
+                    == deployed_bytecode.encoded_metadata_hash_with_length()
+            }
+            None => true,
+        }
+    }
+
     /// Verifies input data provided on initialization by comparing it
     /// with compiler output received when compiling source data locally.
     ///
-    /// Iterates through all contracts received from local compilation and
-    /// returns [`VerificationSuccess`] with file path and contract name
-    /// of succeeded contract, if any. Otherwise, returns [`None`].
-    pub fn verify(&self, output: CompilerOutput) -> Option<VerificationSuccess> {
-        for (path, contracts) in output.contracts {
-            for (name, contract) in contracts {
-                // TODO: add logging in case if error is `VerificationError::InternalError`
-                if let Ok((abi, constructor_args)) = self.compare(&contract) {
-                    return Some(VerificationSuccess {
-                        file_path: path,
-                        contract_name: name,
+    /// Applies `match_strategies` in order, iterating through all contracts
+    /// received from local compilation (or only the selected ones, see [`Verifier::with_contract`])
+    /// for each of them, and returns
+    /// [`VerificationSuccess`] with file path and contract name of the first
+    /// contract, which bytecodes match as required by the `bytecode_match_policy`, if any.
+    /// Otherwise, returns [`VerificationFailure`], which explains the first contract
+    /// matched by the bytecode only, if any.
+    pub fn verify(
+        &self,
+        output: CompilerOutput,
+        match_strategies: &[MatchStrategy],
+        bytecode_match_policy: BytecodeMatchPolicy,
+    ) -> Result<VerificationSuccess, VerificationFailure> {
+        if let Some(selected) = self.selected_contract() {
+            let found = output
+                .contracts
+                .iter()
+                .any(|(path, contracts)| contracts.keys().any(|name| self.selects(path, name)));
+            if !found {
+                return Err(VerificationFailure::ContractNotFound(selected));
+            }
+        }
+        let mut failure = VerificationFailure::NoMatchingContracts;
+        for &match_strategy in match_strategies {
+            for (path, contracts) in &output.contracts {
+                for (name, contract) in contracts {
+                    if !self.selects(path, name) {
+                        continue;
+                    }
+                    // TODO: add logging in case if error is `VerificationError::InternalError`
+                    let compared = self.compare(contract, match_strategy, bytecode_match_policy);
+                    let (abi, constructor_args, bytecode_matches) = match compared {
+                        Ok(matched) => matched,
+                        Err(err) => {
+                            if err.is_constructor_arguments_error()
+                                && failure == VerificationFailure::NoMatchingContracts
+                            {
+                                failure = VerificationFailure::InvalidConstructorArguments {
+                                    contract_name: name.clone(),
+                                    reason: err.to_string(),
+                                };
+                            }
+                            continue;
+                        }
+                    };
+                    let bytes = |bytes: Option<std::borrow::Cow<'_, DisplayBytes>>| {
+                        bytes.map(|bytes| bytes.into_owned()).unwrap_or_default()
+                    };
+                    let (constructor_args, constructor_args_decoded) = match constructor_args {
+                        Some((encoded, decoded)) => (Some(encoded.into()), Some(decoded)),
+                        None => (None, None),
+                    };
+                    return Ok(VerificationSuccess {
+                        compiled_creation: bytes(contract.get_bytecode_bytes()),
+                        compiled_deployed: bytes(contract.get_deployed_bytecode_bytes()),
+                        file_path: path.clone(),
+                        contract_name: name.clone(),
                         abi,
-                        constructor_args: constructor_args.map(DisplayBytes::from),
+                        constructor_args,
+                        constructor_args_decoded,
+                        contract_kind: contract_kind(&output.sources, path, name),
+                        match_strategy,
+                        bytecode_matches,
+                        immutables_masked: self.bc_deployed_bytecode.is_some()
+                            && !immutable_references(contract).is_empty(),
                     });
                 }
             }
         }
 
-        None
+        Err(failure)
     }
 
     /// Compares the result of local contract compilation with data specified on initialization.
+    /// The creation and deployed bytecodes are compared independently, and the contract
+    /// is matched if their outcomes satisfy the `bytecode_match_policy`.
     ///
-    /// On success returns a tuple where first argument is a contract ABI, and the second
-    /// is constructor arguments passed on actual contract initialization.
+    /// On success returns a tuple where first argument is a contract ABI, the second
+    /// is constructor arguments passed on actual contract initialization, both encoded and decoded,
+    /// and the third is the outcomes of the bytecodes. Constructor arguments are extracted
+    /// only if the creation transaction input matches.
     fn compare(
         &self,
         contract: &Contract,
-    ) -> Result<(ethabi::Contract, Option<Bytes>), VerificationError> {
+        match_strategy: MatchStrategy,
+        bytecode_match_policy: BytecodeMatchPolicy,
+    ) -> Result<(ethabi::Contract, Option<ConstructorArgs>, BytecodeMatches), VerificationError>
+    {
         let deployed_bytecode = {
             let bytes = contract
                 .get_deployed_bytecode_bytes()
@@ -457,38 +774,115 @@ impl Verifier {
             DeployedBytecode::try_from(bytes.0.clone())
                 .map_err(|err| VerificationError::InvalidDeployedBytecode(err.to_string()))?
         };
+        let abi = contract
+            .get_abi()
+            .ok_or_else(|| VerificationError::InternalError("Missing abi".into()))?;
+
+        if match_strategy == MatchStrategy::Exact {
+            self.check_metadata_hash_solc_versions(&deployed_bytecode)?;
+        }
+
+        let creation = self.bc_creation_tx_input.as_ref().map(|creation_tx_input| {
+            self.compare_creation_tx_input(
+                creation_tx_input,
+                contract,
+                &deployed_bytecode,
+                abi.constructor(),
+            )
+        });
+        let deployed =
+            self.compare_deployed_bytecode(&deployed_bytecode, &immutable_references(contract));
+        let bytecode_matches = BytecodeMatches {
+            creation: BytecodeMatch::of(&creation),
+            deployed: BytecodeMatch::of(&deployed),
+        };
+        if !bytecode_matches.satisfy(bytecode_match_policy) {
+            // the creation error goes first, as it may explain invalid constructor arguments
+            let err = creation
+                .and_then(Result::err)
+                .or_else(|| deployed.and_then(Result::err));
+            return Err(err.expect("policy is unsatisfied only if some bytecode mismatches"));
+        }
+        let constructor_args = creation.and_then(Result::ok).flatten();
+
+        Ok((abi.into_owned(), constructor_args, bytecode_matches))
+    }
+
+    /// Compares the creation transaction input with the compiled creation bytecode
+    /// and extracts the constructor arguments appended to the former.
+    fn compare_creation_tx_input(
+        &self,
+        creation_tx_input: &OnchainCreationTxInput,
+        contract: &Contract,
+        deployed_bytecode: &DeployedBytecode,
+        abi_constructor: Option<&Constructor>,
+    ) -> Result<Option<ConstructorArgs>, VerificationError> {
         let bytecode = {
             let bytes = contract
                 .get_bytecode_bytes()
                 .ok_or_else(|| VerificationError::InternalError("Missing bytecode bytes".into()))?;
-            Bytecode::<CompilationResult>::try_from_bytes(bytes.0.clone(), &deployed_bytecode)
+            Bytecode::<CompilationResult>::try_from_bytes(bytes.0.clone(), deployed_bytecode)
                 .map_err(|err| {
                     VerificationError::InternalError(format!("Invalid bytecode bytes: {:?}", err))
                 })?
         };
-        let abi = contract
-            .get_abi()
-            .ok_or_else(|| VerificationError::InternalError("Missing abi".into()))?;
-
-        self.check_metadata_hash_solc_versions(&deployed_bytecode)?;
+        let creation_tx_input = match creation_tx_input {
+            OnchainCreationTxInput::Parsed(creation_tx_input) => Cow::Borrowed(creation_tx_input),
+            OnchainCreationTxInput::Unparsed(bytes) => Cow::Owned(
+                Bytecode::try_from_bytes(bytes.clone(), deployed_bytecode).map_err(|_| {
+                    VerificationError::BytecodeMismatch(Mismatch::new(
+                        bytecode.bytecode.clone().into(),
+                        bytes.clone().into(),
+                    ))
+                })?,
+            ),
+        };
 
-        self.bc_creation_tx_input
-            .verify_bytecode_with_extra_data(&bytecode)?;
+        creation_tx_input.verify_bytecode_with_extra_data(&bytecode)?;
 
-        let constructor_args = self.extract_constructor_args(abi.constructor(), &bytecode)?;
+        self.extract_constructor_args(&creation_tx_input, abi_constructor, &bytecode)
+    }
 
-        Ok((abi.into_owned(), constructor_args))
+    /// Compares the deployed bytecode with the compiled one, both without metadata hashes
+    /// and with the `immutable_references` masked. Returns `None` if the deployed bytecode
+    /// has not been provided.
+    fn compare_deployed_bytecode(
+        &self,
+        deployed_bytecode: &DeployedBytecode,
+        immutable_references: &[Range<usize>],
+    ) -> Option<Result<(), VerificationError>> {
+        let onchain = self.bc_deployed_bytecode.as_ref()?;
+        let matches = match immutable_references {
+            [] => onchain.bytecode == deployed_bytecode.bytecode,
+            references => {
+                mask(&onchain.bytecode, references) == mask(&deployed_bytecode.bytecode, references)
+            }
+        };
+        if !matches {
+            return Some(Err(VerificationError::BytecodeMismatch(Mismatch::new(
+                deployed_bytecode.bytecode.clone().into(),
+                onchain.bytecode.clone().into(),
+            ))));
+        }
+        Some(Ok(()))
     }
 
     /// Checks that solc versions obtained from metadata hash correspond
     /// for provided deployed bytecode and deployed bytecode obtained
     /// as a result of local compilation.
+    ///
+    /// Without the provided deployed bytecode there is nothing to check, as the creation
+    /// transaction input is split by the compiled metadata hash itself.
     fn check_metadata_hash_solc_versions(
         &self,
         deployed_bytecode: &DeployedBytecode,
     ) -> Result<(), VerificationError> {
+        let bc_deployed_bytecode = match &self.bc_deployed_bytecode {
+            Some(bc_deployed_bytecode) => bc_deployed_bytecode,
+            None => return Ok(()),
+        };
         let compiled_solc = &deployed_bytecode.metadata_hash().solc;
-        let bc_solc = &self.bc_deployed_bytecode.metadata_hash().solc;
+        let bc_solc = &bc_deployed_bytecode.metadata_hash().solc;
         if bc_solc != compiled_solc {
             let compiled_solc = compiled_solc
                 .as_ref()
@@ -508,38 +902,39 @@ impl Verifier {
     /// [`Verifier`] initialization.
     ///
     /// Returns `Err` if constructor arguments cannot be extracted (should not be the case
-    /// if `Bytecode.verify_bytecode_with_extra_data` was called before).
+    /// if `Bytecode.verify_bytecode_with_extra_data` was called before), or if they
+    /// do not correspond to the constructor parameters.
     fn extract_constructor_args(
         &self,
+        creation_tx_input: &Bytecode<CreationTxInput>,
         abi_constructor: Option<&Constructor>,
         bytecode: &Bytecode<CompilationResult>,
-    ) -> Result<Option<Bytes>, VerificationError> {
-        let encoded_constructor_args = self.bc_creation_tx_input.constructor_args(bytecode)?;
+    ) -> Result<Option<ConstructorArgs>, VerificationError> {
+        let encoded_constructor_args = creation_tx_input.constructor_args(bytecode)?;
 
         let expects_constructor_args =
             abi_constructor.map(|input| input.inputs.len()).unwrap_or(0) > 0;
 
         match encoded_constructor_args {
-            None if expects_constructor_args => Err(
-                VerificationError::InvalidConstructorArguments(DisplayBytes::from([])),
-            ),
+            None if expects_constructor_args => Err(VerificationError::MissingConstructorArguments),
             Some(encoded) if !expects_constructor_args => Err(
-                VerificationError::InvalidConstructorArguments(encoded.into()),
+                VerificationError::UnexpectedConstructorArguments(encoded.into()),
             ),
             None => Ok(None),
             Some(encoded_constructor_args) => {
-                let _constructor_args = self.parse_constructor_args(
+                let constructor_args = self.parse_constructor_args(
                     encoded_constructor_args.clone(),
                     abi_constructor.expect("Is not None as `expects_constructor_args`"),
                 )?;
-                Ok(Some(encoded_constructor_args))
+                Ok(Some((encoded_constructor_args, constructor_args)))
             }
         }
     }
 
     /// Parses encoded arguments via constructor types specified into abi.
     ///
-    /// Returns `Err` if bytes do not correspond to the constructor arguments representation.
+    /// Returns `Err` if bytes do not correspond to the constructor arguments representation,
+    /// including the case of data left after the decoded arguments.
     fn parse_constructor_args(
         &self,
         encoded_args: Bytes,
@@ -549,8 +944,25 @@ impl Verifier {
             inputs.iter().map(|p| p.kind.clone()).collect()
         };
         let param_types = param_types(&abi_constructor.inputs);
-        let tokens = ethabi::decode(&param_types, encoded_args.as_ref())
-            .map_err(|_err| VerificationError::InvalidConstructorArguments(encoded_args.into()))?;
+        let types = param_types
+            .iter()
+            .map(ethabi::param_type::Writer::write)
+            .collect::<Vec<_>>()
+            .join(",");
+        let tokens = ethabi::decode(&param_types, encoded_args.as_ref()).map_err(|err| {
+            VerificationError::InvalidConstructorArguments(
+                encoded_args.clone().into(),
+                format!("{}: {}", types, err),
+            )
+        })?;
+        // decoding ignores the trailing data, so the length is checked explicitly
+        let encoded_len = ethabi::encode(&tokens).len();
+        if encoded_len != encoded_args.len() {
+            return Err(VerificationError::InvalidConstructorArguments(
+                encoded_args.into(),
+                format!("{}: arguments take {} bytes", types, encoded_len),
+            ));
+        }
 
         Ok(tokens)
     }
@@ -562,19 +974,19 @@ mod verifier_initialization_tests {
     use const_format::concatcp;
     use pretty_assertions::assert_eq;
 
-    const DEFAULT_CONSTRUCTOR_ARGS: &'static str =
+    pub(super) const DEFAULT_CONSTRUCTOR_ARGS: &str =
         "0000000000000000000000000000000000000000000000000000000000000fff";
     // {"ipfs": h'1220EB23CE2C13EA8739368F952F6C6A4B1F0623D147D2A19B6D4D26A61AB03FCD3E', "solc": 0.8.14}
-    const DEFAULT_ENCODED_METADATA_HASH: &'static str = "a2646970667358221220eb23ce2c13ea8739368f952f6c6a4b1f0623d147d2a19b6d4d26a61ab03fcd3e64736f6c634300080e0033";
-    const DEFAULT_BYTECODE_WITHOUT_METADATA_HASH: &'static str = "608060405234801561001057600080fd5b5060405161022038038061022083398101604081905261002f91610074565b600080546001600160a01b0319163390811782556040519091907f342827c97908e5e2f71151c08502a66d44b6f758e3ac2f1de95f02eb95f0a735908290a35061008d565b60006020828403121561008657600080fd5b5051919050565b6101848061009c6000396000f3fe608060405234801561001057600080fd5b50600436106100365760003560e01c8063893d20e81461003b578063a6f9dae11461005a575b600080fd5b600054604080516001600160a01b039092168252519081900360200190f35b61006d61006836600461011e565b61006f565b005b6000546001600160a01b031633146100c35760405162461bcd60e51b815260206004820152601360248201527221b0b63632b91034b9903737ba1037bbb732b960691b604482015260640160405180910390fd5b600080546040516001600160a01b03808516939216917f342827c97908e5e2f71151c08502a66d44b6f758e3ac2f1de95f02eb95f0a73591a3600080546001600160a01b0319166001600160a01b0392909216919091179055565b60006020828403121561013057600080fd5b81356001600160a01b038116811461014757600080fd5b939250505056fe";
-    const DEFAULT_DEPLOYED_BYTECODE_WITHOUT_METADATA_HASH: &'static str =  "608060405234801561001057600080fd5b50600436106100365760003560e01c8063893d20e81461003b578063a6f9dae11461005a575b600080fd5b600054604080516001600160a01b039092168252519081900360200190f35b61006d61006836600461011e565b61006f565b005b6000546001600160a01b031633146100c35760405162461bcd60e51b815260206004820152601360248201527221b0b63632b91034b9903737ba1037bbb732b960691b604482015260640160405180910390fd5b600080546040516001600160a01b03808516939216917f342827c97908e5e2f71151c08502a66d44b6f758e3ac2f1de95f02eb95f0a73591a3600080546001600160a01b0319166001600160a01b0392909216919091179055565b60006020828403121561013057600080fd5b81356001600160a01b038116811461014757600080fd5b939250505056fe";
+    pub(super) const DEFAULT_ENCODED_METADATA_HASH: &str = "a2646970667358221220eb23ce2c13ea8739368f952f6c6a4b1f0623d147d2a19b6d4d26a61ab03fcd3e64736f6c634300080e0033";
+    pub(super) const DEFAULT_BYTECODE_WITHOUT_METADATA_HASH: &str = "608060405234801561001057600080fd5b5060405161022038038061022083398101604081905261002f91610074565b600080546001600160a01b0319163390811782556040519091907f342827c97908e5e2f71151c08502a66d44b6f758e3ac2f1de95f02eb95f0a735908290a35061008d565b60006020828403121561008657600080fd5b5051919050565b6101848061009c6000396000f3fe608060405234801561001057600080fd5b50600436106100365760003560e01c8063893d20e81461003b578063a6f9dae11461005a575b600080fd5b600054604080516001600160a01b039092168252519081900360200190f35b61006d61006836600461011e565b61006f565b005b6000546001600160a01b031633146100c35760405162461bcd60e51b815260206004820152601360248201527221b0b63632b91034b9903737ba1037bbb732b960691b604482015260640160405180910390fd5b600080546040516001600160a01b03808516939216917f342827c97908e5e2f71151c08502a66d44b6f758e3ac2f1de95f02eb95f0a73591a3600080546001600160a01b0319166001600160a01b0392909216919091179055565b60006020828403121561013057600080fd5b81356001600160a01b038116811461014757600080fd5b939250505056fe";
+    pub(super) const DEFAULT_DEPLOYED_BYTECODE_WITHOUT_METADATA_HASH: &str =  "608060405234801561001057600080fd5b50600436106100365760003560e01c8063893d20e81461003b578063a6f9dae11461005a575b600080fd5b600054604080516001600160a01b039092168252519081900360200190f35b61006d61006836600461011e565b61006f565b005b6000546001600160a01b031633146100c35760405162461bcd60e51b815260206004820152601360248201527221b0b63632b91034b9903737ba1037bbb732b960691b604482015260640160405180910390fd5b600080546040516001600160a01b03808516939216917f342827c97908e5e2f71151c08502a66d44b6f758e3ac2f1de95f02eb95f0a73591a3600080546001600160a01b0319166001600160a01b0392909216919091179055565b60006020828403121561013057600080fd5b81356001600160a01b038116811461014757600080fd5b939250505056fe";
 
-    const DEFAULT_CREATION_TX_INPUT: &'static str = concatcp!(
+    pub(super) const DEFAULT_CREATION_TX_INPUT: &str = concatcp!(
         DEFAULT_BYTECODE_WITHOUT_METADATA_HASH,
         DEFAULT_ENCODED_METADATA_HASH,
         DEFAULT_CONSTRUCTOR_ARGS
     );
-    const DEFAULT_DEPLOYED_BYTECODE: &'static str = concatcp!(
+    pub(super) const DEFAULT_DEPLOYED_BYTECODE: &str = concatcp!(
         DEFAULT_DEPLOYED_BYTECODE_WITHOUT_METADATA_HASH,
         DEFAULT_ENCODED_METADATA_HASH
     );
@@ -588,22 +1000,27 @@ mod verifier_initialization_tests {
         );
 
         let verifier = Verifier::new(
-            &concatcp!("0x", DEFAULT_CREATION_TX_INPUT),
-            &concatcp!("0x", DEFAULT_DEPLOYED_BYTECODE),
+            concatcp!("0x", DEFAULT_CREATION_TX_INPUT),
+            concatcp!("0x", DEFAULT_DEPLOYED_BYTECODE),
         );
         assert!(verifier.is_ok(), "Initialization with \"0x\" prefix failed");
     }
 
     #[test]
-    fn initialization_with_empty_creation_tx_input_should_fail() {
-        let verifier = Verifier::new("", DEFAULT_DEPLOYED_BYTECODE);
+    fn initialization_with_single_bytecode() {
+        let verifier = Verifier::new("", DEFAULT_DEPLOYED_BYTECODE).expect("creation is optional");
+        assert!(verifier.bc_creation_tx_input.is_none());
+        let verifier =
+            Verifier::new(DEFAULT_CREATION_TX_INPUT, "0x").expect("deployed is optional");
+        assert!(verifier.bc_deployed_bytecode.is_none());
+        assert!(verifier.deployed_bytecode().is_empty());
+    }
+
+    #[test]
+    fn initialization_without_bytecodes_should_fail() {
+        let verifier = Verifier::new("0x", "");
         assert!(verifier.is_err(), "Verifier initialization should fail");
-        assert_eq!(
-            verifier.unwrap_err(),
-            InitializationError::MetadataHashMismatch(Mismatch::expected(
-                DisplayBytes::from_str(DEFAULT_ENCODED_METADATA_HASH).unwrap()
-            ))
-        )
+        assert_eq!(verifier.unwrap_err(), InitializationError::MissingBytecodes)
     }
 
     #[test]
@@ -618,8 +1035,8 @@ mod verifier_initialization_tests {
     }
 
     #[test]
-    fn initialization_with_empty_deployed_bytecode_should_fail() {
-        let verifier = Verifier::new(DEFAULT_CREATION_TX_INPUT, "");
+    fn initialization_with_short_deployed_bytecode_should_fail() {
+        let verifier = Verifier::new(DEFAULT_CREATION_TX_INPUT, "0x00");
         assert!(verifier.is_err(), "Verifier initialization should fail");
         assert_eq!(
             verifier.unwrap_err(),
@@ -659,6 +1076,339 @@ mod verifier_initialization_tests {
     }
 }
 
+#[cfg(test)]
+mod match_strategies_tests {
+    use super::{verifier_initialization_tests::*, *};
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    // {"ipfs": h'1220EB23CE2C13EA8739368F952F6C6A4B1F0623D147D2A19B6D4D26A61AB03FCD3E', "solc": 0.8.15}
+    const ANOTHER_SOLC_ENCODED_METADATA_HASH: &str = "a2646970667358221220eb23ce2c13ea8739368f952f6c6a4b1f0623d147d2a19b6d4d26a61ab03fcd3e64736f6c634300080f0033";
+
+    // {"ipfs": h'1220AB23CE2C13EA8739368F952F6C6A4B1F0623D147D2A19B6D4D26A61AB03FCD3E', "solc": 0.8.14}
+    const ANOTHER_IPFS_ENCODED_METADATA_HASH: &str = "a2646970667358221220ab23ce2c13ea8739368f952f6c6a4b1f0623d147d2a19b6d4d26a61ab03fcd3e64736f6c634300080e0033";
+
+    fn compiler_output(contracts: &[(&str, &str)]) -> CompilerOutput {
+        let contracts: serde_json::Map<_, _> = contracts
+            .iter()
+            .map(|(name, encoded_metadata_hash)| {
+                let contract = json!({
+                    "abi": [{
+                        "type": "constructor",
+                        "stateMutability": "nonpayable",
+                        "inputs": [{"name": "value", "type": "uint256", "internalType": "uint256"}]
+                    }],
+                    "evm": {
+                        "bytecode": {
+                            "object": format!("{}{}", DEFAULT_BYTECODE_WITHOUT_METADATA_HASH, encoded_metadata_hash)
+                        },
+                        "deployedBytecode": {
+                            "object": format!("{}{}", DEFAULT_DEPLOYED_BYTECODE_WITHOUT_METADATA_HASH, encoded_metadata_hash)
+                        }
+                    }
+                });
+                (name.to_string(), contract)
+            })
+            .collect();
+        serde_json::from_value(json!({"contracts": {"source.sol": contracts}}))
+            .expect("valid compiler output")
+    }
+
+    fn verifier() -> Verifier {
+        Verifier::new(DEFAULT_CREATION_TX_INPUT, DEFAULT_DEPLOYED_BYTECODE).unwrap()
+    }
+
+    #[test]
+    fn falls_through_strategies_in_order() {
+        let output = compiler_output(&[("Another", ANOTHER_SOLC_ENCODED_METADATA_HASH)]);
+        assert_eq!(
+            verifier().verify(
+                output.clone(),
+                &[MatchStrategy::Exact],
+                BytecodeMatchPolicy::Either
+            ),
+            Err(VerificationFailure::NoMatchingContracts),
+            "compiler versions in metadata hashes differ"
+        );
+
+        let success = verifier()
+            .verify(
+                output,
+                &[MatchStrategy::Exact, MatchStrategy::MetadataInsensitive],
+                BytecodeMatchPolicy::Either,
+            )
+            .expect("should be matched by metadata insensitive strategy");
+        assert_eq!(success.contract_name, "Another");
+        assert_eq!(success.match_strategy, MatchStrategy::MetadataInsensitive);
+    }
+
+    #[test]
+    fn reports_first_matched_strategy() {
+        // "Another" comes first, but only "Exact" contract is matched by the first strategy
+        let output = compiler_output(&[
+            ("Another", ANOTHER_SOLC_ENCODED_METADATA_HASH),
+            ("Exact", DEFAULT_ENCODED_METADATA_HASH),
+        ]);
+        let success = verifier()
+            .verify(
+                output,
+                &[MatchStrategy::Exact, MatchStrategy::MetadataInsensitive],
+                BytecodeMatchPolicy::Either,
+            )
+            .expect("should be matched");
+        assert_eq!(success.contract_name, "Exact");
+        assert_eq!(success.match_strategy, MatchStrategy::Exact);
+        assert_eq!(
+            success.constructor_args,
+            Some(DisplayBytes::from_str(DEFAULT_CONSTRUCTOR_ARGS).unwrap())
+        );
+        assert_eq!(
+            success.constructor_args_decoded,
+            Some(vec![Token::Uint(0xfff.into())])
+        );
+    }
+
+    #[test]
+    fn compares_selected_contracts_only() {
+        let output = compiler_output(&[
+            ("Another", ANOTHER_SOLC_ENCODED_METADATA_HASH),
+            ("Exact", DEFAULT_ENCODED_METADATA_HASH),
+            ("Library", DEFAULT_ENCODED_METADATA_HASH),
+        ]);
+        let verify = |contract_file: Option<&str>, contract_name: Option<&str>| {
+            verifier()
+                .with_contract(
+                    contract_file.map(str::to_string),
+                    contract_name.map(str::to_string),
+                )
+                .verify(
+                    output.clone(),
+                    &[MatchStrategy::Exact],
+                    BytecodeMatchPolicy::Either,
+                )
+        };
+
+        let success = verify(None, None).expect("should be matched");
+        assert_eq!(success.contract_name, "Exact");
+        let success = verify(Some("source.sol"), Some("Library")).expect("should be matched");
+        assert_eq!(success.contract_name, "Library");
+        assert_eq!(
+            verify(None, Some("Another")),
+            Err(VerificationFailure::NoMatchingContracts),
+            "only the selected contract is compared"
+        );
+        assert_eq!(
+            verify(None, Some("Missing")),
+            Err(VerificationFailure::ContractNotFound("Missing".into()))
+        );
+        assert_eq!(
+            verify(Some("other.sol"), Some("Exact")),
+            Err(VerificationFailure::ContractNotFound(
+                "other.sol:Exact".into()
+            ))
+        );
+    }
+
+    #[test]
+    fn reports_invalid_constructor_args() {
+        let output = compiler_output(&[("Exact", DEFAULT_ENCODED_METADATA_HASH)]);
+        let verify = |constructor_args: &str| {
+            let creation_tx_input = format!(
+                "{}{}{}",
+                DEFAULT_BYTECODE_WITHOUT_METADATA_HASH,
+                DEFAULT_ENCODED_METADATA_HASH,
+                constructor_args
+            );
+            // the deployed bytecode matches, so only the creation one could fail the verification
+            Verifier::new(&creation_tx_input, DEFAULT_DEPLOYED_BYTECODE)
+                .unwrap()
+                .verify(
+                    output.clone(),
+                    &[MatchStrategy::Exact],
+                    BytecodeMatchPolicy::Both,
+                )
+                .unwrap_err()
+        };
+        let reason = |failure| match failure {
+            VerificationFailure::InvalidConstructorArguments {
+                contract_name,
+                reason,
+            } => {
+                assert_eq!(contract_name, "Exact");
+                reason
+            }
+            failure => panic!("unexpected failure: {}", failure),
+        };
+
+        let trailing = reason(verify(&format!("{}ff", DEFAULT_CONSTRUCTOR_ARGS)));
+        assert!(
+            trailing.contains("uint256: arguments take 32 bytes"),
+            "{}",
+            trailing
+        );
+        let truncated = reason(verify(&DEFAULT_CONSTRUCTOR_ARGS[..32]));
+        assert!(truncated.starts_with("16 bytes appended"), "{}", truncated);
+        assert_eq!(
+            reason(verify("")),
+            VerificationError::MissingConstructorArguments.to_string()
+        );
+    }
+    #[test]
+    fn exact_strategy_ignores_metadata_hash_contents() {
+        // e.g. sources compiled from different paths, which changes only the ipfs hash
+        let output = compiler_output(&[("Moved", ANOTHER_IPFS_ENCODED_METADATA_HASH)]);
+        let verifier = verifier();
+        let success = verifier
+            .verify(output, &[MatchStrategy::Exact], BytecodeMatchPolicy::Either)
+            .expect("bytecodes without metadata hashes match");
+        assert_eq!(success.contract_name, "Moved");
+        assert!(!verifier.metadata_matches(&success.compiled_deployed));
+    }
+
+    #[test]
+    fn full_match_requires_equal_metadata_hashes() {
+        let output = compiler_output(&[
+            ("Another", ANOTHER_SOLC_ENCODED_METADATA_HASH),
+            ("Exact", DEFAULT_ENCODED_METADATA_HASH),
+        ]);
+        let verifier = verifier();
+        let matches = |strategy| {
+            let success = verifier
+                .verify(output.clone(), &[strategy], BytecodeMatchPolicy::Either)
+                .expect("should be matched");
+            verifier.metadata_matches(&success.compiled_deployed)
+        };
+        assert!(matches(MatchStrategy::Exact));
+        assert!(!matches(MatchStrategy::MetadataInsensitive));
+        assert!(!verifier.metadata_matches(&DisplayBytes::default()));
+    }
+
+    #[test]
+    fn masks_immutable_references() {
+        // PUSH32 <immutable value> POP, the value is left zeroed by the compiler
+        let deployed_bytecode =
+            |value: &str| format!("0x7f{:0>64}50{}", value, DEFAULT_ENCODED_METADATA_HASH);
+        let output = |immutable_references: serde_json::Value| {
+            let contract = json!({
+                "abi": [],
+                "evm": {
+                    "bytecode": {
+                        "object": format!("{}{}", DEFAULT_BYTECODE_WITHOUT_METADATA_HASH, DEFAULT_ENCODED_METADATA_HASH)
+                    },
+                    "deployedBytecode": {
+                        "object": deployed_bytecode(""),
+                        "immutableReferences": immutable_references
+                    }
+                }
+            });
+            serde_json::from_value::<CompilerOutput>(
+                json!({"contracts": {"source.sol": {"Immutable": contract}}}),
+            )
+            .expect("valid compiler output")
+        };
+        let verify = |onchain: &str, output| {
+            Verifier::new("", onchain).unwrap().verify(
+                output,
+                &[MatchStrategy::Exact],
+                BytecodeMatchPolicy::Either,
+            )
+        };
+
+        let success = verify(
+            &deployed_bytecode("cafe"),
+            output(json!({"3": [{"start": 1, "length": 32}]})),
+        )
+        .expect("immutable value should be masked");
+        assert!(success.immutables_masked);
+        assert_eq!(success.bytecode_matches.deployed, BytecodeMatch::Matched);
+
+        let success = verify(&deployed_bytecode(""), output(json!({})))
+            .expect("bytecodes without immutables are equal");
+        assert!(!success.immutables_masked);
+        assert_eq!(
+            verify(&deployed_bytecode("cafe"), output(json!({}))),
+            Err(VerificationFailure::NoMatchingContracts),
+            "only referenced ranges are masked"
+        );
+    }
+
+    #[test]
+    fn compares_bytecodes_independently() {
+        use BytecodeMatch::*;
+
+        let output = compiler_output(&[("Exact", DEFAULT_ENCODED_METADATA_HASH)]);
+        let verify = |creation_tx_input: &str, deployed_bytecode: &str, policy| {
+            Verifier::new(creation_tx_input, deployed_bytecode)
+                .unwrap()
+                .verify(output.clone(), &[MatchStrategy::Exact], policy)
+        };
+        let matches = |creation, deployed| BytecodeMatches { creation, deployed };
+        let mismatching_creation_tx_input = format!(
+            "6001{}{}",
+            DEFAULT_ENCODED_METADATA_HASH, DEFAULT_CONSTRUCTOR_ARGS
+        );
+        let mismatching_deployed_bytecode = format!("6001{}", DEFAULT_ENCODED_METADATA_HASH);
+
+        for policy in [BytecodeMatchPolicy::Either, BytecodeMatchPolicy::Both] {
+            let success = verify(DEFAULT_CREATION_TX_INPUT, DEFAULT_DEPLOYED_BYTECODE, policy)
+                .expect("both bytecodes match");
+            assert_eq!(success.bytecode_matches, matches(Matched, Matched));
+
+            let success = verify("", DEFAULT_DEPLOYED_BYTECODE, policy)
+                .expect("deployed bytecode matches without creation one");
+            assert_eq!(success.bytecode_matches, matches(NotProvided, Matched));
+            assert_eq!(success.constructor_args, None);
+
+            let verifier = Verifier::new(DEFAULT_CREATION_TX_INPUT, "").unwrap();
+            let success = verifier
+                .verify(output.clone(), &[MatchStrategy::Exact], policy)
+                .expect("creation bytecode matches without deployed one");
+            assert_eq!(success.bytecode_matches, matches(Matched, NotProvided));
+            assert_eq!(
+                success.constructor_args,
+                Some(DisplayBytes::from_str(DEFAULT_CONSTRUCTOR_ARGS).unwrap())
+            );
+            assert!(verifier.metadata_matches(&success.compiled_deployed));
+        }
+
+        let success = verify(
+            &mismatching_creation_tx_input,
+            DEFAULT_DEPLOYED_BYTECODE,
+            BytecodeMatchPolicy::Either,
+        )
+        .expect("deployed bytecode matches");
+        assert_eq!(success.bytecode_matches, matches(Mismatched, Matched));
+        assert_eq!(success.constructor_args, None);
+        let success = verify(
+            DEFAULT_CREATION_TX_INPUT,
+            &mismatching_deployed_bytecode,
+            BytecodeMatchPolicy::Either,
+        )
+        .expect("creation bytecode matches");
+        assert_eq!(success.bytecode_matches, matches(Matched, Mismatched));
+
+        for (creation_tx_input, deployed_bytecode) in [
+            (
+                mismatching_creation_tx_input.as_str(),
+                DEFAULT_DEPLOYED_BYTECODE,
+            ),
+            (
+                DEFAULT_CREATION_TX_INPUT,
+                mismatching_deployed_bytecode.as_str(),
+            ),
+        ] {
+            assert_eq!(
+                verify(
+                    creation_tx_input,
+                    deployed_bytecode,
+                    BytecodeMatchPolicy::Both
+                ),
+                Err(VerificationFailure::NoMatchingContracts)
+            );
+        }
+    }
+}
+
 #[cfg(test)]
 mod metadata_hash_deserialization_tests {
     use super::*;
@@ -817,7 +1567,7 @@ mod metadata_hash_deserialization_tests {
         // 3 elements expected in the map but got only 2:
         // { "ipfs": b"1220BCC988B1311237F2C00CCD0BFBD8B01D24DC18F720603B0DE93FE6327DF53625", "solc": b'00080e' }
         let hex = "a3646970667358221220bcc988b1311237f2c00ccd0bfbd8b01d24dc18f720603b0de93fe6327df5362564736f6c634300080e";
-        let encoded = DisplayBytes::from_str(&hex).unwrap().0;
+        let encoded = DisplayBytes::from_str(hex).unwrap().0;
 
         // when
         let decoded = MetadataHash::from_cbor(encoded);
@@ -835,7 +1585,7 @@ mod metadata_hash_deserialization_tests {
         // given
         // { "ipfs": b"1220BCC988B1311237F2C00CCD0BFBD8B01D24DC18F720603B0DE93FE6327DF53625", "solc": 123 } \
         let hex= "a2646970667358221220bcc988b1311237f2c00ccd0bfbd8b01d24dc18f720603b0de93fe6327df5362564736f6c63187B";
-        let encoded = DisplayBytes::from_str(&hex).unwrap().0;
+        let encoded = DisplayBytes::from_str(hex).unwrap().0;
 
         // when
         let decoded = MetadataHash::from_cbor(encoded);
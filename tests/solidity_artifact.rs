@@ -0,0 +1,106 @@
+use actix_web::{
+    test::{self, read_body_json, TestRequest},
+    App,
+};
+use async_once_cell::OnceCell;
+use pretty_assertions::assert_eq;
+use serde_json::json;
+use std::fs;
+use verification::{configure_router, AppRouter, Config, VerificationResponse, VerificationStatus};
+
+const CONTRACTS_DIR: &str = "tests/contracts";
+const ROUTE: &str = "/api/v1/solidity/verify/artifact";
+
+async fn global_app_router() -> &'static AppRouter {
+    static APP_ROUTER: OnceCell<AppRouter> = OnceCell::new();
+    APP_ROUTER
+        .get_or_init(async {
+            let mut config = Config::default();
+            config.sourcify.enabled = false;
+            AppRouter::new(config)
+                .await
+                .expect("couldn't initialize the app")
+        })
+        .await
+}
+
+fn read(dir: &str, name: &str) -> String {
+    fs::read_to_string(format!("{}/{}/{}", CONTRACTS_DIR, dir, name))
+        .unwrap_or_else(|_| panic!("Error while reading {}", name))
+}
+
+fn foundry_artifact(dir: &str) -> serde_json::Value {
+    json!({
+        "abi": [
+            {"type": "function", "name": "get", "inputs": [], "outputs": [{"name": "", "type": "uint256"}], "stateMutability": "view"},
+            {"type": "function", "name": "set", "inputs": [{"name": "x", "type": "uint256"}], "outputs": [], "stateMutability": "nonpayable"}
+        ],
+        "bytecode": { "object": read(dir, "creation_tx_input"), "sourceMap": "", "linkReferences": {} },
+        "deployedBytecode": { "object": read(dir, "deployed_bytecode"), "sourceMap": "", "linkReferences": {} },
+        "methodIdentifiers": { "get()": "6d4ce63c", "set(uint256)": "60fe47b1" }
+    })
+}
+
+#[actix_rt::test]
+async fn verifies_foundry_artifact() {
+    let app_router = global_app_router().await;
+    let app = test::init_service(App::new().configure(configure_router(app_router))).await;
+
+    let dir = "simple_storage";
+    let artifact = foundry_artifact(dir);
+    let request = json!({
+        "compiler_version": "v0.4.24+commit.e67f0147",
+        "artifact": artifact,
+        "sources": { "source.sol": read(dir, "source.sol") },
+        "evm_version": "default",
+    });
+    let response = TestRequest::post()
+        .uri(ROUTE)
+        .set_json(&request)
+        .send_request(&app)
+        .await;
+    assert!(
+        response.status().is_success(),
+        "Invalid status code (success expected): {}",
+        response.status()
+    );
+
+    let verification_response: VerificationResponse = read_body_json(response).await;
+    assert_eq!(
+        verification_response.status,
+        VerificationStatus::FullMatch,
+        "Invalid verification status. Response: {:?}",
+        verification_response
+    );
+    let result = verification_response
+        .result
+        .expect("result should be present");
+    assert_eq!(result.contract_name, "SimpleStorage");
+    assert_eq!(result.abi, artifact["abi"].to_string());
+}
+
+#[actix_rt::test]
+async fn artifact_without_deployed_bytecode_is_rejected() {
+    let app_router = global_app_router().await;
+    let app = test::init_service(App::new().configure(configure_router(app_router))).await;
+
+    let dir = "simple_storage";
+    let mut artifact = foundry_artifact(dir);
+    artifact.as_object_mut().unwrap().remove("deployedBytecode");
+    let request = json!({
+        "compiler_version": "v0.4.24+commit.e67f0147",
+        "artifact": artifact,
+        "sources": { "source.sol": read(dir, "source.sol") },
+        "evm_version": "default",
+    });
+    let response = TestRequest::post()
+        .uri(ROUTE)
+        .set_json(&request)
+        .send_request(&app)
+        .await;
+    assert!(
+        response.status().is_client_error(),
+        "Invalid status code (client error expected): {}",
+        response.status()
+    );
+}
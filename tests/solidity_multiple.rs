@@ -15,7 +15,8 @@ use std::{
     str::{from_utf8, FromStr},
 };
 use verification::{
-    configure_router, AppRouter, Config, DisplayBytes, VerificationResponse, VerificationStatus,
+    configure_router, AppRouter, Config, DisplayBytes, VerificationResponse, VerificationResult,
+    VerificationStatus,
 };
 
 const CONTRACTS_DIR: &'static str = "tests/contracts";
@@ -62,6 +63,8 @@ async fn test_setup(
         .expect("Expected constructor args must be valid")
     });
 
+    let libraries = (!input.libraries.is_empty())
+        .then(|| BTreeMap::from([(contract_path.clone(), input.libraries.clone())]));
     let request = if let Some(optimization_runs) = input.optimization_runs {
         json!({
             "deployed_bytecode": input.deployed_bytecode.as_ref().unwrap(),
@@ -70,7 +73,9 @@ async fn test_setup(
             "sources": BTreeMap::from([(contract_path, input.source_code.as_ref().unwrap())]),
             "evm_version": input.evm_version,
             "contract_libraries": input.contract_libraries,
-            "optimization_runs": optimization_runs
+            "libraries": libraries,
+            "optimization_runs": optimization_runs,
+            "verification_metadata": input.verification_metadata
         })
     } else {
         json!({
@@ -79,12 +84,18 @@ async fn test_setup(
             "compiler_version": input.compiler_version,
             "sources": BTreeMap::from([(contract_path, input.source_code.as_ref().unwrap())]),
             "evm_version": input.evm_version,
-            "contract_libraries": input.contract_libraries
+            "contract_libraries": input.contract_libraries,
+            "libraries": libraries,
+            "verification_metadata": input.verification_metadata
         })
     };
 
+    let uri = match input.debug {
+        true => format!("{}?debug=true", ROUTE),
+        false => ROUTE.to_string(),
+    };
     let response = TestRequest::post()
-        .uri(ROUTE)
+        .uri(&uri)
         .set_json(&request)
         .send_request(&app)
         .await;
@@ -92,7 +103,7 @@ async fn test_setup(
     (response, expected_constructor_argument)
 }
 
-async fn test_success(dir: &'static str, mut input: TestInput) {
+async fn test_success(dir: &'static str, mut input: TestInput) -> VerificationResult {
     let (response, expected_constructor_argument) = test_setup(dir, &mut input).await;
 
     // Assert that status code is success
@@ -110,7 +121,7 @@ async fn test_success(dir: &'static str, mut input: TestInput) {
 
     assert_eq!(
         verification_response.status,
-        VerificationStatus::Ok,
+        VerificationStatus::FullMatch,
         "Invalid verification status. Response: {:?}",
         verification_response
     );
@@ -137,16 +148,25 @@ async fn test_success(dir: &'static str, mut input: TestInput) {
         "Invalid constructor args"
     );
 
-    assert_eq!(
-        verification_result.evm_version, input.evm_version,
-        "Invalid evm version"
-    );
+    if input.evm_version == "default" {
+        assert_ne!(
+            verification_result.evm_version, "default",
+            "The compiler default evm version should be resolved"
+        );
+    } else {
+        assert_eq!(
+            verification_result.evm_version, input.evm_version,
+            "Invalid evm version"
+        );
+    }
     assert_eq!(
         verification_result.compiler_version, input.compiler_version,
         "Invalid compiler version"
     );
+    let mut expected_libraries = input.contract_libraries.clone();
+    expected_libraries.extend(input.libraries.clone());
     assert_eq!(
-        verification_result.contract_libraries, input.contract_libraries,
+        verification_result.contract_libraries, expected_libraries,
         "Invalid contract libraries"
     );
     assert_eq!(
@@ -168,6 +188,8 @@ async fn test_success(dir: &'static str, mut input: TestInput) {
         &input.source_code.expect("Set `Some` on test_setup"),
         "Invalid source"
     );
+
+    verification_result
 }
 
 /// Test verification failures (note: do not handle 400 BadRequest responses)
@@ -208,7 +230,8 @@ mod success_tests {
     async fn verifies_the_generated_bytecode_against_bytecode_retrieved_from_the_blockchain() {
         let contract_dir = "simple_storage";
         let test_input = TestInput::new("SimpleStorage", "v0.4.24+commit.e67f0147");
-        test_success(contract_dir, test_input).await;
+        let result = test_success(contract_dir, test_input).await;
+        pretty_assertions::assert_eq!(result.evm_version, "byzantium");
     }
 
     #[actix_rt::test]
@@ -226,6 +249,19 @@ mod success_tests {
         test_success(contract_dir, test_input).await;
     }
 
+    #[actix_rt::test]
+    async fn verifies_the_generated_bytecode_with_file_libraries() {
+        let contract_dir = "contract_with_lib";
+        let libraries = BTreeMap::from([(
+            "BadSafeMath".to_string(),
+            "0x9Bca1BF2810c9b68F25c82e8eBb9dC0A5301e310".to_string(),
+        )]);
+        let test_input = TestInput::new("SimpleStorage", "v0.5.11+commit.22be8592")
+            .with_optimization_runs(200)
+            .with_libraries(libraries);
+        test_success(contract_dir, test_input).await;
+    }
+
     #[actix_rt::test]
     // `whisper` metadata - (bzz0 => bzz1) in solidity 0.5.11()
     async fn verifies_smart_contract_with_new_whisper_metadata() {
@@ -241,7 +277,9 @@ mod success_tests {
         // let test_input = TestInput::new("Foo", ""v0.5.11+commit.c082d0b4"")
         let test_input =
             TestInput::new("Foo", "v0.5.11+commit.22be8592").with_optimization_runs(200);
-        test_success(contract_dir, test_input).await;
+        let result = test_success(contract_dir, test_input).await;
+        pretty_assertions::assert_eq!(result.language, "Solidity");
+        pretty_assertions::assert_eq!(result.contract_kind.as_deref(), Some("library"));
     }
 
     #[actix_rt::test]
@@ -262,7 +300,11 @@ mod success_tests {
         let test_input = TestInput::new("C", "v0.6.7+commit.b8d736ae")
             .with_optimization_runs(200)
             .has_constructor_args();
-        test_success(contract_dir, test_input).await;
+        let result = test_success(contract_dir, test_input).await;
+        assert!(
+            result.immutables_masked,
+            "immutable values should be masked in the deployed bytecode"
+        );
     }
 
     #[actix_rt::test]
@@ -274,6 +316,79 @@ mod success_tests {
             .has_constructor_args();
         test_success(contract_dir, test_input).await;
     }
+
+    #[actix_rt::test]
+    // contract verified once could be re-verified with the emitted verification metadata
+    async fn verifies_with_emitted_verification_metadata() {
+        let contract_dir = "with_immutable_assignment";
+        let test_input = || {
+            TestInput::new("C", "v0.6.7+commit.b8d736ae")
+                .with_optimization_runs(200)
+                .has_constructor_args()
+        };
+        let verification_metadata = test_success(contract_dir, test_input())
+            .await
+            .verification_metadata
+            .expect("verification metadata should be emitted");
+        test_success(
+            contract_dir,
+            test_input().with_verification_metadata(verification_metadata),
+        )
+        .await;
+    }
+}
+
+mod debug_tests {
+    use super::*;
+
+    async fn verification_response(mut input: TestInput) -> VerificationResponse {
+        let (response, _) = test_setup("simple_storage", &mut input).await;
+        assert!(
+            response.status().is_success(),
+            "Invalid status code (success expected): {}",
+            response.status()
+        );
+        read_body_json(response).await
+    }
+
+    #[actix_rt::test]
+    async fn returns_bytecodes_only_if_requested() {
+        let test_input = || TestInput::new("SimpleStorage", "v0.4.24+commit.e67f0147");
+        let response = verification_response(test_input()).await;
+        pretty_assertions::assert_eq!(response.status, VerificationStatus::FullMatch);
+        assert!(response.debug.is_none(), "debug section should be absent");
+
+        let response = verification_response(test_input().with_debug()).await;
+        pretty_assertions::assert_eq!(response.status, VerificationStatus::FullMatch);
+        let debug = response.debug.expect("debug section should be present");
+        let read = |name| {
+            DisplayBytes::from_str(
+                fs::read_to_string(format!("{}/simple_storage/{}", CONTRACTS_DIR, name))
+                    .unwrap()
+                    .trim(),
+            )
+            .unwrap()
+        };
+        pretty_assertions::assert_eq!(debug.onchain_creation, read("creation_tx_input"));
+        pretty_assertions::assert_eq!(debug.onchain_deployed, read("deployed_bytecode"));
+        assert!(debug.compiled_creation.is_some());
+        assert!(debug.compiled_deployed.is_some());
+    }
+
+    #[actix_rt::test]
+    async fn rejects_malformed_query() {
+        let app_router = global_app_router().await;
+        let app = test::init_service(App::new().configure(configure_router(app_router))).await;
+        let response = TestRequest::post()
+            .uri(&format!("{}?debug=maybe", ROUTE))
+            .set_json(&json!({}))
+            .send_request(&app)
+            .await;
+        assert_eq!(response.status(), 400);
+        let body = read_body(response).await;
+        let message = from_utf8(&body).expect("Read body as UTF-8");
+        assert!(message.contains("Query deserialize error"), "{}", message);
+    }
 }
 
 mod error_tests {
@@ -299,6 +414,15 @@ mod error_tests {
             .with_source_code("pragma solidity ^0.4.24; contract SimpleStorage { ".to_string());
         test_failure(contract_dir, test_input, "ParserError").await;
     }
+
+    #[actix_rt::test]
+    async fn returns_failure_for_vyper_sources() {
+        let contract_dir = "simple_storage";
+        let vyper = "# @version 0.3.7\n\nstored: uint256\n\n@external\ndef set(x: uint256):\n    self.stored = x\n";
+        let test_input = TestInput::new("SimpleStorage", "v0.4.24+commit.e67f0147")
+            .with_source_code(vyper.to_string());
+        test_failure(contract_dir, test_input, "Sources look like Vyper").await;
+    }
 }
 
 mod regression_tests {
@@ -8,7 +8,12 @@ pub struct TestInput {
     pub evm_version: &'static str,
     pub optimization_runs: Option<usize>,
     pub contract_libraries: BTreeMap<String, String>,
+    /// Libraries specified for the source file
+    pub libraries: BTreeMap<String, String>,
     pub has_constructor_args: bool,
+    pub verification_metadata: Option<String>,
+    /// If set, the request is sent with `?debug=true`
+    pub debug: bool,
 
     /// If None, the input would be read from the corresponding file
     pub source_code: Option<String>,
@@ -26,7 +31,10 @@ impl TestInput {
             evm_version: "default",
             optimization_runs: None,
             contract_libraries: Default::default(),
+            libraries: Default::default(),
             has_constructor_args: false,
+            verification_metadata: None,
+            debug: false,
 
             source_code: None,
             creation_tx_input: None,
@@ -49,11 +57,26 @@ impl TestInput {
         self
     }
 
+    pub fn with_libraries(mut self, libraries: BTreeMap<String, String>) -> Self {
+        self.libraries = libraries;
+        self
+    }
+
     pub fn has_constructor_args(mut self) -> Self {
         self.has_constructor_args = true;
         self
     }
 
+    pub fn with_verification_metadata(mut self, verification_metadata: String) -> Self {
+        self.verification_metadata = Some(verification_metadata);
+        self
+    }
+
+    pub fn with_debug(mut self) -> Self {
+        self.debug = true;
+        self
+    }
+
     pub fn with_source_code(mut self, source_code: String) -> Self {
         self.source_code = Some(source_code);
         self
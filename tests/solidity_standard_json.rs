@@ -0,0 +1,380 @@
+use actix_web::{
+    test::{self, read_body, read_body_json, TestRequest},
+    App,
+};
+use async_once_cell::OnceCell;
+use pretty_assertions::assert_eq;
+use serde_json::json;
+use serde_json::Value;
+use std::{fs, str::from_utf8, time::Duration};
+use verification::{configure_router, AppRouter, Config, VerificationResponse, VerificationStatus};
+
+const CONTRACTS_DIR: &str = "tests/contracts";
+const ROUTE: &str = "/api/v1/solidity/verify/standard-json";
+const ASYNC_ROUTE: &str = "/api/v1/solidity/verify/standard-json/async";
+const JOBS_ROUTE: &str = "/api/v1/solidity/verify/jobs";
+const UPLOADS_ROUTE: &str = "/api/v1/uploads";
+
+async fn global_app_router() -> &'static AppRouter {
+    static APP_ROUTER: OnceCell<AppRouter> = OnceCell::new();
+    APP_ROUTER
+        .get_or_init(async {
+            let mut config = Config::default();
+            config.sourcify.enabled = false;
+            config.uploads.enabled = true;
+            AppRouter::new(config)
+                .await
+                .expect("couldn't initialize the app")
+        })
+        .await
+}
+
+#[actix_rt::test]
+async fn verifies_input_with_model_checker_settings() {
+    const TIMEOUT: Duration = Duration::from_secs(60);
+
+    let app_router = global_app_router().await;
+    let app = test::init_service(App::new().configure(configure_router(app_router))).await;
+
+    let prefix = format!("{}/{}", CONTRACTS_DIR, "issue_5431");
+    let source_path = format!("{}/source.sol", prefix);
+    let read = |name: &str| {
+        fs::read_to_string(format!("{}/{}", prefix, name))
+            .unwrap_or_else(|_| panic!("Error while reading {}", name))
+    };
+    let request = json!({
+        "deployed_bytecode": read("deployed_bytecode"),
+        "creation_bytecode": read("creation_tx_input"),
+        "compiler_version": "v0.8.8+commit.dddeac2f",
+        "input": {
+            "language": "Solidity",
+            "sources": {
+                source_path: { "content": read("source.sol") }
+            },
+            "settings": {
+                "modelChecker": {
+                    "engine": "all",
+                    "targets": ["assert", "underflow", "overflow", "divByZero", "balance"],
+                    "timeout": 1000000
+                },
+                "optimizer": { "enabled": false },
+                "metadata": { "bytecodeHash": "none" },
+                "outputSelection": {
+                    "*": { "*": ["abi", "evm.bytecode", "evm.deployedBytecode"] }
+                }
+            }
+        }
+    });
+
+    let response = tokio::time::timeout(
+        TIMEOUT,
+        TestRequest::post()
+            .uri(ROUTE)
+            .set_json(&request)
+            .send_request(&app),
+    )
+    .await
+    .expect("verification with model checker settings took too long");
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = read_body(response).await;
+        let message = from_utf8(&body).expect("Read body as UTF-8");
+        panic!(
+            "Invalid status code (success expected). Status: {}. Messsage: {}",
+            status, message
+        )
+    }
+
+    let verification_response: VerificationResponse = read_body_json(response).await;
+    assert_eq!(
+        verification_response.status,
+        VerificationStatus::FullMatch,
+        "Invalid verification status. Response: {:?}",
+        verification_response
+    );
+    let verification_result = verification_response.result.expect("Checked above");
+    assert_eq!(verification_result.contract_name, "Owner");
+    assert!(
+        verification_result.model_checker_stripped,
+        "Model checker settings should be reported as stripped"
+    );
+}
+
+#[actix_rt::test]
+async fn reports_stripped_model_checker_settings_on_failure() {
+    let app_router = global_app_router().await;
+    let app = test::init_service(App::new().configure(configure_router(app_router))).await;
+
+    let prefix = format!("{}/{}", CONTRACTS_DIR, "issue_5431");
+    let source_path = format!("{}/source.sol", prefix);
+    let source = fs::read_to_string(format!("{}/source.sol", prefix)).expect("Read source");
+    // bytecodes of another contract, so that nothing matches
+    let read = |name: &str| {
+        fs::read_to_string(format!("{}/simple_storage/{}", CONTRACTS_DIR, name))
+            .unwrap_or_else(|_| panic!("Error while reading {}", name))
+            .trim()
+            .to_string()
+    };
+    let request = json!({
+        "deployed_bytecode": read("deployed_bytecode"),
+        "creation_bytecode": read("creation_tx_input"),
+        "compiler_version": "v0.8.8+commit.dddeac2f",
+        "input": {
+            "language": "Solidity",
+            "sources": {
+                source_path: { "content": source }
+            },
+            "settings": {
+                "modelChecker": { "engine": "all", "timeout": 1000000 },
+                "optimizer": { "enabled": false },
+                "outputSelection": {
+                    "*": { "*": ["abi", "evm.bytecode", "evm.deployedBytecode"] }
+                }
+            }
+        }
+    });
+
+    let response = TestRequest::post()
+        .uri(ROUTE)
+        .set_json(&request)
+        .send_request(&app)
+        .await;
+    assert!(response.status().is_success());
+    let verification_response: VerificationResponse = read_body_json(response).await;
+    assert_eq!(verification_response.status, VerificationStatus::Failed);
+    assert!(
+        verification_response.model_checker_stripped,
+        "Model checker settings should be reported as stripped"
+    );
+}
+
+#[actix_rt::test]
+async fn verifies_uploaded_input() {
+    let app_router = global_app_router().await;
+    let app = test::init_service(App::new().configure(configure_router(app_router))).await;
+
+    let prefix = format!("{}/{}", CONTRACTS_DIR, "issue_5431");
+    let source_path = format!("{}/source.sol", prefix);
+    let read = |name: &str| {
+        fs::read_to_string(format!("{}/{}", prefix, name))
+            .unwrap_or_else(|_| panic!("Error while reading {}", name))
+    };
+    let input = json!({
+        "language": "Solidity",
+        "sources": {
+            source_path: { "content": read("source.sol") }
+        },
+        "settings": {
+            "optimizer": { "enabled": false },
+            "metadata": { "bytecodeHash": "none" },
+            "outputSelection": {
+                "*": { "*": ["abi", "evm.bytecode", "evm.deployedBytecode"] }
+            }
+        }
+    })
+    .to_string();
+
+    let response = TestRequest::post()
+        .uri(UPLOADS_ROUTE)
+        .send_request(&app)
+        .await;
+    assert_eq!(response.status(), 201, "Upload should be started");
+    let upload: Value = read_body_json(response).await;
+    let upload_id = upload["id"]
+        .as_str()
+        .expect("id should be returned")
+        .to_string();
+
+    let (first, second) = input.split_at(input.len() / 2);
+    for (offset, chunk) in [(0, first), (first.len(), second)] {
+        let response = TestRequest::patch()
+            .uri(&format!("{}/{}", UPLOADS_ROUTE, upload_id))
+            .insert_header(("Upload-Offset", offset.to_string()))
+            .set_payload(chunk.to_string())
+            .send_request(&app)
+            .await;
+        assert!(response.status().is_success(), "Chunk should be appended");
+        let upload: Value = read_body_json(response).await;
+        assert_eq!(upload["size"], offset + chunk.len());
+    }
+
+    let request = json!({
+        "deployed_bytecode": read("deployed_bytecode"),
+        "creation_bytecode": read("creation_tx_input"),
+        "compiler_version": "v0.8.8+commit.dddeac2f",
+        "upload_id": upload_id,
+    });
+    let response = TestRequest::post()
+        .uri(ROUTE)
+        .set_json(&request)
+        .send_request(&app)
+        .await;
+    assert!(
+        response.status().is_success(),
+        "Invalid status code (success expected). Status: {}",
+        response.status()
+    );
+    let verification_response: VerificationResponse = read_body_json(response).await;
+    assert_eq!(
+        verification_response.status,
+        VerificationStatus::FullMatch,
+        "Invalid verification status. Response: {:?}",
+        verification_response
+    );
+    assert_eq!(
+        verification_response
+            .result
+            .expect("Checked above")
+            .contract_name,
+        "Owner"
+    );
+
+    let response = TestRequest::get()
+        .uri(&format!("{}/{}", UPLOADS_ROUTE, upload_id))
+        .send_request(&app)
+        .await;
+    assert_eq!(response.status(), 404, "Upload should be consumed");
+}
+
+#[actix_rt::test]
+async fn verifies_input_with_empty_output_selection() {
+    let app_router = global_app_router().await;
+    let app = test::init_service(App::new().configure(configure_router(app_router))).await;
+
+    let prefix = format!("{}/{}", CONTRACTS_DIR, "issue_5431");
+    let source_path = format!("{}/source.sol", prefix);
+    let read = |name: &str| {
+        fs::read_to_string(format!("{}/{}", prefix, name))
+            .unwrap_or_else(|_| panic!("Error while reading {}", name))
+    };
+    let request = json!({
+        "deployed_bytecode": read("deployed_bytecode"),
+        "creation_bytecode": read("creation_tx_input"),
+        "compiler_version": "v0.8.8+commit.dddeac2f",
+        "input": {
+            "language": "Solidity",
+            "sources": {
+                source_path: { "content": read("source.sol") }
+            },
+            "settings": {
+                "optimizer": { "enabled": false },
+                "metadata": { "bytecodeHash": "none" },
+                "outputSelection": {}
+            }
+        }
+    });
+
+    let response = TestRequest::post()
+        .uri(ROUTE)
+        .set_json(&request)
+        .send_request(&app)
+        .await;
+    assert!(
+        response.status().is_success(),
+        "Invalid status code (success expected). Status: {}",
+        response.status()
+    );
+    let verification_response: VerificationResponse = read_body_json(response).await;
+    assert_eq!(
+        verification_response.status,
+        VerificationStatus::FullMatch,
+        "Required outputs should be selected. Response: {:?}",
+        verification_response
+    );
+}
+
+#[actix_rt::test]
+async fn verifies_input_asynchronously() {
+    const TIMEOUT: Duration = Duration::from_secs(60);
+
+    let app_router = global_app_router().await;
+    let app = test::init_service(App::new().configure(configure_router(app_router))).await;
+
+    let prefix = format!("{}/{}", CONTRACTS_DIR, "issue_5431");
+    let source_path = format!("{}/source.sol", prefix);
+    let read = |name: &str| {
+        fs::read_to_string(format!("{}/{}", prefix, name))
+            .unwrap_or_else(|_| panic!("Error while reading {}", name))
+    };
+    let request = json!({
+        "deployed_bytecode": read("deployed_bytecode"),
+        "creation_bytecode": read("creation_tx_input"),
+        "compiler_version": "v0.8.8+commit.dddeac2f",
+        "input": {
+            "language": "Solidity",
+            "sources": {
+                source_path: { "content": read("source.sol") }
+            },
+            "settings": {
+                "optimizer": { "enabled": false },
+                "metadata": { "bytecodeHash": "none" },
+                "outputSelection": {
+                    "*": { "*": ["abi", "evm.bytecode", "evm.deployedBytecode"] }
+                }
+            }
+        }
+    });
+
+    let response = TestRequest::post()
+        .uri(ASYNC_ROUTE)
+        .set_json(&request)
+        .send_request(&app)
+        .await;
+    assert_eq!(response.status(), 202, "Job should be started");
+    let job: Value = read_body_json(response).await;
+    let job_id = job["job_id"]
+        .as_str()
+        .expect("job id should be returned")
+        .to_string();
+
+    let status = tokio::time::timeout(TIMEOUT, async {
+        loop {
+            let response = TestRequest::get()
+                .uri(&format!("{}/{}", JOBS_ROUTE, job_id))
+                .send_request(&app)
+                .await;
+            assert!(
+                response.status().is_success(),
+                "Invalid status code (success expected). Status: {}",
+                response.status()
+            );
+            let status: Value = read_body_json(response).await;
+            if status["status"] != "pending" {
+                break status;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+    })
+    .await
+    .expect("asynchronous verification took too long");
+
+    assert_eq!(status["status"], "done", "Job status: {}", status);
+    let verification_response: VerificationResponse =
+        serde_json::from_value(status["response"].clone()).expect("Verification response");
+    assert_eq!(
+        verification_response.status,
+        VerificationStatus::FullMatch,
+        "Invalid verification status. Response: {:?}",
+        verification_response
+    );
+    assert_eq!(
+        verification_response
+            .result
+            .expect("Checked above")
+            .contract_name,
+        "Owner"
+    );
+}
+
+#[actix_rt::test]
+async fn unknown_job_is_not_found() {
+    let app_router = global_app_router().await;
+    let app = test::init_service(App::new().configure(configure_router(app_router))).await;
+
+    let response = TestRequest::get()
+        .uri(&format!("{}/{}", JOBS_ROUTE, "0123456789abcdef"))
+        .send_request(&app)
+        .await;
+    assert_eq!(response.status(), 404, "Unknown job should not be found");
+}
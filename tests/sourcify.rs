@@ -51,13 +51,30 @@ async fn should_return_200() {
             "result": {
                 "file_name": "contracts/1_Storage.sol",
                 "contract_name": "Storage",
+                "language": "Solidity",
+                "contract_kind": null,
                 "compiler_version": "0.8.7+commit.e28d00a7",
+                "compiler_version_alias": null,
                 "evm_version": "london",
                 "constructor_arguments": null,
+                "decoded_constructor_arguments": null,
                 "optimization": false,
                 "optimization_runs": 200,
                 "contract_libraries": {},
                 "abi": "[{\"inputs\":[],\"name\":\"retrieve\",\"outputs\":[{\"internalType\":\"uint256\",\"name\":\"\",\"type\":\"uint256\"}],\"stateMutability\":\"view\",\"type\":\"function\"},{\"inputs\":[{\"internalType\":\"uint256\",\"name\":\"num\",\"type\":\"uint256\"}],\"name\":\"store\",\"outputs\":[],\"stateMutability\":\"nonpayable\",\"type\":\"function\"}]",
+                "model_checker_stripped": false,
+                "source_transforms": [],
+                "immutables_masked": false,
+                "function_selectors": {
+                    "retrieve()": "0x2e64cec1",
+                    "store(uint256)": "0x6057361d"
+                },
+                "event_topics": {},
+                "verification_metadata": null,
+                "match_strategy": null,
+                "match_source": "sourcify",
+                "metadata_ipfs_hash": "Qmc2PReXfCBfQicjJQ4wD5rE5ZrqTXNBQYTxj1gsynsDnG",
+                "sources_hash": "0x30b4726cd55be601acd01aa4e2339e08926b11c0f795d27fcb7ba3d901338101",
                 "sources": {
                     "1_Storage.sol": "// SPDX-License-Identifier: GPL-3.0\n\npragma solidity >=0.7.0 <0.9.0;\n\n/**\n * @title Storage\n * @dev Store & retrieve value in a variable\n * @custom:dev-run-script ./scripts/deploy_with_ethers.ts\n */\ncontract Storage {\n\n    uint256 number;\n\n    /**\n     * @dev Store value in variable\n     * @param num value to store\n     */\n    function store(uint256 num) public {\n        number = num;\n    }\n\n    /**\n     * @dev Return value \n     * @return value of 'number'\n     */\n    function retrieve() public view returns (uint256){\n        return number;\n    }\n}"
                 }